@@ -97,5 +97,55 @@ fn phylip_parsing(c: &mut Criterion) {
     }
 }
 
-criterion_group!(benches, dm_vs_treesize, newick_parsing, phylip_parsing);
+/// Compare lazy node iterators with traversals that collect node ids
+fn traversals(c: &mut Criterion) {
+    let mut group = c.benchmark_group("traversals");
+    for size in [100, 1000, 10000].iter() {
+        let tree = generate_tree(*size, true, Uniform).unwrap();
+        let root = tree.get_root().unwrap();
+
+        group.bench_with_input(BenchmarkId::new("Vec", size), size, |bencher, _| {
+            bencher.iter(|| {
+                tree.preorder(&root)
+                    .unwrap()
+                    .iter()
+                    .filter_map(|id| tree.get(id).unwrap().parent_edge)
+                    .sum::<f64>()
+            })
+        });
+        group.bench_with_input(BenchmarkId::new("Lazy", size), size, |bencher, _| {
+            bencher.iter(|| {
+                tree.iter_preorder(&root)
+                    .unwrap()
+                    .filter_map(|node| node.parent_edge)
+                    .sum::<f64>()
+            })
+        });
+    }
+}
+
+/// Compare sequential and parallel distance matrix computation
+fn parallel_dm(c: &mut Criterion) {
+    let mut group = c.benchmark_group("parallel_dm");
+    group.sample_size(10);
+    for size in [1000, 5000].iter() {
+        let tree = generate_tree(*size, true, Uniform).unwrap();
+
+        group.bench_with_input(BenchmarkId::new("Sequential", size), size, |bencher, _| {
+            bencher.iter(|| tree.distance_matrix())
+        });
+        group.bench_with_input(BenchmarkId::new("Parallel", size), size, |bencher, _| {
+            bencher.iter(|| tree.distance_matrix_parallel())
+        });
+    }
+}
+
+criterion_group!(
+    benches,
+    dm_vs_treesize,
+    newick_parsing,
+    phylip_parsing,
+    traversals,
+    parallel_dm
+);
 criterion_main!(benches);
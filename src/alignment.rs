@@ -0,0 +1,518 @@
+//! Read multiple sequence alignments, attach sequences to the leaves of trees
+//! and compute parsimony scores.
+
+use std::collections::{BTreeMap, HashMap};
+
+use thiserror::Error;
+
+use crate::tree::{NodeId, Tree, TreeError};
+
+/// Errors that can occur when reading and using [`Alignment`] structs.
+#[derive(Error, Debug)]
+pub enum AlignmentError {
+    /// The alignment file is empty
+    #[error("Alignment file is empty.")]
+    EmptyFile,
+    /// The header line does not contain the number of taxa and sites
+    #[error("The header must contain the number of taxa and the number of sites.")]
+    InvalidHeader,
+    /// There was a [`std::num::ParseIntError`] when reading the header
+    #[error("Could not parse the alignment dimensions.")]
+    SizeParseError(#[from] std::num::ParseIntError),
+    /// The number of sequences does not match the header
+    #[error("Found {0} sequences but expected {1}")]
+    WrongNumberOfTaxa(usize, usize),
+    /// A sequence length does not match the header
+    #[error("Sequence {0} has {1} sites but expected {2}")]
+    WrongSequenceLength(String, usize, usize),
+    /// A taxon is present several times
+    #[error("Duplicate taxon {0}")]
+    DuplicateTaxon(String),
+    /// We are trying to access a taxon that does not exist
+    #[error("Missing taxon {0}")]
+    MissingTaxon(String),
+}
+
+/// A multiple sequence alignment, mapping taxa names to their aligned sequences.
+#[derive(Debug, Clone, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Alignment {
+    /// Names of the taxa, in the order they were read
+    taxa: Vec<String>,
+    /// Aligned sequence of each taxon
+    sequences: HashMap<String, Vec<char>>,
+    /// Number of sites in the alignment
+    n_sites: usize,
+}
+
+impl Alignment {
+    /// Reads an alignment in the PHYLIP format. The first line contains the number
+    /// of taxa and the number of sites, and each sequence starts with the taxon name
+    /// followed by whitespace *(relaxed PHYLIP)*. Whitespace within sequences is ignored.
+    ///
+    /// Sequences can be written sequentially *(possibly over several lines)*, or
+    /// interleaved in blocks separated by empty lines where only the first block
+    /// contains taxa names.
+    /// # Example
+    /// ```
+    /// use phylotree::alignment::Alignment;
+    ///
+    /// let sequential = "3 8\nA ACGTACGT\nB ACGT\nACGA\nC ACGTTCGA\n";
+    /// let interleaved = "3 8\nA ACGT\nB ACGT\nC ACGT\n\nACGT\nACGA\nTCGA\n";
+    ///
+    /// let aln = Alignment::from_phylip(sequential).unwrap();
+    /// assert_eq!(aln.taxa(), &["A", "B", "C"]);
+    /// assert_eq!(aln.n_sites(), 8);
+    /// assert_eq!(aln.get("B").unwrap().iter().collect::<String>(), "ACGTACGA");
+    ///
+    /// assert_eq!(Alignment::from_phylip(interleaved).unwrap(), aln);
+    /// ```
+    pub fn from_phylip(s: &str) -> Result<Self, AlignmentError> {
+        let mut lines = s.lines().skip_while(|line| line.trim().is_empty());
+
+        let header = lines.next().ok_or(AlignmentError::EmptyFile)?;
+        let mut dims = header.split_whitespace();
+        let (Some(n_taxa), Some(n_sites), None) = (dims.next(), dims.next(), dims.next()) else {
+            return Err(AlignmentError::InvalidHeader);
+        };
+        let (n_taxa, n_sites): (usize, usize) = (n_taxa.parse()?, n_sites.parse()?);
+
+        // Blocks of consecutive non-empty lines
+        let mut blocks: Vec<Vec<&str>> = vec![];
+        let mut new_block = true;
+        for line in lines {
+            if line.trim().is_empty() {
+                new_block = true;
+            } else if new_block {
+                blocks.push(vec![line]);
+                new_block = false;
+            } else {
+                blocks.last_mut().unwrap().push(line);
+            }
+        }
+
+        let mut alignment = Self {
+            n_sites,
+            ..Default::default()
+        };
+        let mut records: Vec<(String, Vec<char>)> = vec![];
+        if blocks.len() > 1 {
+            // Interleaved: names are only in the first block
+            for line in blocks[0].iter() {
+                records.push(split_record(line));
+            }
+            for block in blocks[1..].iter() {
+                if block.len() != records.len() {
+                    return Err(AlignmentError::WrongNumberOfTaxa(block.len(), n_taxa));
+                }
+                for ((_, sequence), line) in records.iter_mut().zip(block) {
+                    sequence.extend(line.chars().filter(|c| !c.is_whitespace()));
+                }
+            }
+        } else {
+            // Sequential: sequences continue on the next lines until they are complete
+            for line in blocks.into_iter().flatten() {
+                match records.last_mut() {
+                    Some((_, sequence)) if sequence.len() < n_sites => {
+                        sequence.extend(line.chars().filter(|c| !c.is_whitespace()))
+                    }
+                    _ => records.push(split_record(line)),
+                }
+            }
+        }
+
+        if records.len() != n_taxa {
+            return Err(AlignmentError::WrongNumberOfTaxa(records.len(), n_taxa));
+        }
+        for (name, sequence) in records {
+            if sequence.len() != n_sites {
+                return Err(AlignmentError::WrongSequenceLength(
+                    name,
+                    sequence.len(),
+                    n_sites,
+                ));
+            }
+            if alignment.sequences.contains_key(&name) {
+                return Err(AlignmentError::DuplicateTaxon(name));
+            }
+            alignment.taxa.push(name.clone());
+            alignment.sequences.insert(name, sequence);
+        }
+
+        Ok(alignment)
+    }
+
+    /// Returns the names of the taxa in the alignment
+    pub fn taxa(&self) -> &[String] {
+        &self.taxa
+    }
+
+    /// Returns the aligned sequence of a taxon
+    pub fn get(&self, taxon: &str) -> Option<&[char]> {
+        self.sequences.get(taxon).map(|s| s.as_slice())
+    }
+
+    /// Returns the number of taxa in the alignment
+    pub fn n_taxa(&self) -> usize {
+        self.taxa.len()
+    }
+
+    /// Returns the number of sites *(i.e. columns)* of the alignment
+    pub fn n_sites(&self) -> usize {
+        self.n_sites
+    }
+}
+
+// Sets of possible states at each site of a sequence
+type StateSets = Vec<Vec<char>>;
+
+// Splits a PHYLIP line into the taxon name and the start of its sequence
+fn split_record(line: &str) -> (String, Vec<char>) {
+    let line = line.trim_start();
+    let (name, sequence) = line.split_once(char::is_whitespace).unwrap_or((line, ""));
+
+    (
+        name.to_string(),
+        sequence.chars().filter(|c| !c.is_whitespace()).collect(),
+    )
+}
+
+impl Tree {
+    /// Stores the aligned sequence of each leaf of the tree in the
+    /// [`Node::sequence`](crate::tree::Node::sequence) field. Sequences are matched
+    /// to leaves by name, all leaves must be named and present in the alignment.
+    /// # Example
+    /// ```
+    /// use phylotree::alignment::Alignment;
+    /// use phylotree::tree::Tree;
+    ///
+    /// let aln = Alignment::from_phylip("3 4\nA ACGT\nB ACGA\nC TCGA\n").unwrap();
+    /// let mut tree = Tree::from_newick("((A,B),C);").unwrap();
+    ///
+    /// tree.annotate_sequences(&aln).unwrap();
+    ///
+    /// let b = tree.get_by_name("B").unwrap();
+    /// assert_eq!(b.sequence, Some(vec!['A', 'C', 'G', 'A']));
+    /// ```
+    pub fn annotate_sequences(&mut self, aln: &Alignment) -> Result<(), TreeError> {
+        let mut sequences = vec![];
+        for leaf in self.get_leaves() {
+            let name = self
+                .get(&leaf)?
+                .name
+                .as_ref()
+                .ok_or(TreeError::UnnamedLeaves)?;
+            let sequence = aln
+                .get(name)
+                .ok_or_else(|| AlignmentError::MissingTaxon(name.clone()))?;
+            sequences.push((leaf, sequence.to_vec()));
+        }
+
+        for (leaf, sequence) in sequences {
+            self.get_mut(&leaf)?.sequence = Some(sequence);
+        }
+
+        Ok(())
+    }
+
+    // Fitch state sets of each site for every node indexed by node id,
+    // along with the parsimony score of the tree
+    fn fitch_state_sets(&self) -> Result<(Vec<StateSets>, usize), TreeError> {
+        if !self.is_rooted()? {
+            return Err(TreeError::IsNotRooted);
+        }
+
+        let root = self.get_root()?;
+        let mut n_sites = None;
+        let mut sets = vec![vec![]; self.size()];
+        let mut score = 0;
+        for node in self.iter_postorder(&root)? {
+            if node.is_tip() {
+                let sequence = node.sequence.as_ref().ok_or(TreeError::MissingSequences)?;
+                if *n_sites.get_or_insert(sequence.len()) != sequence.len() {
+                    return Err(TreeError::MissingSequences);
+                }
+                sets[node.id] = sequence.iter().map(|state| vec![*state]).collect();
+                continue;
+            }
+
+            // Leaves are visited before internal nodes
+            let node_sets = (0..n_sites.unwrap())
+                .map(|site| {
+                    let mut counts: BTreeMap<char, usize> = BTreeMap::new();
+                    for child in node.children.iter() {
+                        for state in sets[*child][site].iter() {
+                            *counts.entry(*state).or_default() += 1;
+                        }
+                    }
+                    let max = *counts.values().max().unwrap();
+                    score += node.children.len() - max;
+
+                    counts
+                        .into_iter()
+                        .filter(|(_, count)| *count == max)
+                        .map(|(state, _)| state)
+                        .collect()
+                })
+                .collect();
+            sets[node.id] = node_sets;
+        }
+
+        Ok((sets, score))
+    }
+
+    /// Computes the Fitch parsimony score of the tree *(Fitch, 1971)*, *i.e.* the
+    /// minimum number of state changes needed to explain the sequences stored in the
+    /// [`Node::sequence`](crate::tree::Node::sequence) field of the leaves, summed over
+    /// all sites. Multifurcations are handled with the generalisation of Hartigan (1973).
+    ///
+    /// The tree must be rooted, otherwise [`TreeError::IsNotRooted`] is returned, and all
+    /// leaves must have sequences of the same length, otherwise
+    /// [`TreeError::MissingSequences`] is returned.
+    /// # Example
+    /// ```
+    /// use phylotree::alignment::Alignment;
+    /// use phylotree::tree::Tree;
+    ///
+    /// let aln = Alignment::from_phylip("4 3\nA AAC\nB AAC\nC GTC\nD GAC\n").unwrap();
+    /// let mut tree = Tree::from_newick("((A,B),(C,D));").unwrap();
+    /// tree.annotate_sequences(&aln).unwrap();
+    ///
+    /// assert_eq!(tree.fitch_parsimony().unwrap(), 2);
+    /// ```
+    pub fn fitch_parsimony(&self) -> Result<usize, TreeError> {
+        Ok(self.fitch_state_sets()?.1)
+    }
+
+    /// Reconstructs the sequences of the internal nodes of the tree that minimize the
+    /// [Fitch parsimony score](Tree::fitch_parsimony()). After computing the Fitch state
+    /// sets in a postorder traversal, the states are chosen in a preorder traversal:
+    /// each node keeps the state of its parent if possible. When several states are
+    /// possible, the first one in alphabetical order is chosen.
+    /// # Example
+    /// ```
+    /// use phylotree::alignment::Alignment;
+    /// use phylotree::tree::Tree;
+    ///
+    /// let aln = Alignment::from_phylip("4 2\nA AC\nB AC\nC GC\nD GT\n").unwrap();
+    /// let mut tree = Tree::from_newick("((A,B)E,(C,D)F)G;").unwrap();
+    /// tree.annotate_sequences(&aln).unwrap();
+    ///
+    /// let states = tree.fitch_ancestral_states().unwrap();
+    /// assert_eq!(states[&tree.get_by_name("E").unwrap().id], vec!['A', 'C']);
+    /// assert_eq!(states[&tree.get_by_name("F").unwrap().id], vec!['G', 'C']);
+    /// ```
+    pub fn fitch_ancestral_states(&self) -> Result<HashMap<NodeId, Vec<char>>, TreeError> {
+        let (sets, _) = self.fitch_state_sets()?;
+
+        let mut states: HashMap<NodeId, Vec<char>> = HashMap::new();
+        for node in self.iter_preorder(&self.get_root()?)? {
+            if node.is_tip() {
+                continue;
+            }
+            let parent_states = node.parent.map(|parent| &states[&parent]);
+            let node_states = sets[node.id]
+                .iter()
+                .enumerate()
+                .map(|(site, set)| match parent_states {
+                    Some(parent) if set.contains(&parent[site]) => parent[site],
+                    _ => set[0],
+                })
+                .collect();
+            states.insert(node.id, node_states);
+        }
+
+        Ok(states)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn read_phylip_alignments() {
+        let sequential = "\
+5 12
+Alpha     AACGTGGCCA AA
+Beta      AAGGTCGCCA AA
+Gamma     CATTTCGTCA CA
+Delta     GGTATTTCGG CC
+Epsilon   GGGATCTCGG CC
+";
+        let multiline = "\
+5 12
+Alpha AACGTGGCCA
+AA
+Beta AAGGTCG
+CCAAA
+Gamma CATTTCGTCACA
+Delta GGTATTTCGG
+CC
+Epsilon GGGATCTCGGCC
+";
+        let interleaved = "\
+  5    12
+Alpha AACGTG
+Beta  AAGGTC
+Gamma CATTTC
+Delta GGTATT
+Epsilon GGGATC
+
+GCCA
+GCCA
+GTCA
+TCGG
+TCGG
+
+AA
+AA
+CA
+CC
+CC
+";
+        let aln = Alignment::from_phylip(sequential).unwrap();
+        assert_eq!(aln.n_taxa(), 5);
+        assert_eq!(aln.n_sites(), 12);
+        assert_eq!(aln.taxa(), &["Alpha", "Beta", "Gamma", "Delta", "Epsilon"]);
+        assert_eq!(
+            aln.get("Gamma").unwrap().iter().collect::<String>(),
+            "CATTTCGTCACA"
+        );
+        assert!(aln.get("Zeta").is_none());
+
+        assert_eq!(Alignment::from_phylip(multiline).unwrap(), aln);
+        assert_eq!(Alignment::from_phylip(interleaved).unwrap(), aln);
+    }
+
+    #[test]
+    fn phylip_alignment_errors() {
+        assert!(matches!(
+            Alignment::from_phylip("\n\n"),
+            Err(AlignmentError::EmptyFile)
+        ));
+        assert!(matches!(
+            Alignment::from_phylip("2\nA AC\nB AC\n"),
+            Err(AlignmentError::InvalidHeader)
+        ));
+        assert!(matches!(
+            Alignment::from_phylip("2 x\nA AC\nB AC\n"),
+            Err(AlignmentError::SizeParseError(_))
+        ));
+        assert!(matches!(
+            Alignment::from_phylip("3 2\nA AC\nB AC\n"),
+            Err(AlignmentError::WrongNumberOfTaxa(2, 3))
+        ));
+        assert!(matches!(
+            Alignment::from_phylip("2 3\nA ACG\nB ACGT\n"),
+            Err(AlignmentError::WrongSequenceLength(name, 4, 3)) if name == "B"
+        ));
+        assert!(matches!(
+            Alignment::from_phylip("2 4\nA AC\nB AC\n\nGT\n"),
+            Err(AlignmentError::WrongNumberOfTaxa(1, 2))
+        ));
+        assert!(matches!(
+            Alignment::from_phylip("2 2\nA AC\nA AC\n"),
+            Err(AlignmentError::DuplicateTaxon(name)) if name == "A"
+        ));
+    }
+
+    #[test]
+    fn annotate_tree_sequences() {
+        let aln = Alignment::from_phylip("4 3\nA ACG\nB ACC\nC TCG\nD TTG\n").unwrap();
+
+        let mut tree = Tree::from_newick("((A,B)E,(C,D)F)G;").unwrap();
+        tree.annotate_sequences(&aln).unwrap();
+        for leaf in tree.get_leaves() {
+            let node = tree.get(&leaf).unwrap();
+            let name = node.name.as_ref().unwrap();
+            assert_eq!(node.sequence.as_deref(), aln.get(name));
+        }
+        assert!(tree.get_by_name("E").unwrap().sequence.is_none());
+
+        let mut tree = Tree::from_newick("((A,B),(C,X));").unwrap();
+        assert!(matches!(
+            tree.annotate_sequences(&aln),
+            Err(TreeError::AlignmentError(AlignmentError::MissingTaxon(name))) if name == "X"
+        ));
+        assert!(tree.get_by_name("A").unwrap().sequence.is_none());
+
+        let mut tree = Tree::from_newick("((A,B),(C,));").unwrap();
+        assert!(matches!(
+            tree.annotate_sequences(&aln),
+            Err(TreeError::UnnamedLeaves)
+        ));
+    }
+
+    #[test]
+    fn fitch_parsimony_scores() {
+        let aln = Alignment::from_phylip("5 6\nA ACGTAA\nB ACGTTA\nC AGGACA\nD TGCACA\nE TGCATA\n")
+            .unwrap();
+
+        let mut tree = Tree::from_newick("(((A,B),C),(D,E));").unwrap();
+        tree.annotate_sequences(&aln).unwrap();
+        // Sites: 1 + 1 + 1 + 1 + 3 + 0
+        assert_eq!(tree.fitch_parsimony().unwrap(), 7);
+
+        // Rotating the tree does not change the score
+        let mut rotated = Tree::from_newick("((E,D),(C,(B,A)));").unwrap();
+        rotated.annotate_sequences(&aln).unwrap();
+        assert_eq!(rotated.fitch_parsimony().unwrap(), 7);
+
+        // A worse topology has a higher score
+        let mut other = Tree::from_newick("(((A,D),C),(B,E));").unwrap();
+        other.annotate_sequences(&aln).unwrap();
+        assert!(other.fitch_parsimony().unwrap() > 7);
+
+        // Multifurcations
+        let mut star = Tree::from_newick("(A,B,C,(D,E));").unwrap();
+        star.annotate_sequences(&aln).unwrap();
+        assert!(matches!(
+            star.fitch_parsimony(),
+            Err(TreeError::IsNotRooted)
+        ));
+        let mut star = Tree::from_newick("((A,B,C),(D,E));").unwrap();
+        star.annotate_sequences(&aln).unwrap();
+        assert_eq!(star.fitch_parsimony().unwrap(), 9);
+
+        // Internal node states explain the leaves with the minimum number of changes
+        let states = tree.fitch_ancestral_states().unwrap();
+        assert_eq!(states.len(), 4);
+        let mut changes = 0;
+        for node_id in tree.preorder(&tree.get_root().unwrap()).unwrap() {
+            let node = tree.get(&node_id).unwrap();
+            let Some(parent) = node.parent else {
+                continue;
+            };
+            let sequence = node.sequence.as_ref().unwrap_or_else(|| &states[&node_id]);
+            changes += zip_count(&states[&parent], sequence);
+        }
+        assert_eq!(changes, 7);
+    }
+
+    fn zip_count(seq1: &[char], seq2: &[char]) -> usize {
+        seq1.iter().zip(seq2).filter(|(a, b)| a != b).count()
+    }
+
+    #[test]
+    fn fitch_parsimony_errors() {
+        let mut tree = Tree::from_newick("((A,B),(C,D));").unwrap();
+        assert!(matches!(
+            tree.fitch_parsimony(),
+            Err(TreeError::MissingSequences)
+        ));
+
+        let aln = Alignment::from_phylip("4 2\nA AC\nB AC\nC GC\nD GT\n").unwrap();
+        tree.annotate_sequences(&aln).unwrap();
+        let d = tree.get_by_name("D").unwrap().id;
+        tree.get_mut(&d).unwrap().sequence = Some(vec!['A']);
+        assert!(matches!(
+            tree.fitch_parsimony(),
+            Err(TreeError::MissingSequences)
+        ));
+        assert!(matches!(
+            tree.fitch_ancestral_states(),
+            Err(TreeError::MissingSequences)
+        ));
+    }
+}
@@ -1,8 +1,9 @@
-use clap::{Parser, Subcommand};
+use clap::{Parser, Subcommand, ValueEnum};
 use clap_complete::Shell;
-use std::path::PathBuf;
+use std::{fmt::Display, path::PathBuf};
 
 use phylotree::distr::Distr;
+use phylotree::tree::{Tree, TreeError};
 use phylotree::TreeShape;
 
 const ABOUT: &str = "A Simple command line tool to manipulate phylogenetic trees";
@@ -41,8 +42,9 @@ pub enum Commands {
         #[arg(value_enum, short, long, default_value_t=TreeShape::Yule)]
         shape: TreeShape,
 
-        /// Distribution of branch lengths
-        #[arg(value_enum, short, long, default_value_t=Distr::Uniform)]
+        /// Distribution of branch lengths: uniform, gamma, exponential[:rate]
+        /// or lognormal[:mu,sigma] (e.g. exponential:0.5 or lognormal:-1,0.5)
+        #[arg(short, long, default_value_t=Distr::Uniform)]
         distribution: Distr,
     },
 
@@ -50,25 +52,32 @@ pub enum Commands {
     Stats {
         /// Input newick file of the tree
         trees: Vec<PathBuf>,
+        /// Output the statistics as a JSON array instead of a TSV table
+        #[cfg(feature = "json")]
+        #[arg(short, long)]
+        json: bool,
     },
 
     /// Compare phylogenetic trees to a reference
     ///
-    /// This will return:
+    /// This will return a TSV with the following columns:
+    ///  - the path of the reference tree
     ///  - the path of the compared tree
-    ///  - the number of bipartitions unique to the reference tree
-    ///  - the number of common bipartitions
-    ///  - the number of bipartitions unique to the compared tree
-    ///  - the Robinson-Foulds distance
-    ///  - the normalize Robinson-Foulds distance
-    ///  - the weighted Robinson-Foulds distance
-    ///  - the Khuner-Felsenstein branch-score
+    ///  - the distance metric
+    ///  - the distance between the compared tree and the reference
+    ///
+    /// Trees that do not have the same leaves as the reference are skipped with a warning.
     #[clap(verbatim_doc_comment)]
     Compare {
         /// Reference tree
-        reftree: PathBuf,
+        #[arg(short, long)]
+        reference: PathBuf,
         /// Tree(s) to compare to reference
-        tocompare: Vec<PathBuf>,
+        #[arg(short, long, num_args = 1.., required = true)]
+        trees: Vec<PathBuf>,
+        /// Distance metric(s) to compute
+        #[arg(value_enum, short, long, num_args = 1.., default_values_t = [Metric::Rf])]
+        metric: Vec<Metric>,
     },
     /// Output the phylogenetic distance matrix of the tree
     Matrix {
@@ -125,6 +134,22 @@ pub enum Commands {
         #[arg(short, long)]
         output: Option<PathBuf>,
     },
+    /// Keep only the given taxa in tree(s)
+    ///
+    /// If the input is a directory, all the `.nwk` and `.tree` files it
+    /// contains are pruned and saved to the output directory.
+    Prune {
+        /// The phylogenetic tree, or a directory of trees
+        input: PathBuf,
+        /// Names of the taxa to keep
+        keep: Vec<String>,
+        /// File with the names of the taxa to keep, one per line
+        #[arg(short, long)]
+        keep_file: Option<PathBuf>,
+        /// File (or directory if the input is a directory) to save the tree(s) to
+        #[arg(short, long)]
+        output: PathBuf,
+    },
     /// Remove or collapse branches corresponding to identical sequences in a reference alignment
     Deduplicate {
         /// The phylogenetic tree
@@ -181,3 +206,39 @@ pub enum Commands {
 }
 
 impl Commands {}
+
+/// Distance metrics between trees available in the `compare` command
+#[derive(ValueEnum, Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Metric {
+    /// Robinson-Foulds distance
+    Rf,
+    /// Weighted Robinson-Foulds distance
+    WeightedRf,
+    /// Khuner-Felsenstein branch-score distance
+    Kf,
+    /// Quartet distance
+    Quartet,
+}
+
+impl Metric {
+    /// Computes the distance between two trees with this metric
+    pub fn distance(&self, reference: &Tree, tree: &Tree) -> Result<f64, TreeError> {
+        match self {
+            Self::Rf => reference.robinson_foulds(tree).map(|d| d as f64),
+            Self::WeightedRf => reference.weighted_robinson_foulds(tree),
+            Self::Kf => reference.khuner_felsenstein(tree),
+            Self::Quartet => reference.quartet_distance(tree).map(|d| d as f64),
+        }
+    }
+}
+
+impl Display for Metric {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Rf => write!(f, "rf"),
+            Self::WeightedRf => write!(f, "weighted-rf"),
+            Self::Kf => write!(f, "kf"),
+            Self::Quartet => write!(f, "quartet"),
+        }
+    }
+}
@@ -70,6 +70,70 @@ fn print_stats(path: &Path, name: bool) {
     )
 }
 
+/// Statistics of a tree written by the `stats` command with the `--json` flag
+#[cfg(feature = "json")]
+#[derive(Serialize)]
+struct TreeStats {
+    path: String,
+    height: Option<phylotree::tree::EdgeLength>,
+    nodes: usize,
+    tips: usize,
+    rooted: bool,
+    binary: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    sackin: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    colless: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    diameter: Option<phylotree::tree::EdgeLength>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    n_cherries: Option<usize>,
+}
+
+/// Entry of the JSON output of the `stats` command, trees that could not be
+/// read are reported with the corresponding error message
+#[cfg(feature = "json")]
+#[derive(Serialize)]
+#[serde(untagged)]
+enum StatsEntry {
+    Stats(TreeStats),
+    Error { path: String, error: String },
+}
+
+#[cfg(feature = "json")]
+fn get_stats(path: &Path) -> Result<TreeStats, Box<dyn std::error::Error>> {
+    let tree = Tree::from_file(path)?;
+
+    Ok(TreeStats {
+        path: path.display().to_string(),
+        height: tree.height().ok(),
+        nodes: tree.size(),
+        tips: tree.n_leaves(),
+        rooted: tree.is_rooted()?,
+        binary: tree.is_binary()?,
+        sackin: tree.sackin().ok(),
+        colless: tree.colless().ok(),
+        diameter: tree.diameter().ok(),
+        n_cherries: tree.cherries().ok(),
+    })
+}
+
+#[cfg(feature = "json")]
+fn print_stats_json(paths: &[std::path::PathBuf]) {
+    let entries: Vec<_> = paths
+        .iter()
+        .map(|path| match get_stats(path) {
+            Ok(stats) => StatsEntry::Stats(stats),
+            Err(error) => StatsEntry::Error {
+                path: path.display().to_string(),
+                error: error.to_string(),
+            },
+        })
+        .collect();
+
+    println!("{}", serde_json::to_string_pretty(&entries).unwrap())
+}
+
 fn main() {
     match cli::Args::parse().command {
         cli::Commands::Generate {
@@ -115,41 +179,54 @@ fn main() {
                 }
             }
         }
-        cli::Commands::Stats { trees } => {
+        cli::Commands::Stats {
+            trees,
+            #[cfg(feature = "json")]
+            json,
+        } => {
+            #[cfg(feature = "json")]
+            if json {
+                print_stats_json(&trees);
+                return;
+            }
+
             let print_name = trees.len() > 1;
             print_stats_header(print_name);
             for tree in trees {
                 print_stats(&tree, print_name)
             }
         }
-        cli::Commands::Compare { reftree, tocompare } => {
-            // Read reference tree
-            let reftree = Tree::from_file(&reftree).unwrap();
-            let ref_parts = reftree.get_partitions().unwrap();
-
-            // Print header
-            println!("tree\tpath\treference\tcommon\tcompared\trf\tnorm_rf\trf_w\tbranch_score");
-            for (i, cmp_path) in tocompare.into_iter().enumerate() {
-                let compare = Tree::from_file(&cmp_path).unwrap();
-
-                let other_parts = compare.get_partitions().unwrap();
-
-                let common = ref_parts.intersection(&other_parts).count();
+        cli::Commands::Compare {
+            reference,
+            trees,
+            metric,
+        } => {
+            let reftree = Tree::from_file(&reference).unwrap();
 
-                let stats = reftree.compare_topologies(&compare).unwrap();
+            println!("reference\tcomparison\tmetric\tdistance");
+            for cmp_path in trees {
+                let compare = Tree::from_file(&cmp_path).unwrap();
 
-                println!(
-                    "{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}",
-                    i,
-                    cmp_path.to_str().unwrap_or("-"),
-                    ref_parts.len() - common,
-                    common,
-                    other_parts.len() - common,
-                    stats.rf,
-                    stats.norm_rf,
-                    stats.weighted_rf,
-                    stats.branch_score,
-                )
+                for metric in metric.iter() {
+                    match metric.distance(&reftree, &compare) {
+                        Ok(distance) => println!(
+                            "{}\t{}\t{metric}\t{distance}",
+                            reference.display(),
+                            cmp_path.display(),
+                        ),
+                        Err(TreeError::DifferentTipIndices) => {
+                            eprintln!(
+                                "Warning: {} does not have the same leaves as the reference tree, skipping",
+                                cmp_path.display()
+                            );
+                            break;
+                        }
+                        Err(err) => eprintln!(
+                            "Warning: could not compute the {metric} distance for {}: {err}",
+                            cmp_path.display()
+                        ),
+                    }
+                }
             }
         }
         cli::Commands::Matrix {
@@ -226,6 +303,56 @@ fn main() {
                 println!("{}", tree.to_newick().unwrap())
             }
         }
+        cli::Commands::Prune {
+            input,
+            mut keep,
+            keep_file,
+            output,
+        } => {
+            if let Some(path) = keep_file {
+                let names = fs::read_to_string(path).unwrap();
+                keep.extend(
+                    names
+                        .lines()
+                        .map(str::trim)
+                        .filter(|name| !name.is_empty())
+                        .map(String::from),
+                );
+            }
+            let keep: Vec<_> = keep.iter().map(String::as_str).collect();
+
+            let prune = |input: &Path, output: &Path| -> Result<(), Box<dyn std::error::Error>> {
+                let mut tree = Tree::from_file(input)?;
+                tree.prune_to_taxa(&keep)?;
+                tree.to_file(output)?;
+                Ok(())
+            };
+
+            if input.is_dir() {
+                fs::create_dir_all(&output).unwrap();
+
+                let paths: Vec<_> = fs::read_dir(&input)
+                    .unwrap()
+                    .map(|entry| entry.unwrap().path())
+                    .filter(|path| {
+                        matches!(
+                            path.extension().and_then(|ext| ext.to_str()),
+                            Some("nwk" | "tree")
+                        )
+                    })
+                    .sorted()
+                    .collect();
+
+                for path in paths.into_iter().progress() {
+                    let pruned = output.join(path.file_name().unwrap());
+                    if let Err(err) = prune(&path, &pruned) {
+                        eprintln!("Warning: could not prune {}: {err}", path.display());
+                    }
+                }
+            } else {
+                prune(&input, &output).unwrap();
+            }
+        }
         cli::Commands::Distance { tree, tips, output } => {
             let tree = Tree::from_file(&tree).unwrap();
             let mut writer = BufWriter::new(match output {
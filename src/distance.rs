@@ -5,12 +5,15 @@ use std::{
     collections::{HashMap, HashSet},
     fmt::{Debug, Display},
     fs,
+    io::{Read, Write},
+    iter::zip,
     path::Path,
     str::FromStr,
 };
 
 use itertools::Itertools;
 use num_traits::{zero, Float, Zero};
+use rand::{seq::SliceRandom, Rng};
 use thiserror::Error;
 use trait_set::trait_set;
 
@@ -41,12 +44,23 @@ pub enum MatrixError {
     /// We are trying to access a taxon that does not exist
     #[error("Missing taxon {0}")]
     MissingTaxon(String),
+    /// A taxon is present several times
+    #[error("Duplicate taxon {0}")]
+    DuplicateTaxon(String),
     /// We are trying to get the pair index for the same leaf
     #[error("Pair index only exists for pairs of different leaves")]
     IndexError,
     /// We are trying to set a non zero distance for an identical taxa pair
     #[error("Identical taxa cannot have a non zero distance")]
     NonZeroIdenticalDistance,
+    /// The matrix does not have enough taxa for the requested computation
+    #[error("At least {needed} taxa are needed, the matrix has {n_taxa}")]
+    NotEnoughTaxa {
+        /// Minimum number of taxa needed
+        needed: usize,
+        /// Number of taxa in the matrix
+        n_taxa: usize,
+    },
     /// We are trying to add a different number of taxa than what we alloted
     #[error("Trying to add {n_taxa} taxa to a matrix of size {size}")]
     SizeError {
@@ -90,6 +104,9 @@ where
     /// The square phylip matrix is not symmetric
     #[error("Matrix is not symetric.")]
     NonSymmetricMat,
+    /// The name of a row does not match the name of the corresponding column
+    #[error("Row name {0} does not match column name {1}")]
+    TaxaMismatch(String, String),
     /// There was a [`MatrixError`] when create the distance matrix object
     #[error("Error creating matrix.")]
     MatrixError(#[from] MatrixError),
@@ -98,7 +115,29 @@ where
     IoError(#[from] std::io::Error),
 }
 
+/// Result of a Mantel test computed with [`DistanceMatrix::mantel_test()`]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MantelResult {
+    /// Pearson correlation between the distances of both matrices
+    pub r: f64,
+    /// One-tailed p-value of the correlation, estimated by permutations
+    pub p_value: f64,
+}
+
+/// Result of a principal coordinates analysis computed with [`DistanceMatrix::pcoa()`]
+#[cfg(feature = "pcoa")]
+#[derive(Debug, Clone, PartialEq)]
+pub struct PcoaResult {
+    /// Coordinates of each taxon on the principal axes, in the order of `taxa`
+    pub coordinates: Vec<Vec<f64>>,
+    /// Eigenvalues of the principal axes, in decreasing order
+    pub eigenvalues: Vec<f64>,
+    /// Identifiers of the taxa
+    pub taxa: Vec<String>,
+}
+
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 /// A phylogenetic distance matrix
 pub struct DistanceMatrix<T> {
     /// Number of taxa in the matrix
@@ -247,6 +286,53 @@ where
         ))
     }
 
+    /// Returns the names of the taxa in the row/column order of the matrix
+    pub fn taxa_names(&self) -> Vec<&str> {
+        self.taxa.iter().map(|name| name.as_str()).collect()
+    }
+
+    /// Extracts the sub-matrix of distances between a set of taxa, in the given order.
+    /// Returns [`MatrixError::MissingTaxon`] if one of the taxa is not in the matrix,
+    /// and [`MatrixError::SizeError`] if no taxa are given.
+    /// ```
+    /// use phylotree::distance::DistanceMatrix;
+    ///
+    /// let matrix = DistanceMatrix::new(vec!["A".into(), "B".into(), "C".into()], &[1., 2., 3.]);
+    /// let sub = matrix.subset(&["C", "A"]).unwrap();
+    ///
+    /// assert_eq!(sub.taxa_names(), vec!["C", "A"]);
+    /// assert_eq!(sub.get("A", "C").unwrap(), &2.0);
+    /// assert!(matrix.subset(&["A", "D"]).is_err());
+    /// ```
+    pub fn subset(&self, taxa: &[&str]) -> Result<Self, MatrixError> {
+        if taxa.is_empty() {
+            return Err(MatrixError::SizeError {
+                size: self.size,
+                n_taxa: 0,
+            });
+        }
+
+        let mut seen = HashSet::new();
+        let indices = taxa
+            .iter()
+            .map(|name| {
+                if !seen.insert(name) {
+                    return Err(MatrixError::DuplicateTaxon(name.to_string()));
+                }
+                self.get_taxa_index(name)
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let mut matrix = Vec::with_capacity(taxa.len() * taxa.len().saturating_sub(1) / 2);
+        for (k, i) in indices.iter().enumerate() {
+            for j in indices.iter().take(k) {
+                matrix.push(self.matrix[self.tril_to_vec_index(*i, *j)?]);
+            }
+        }
+
+        Self::from_precomputed(taxa.iter().map(|name| name.to_string()).collect(), matrix)
+    }
+
     /// Outputs a matrix as a phylip formatted string
     pub fn to_phylip(&self, square: bool) -> Result<String, MatrixError> {
         let body = self
@@ -387,6 +473,118 @@ where
         Ok(matrix)
     }
 
+    /// Writes the matrix as a square CSV table with a header row of taxa names
+    /// and a leading column of row names.
+    /// ```
+    /// use phylotree::distance::DistanceMatrix;
+    ///
+    /// let matrix = DistanceMatrix::new(vec!["A".into(), "B".into(), "C".into()], &[1., 2., 3.]);
+    /// let mut csv = vec![];
+    /// matrix.to_csv(&mut csv).unwrap();
+    ///
+    /// assert_eq!(String::from_utf8(csv).unwrap(), ",A,B,C\nA,0,1,2\nB,1,0,3\nC,2,3,0\n");
+    /// ```
+    pub fn to_csv(&self, mut writer: impl Write) -> Result<(), MatrixError> {
+        writeln!(writer, ",{}", self.taxa.join(","))?;
+        for (i, name) in self.taxa.iter().enumerate() {
+            let row = (0..self.size)
+                .map(|j| {
+                    if i == j {
+                        self.zero
+                    } else {
+                        self.matrix[self.tril_to_vec_index(i, j).unwrap()]
+                    }
+                })
+                .join(",");
+            writeln!(writer, "{name},{row}")?;
+        }
+
+        Ok(())
+    }
+
+    /// Reads a matrix from a CSV table with a header row of taxa names and a leading
+    /// column of row names, in the same order. The table can be square, or only contain
+    /// the upper *(or lower)* triangle with the other cells left empty, missing
+    /// distances are filled in by symmetry. Diagonal cells must be empty or 0.
+    /// ```
+    /// use phylotree::distance::DistanceMatrix;
+    ///
+    /// let square = ",A,B,C\nA,0,1,2\nB,1,0,3\nC,2,3,0\n";
+    /// let upper = ",A,B,C\nA,0,1,2\nB,,0,3\nC,,,0\n";
+    ///
+    /// let square = DistanceMatrix::<f64>::from_csv(square.as_bytes()).unwrap();
+    /// let upper = DistanceMatrix::<f64>::from_csv(upper.as_bytes()).unwrap();
+    ///
+    /// assert_eq!(square.get("C", "B").unwrap(), &3.0);
+    /// assert_eq!(upper.get("C", "B").unwrap(), &3.0);
+    /// ```
+    pub fn from_csv(mut reader: impl Read) -> Result<Self, PhylipParseError<T>> {
+        let mut csv = String::new();
+        reader.read_to_string(&mut csv)?;
+        let unquote = |field: &str| field.trim().trim_matches('"').to_string();
+
+        let mut lines = csv.lines().filter(|line| !line.trim().is_empty());
+        let header = lines.next().ok_or(PhylipParseError::EmptyMatrixFile)?;
+        let taxa: Vec<String> = header.split(',').skip(1).map(unquote).collect();
+        let size = taxa.len();
+        if size == 0 {
+            return Err(PhylipParseError::EmptyMatrixFile);
+        }
+
+        let mut rows: Vec<Vec<Option<T>>> = Vec::with_capacity(size);
+        for (i, line) in lines.enumerate() {
+            let mut fields = line.split(',');
+            let name = fields.next().map(unquote).unwrap_or_default();
+            if i >= size {
+                return Err(PhylipParseError::SizeAndRowsMismatch(i + 1, size));
+            }
+            if name != taxa[i] {
+                return Err(PhylipParseError::TaxaMismatch(name, taxa[i].clone()));
+            }
+
+            let mut row = fields
+                .map(|d| match d.trim() {
+                    "" => Ok(None),
+                    d => d
+                        .parse()
+                        .map(Some)
+                        .map_err(|_| PhylipParseError::DistParseError),
+                })
+                .collect::<Result<Vec<_>, _>>()?;
+            if row.len() > size {
+                return Err(PhylipParseError::SizeAndRowsMismatch(row.len(), size));
+            }
+            row.resize(size, None);
+            if row[i].is_some_and(|d| d != zero()) {
+                return Err(PhylipParseError::NonZeroDiagonalValue(name));
+            }
+
+            rows.push(row);
+        }
+
+        if rows.len() != size {
+            return Err(PhylipParseError::SizeAndRowsMismatch(rows.len(), size));
+        }
+
+        let mut matrix = Self::new_with_size(size);
+        matrix.set_taxa(taxa)?;
+        for (i, row) in rows.iter().enumerate() {
+            for (j, other) in rows.iter().enumerate().skip(i + 1) {
+                let dist = match (row[j], other[i]) {
+                    (Some(d1), Some(d2)) if d1 != d2 => {
+                        return Err(PhylipParseError::NonSymmetric(d1, d2))
+                    }
+                    (Some(d), _) | (_, Some(d)) => d,
+                    (None, None) => return Err(PhylipParseError::MissingDistance(i + 1)),
+                };
+                let idx = matrix.tril_to_vec_index(i, j)?;
+                matrix.matrix[idx] = dist;
+            }
+        }
+
+        Ok(matrix)
+    }
+
     /// Find smallest distance in the distance matrix as well as the
     /// corresponding index
     pub fn min(&self) -> Option<((usize, usize), T)> {
@@ -553,11 +751,281 @@ where
 
     /// Build a [`Tree`] from the distance matrix using the
     /// [neighbor joining](https://en.wikipedia.org/wiki/Neighbor_joining) algorithm
+    /// The resulting tree is unrooted, *i.e.* its virtual root has 3 children.
     pub fn neighbor_joining(&self) -> Result<Tree, MatrixError> {
-        todo!()
+        if self.size < 2 {
+            return Err(MatrixError::IndexError);
+        }
+
+        // Setup dense working matrix
+        let mut dm = vec![vec![0.; self.size]; self.size];
+        for ((i, j), d) in self.indexed_iter() {
+            let d = d.to_f64().unwrap();
+            if d.is_nan() {
+                return Err(MatrixError::MissingDistance(
+                    self.taxa[i].clone(),
+                    self.taxa[j].clone(),
+                ));
+            }
+            dm[i][j] = d;
+            dm[j][i] = d;
+        }
+        let mut active = (0..self.size).collect_vec();
+
+        // Build starting star tree
+        let mut tree = Tree::new();
+        let virt_root = tree.add(Node::new());
+        let mut node_ids = self
+            .taxa
+            .iter()
+            .map(|n| tree.add_child(Node::new_named(n), virt_root, None).unwrap())
+            .collect_vec();
+
+        while active.len() > 3 {
+            let n = active.len() as f64;
+            let mut r = vec![0.; self.size];
+            for &i in active.iter() {
+                r[i] = active.iter().map(|&j| dm[i][j]).sum();
+            }
+
+            // Find the pair minimizing the Q criterion
+            let (a, b) = active
+                .iter()
+                .tuple_combinations()
+                .map(|(&i, &j)| ((i, j), (n - 2.) * dm[i][j] - r[i] - r[j]))
+                .min_by(|(_, q1), (_, q2)| q1.partial_cmp(q2).unwrap())
+                .map(|(pair, _)| pair)
+                .ok_or(MatrixError::IndexError)?;
+
+            let d_au = dm[a][b] / 2. + (r[a] - r[b]) / (2. * (n - 2.));
+            let d_bu = dm[a][b] - d_au;
+
+            // Merge nodes a and b into new parent u
+            let u_node = tree
+                .merge_children(
+                    &node_ids[a],
+                    &node_ids[b],
+                    Some(d_au),
+                    Some(d_bu),
+                    None,
+                    None,
+                )
+                .unwrap();
+
+            // Replace a with new node u and remove b
+            for &x in active.iter().filter(|&&x| x != a && x != b) {
+                let d_ux = (dm[a][x] + dm[b][x] - dm[a][b]) / 2.;
+                dm[a][x] = d_ux;
+                dm[x][a] = d_ux;
+            }
+            node_ids[a] = u_node;
+            active.retain(|&x| x != b);
+        }
+
+        // Join the remaining nodes to the virtual root
+        let edges = match active[..] {
+            [a, b] => vec![(a, dm[a][b] / 2.), (b, dm[a][b] / 2.)],
+            [a, b, c] => vec![
+                (a, (dm[a][b] + dm[a][c] - dm[b][c]) / 2.),
+                (b, (dm[a][b] + dm[b][c] - dm[a][c]) / 2.),
+                (c, (dm[a][c] + dm[b][c] - dm[a][b]) / 2.),
+            ],
+            _ => return Err(MatrixError::IndexError),
+        };
+
+        for (i, d) in edges {
+            let id = node_ids[i];
+            tree.get_mut(&virt_root)
+                .unwrap()
+                .set_child_edge(&id, Some(d));
+            tree.get_mut(&id).unwrap().parent_edge = Some(d);
+        }
+
+        Ok(tree)
     }
 }
 
+/// Statistical methods
+impl<T> DistanceMatrix<T>
+where
+    T: PairwiseDist,
+{
+    // Dense copy of the matrix with rows and columns in the given taxa order
+    fn to_dense(&self, taxa: &[String]) -> Result<Vec<Vec<f64>>, MatrixError> {
+        let indices: Vec<_> = taxa
+            .iter()
+            .map(|taxon| self.get_taxa_index(taxon))
+            .collect::<Result<_, _>>()?;
+
+        let mut dense = vec![vec![0.; taxa.len()]; taxa.len()];
+        for (i, &x) in indices.iter().enumerate() {
+            for (j, &y) in indices.iter().enumerate().skip(i + 1) {
+                let d = self.matrix[self.tril_to_vec_index(x, y)?].to_f64().unwrap();
+                dense[i][j] = d;
+                dense[j][i] = d;
+            }
+        }
+
+        Ok(dense)
+    }
+
+    /// Computes the [Mantel test](https://en.wikipedia.org/wiki/Mantel_test) between this
+    /// matrix and `other`, which must contain the same taxa *(in any order)*. The statistic
+    /// is the Pearson correlation between the pairwise distances of both matrices. The
+    /// p-value is the proportion of the `n_permutations` random permutations of the taxa
+    /// of `other`, plus the observed one, that yield a correlation at least as high as the
+    /// observed one. If the distances of one matrix are all equal, the correlation is
+    /// undefined and `r` is `NaN`.
+    /// ```
+    /// use phylotree::distance::DistanceMatrix;
+    /// use rand::{rngs::StdRng, SeedableRng};
+    ///
+    /// let taxa: Vec<_> = ["A", "B", "C", "D"].iter().map(|t| t.to_string()).collect();
+    /// let dm1 = DistanceMatrix::new(taxa.clone(), &[1., 2., 3., 4., 5., 6.]);
+    /// let dm2 = DistanceMatrix::new(taxa, &[2., 4., 6., 8., 10., 12.]);
+    ///
+    /// let mut rng = StdRng::seed_from_u64(42);
+    /// let result = dm1.mantel_test(&dm2, 99, &mut rng).unwrap();
+    ///
+    /// assert!((result.r - 1.0).abs() < 1e-10);
+    /// assert!(result.p_value > 0.0 && result.p_value <= 1.0);
+    /// ```
+    pub fn mantel_test(
+        &self,
+        other: &Self,
+        n_permutations: usize,
+        rng: &mut impl Rng,
+    ) -> Result<MantelResult, MatrixError> {
+        if self.size < 3 {
+            return Err(MatrixError::NotEnoughTaxa {
+                needed: 3,
+                n_taxa: self.size,
+            });
+        }
+        if other.size != self.size {
+            return Err(MatrixError::SizeError {
+                size: self.size,
+                n_taxa: other.size,
+            });
+        }
+
+        let x = self.to_dense(&self.taxa)?;
+        let y = other.to_dense(&self.taxa)?;
+
+        let correlation = |order: &[usize]| {
+            let pairs = (0..self.size).tuple_combinations();
+            let (xs, ys): (Vec<_>, Vec<_>) =
+                pairs.map(|(i, j)| (x[i][j], y[order[i]][order[j]])).unzip();
+            pearson_correlation(&xs, &ys)
+        };
+
+        let mut order = (0..self.size).collect_vec();
+        let r = correlation(&order);
+
+        let mut n_greater = 0;
+        for _ in 0..n_permutations {
+            order.shuffle(rng);
+            if correlation(&order) >= r {
+                n_greater += 1;
+            }
+        }
+
+        Ok(MantelResult {
+            r,
+            p_value: (n_greater + 1) as f64 / (n_permutations + 1) as f64,
+        })
+    }
+}
+
+/// Ordination methods
+#[cfg(feature = "pcoa")]
+impl<T> DistanceMatrix<T>
+where
+    T: PairwiseDist,
+{
+    /// Computes Gower's [principal coordinates analysis](https://en.wikipedia.org/wiki/Multidimensional_scaling#Classical_multidimensional_scaling)
+    /// of the matrix and returns the coordinates of the taxa on the first `n_components`
+    /// principal axes. The coordinates on an axis are the corresponding eigenvector of the
+    /// double-centered matrix, scaled by the square root of its eigenvalue. Axes with a
+    /// negative eigenvalue, which occur with non euclidean distances, have null coordinates.
+    /// The sign of each axis is arbitrary.
+    ///
+    /// *N.B.: this method is only available with the `pcoa` feature.*
+    /// ```
+    /// use phylotree::distance::DistanceMatrix;
+    ///
+    /// // Taxa along a line
+    /// let taxa: Vec<_> = ["A", "B", "C"].iter().map(|t| t.to_string()).collect();
+    /// let dm = DistanceMatrix::new(taxa, &[1., 3., 2.]);
+    ///
+    /// let pcoa = dm.pcoa(1).unwrap();
+    /// let axis: Vec<_> = pcoa.coordinates.iter().map(|c| c[0]).collect();
+    ///
+    /// assert!(((axis[1] - axis[0]).abs() - 1.).abs() < 1e-10);
+    /// assert!(((axis[2] - axis[0]).abs() - 3.).abs() < 1e-10);
+    /// assert!((pcoa.eigenvalues[0] - 14. / 3.).abs() < 1e-10);
+    /// ```
+    pub fn pcoa(&self, n_components: usize) -> Result<PcoaResult, MatrixError> {
+        if n_components > self.size {
+            return Err(MatrixError::NotEnoughTaxa {
+                needed: n_components,
+                n_taxa: self.size,
+            });
+        }
+
+        let n = self.size;
+        let dense = self.to_dense(&self.taxa)?;
+
+        // Double-centering of the squared distances
+        let a: Vec<Vec<f64>> = dense
+            .iter()
+            .map(|row| row.iter().map(|d| -0.5 * d * d).collect())
+            .collect();
+        let row_means: Vec<f64> = a
+            .iter()
+            .map(|row| row.iter().sum::<f64>() / n as f64)
+            .collect();
+        let mean = row_means.iter().sum::<f64>() / n as f64;
+        let b =
+            nalgebra::DMatrix::from_fn(n, n, |i, j| a[i][j] - row_means[i] - row_means[j] + mean);
+
+        let eigen = b.symmetric_eigen();
+        let mut axes = (0..n).collect_vec();
+        axes.sort_by(|&i, &j| eigen.eigenvalues[j].total_cmp(&eigen.eigenvalues[i]));
+        axes.truncate(n_components);
+
+        let coordinates = (0..n)
+            .map(|i| {
+                axes.iter()
+                    .map(|&k| eigen.eigenvectors[(i, k)] * eigen.eigenvalues[k].max(0.).sqrt())
+                    .collect()
+            })
+            .collect();
+
+        Ok(PcoaResult {
+            coordinates,
+            eigenvalues: axes.iter().map(|&k| eigen.eigenvalues[k]).collect(),
+            taxa: self.taxa.clone(),
+        })
+    }
+}
+
+// Pearson correlation coefficient between two samples of the same size
+fn pearson_correlation(xs: &[f64], ys: &[f64]) -> f64 {
+    let n = xs.len() as f64;
+    let mean_x = xs.iter().sum::<f64>() / n;
+    let mean_y = ys.iter().sum::<f64>() / n;
+
+    let (mut cov, mut var_x, mut var_y) = (0., 0., 0.);
+    for (x, y) in zip(xs, ys) {
+        cov += (x - mean_x) * (y - mean_y);
+        var_x += (x - mean_x).powi(2);
+        var_y += (y - mean_y).powi(2);
+    }
+
+    cov / (var_x * var_y).sqrt()
+}
+
 ////////////////////////
 // INDEXING UTILITIES //
 ////////////////////////
@@ -668,6 +1136,92 @@ s5    5  10  15
         assert_eq!(TRIANGLE, matrix.to_phylip(false).unwrap());
     }
 
+    #[test]
+    fn matrix_subset() {
+        let matrix = build_matrix();
+        assert_eq!(matrix.taxa_names(), vec!["s1", "s2", "s3", "s5"]);
+
+        let sub = matrix.subset(&["s5", "s2", "s1"]).unwrap();
+        assert_eq!(sub.size, 3);
+        assert_eq!(sub.taxa_names(), vec!["s5", "s2", "s1"]);
+        for pair in sub.taxa_names().iter().combinations(2) {
+            assert_eq!(
+                sub.get(pair[0], pair[1]).unwrap(),
+                matrix.get(pair[0], pair[1]).unwrap()
+            );
+        }
+
+        let single = matrix.subset(&["s3"]).unwrap();
+        assert_eq!(single.taxa_names(), vec!["s3"]);
+        assert_eq!(single.iter().count(), 0);
+
+        assert!(matches!(
+            matrix.subset(&["s1", "s4"]),
+            Err(MatrixError::MissingTaxon(name)) if name == "s4"
+        ));
+        assert!(matches!(
+            matrix.subset(&["s1", "s1"]),
+            Err(MatrixError::DuplicateTaxon(_))
+        ));
+        assert!(matches!(
+            matrix.subset(&[]),
+            Err(MatrixError::SizeError { .. })
+        ));
+    }
+
+    #[test]
+    fn csv_roundtrip() {
+        let matrix = build_matrix();
+        let mut csv = vec![];
+        matrix.to_csv(&mut csv).unwrap();
+        let csv = String::from_utf8(csv).unwrap();
+        assert_eq!(
+            csv,
+            ",s1,s2,s3,s5\ns1,0,2,3,5\ns2,2,0,6,10\ns3,3,6,0,15\ns5,5,10,15,0\n"
+        );
+
+        let read = DistanceMatrix::<f32>::from_csv(csv.as_bytes()).unwrap();
+        assert_eq!(read.taxa, matrix.taxa);
+        assert_eq!(read.matrix, matrix.matrix);
+
+        let upper = "\"\",\"s1\",\"s2\",\"s3\",\"s5\"\n\"s1\",0,2,3,5\n\"s2\",,0,6,10\n\"s3\",,,0,15\n\"s5\"\n";
+        let lower = ",s1,s2,s3,s5\ns1\ns2,2\ns3,3,6\ns5,5,10,15\n";
+        for csv in [upper, lower] {
+            let read = DistanceMatrix::<f32>::from_csv(csv.as_bytes()).unwrap();
+            assert_eq!(read.taxa, matrix.taxa);
+            assert_eq!(read.matrix, matrix.matrix);
+        }
+    }
+
+    #[test]
+    fn csv_errors() {
+        let cases = [
+            "",
+            ",s1,s2\ns1,0,1\ns3,1,0\n",
+            ",s1,s2\ns1,0,1\ns2,2,0\n",
+            ",s1,s2\ns1,1,1\ns2,1,0\n",
+            ",s1,s2\ns1,0,\ns2,,0\n",
+            ",s1,s2\ns1,0,a\ns2,,0\n",
+            ",s1,s2\ns1,0,1\n",
+            ",s1,s2\ns1,0,1,4\ns2,1,0\n",
+        ];
+        for csv in cases {
+            let res = DistanceMatrix::<f64>::from_csv(csv.as_bytes());
+            match (csv, res) {
+                ("", Err(PhylipParseError::EmptyMatrixFile)) => {}
+                (_, Err(PhylipParseError::TaxaMismatch(row, col))) => {
+                    assert_eq!((row.as_str(), col.as_str()), ("s3", "s2"))
+                }
+                (_, Err(PhylipParseError::NonSymmetric(1.0, 2.0))) => {}
+                (_, Err(PhylipParseError::NonZeroDiagonalValue(name))) => assert_eq!(name, "s1"),
+                (_, Err(PhylipParseError::MissingDistance(1))) => {}
+                (_, Err(PhylipParseError::DistParseError)) => {}
+                (_, Err(PhylipParseError::SizeAndRowsMismatch(_, 2))) => {}
+                (csv, res) => panic!("Unexpected result for {csv:?}: {res:?}"),
+            }
+        }
+    }
+
     #[test]
     fn from_phylip() -> Result<(), PhylipParseError<f32>> {
         let build: DistanceMatrix<f32> = DistanceMatrix::from_phylip_strict(SQUARE, true)?;
@@ -885,6 +1439,93 @@ s5    5  10  15  0
         assert_eq!(dm.max(), max);
     }
 
+    #[cfg(feature = "serde")]
+    #[test]
+    fn serde_roundtrip() {
+        let dm = build_matrix();
+
+        let json = serde_json::to_string(&dm).unwrap();
+        let from_json: DistanceMatrix<f64> = serde_json::from_str(&json).unwrap();
+        let toml = toml::to_string(&dm).unwrap();
+        let from_toml: DistanceMatrix<f64> = toml::from_str(&toml).unwrap();
+
+        for copy in [from_json, from_toml] {
+            assert_eq!(copy.taxa, dm.taxa);
+            assert_eq!(copy.to_phylip(true).unwrap(), dm.to_phylip(true).unwrap());
+        }
+    }
+
+    #[cfg(feature = "pcoa")]
+    #[test]
+    fn pcoa() {
+        // Corners of a 3x4 rectangle, so distances are euclidean in 2 dimensions
+        let p_str = "4
+a    0  3  5  4
+b    3  0  4  5
+c    5  4  0  3
+d    4  5  3  0
+";
+        let dm = DistanceMatrix::<f64>::from_phylip_strict(p_str, true).unwrap();
+        let pcoa = dm.pcoa(4).unwrap();
+
+        assert_eq!(pcoa.taxa, dm.taxa);
+        assert_eq!(pcoa.coordinates.len(), 4);
+        assert!(pcoa.coordinates.iter().all(|c| c.len() == 4));
+        assert!(pcoa.eigenvalues.windows(2).all(|w| w[0] >= w[1]));
+        assert!((pcoa.eigenvalues[0] - 16.).abs() < 1e-10);
+        assert!((pcoa.eigenvalues[1] - 9.).abs() < 1e-10);
+        assert!(pcoa.eigenvalues[2..].iter().all(|l| l.abs() < 1e-10));
+
+        // Distances between taxa are recovered from their coordinates
+        for ((i, j), d) in dm.indexed_iter() {
+            let (x, y) = (&pcoa.coordinates[i], &pcoa.coordinates[j]);
+            let dist = zip(x, y).map(|(a, b)| (a - b).powi(2)).sum::<f64>().sqrt();
+            assert!((dist - d).abs() < 1e-10);
+        }
+
+        // Coordinates are centered
+        for k in 0..2 {
+            let sum: f64 = pcoa.coordinates.iter().map(|c| c[k]).sum();
+            assert!(sum.abs() < 1e-10);
+        }
+
+        assert_eq!(dm.pcoa(2).unwrap().eigenvalues, pcoa.eigenvalues[..2]);
+        assert!(dm.pcoa(0).unwrap().coordinates.iter().all(|c| c.is_empty()));
+        assert!(matches!(
+            dm.pcoa(5),
+            Err(MatrixError::NotEnoughTaxa {
+                needed: 5,
+                n_taxa: 4
+            })
+        ));
+    }
+
+    #[test]
+    fn build_nj() {
+        // Example from https://en.wikipedia.org/wiki/Neighbor_joining
+        let p_str = r"5
+a    0  5  9  9  8
+b    5  0  10 10 9
+c    9  10 0  8  7
+d    9  10 8  0  3
+e    8  9  7  3  0
+";
+        let expected = Tree::from_newick("(c:2,(a:2,b:3):3,(d:2,e:1):2);").unwrap();
+
+        let dm = DistanceMatrix::<f64>::from_phylip_strict(p_str, true).unwrap();
+        let built = dm.neighbor_joining().unwrap();
+
+        assert_eq!(expected.robinson_foulds(&built).unwrap(), 0);
+        assert!(!built.is_rooted().unwrap());
+
+        // The distances of the tree are additive so they should be recovered exactly
+        let built_dm = built.distance_matrix().unwrap();
+        for ((i, j), d) in dm.indexed_iter() {
+            let (a, b) = (&dm.taxa[i], &dm.taxa[j]);
+            assert!((built_dm.get(a, b).unwrap() - d).abs() < 1e-10);
+        }
+    }
+
     #[test]
     fn build_upgma() {
         // Expected tree
@@ -908,4 +1549,70 @@ e    23 21 39 43 0
         assert_eq!(wrf, 0.0);
         assert_eq!(rf, 0,);
     }
+
+    #[test]
+    fn mantel_test() {
+        use rand::{rngs::StdRng, SeedableRng};
+
+        let p_str = "6
+a    0  5  9  9  8  12
+b    5  0  10 10 9  13
+c    9  10 0  8  7  11
+d    9  10 8  0  3  6
+e    8  9  7  3  0  5
+f    12 13 11 6  5  0
+";
+        // Same distances with taxa in a different order
+        let reordered = "6
+f    0  6  11 12 5  13
+d    6  0  8  9  3  10
+c    11 8  0  9  7  10
+a    12 9  9  0  8  5
+e    5  3  7  8  0  9
+b    13 10 10 5  9  0
+";
+        let dm = DistanceMatrix::<f64>::from_phylip_strict(p_str, true).unwrap();
+        let other = DistanceMatrix::<f64>::from_phylip_strict(reordered, true).unwrap();
+        let mut rng = StdRng::seed_from_u64(1234);
+
+        let result = dm.mantel_test(&other, 999, &mut rng).unwrap();
+        assert!((result.r - 1.0).abs() < 1e-10);
+        assert!(result.p_value < 0.05);
+
+        // Negated distances are perfectly anti-correlated
+        let mut negated = dm.clone();
+        negated.matrix.iter_mut().for_each(|d| *d = -*d);
+        let result = dm.mantel_test(&negated, 999, &mut rng).unwrap();
+        assert!((result.r + 1.0).abs() < 1e-10);
+        assert!(result.p_value > 0.95);
+
+        // No permutations
+        let result = dm.mantel_test(&other, 0, &mut rng).unwrap();
+        assert_eq!(result.p_value, 1.0);
+
+        let small = dm.subset(&["a", "b"]).unwrap();
+        assert!(matches!(
+            small.mantel_test(&small, 10, &mut rng),
+            Err(MatrixError::NotEnoughTaxa {
+                needed: 3,
+                n_taxa: 2
+            })
+        ));
+        let subset = dm.subset(&["a", "b", "c", "d", "e"]).unwrap();
+        assert!(matches!(
+            dm.mantel_test(&subset, 10, &mut rng),
+            Err(MatrixError::SizeError { .. })
+        ));
+        let renamed = DistanceMatrix::new(
+            ["a", "b", "c", "d", "e", "g"]
+                .iter()
+                .map(|t| t.to_string())
+                .collect(),
+            &dm.matrix,
+        );
+        assert!(matches!(
+            dm.mantel_test(&renamed, 10, &mut rng),
+            Err(MatrixError::MissingTaxon(taxon)) if taxon == "f"
+        ));
+    }
 }
@@ -2,11 +2,12 @@
 //!
 
 use std::fmt::{Debug, Display};
+use std::str::FromStr;
 
-use clap::ValueEnum;
 use num_traits::{Float, Zero};
 use numeric_literals::replace_numeric_literals;
-use rand_distr::{uniform::SampleUniform, Distribution, Exp, Gamma, Uniform};
+use rand_distr::{uniform::SampleUniform, Distribution, Exp, Gamma, LogNormal, Uniform};
+use thiserror::Error;
 use trait_set::trait_set;
 
 trait_set! {
@@ -15,18 +16,138 @@ trait_set! {
     pub trait BranchLength = Debug + Display + Float + Zero + SampleUniform;
 }
 
+/// Errors that can occur when creating a branch length distribution
+#[derive(Error, Debug)]
+pub enum DistrError {
+    /// The rate of an exponential distribution is not strictly positive
+    #[error("The rate of an exponential distribution must be strictly positive, got {0}")]
+    InvalidRate(f64),
+    /// The standard deviation of a log-normal distribution is not strictly positive
+    #[error("The sigma of a log-normal distribution must be strictly positive, got {0}")]
+    InvalidSigma(f64),
+    /// The distribution could not be parsed from a string
+    #[error("Could not parse distribution: {0}")]
+    ParseError(String),
+}
+
 /// Available branch length distributions
-#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, ValueEnum)]
+#[derive(Debug, Copy, Clone, PartialEq, Default)]
 pub enum Distr {
     /// A [uniform](https://en.wikipedia.org/wiki/Continuous_uniform_distribution)
     /// distribution over $[0.002, 1.0)$
+    #[default]
     Uniform,
     /// An [exponential](https://en.wikipedia.org/wiki/Exponential_distribution)
-    /// distribution with rate $\lambda=0.15$
-    Exponential,
+    /// distribution with rate $\lambda$ *(0.15 by default in the CLI)*
+    Exponential {
+        /// Rate of the distribution, must be strictly positive
+        rate: f64,
+    },
     /// A [gamma](https://en.wikipedia.org/wiki/Gamma_distribution) distribution
     /// with a shape $k=4$ and scale $\theta=1.0$.
     Gamma,
+    /// A [log-normal](https://en.wikipedia.org/wiki/Log-normal_distribution)
+    /// distribution, *i.e.* the logarithm of the branch lengths is normally
+    /// distributed with mean $\mu$ and standard deviation $\sigma$
+    /// *($\mu=0$ and $\sigma=1$ by default in the CLI)*
+    LogNormal {
+        /// Mean of the underlying normal distribution
+        mu: f64,
+        /// Standard deviation of the underlying normal distribution, must be strictly positive
+        sigma: f64,
+    },
+}
+
+impl Distr {
+    /// Checks that the distribution parameters are valid
+    /// # Example
+    /// ```
+    /// use phylotree::distr::Distr;
+    ///
+    /// assert!(Distr::Exponential { rate: 0.5 }.validate().is_ok());
+    /// assert!(Distr::Exponential { rate: 0.0 }.validate().is_err());
+    /// assert!(Distr::LogNormal { mu: 0.0, sigma: -1.0 }.validate().is_err());
+    /// ```
+    pub fn validate(&self) -> Result<(), DistrError> {
+        match *self {
+            Distr::Exponential { rate } if !(rate > 0.0 && rate.is_finite()) => {
+                Err(DistrError::InvalidRate(rate))
+            }
+            Distr::LogNormal { sigma, .. } if !(sigma > 0.0 && sigma.is_finite()) => {
+                Err(DistrError::InvalidSigma(sigma))
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+impl Display for Distr {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Distr::Uniform => write!(f, "uniform"),
+            Distr::Exponential { rate } => write!(f, "exponential:{rate}"),
+            Distr::Gamma => write!(f, "gamma"),
+            Distr::LogNormal { mu, sigma } => write!(f, "lognormal:{mu},{sigma}"),
+        }
+    }
+}
+
+impl FromStr for Distr {
+    type Err = DistrError;
+
+    /// Parses a distribution from a string of the form `name[:parameters]`,
+    /// *e.g.* `uniform`, `exponential:0.5` or `lognormal:-1,0.5`. Parameters
+    /// that are not specified are set to their default values.
+    /// # Example
+    /// ```
+    /// use phylotree::distr::Distr;
+    ///
+    /// assert_eq!("exponential".parse::<Distr>().unwrap(), Distr::Exponential { rate: 0.15 });
+    /// assert_eq!(
+    ///     "lognormal:-1,0.5".parse::<Distr>().unwrap(),
+    ///     Distr::LogNormal { mu: -1.0, sigma: 0.5 }
+    /// );
+    /// assert!("exponential:-2".parse::<Distr>().is_err());
+    /// ```
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (name, params) = match s.split_once(':') {
+            Some((name, params)) => (name, Some(params)),
+            None => (s, None),
+        };
+        let params = params
+            .map(|p| {
+                p.split(',')
+                    .map(|v| v.trim().parse::<f64>())
+                    .collect::<Result<Vec<_>, _>>()
+            })
+            .transpose()
+            .map_err(|e| DistrError::ParseError(format!("{s} ({e})")))?;
+        let n_params = params.as_ref().map_or(0, |p| p.len());
+
+        let distr = match (name.trim().to_lowercase().as_str(), n_params) {
+            ("uniform", 0) => Distr::Uniform,
+            ("gamma", 0) => Distr::Gamma,
+            ("exponential", 0) => Distr::Exponential { rate: 0.15 },
+            ("exponential", 1) => Distr::Exponential {
+                rate: params.unwrap()[0],
+            },
+            ("lognormal", 0) => Distr::LogNormal {
+                mu: 0.0,
+                sigma: 1.0,
+            },
+            ("lognormal", 2) => {
+                let params = params.unwrap();
+                Distr::LogNormal {
+                    mu: params[0],
+                    sigma: params[1],
+                }
+            }
+            _ => return Err(DistrError::ParseError(s.to_string())),
+        };
+        distr.validate()?;
+
+        Ok(distr)
+    }
 }
 
 pub(crate) enum Sampler<T>
@@ -39,6 +160,7 @@ where
     Uniform(Uniform<T>),
     Exponential(Exp<T>),
     Gamma(Gamma<T>),
+    LogNormal(LogNormal<T>),
 }
 
 impl<T> Sampler<T>
@@ -49,12 +171,21 @@ where
     rand_distr::Open01: rand_distr::Distribution<T>,
 {
     #[replace_numeric_literals(T::from(literal).unwrap())]
-    pub(crate) fn new(v: Distr) -> Self {
-        match v {
+    pub(crate) fn new(v: Distr) -> Result<Self, DistrError> {
+        v.validate()?;
+        let sampler = match v {
             Distr::Uniform => Self::Uniform(Uniform::<T>::new(0.002, 1.0)),
-            Distr::Exponential => Self::Exponential(Exp::new(0.15).unwrap()),
+            Distr::Exponential { rate } => Self::Exponential(
+                Exp::new(T::from(rate).unwrap()).map_err(|_| DistrError::InvalidRate(rate))?,
+            ),
             Distr::Gamma => Self::Gamma(Gamma::new(4.0, 1.0).unwrap()),
-        }
+            Distr::LogNormal { mu, sigma } => Self::LogNormal(
+                LogNormal::new(T::from(mu).unwrap(), T::from(sigma).unwrap())
+                    .map_err(|_| DistrError::InvalidSigma(sigma))?,
+            ),
+        };
+
+        Ok(sampler)
     }
 }
 
@@ -70,6 +201,7 @@ where
             Sampler::Uniform(u) => u.sample(rng),
             Sampler::Exponential(e) => e.sample(rng),
             Sampler::Gamma(p) => p.sample(rng),
+            Sampler::LogNormal(l) => l.sample(rng),
         }
     }
 }
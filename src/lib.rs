@@ -24,6 +24,8 @@
 //! [`crate::distance`] module is used to dealt with phylolgenetic distance matrices.
 //! [`crate::distr`] is a helper module to provide different branch
 //! length distributions when generating random phylogenetic trees.
+//! [`crate::alignment`] reads multiple sequence alignments whose sequences
+//! can be attached to the leaves of a tree.
 //!
 //! ## Building trees
 //! The simplest way to build a tree is to create an empty tree, add a root node and
@@ -175,6 +177,7 @@ use tree::{Node, Tree, TreeError};
 #[cfg(feature = "python")]
 pub mod python;
 
+pub mod alignment;
 pub mod distance;
 pub mod distr;
 pub mod tree;
@@ -205,7 +208,7 @@ pub fn generate_tree(
 
     let mut rng = thread_rng();
 
-    let sampler = Sampler::new(sampler_type);
+    let sampler = Sampler::new(sampler_type)?;
 
     let mut next_deq = VecDeque::new();
     next_deq.push_back(0);
@@ -249,7 +252,7 @@ pub fn generate_yule(
     let root = tree.add(Node::default());
 
     let mut rng = thread_rng();
-    let sampler = Sampler::new(sampler_type);
+    let sampler = Sampler::new(sampler_type)?;
 
     let mut parent_candidates = vec![root];
 
@@ -280,6 +283,116 @@ pub fn generate_yule(
     Ok(tree)
 }
 
+/// Generate a random binary tree under a constant rate birth-death process.
+///
+/// The simulation starts with two lineages descending from the root, and at each
+/// step a lineage is chosen at random to either split in two *(with rate `birth_rate`)*
+/// or go extinct *(with rate `death_rate`)*. Waiting times between events are
+/// exponentially distributed. The simulation stops when `n_leaves` lineages are alive,
+/// after an extra waiting time so that tips do not have null branch lengths.
+/// Extinct lineages are then pruned from the tree.
+///
+/// Returns a [`TreeError::GeneralError`] if the rates are invalid or if all
+/// lineages went extinct before reaching `n_leaves`.
+///
+/// # Example
+/// ```
+/// use phylotree::generate_birth_death_tree;
+/// use rand::{rngs::StdRng, SeedableRng};
+///
+/// let mut rng = StdRng::seed_from_u64(42);
+/// let tree = generate_birth_death_tree(20, 1.0, 0.2, &mut rng).unwrap();
+///
+/// assert_eq!(tree.n_leaves(), 20);
+/// assert!(tree.is_binary().unwrap());
+/// ```
+pub fn generate_birth_death_tree(
+    n_leaves: usize,
+    birth_rate: f64,
+    death_rate: f64,
+    rng: &mut impl Rng,
+) -> Result<Tree, TreeError> {
+    if n_leaves < 2 {
+        return Err(TreeError::GeneralError(
+            "A birth-death tree must have at least 2 leaves",
+        ));
+    }
+    if !(birth_rate > 0.0 && birth_rate.is_finite()) {
+        return Err(TreeError::GeneralError(
+            "The birth rate must be strictly positive",
+        ));
+    }
+    if !(death_rate >= 0.0 && death_rate.is_finite()) {
+        return Err(TreeError::GeneralError("The death rate must be positive"));
+    }
+
+    let event_rate = birth_rate + death_rate;
+    let birth_prob = birth_rate / event_rate;
+
+    // Simulated lineages: (parent, start time, end time, extinct)
+    let mut lineages: Vec<(Option<usize>, f64, f64, bool)> = vec![(None, 0.0, 0.0, false)];
+    let mut active = vec![];
+    for _ in 0..2 {
+        active.push(lineages.len());
+        lineages.push((Some(0), 0.0, 0.0, false));
+    }
+
+    let mut time = 0.0;
+    while active.len() < n_leaves {
+        if active.is_empty() {
+            return Err(TreeError::GeneralError(
+                "All lineages went extinct before reaching the number of leaves",
+            ));
+        }
+
+        let wait = rand_distr::Exp::new(active.len() as f64 * event_rate).unwrap();
+        time += wait.sample(rng);
+
+        let lineage = active.swap_remove(rng.gen_range(0..active.len()));
+        lineages[lineage].2 = time;
+        if rng.gen_bool(birth_prob) {
+            for _ in 0..2 {
+                active.push(lineages.len());
+                lineages.push((Some(lineage), time, 0.0, false));
+            }
+        } else {
+            lineages[lineage].3 = true;
+        }
+    }
+
+    let wait = rand_distr::Exp::new(active.len() as f64 * event_rate).unwrap();
+    time += wait.sample(rng);
+    for lineage in active.iter() {
+        lineages[*lineage].2 = time;
+    }
+
+    // Build the complete tree, extinct lineages included
+    let mut tree = Tree::new();
+    let mut ids = Vec::with_capacity(lineages.len());
+    let mut keep = Vec::with_capacity(n_leaves);
+    for (i, (parent, start, end, extinct)) in lineages.iter().enumerate() {
+        let id = match parent {
+            None => tree.add(Node::default()),
+            Some(parent) => tree.add_child(Node::new(), ids[*parent], Some(end - start))?,
+        };
+        if *extinct {
+            tree.get_mut(&id)?.set_name(format!("Extinct_{i}"));
+        }
+        ids.push(id);
+    }
+    for (i, lineage) in active.iter().enumerate() {
+        let name = format!("Tip_{i}");
+        tree.get_mut(&ids[*lineage])?.set_name(name.clone());
+        keep.push(name);
+    }
+
+    // Remove extinct lineages
+    let keep: Vec<&str> = keep.iter().map(|name| name.as_str()).collect();
+    tree.prune_to_taxa(&keep)?;
+
+    Ok(tree)
+}
+
 /// Generates a caterpillar tree by adding children to the last node addesd to the tree
 /// until we reach the desired numebr of leaves.
 pub fn generate_caterpillar(
@@ -291,7 +404,7 @@ pub fn generate_caterpillar(
     tree.add(Node::default());
 
     let mut rng = thread_rng();
-    let sampler = Sampler::new(sampler_type);
+    let sampler = Sampler::new(sampler_type)?;
 
     let mut parent = 0;
     for i in 1..n_leaves {
@@ -0,0 +1,335 @@
+//! Read trees and node annotations from BEAST output files.
+//!
+//! BEAST and TreeAnnotator write trees in NEXUS files, with node annotations
+//! stored in comments such as `[&height=1.23,height_95%_HPD={0.9,1.5},posterior=0.99]`.
+//! Leaves are usually written as numbers, translated to taxon names by a
+//! `Translate` table at the start of the `trees` block.
+use std::{collections::HashMap, fs, num::ParseFloatError, path::Path};
+
+use thiserror::Error;
+
+use super::{NewickParseError, NodeId, Tree};
+
+/// Errors that can occur when reading a BEAST NEXUS file
+#[derive(Error, Debug)]
+pub enum BeastError {
+    /// There was a [`std::io::Error`] when reading the file
+    #[error("Problem reading file")]
+    IoError(#[from] std::io::Error),
+    /// There was a [`NewickParseError`] when reading one of the trees
+    #[error("Could not parse a tree")]
+    NewickParseError(#[from] NewickParseError),
+    /// A numeric annotation is not a number
+    #[error("Could not parse a numeric annotation")]
+    FloatError(#[from] ParseFloatError),
+    /// The file does not contain a `trees` block
+    #[error("The file does not contain a trees block")]
+    MissingTreesBlock,
+}
+
+/// Annotations of a node in a BEAST tree
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct BeastAnnotation {
+    /// Height *(i.e. age)* of the node
+    pub height: Option<f64>,
+    /// Posterior probability of the clade
+    pub posterior: Option<f64>,
+    /// Substitution rate on the branch above the node
+    pub rate: Option<f64>,
+    /// All other annotations, with their raw values *(e.g. `{0.9,1.5}` for HPD intervals)*
+    pub other: HashMap<String, String>,
+}
+
+impl BeastAnnotation {
+    /// Parses an annotation comment, with or without the leading `&`.
+    /// ```
+    /// use phylotree::tree::beast::BeastAnnotation;
+    ///
+    /// let annotation =
+    ///     BeastAnnotation::from_comment("&height=1.23,height_95%_HPD={0.9,1.5},posterior=0.99")
+    ///         .unwrap();
+    ///
+    /// assert_eq!(annotation.height, Some(1.23));
+    /// assert_eq!(annotation.posterior, Some(0.99));
+    /// assert_eq!(annotation.rate, None);
+    /// assert_eq!(annotation.other["height_95%_HPD"], "{0.9,1.5}");
+    /// ```
+    pub fn from_comment(comment: &str) -> Result<Self, BeastError> {
+        let mut annotation = Self::default();
+
+        for field in split_outside(comment, ',', '{', '}') {
+            let field = field.trim().trim_start_matches('&');
+            if field.is_empty() {
+                continue;
+            }
+            let (key, value) = field.split_once('=').unwrap_or((field, ""));
+            match key {
+                "height" => annotation.height = Some(value.parse()?),
+                "posterior" => annotation.posterior = Some(value.parse()?),
+                "rate" => annotation.rate = Some(value.parse()?),
+                _ => {
+                    annotation.other.insert(key.into(), value.into());
+                }
+            }
+        }
+
+        Ok(annotation)
+    }
+}
+
+/// Trees and node annotations read from a BEAST NEXUS file
+#[derive(Debug, Clone, Default)]
+pub struct BeastOutput {
+    /// Trees of the file, with leaves named after the `Translate` table
+    pub trees: Vec<Tree>,
+    /// Annotations of the nodes of each tree, for nodes that have an annotation comment
+    pub annotations: Vec<HashMap<NodeId, BeastAnnotation>>,
+}
+
+impl BeastOutput {
+    /// Reads trees and node annotations from a BEAST NEXUS string.
+    /// ```
+    /// use phylotree::tree::beast::BeastOutput;
+    ///
+    /// let nexus = "#NEXUS
+    /// Begin trees;
+    ///     Translate
+    ///         1 A,
+    ///         2 B,
+    ///         3 C
+    ///     ;
+    /// tree TREE1 = [&R] ((1[&rate=0.9]:1.0,2:1.0)[&height=1.0,posterior=0.95]:0.5,3:1.5)[&height=1.5];
+    /// End;
+    /// ";
+    ///
+    /// let output = BeastOutput::from_nexus(nexus).unwrap();
+    /// let tree = &output.trees[0];
+    /// let annotations = &output.annotations[0];
+    ///
+    /// let a = tree.get_by_name("A").unwrap();
+    /// let clade = a.parent.unwrap();
+    ///
+    /// assert_eq!(annotations[&a.id].rate, Some(0.9));
+    /// assert_eq!(annotations[&clade].posterior, Some(0.95));
+    /// assert_eq!(annotations[&tree.get_root().unwrap()].height, Some(1.5));
+    /// ```
+    pub fn from_nexus(nexus: &str) -> Result<Self, BeastError> {
+        let mut output = Self::default();
+        let mut translate = HashMap::new();
+        let mut in_trees = false;
+        let mut found_trees = false;
+
+        for statement in split_outside(nexus, ';', '[', ']') {
+            let statement = skip_comments(statement.trim());
+            let statement = match statement.get(..6) {
+                Some(start) if start.eq_ignore_ascii_case("#nexus") => {
+                    skip_comments(statement[6..].trim_start())
+                }
+                _ => statement,
+            };
+            let lower = statement.to_lowercase();
+            let keyword = lower.split_whitespace().next().unwrap_or_default();
+
+            match keyword {
+                "begin" => {
+                    in_trees = lower.split_whitespace().nth(1) == Some("trees");
+                    found_trees |= in_trees;
+                }
+                "end" | "endblock" => in_trees = false,
+                "translate" if in_trees => {
+                    for entry in statement["translate".len()..].split(',') {
+                        if let Some((key, name)) = entry.trim().split_once(char::is_whitespace) {
+                            let name = name.trim().trim_matches(|c| c == '\'' || c == '"');
+                            translate.insert(key.to_string(), name.to_string());
+                        }
+                    }
+                }
+                "tree" if in_trees => {
+                    let parts = split_outside(statement, '=', '[', ']');
+                    if parts.len() < 2 {
+                        continue;
+                    }
+                    let mut tree = Tree::from_newick(&format!("{};", parts[1..].join("=")))?;
+
+                    for leaf in tree.get_leaves() {
+                        let node = tree.get_mut(&leaf).expect("Leaves are in the tree");
+                        if let Some(name) = node.name.as_ref().and_then(|n| translate.get(n)) {
+                            node.set_name(name.clone());
+                        }
+                    }
+
+                    let mut annotations = HashMap::new();
+                    for id in 0..tree.size() {
+                        let Ok(node) = tree.get(&id) else {
+                            continue;
+                        };
+                        if let Some(comment) = node.comment.as_ref().filter(|c| c.starts_with('&'))
+                        {
+                            annotations.insert(id, BeastAnnotation::from_comment(comment)?);
+                        }
+                    }
+
+                    output.trees.push(tree);
+                    output.annotations.push(annotations);
+                }
+                _ => (),
+            }
+        }
+
+        if !found_trees {
+            return Err(BeastError::MissingTreesBlock);
+        }
+
+        Ok(output)
+    }
+
+    /// Reads trees and node annotations from a BEAST NEXUS file
+    /// *(see [`BeastOutput::from_nexus()`])*.
+    pub fn from_nexus_file(path: &Path) -> Result<Self, BeastError> {
+        let nexus = fs::read_to_string(path)?;
+        Self::from_nexus(&nexus)
+    }
+}
+
+// Splits a string on a separator, ignoring separators between delimiters
+fn split_outside(s: &str, separator: char, open: char, close: char) -> Vec<&str> {
+    let mut parts = vec![];
+    let mut depth = 0;
+    let mut start = 0;
+    for (i, c) in s.char_indices() {
+        match c {
+            c if c == open => depth += 1,
+            c if c == close => depth -= 1,
+            c if c == separator && depth == 0 => {
+                parts.push(&s[start..i]);
+                start = i + c.len_utf8();
+            }
+            _ => (),
+        }
+    }
+    parts.push(&s[start..]);
+
+    parts
+}
+
+// Skips leading NEXUS comments in a statement
+fn skip_comments(mut s: &str) -> &str {
+    while let Some(rest) = s.strip_prefix('[') {
+        s = rest
+            .split_once(']')
+            .map(|(_, s)| s)
+            .unwrap_or_default()
+            .trim_start();
+    }
+
+    s
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MCC_TREE: &str = "#NEXUS
+
+[R-package APE, Mon Jan 01 2024]
+
+Begin taxa;
+    Dimensions ntax=4;
+    Taxlabels
+        'Homo sapiens'
+        Pan
+        Gorilla
+        Pongo
+        ;
+End;
+
+Begin trees;
+    Translate
+        1 'Homo sapiens',
+        2 Pan,
+        3 Gorilla,
+        4 Pongo
+        ;
+tree TREE1 = [&R] (((1[&height=0.0,rate=1.1]:6.0,2[&height=0.0,rate=0.9]:6.0)[&height=6.0,height_95%_HPD={5.1,7.2},posterior=1.0,rate=1.0]:2.0,3[&height=0.0]:8.0)[&height=8.0,posterior=0.87]:7.0,4:15.0)[&height=15.0,posterior=1.0];
+End;
+";
+
+    #[test]
+    fn read_mcc_tree() {
+        let output = BeastOutput::from_nexus(MCC_TREE).unwrap();
+        assert_eq!(output.trees.len(), 1);
+        assert_eq!(output.annotations.len(), 1);
+
+        let tree = &output.trees[0];
+        let annotations = &output.annotations[0];
+        assert_eq!(
+            tree.get_leaf_names_sorted().unwrap(),
+            vec!["Gorilla", "Homo sapiens", "Pan", "Pongo"]
+        );
+        // All nodes except Pongo are annotated
+        assert_eq!(annotations.len(), 6);
+
+        let human = tree.get_by_name("Homo sapiens").unwrap();
+        assert_eq!(
+            annotations[&human.id],
+            BeastAnnotation {
+                height: Some(0.0),
+                rate: Some(1.1),
+                ..Default::default()
+            }
+        );
+
+        let clade = &annotations[&human.parent.unwrap()];
+        assert_eq!(clade.height, Some(6.0));
+        assert_eq!(clade.posterior, Some(1.0));
+        assert_eq!(clade.rate, Some(1.0));
+        assert_eq!(clade.other.len(), 1);
+        assert_eq!(clade.other["height_95%_HPD"], "{5.1,7.2}");
+
+        let root = tree.get_root().unwrap();
+        assert_eq!(annotations[&root].height, Some(15.0));
+        assert_eq!(tree.get(&root).unwrap().children.len(), 2);
+        assert_eq!(tree.height().unwrap(), 15.0);
+
+        let pongo = tree.get_by_name("Pongo").unwrap().id;
+        assert!(!annotations.contains_key(&pongo));
+    }
+
+    #[test]
+    fn read_posterior_trees() {
+        let nexus = "#NEXUS
+begin trees;
+tree STATE_0 [&lnP=-3000.5,posterior=-3000.5] = [&R] ((A:1.0,B:1.0):1.0,C:2.0);
+tree STATE_1000 [&lnP=-2900.1,posterior=-2900.1] = [&R] ((A[&rate=2]:1.5,C:1.5):0.5,B:2.0);
+end;
+";
+        let output = BeastOutput::from_nexus(nexus).unwrap();
+        assert_eq!(output.trees.len(), 2);
+        assert_eq!(
+            output.trees[1].to_newick().unwrap(),
+            "((A:1.5[&rate=2],C:1.5):0.5,B:2);"
+        );
+        assert!(output.annotations[0].is_empty());
+        assert_eq!(output.annotations[1].len(), 1);
+    }
+
+    #[test]
+    fn beast_errors() {
+        assert!(matches!(
+            BeastOutput::from_nexus("#NEXUS\nbegin taxa;\nend;\n"),
+            Err(BeastError::MissingTreesBlock)
+        ));
+        assert!(matches!(
+            BeastOutput::from_nexus("#NEXUS\nbegin trees;\ntree t = ((A,B),C;\nend;"),
+            Err(BeastError::NewickParseError(_))
+        ));
+        assert!(matches!(
+            BeastOutput::from_nexus("#NEXUS\nbegin trees;\ntree t = (A[&height=x],B);\nend;"),
+            Err(BeastError::FloatError(_))
+        ));
+        assert!(matches!(
+            BeastOutput::from_nexus_file(Path::new("/does/not/exist.trees")),
+            Err(BeastError::IoError(_))
+        ));
+    }
+}
@@ -0,0 +1,186 @@
+//! Build trees node by node, referring to nodes by name
+use std::collections::{HashMap, HashSet};
+
+use super::{EdgeLength, Node, NodeId, Tree, TreeError};
+
+/// A builder to construct a [`Tree`] by adding named nodes under named parents,
+/// without having to keep track of [`NodeId`]s. All node names must be unique.
+/// ```
+/// use phylotree::tree::TreeBuilder;
+///
+/// let tree = TreeBuilder::new()
+///     .root("R")
+///     .add_internal("R", "E", Some(0.5))
+///     .unwrap()
+///     .add_leaf("E", "A", Some(1.0))
+///     .unwrap()
+///     .add_leaf("E", "B", Some(2.0))
+///     .unwrap()
+///     .add_leaf("R", "C", None)
+///     .unwrap()
+///     .build()
+///     .unwrap();
+///
+/// assert_eq!(tree.to_newick().unwrap(), "((A:1,B:2)E:0.5,C)R;");
+/// ```
+#[derive(Debug, Clone)]
+pub struct TreeBuilder {
+    tree: Tree,
+    ids: HashMap<String, NodeId>,
+    leaves: HashSet<NodeId>,
+}
+
+impl TreeBuilder {
+    /// Creates an empty builder
+    pub fn new() -> Self {
+        Self {
+            tree: Tree::new(),
+            ids: HashMap::new(),
+            leaves: HashSet::new(),
+        }
+    }
+
+    /// Sets the root of the tree. Any nodes that were previously added are discarded.
+    pub fn root(&mut self, name: &str) -> &mut Self {
+        *self = Self::new();
+        let id = self.tree.add(Node::new_named(name));
+        self.ids.insert(name.to_string(), id);
+
+        self
+    }
+
+    /// Adds a leaf named `leaf_name` as a child of the node named `parent_name`.
+    /// Returns [`TreeError::DuplicateLeafNames`] if the name is already used in the tree.
+    pub fn add_leaf(
+        &mut self,
+        parent_name: &str,
+        leaf_name: &str,
+        edge: Option<EdgeLength>,
+    ) -> Result<&mut Self, TreeError> {
+        let id = self.add_node(parent_name, leaf_name, edge)?;
+        self.leaves.insert(id);
+
+        Ok(self)
+    }
+
+    /// Adds an internal node named `node_name` as a child of the node named `parent_name`.
+    /// Returns [`TreeError::DuplicateLeafNames`] if the name is already used in the tree.
+    pub fn add_internal(
+        &mut self,
+        parent_name: &str,
+        node_name: &str,
+        edge: Option<EdgeLength>,
+    ) -> Result<&mut Self, TreeError> {
+        self.add_node(parent_name, node_name, edge)?;
+
+        Ok(self)
+    }
+
+    /// Returns the tree built so far. Returns [`TreeError::IsEmpty`] if no root was set.
+    pub fn build(&self) -> Result<Tree, TreeError> {
+        if self.ids.is_empty() {
+            return Err(TreeError::IsEmpty);
+        }
+
+        Ok(self.tree.clone())
+    }
+
+    // Adds a named node under a named parent, that must not be a leaf
+    fn add_node(
+        &mut self,
+        parent_name: &str,
+        name: &str,
+        edge: Option<EdgeLength>,
+    ) -> Result<NodeId, TreeError> {
+        if self.ids.contains_key(name) {
+            return Err(TreeError::DuplicateLeafNames);
+        }
+        let parent = *self
+            .ids
+            .get(parent_name)
+            .ok_or(TreeError::GeneralError("The parent node does not exist"))?;
+        if self.leaves.contains(&parent) {
+            return Err(TreeError::GeneralError("Cannot add children to a leaf"));
+        }
+
+        let id = self.tree.add_child(Node::new_named(name), parent, edge)?;
+        self.ids.insert(name.to_string(), id);
+
+        Ok(id)
+    }
+}
+
+impl Default for TreeBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn build_trees() {
+        let mut builder = TreeBuilder::new();
+        assert!(matches!(builder.build(), Err(TreeError::IsEmpty)));
+
+        builder
+            .root("G")
+            .add_internal("G", "E", Some(1.0))
+            .unwrap()
+            .add_internal("G", "F", Some(2.0))
+            .unwrap()
+            .add_leaf("E", "A", Some(0.1))
+            .unwrap()
+            .add_leaf("E", "B", Some(0.2))
+            .unwrap()
+            .add_leaf("F", "C", None)
+            .unwrap()
+            .add_leaf("F", "D", Some(0.4))
+            .unwrap();
+
+        let tree = builder.build().unwrap();
+        assert_eq!(
+            tree.to_newick().unwrap(),
+            "((A:0.1,B:0.2)E:1,(C,D:0.4)F:2)G;"
+        );
+        assert_eq!(tree.n_leaves(), 4);
+        assert_eq!(tree.get_by_name("A").unwrap().get_depth(), 2);
+
+        // Setting the root starts a new tree
+        let tree = builder
+            .root("R")
+            .add_leaf("R", "A", None)
+            .unwrap()
+            .build()
+            .unwrap();
+        assert_eq!(tree.to_newick().unwrap(), "(A)R;");
+    }
+
+    #[test]
+    fn builder_errors() {
+        let mut builder = TreeBuilder::new();
+        builder.root("R").add_leaf("R", "A", None).unwrap();
+
+        assert!(matches!(
+            builder.add_leaf("R", "A", None),
+            Err(TreeError::DuplicateLeafNames)
+        ));
+        assert!(matches!(
+            builder.add_internal("R", "R", None),
+            Err(TreeError::DuplicateLeafNames)
+        ));
+        assert!(matches!(
+            builder.add_leaf("X", "B", None),
+            Err(TreeError::GeneralError(_))
+        ));
+        assert!(matches!(
+            builder.add_leaf("A", "B", None),
+            Err(TreeError::GeneralError(_))
+        ));
+
+        // Failed insertions do not change the tree
+        assert_eq!(builder.build().unwrap().to_newick().unwrap(), "(A)R;");
+    }
+}
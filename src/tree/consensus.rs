@@ -0,0 +1,271 @@
+//! A module to build consensus trees from collections of phylogenetic trees
+use std::collections::HashMap;
+
+use fixedbitset::FixedBitSet;
+use itertools::Itertools;
+
+use super::{Node, NodeId, Tree, TreeError};
+
+/// Returns the sorted leaf names shared by all the trees
+fn common_leaf_names(trees: &[Tree]) -> Result<Vec<String>, TreeError> {
+    let mut leaf_sets = trees.iter().map(|tree| {
+        let names: Vec<_> = tree
+            .get_leaf_names()
+            .into_iter()
+            .flatten()
+            .sorted()
+            .collect();
+        if names.len() != tree.n_leaves() {
+            return Err(TreeError::UnnamedLeaves);
+        }
+        Ok(names)
+    });
+
+    let leaf_names = leaf_sets.next().ok_or(TreeError::IsEmpty)??;
+    for names in leaf_sets {
+        if names? != leaf_names {
+            return Err(TreeError::DifferentTipIndices);
+        }
+    }
+
+    Ok(leaf_names)
+}
+
+/// Converts a bipartition to the cluster of leaves that does not contain the first leaf
+pub(super) fn to_cluster(partition: &FixedBitSet, n_leaves: usize) -> FixedBitSet {
+    let mut cluster = partition.clone();
+    cluster.grow(n_leaves);
+    if cluster.contains(0) {
+        cluster.toggle_range(..);
+    }
+    cluster
+}
+
+/// Builds a tree from a set of compatible bipartitions and their support values.
+/// The first leaf is used to orient the bipartitions, it is a direct child of the root.
+fn tree_from_partitions(
+    leaf_names: &[String],
+    partitions: &[(FixedBitSet, f64)],
+) -> Result<Tree, TreeError> {
+    let clusters: Vec<_> = partitions
+        .iter()
+        .map(|(partition, support)| (to_cluster(partition, leaf_names.len()), *support))
+        .sorted_by_key(|(cluster, _)| std::cmp::Reverse(cluster.count_ones(..)))
+        .collect();
+
+    let mut tree = Tree::new();
+    let root = tree.add(Node::new());
+
+    // Each cluster is attached to the smallest cluster that contains it
+    let mut added: Vec<(FixedBitSet, NodeId)> = vec![];
+    for (cluster, support) in clusters {
+        let parent = added
+            .iter()
+            .rev()
+            .find(|(other, _)| cluster.is_subset(other))
+            .map(|(_, id)| *id)
+            .unwrap_or(root);
+        let id = tree.add_child(Node::new(), parent, None)?;
+        tree.get_mut(&id)?.support = Some(support);
+        added.push((cluster, id));
+    }
+
+    for (i, name) in leaf_names.iter().enumerate() {
+        let parent = added
+            .iter()
+            .rev()
+            .find(|(cluster, _)| cluster.contains(i))
+            .map(|(_, id)| *id)
+            .unwrap_or(root);
+        tree.add_child(Node::new_named(name), parent, None)?;
+    }
+
+    Ok(tree)
+}
+
+/// Builds the strict consensus of a collection of trees, *i.e.* the tree containing
+/// only the bipartitions that are present in all the input trees. All the trees
+/// must have the same set of uniquely named leaves.
+/// ```
+/// use phylotree::tree::Tree;
+/// use phylotree::tree::consensus::strict_consensus;
+///
+/// let trees = vec![
+///     Tree::from_newick("((A,B),(C,D),(E,F));").unwrap(),
+///     Tree::from_newick("((A,B),(C,E),(D,F));").unwrap(),
+/// ];
+///
+/// let consensus = strict_consensus(&trees).unwrap();
+/// let expected = Tree::from_newick("((A,B),C,D,E,F);").unwrap();
+///
+/// assert_eq!(consensus.robinson_foulds(&expected).unwrap(), 0);
+/// ```
+pub fn strict_consensus(trees: &[Tree]) -> Result<Tree, TreeError> {
+    let leaf_names = common_leaf_names(trees)?;
+
+    let mut partitions = trees[0].get_partitions()?;
+    for tree in trees.iter().skip(1) {
+        let other = tree.get_partitions()?;
+        partitions.retain(|partition| other.contains(partition));
+    }
+
+    let partitions: Vec<_> = partitions.into_iter().map(|p| (p, 1.0)).collect();
+    tree_from_partitions(&leaf_names, &partitions)
+}
+
+/// Builds the majority-rule consensus of a collection of trees. Bipartitions present
+/// in at least `threshold * trees.len()` of the input trees are added to the consensus
+/// by decreasing order of support, skipping any bipartition that is incompatible
+/// with the ones already added. The usual threshold is `0.5`, lower values give
+/// an extended majority-rule consensus.
+///
+/// The fraction of input trees containing each bipartition is stored in the
+/// [`Node::support`] field of the corresponding consensus node. All the trees
+/// must have the same set of uniquely named leaves.
+/// ```
+/// use phylotree::tree::Tree;
+/// use phylotree::tree::consensus::majority_rule_consensus;
+///
+/// let trees = vec![
+///     Tree::from_newick("((A,B),(C,D),(E,F));").unwrap(),
+///     Tree::from_newick("((A,B),(C,E),(D,F));").unwrap(),
+///     Tree::from_newick("((A,C),(B,D),(E,F));").unwrap(),
+/// ];
+///
+/// let consensus = majority_rule_consensus(&trees, 0.5).unwrap();
+/// let expected = Tree::from_newick("((A,B),C,D,(E,F));").unwrap();
+///
+/// assert_eq!(consensus.robinson_foulds(&expected).unwrap(), 0);
+/// ```
+pub fn majority_rule_consensus(trees: &[Tree], threshold: f64) -> Result<Tree, TreeError> {
+    if !(0.0..=1.0).contains(&threshold) {
+        return Err(TreeError::GeneralError(
+            "The consensus threshold must be between 0 and 1",
+        ));
+    }
+
+    let leaf_names = common_leaf_names(trees)?;
+    let n_leaves = leaf_names.len();
+
+    let mut counts: HashMap<FixedBitSet, usize> = HashMap::new();
+    for tree in trees {
+        for partition in tree.get_partitions()? {
+            *counts.entry(partition).or_default() += 1;
+        }
+    }
+
+    let min_count = threshold * trees.len() as f64;
+    let candidates = counts
+        .into_iter()
+        .filter(|(_, count)| *count as f64 >= min_count)
+        .sorted_by(|(p1, c1), (p2, c2)| c2.cmp(c1).then_with(|| p1.cmp(p2)));
+
+    let mut accepted: Vec<(FixedBitSet, f64)> = vec![];
+    let mut clusters: Vec<FixedBitSet> = vec![];
+    for (partition, count) in candidates {
+        let cluster = to_cluster(&partition, n_leaves);
+        let compatible = clusters.iter().all(|other| {
+            cluster.is_disjoint(other) || cluster.is_subset(other) || cluster.is_superset(other)
+        });
+        if compatible {
+            clusters.push(cluster);
+            accepted.push((partition, count as f64 / trees.len() as f64));
+        }
+    }
+
+    tree_from_partitions(&leaf_names, &accepted)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn strict_consensus_nni() {
+        // The two trees differ by a single NNI move around the (A,B,C,D) branch
+        let trees = vec![
+            Tree::from_newick("(((A,B),(C,D)),E,F);").unwrap(),
+            Tree::from_newick("(((A,C),(B,D)),E,F);").unwrap(),
+        ];
+
+        let consensus = strict_consensus(&trees).unwrap();
+        let expected = Tree::from_newick("((A,B,C,D),E,F);").unwrap();
+
+        assert_eq!(consensus.robinson_foulds(&expected).unwrap(), 0);
+        assert_eq!(consensus.n_leaves(), 6);
+        assert!(!consensus.is_binary().unwrap());
+    }
+
+    #[test]
+    fn strict_consensus_identical() {
+        let newick = "((A,(B,C)),(D,E),F);";
+        let trees = vec![Tree::from_newick(newick).unwrap(); 3];
+
+        let consensus = strict_consensus(&trees).unwrap();
+        let reference = Tree::from_newick(newick).unwrap();
+
+        assert_eq!(consensus.robinson_foulds(&reference).unwrap(), 0);
+    }
+
+    #[test]
+    fn majority_rule_supports() {
+        let trees = vec![
+            Tree::from_newick("(((A,B),C),(D,E),F);").unwrap(),
+            Tree::from_newick("(((A,B),D),(C,E),F);").unwrap(),
+            Tree::from_newick("(((A,B),C),(D,F),E);").unwrap(),
+            Tree::from_newick("(((A,C),B),(D,E),F);").unwrap(),
+        ];
+
+        let consensus = majority_rule_consensus(&trees, 0.5).unwrap();
+        let expected = Tree::from_newick("(((A,B),C),(D,E),F);").unwrap();
+        assert_eq!(consensus.robinson_foulds(&expected).unwrap(), 0);
+
+        let supports: Vec<_> = consensus
+            .search_nodes(|node| !node.is_tip() && !node.is_root())
+            .into_iter()
+            .map(|id| {
+                let node = consensus.get(&id).unwrap();
+                let leaves = consensus.get_subtree_leaves(&id).unwrap().len();
+                (leaves, node.support.unwrap())
+            })
+            .sorted_by(|a, b| a.partial_cmp(b).unwrap())
+            .collect();
+        // Clusters are oriented away from A: (C,D,E,F) is (A,B), (D,E,F) is (A,B,C)
+        assert_eq!(supports, vec![(2, 0.5), (3, 0.75), (4, 0.75)]);
+
+        // With a higher threshold only the strict consensus remains
+        let consensus = majority_rule_consensus(&trees, 1.0).unwrap();
+        let strict = strict_consensus(&trees).unwrap();
+        assert_eq!(consensus.robinson_foulds(&strict).unwrap(), 0);
+    }
+
+    #[test]
+    fn majority_rule_greedy() {
+        // (A,B) and (B,C) are incompatible, (A,B) has the highest support
+        let trees = vec![
+            Tree::from_newick("((A,B),C,(D,E));").unwrap(),
+            Tree::from_newick("((A,B),C,(D,E));").unwrap(),
+            Tree::from_newick("((B,C),A,(D,E));").unwrap(),
+        ];
+
+        let consensus = majority_rule_consensus(&trees, 0.3).unwrap();
+        let expected = Tree::from_newick("((A,B),C,(D,E));").unwrap();
+        assert_eq!(consensus.robinson_foulds(&expected).unwrap(), 0);
+
+        assert!(majority_rule_consensus(&trees, 1.5).is_err());
+    }
+
+    #[test]
+    fn strict_consensus_errors() {
+        assert!(matches!(strict_consensus(&[]), Err(TreeError::IsEmpty)));
+
+        let trees = vec![
+            Tree::from_newick("((A,B),(C,D));").unwrap(),
+            Tree::from_newick("((A,B),(C,E));").unwrap(),
+        ];
+        assert!(matches!(
+            strict_consensus(&trees),
+            Err(TreeError::DifferentTipIndices)
+        ));
+    }
+}
@@ -3,6 +3,20 @@ use serde::Serialize;
 
 use super::{NodeError, Tree, TreeError};
 use std::f64::consts::PI;
+#[cfg(feature = "svg")]
+use svg::{
+    node::element::{Circle, Group, Line, Text},
+    Document,
+};
+
+// Margin around the drawing and font size of leaf labels in SVG output, in pixels
+#[cfg(feature = "svg")]
+const SVG_MARGIN: f64 = 10.0;
+#[cfg(feature = "svg")]
+const SVG_FONT_SIZE: f64 = 12.0;
+// Width of a character of a leaf label in SVG output, as a fraction of the font size
+#[cfg(feature = "svg")]
+const SVG_CHAR_WIDTH: f64 = 0.6;
 
 #[derive(Serialize)]
 /// Represents a branch in a visualization as a line between 2 points
@@ -140,3 +154,250 @@ pub fn radial_layout(tree: &Tree) -> Result<Layout, TreeError> {
 
     Ok(layout)
 }
+
+/// Returns the rectangular layout of a tree, with the root on the left and
+/// leaves on the right. The x coordinate of a node is its distance from the root,
+/// using branch lengths if all branches have one and counting edges otherwise.
+/// The y coordinate of the `i`-th leaf is `i` and internal nodes are centered
+/// between their first and last children. Nodes are listed in preorder, root included.
+/// ```
+/// use phylotree::tree::{draw, Tree};
+///
+/// let tree = Tree::from_newick("((A:1,B:2):1,C:1);").unwrap();
+/// let layout = draw::rectangular_layout(&tree).unwrap();
+///
+/// let coords: Vec<_> = layout.nodes.iter().map(|node| (node.x, node.y)).collect();
+/// assert_eq!(coords, vec![(0., 1.25), (1., 0.5), (2., 0.), (3., 1.), (1., 2.)]);
+/// ```
+#[cfg(feature = "svg")]
+pub fn rectangular_layout(tree: &Tree) -> Result<Layout, TreeError> {
+    let mut x = vec![0.; tree.size()];
+    let mut y = vec![0.; tree.size()];
+
+    let root = tree.get_root()?;
+    let preorder = tree.preorder(&root)?;
+
+    let mut phylogram = true;
+    for v in preorder.iter().skip(1) {
+        phylogram &= tree.get(v)?.parent_edge.is_some();
+    }
+
+    // Get distances from the root
+    for v in preorder.iter().skip(1) {
+        let node = tree.get(v)?;
+        let u = node
+            .parent
+            .ok_or(TreeError::from(NodeError::HasNoParent(*v)))?;
+        let d = node.parent_edge.filter(|_| phylogram).unwrap_or(1.);
+        x[*v] = x[u] + d;
+    }
+
+    // Get vertical positions
+    let mut n_leaves = 0;
+    for v in tree.postorder(&root)?.iter() {
+        let node = tree.get(v)?;
+        match (node.children.first(), node.children.last()) {
+            (Some(first), Some(last)) => y[*v] = (y[*first] + y[*last]) / 2.,
+            _ => {
+                y[*v] = n_leaves as f64;
+                n_leaves += 1;
+            }
+        }
+    }
+
+    let mut layout = Layout::default();
+
+    for v in preorder.iter() {
+        let node = tree.get(v)?;
+
+        if let Some(u) = node.parent {
+            layout.branches.push(Branch {
+                xstart: x[u],
+                ystart: y[*v],
+                xend: x[*v],
+                yend: y[*v],
+            });
+        }
+        if let (Some(first), Some(last)) = (node.children.first(), node.children.last()) {
+            layout.branches.push(Branch {
+                xstart: x[*v],
+                ystart: y[*first],
+                xend: x[*v],
+                yend: y[*last],
+            });
+        }
+
+        layout.nodes.push(Node {
+            x: x[*v],
+            y: y[*v],
+            label: node.name.clone(),
+        });
+    }
+
+    Ok(layout)
+}
+
+#[cfg(feature = "svg")]
+impl Tree {
+    /// Draws the tree as an SVG document of the given size in pixels, using the
+    /// [`rectangular_layout`]. If all the branches have lengths the tree is drawn
+    /// as a phylogram, otherwise as a cladogram. Leaf names are right-aligned on the
+    /// right of the drawing and internal nodes are drawn as small dots. Room is kept
+    /// for the longest leaf name at 0.6 font sizes per character, and each label is
+    /// fitted to that width with the `textLength` attribute so that it never overlaps
+    /// the tree.
+    /// Returns [`TreeError::IsEmpty`] if the tree has no nodes.
+    /// ```
+    /// use phylotree::tree::Tree;
+    ///
+    /// let tree = Tree::from_newick("((A:0.1,B:0.2):0.3,C:0.4);").unwrap();
+    /// let svg = tree.to_svg(400, 300).unwrap();
+    ///
+    /// assert!(svg.starts_with("<svg"));
+    /// assert!(svg.contains(">A</text>"));
+    /// assert_eq!(svg.matches("<circle").count(), 2);
+    /// ```
+    pub fn to_svg(&self, width: u32, height: u32) -> Result<String, TreeError> {
+        if self.size() == 0 {
+            return Err(TreeError::IsEmpty);
+        }
+
+        let root = self.get_root()?;
+        let preorder = self.preorder(&root)?;
+        let layout = rectangular_layout(self)?;
+
+        // Keep room on the right for leaf labels
+        let label_length =
+            |name: &str| name.chars().count() as f64 * SVG_FONT_SIZE * SVG_CHAR_WIDTH;
+        let mut label_width: f64 = 0.;
+        for v in preorder.iter() {
+            let node = self.get(v)?;
+            if let (true, Some(name)) = (node.is_tip(), &node.name) {
+                label_width = label_width.max(label_length(name));
+            }
+        }
+        let label_x = width as f64 - SVG_MARGIN;
+        let tree_width = (label_x - label_width - 2. * SVG_MARGIN).max(0.);
+        let tree_height = (height as f64 - 2. * SVG_MARGIN).max(0.);
+
+        let xmax = layout.nodes.iter().map(|node| node.x).fold(0., f64::max);
+        let ymax = layout.nodes.iter().map(|node| node.y).fold(0., f64::max);
+        let xscale = if xmax > 0. { tree_width / xmax } else { 0. };
+        let yscale = if ymax > 0. { tree_height / ymax } else { 0. };
+        // Coordinates are rounded to the hundredth of a pixel to keep the document small
+        let round = |coord: f64| (coord * 100.).round() / 100.;
+        let to_x = |x: f64| round(SVG_MARGIN + x * xscale);
+        let to_y = |y: f64| round(SVG_MARGIN + y * yscale);
+
+        let mut branches = Group::new()
+            .set("stroke", "black")
+            .set("stroke-width", 1)
+            .set("fill", "none");
+        for branch in layout.branches.iter() {
+            branches = branches.add(
+                Line::new()
+                    .set("x1", to_x(branch.xstart))
+                    .set("y1", to_y(branch.ystart))
+                    .set("x2", to_x(branch.xend))
+                    .set("y2", to_y(branch.yend)),
+            );
+        }
+
+        let mut dots = Group::new().set("fill", "black");
+        let mut labels = Group::new()
+            .set("font-family", "sans-serif")
+            .set("font-size", SVG_FONT_SIZE)
+            .set("text-anchor", "end")
+            .set("dominant-baseline", "middle");
+        for (v, node) in preorder.iter().zip(layout.nodes.iter()) {
+            if !self.get(v)?.is_tip() {
+                dots = dots.add(
+                    Circle::new()
+                        .set("cx", to_x(node.x))
+                        .set("cy", to_y(node.y))
+                        .set("r", 2),
+                );
+            } else if let Some(label) = &node.label {
+                labels = labels.add(
+                    Text::new(label.as_str())
+                        .set("x", round(label_x))
+                        .set("y", to_y(node.y))
+                        .set("textLength", round(label_length(label)))
+                        .set("lengthAdjust", "spacingAndGlyphs"),
+                );
+            }
+        }
+
+        let document = Document::new()
+            .set("width", width)
+            .set("height", height)
+            .set("viewBox", format!("0 0 {width} {height}"))
+            .add(branches)
+            .add(dots)
+            .add(labels);
+
+        Ok(document.to_string())
+    }
+}
+
+#[cfg(all(test, feature = "svg"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rectangular_layouts() {
+        let phylogram = Tree::from_newick("((A:1,B:3)E:1,(C:1,D:1)F:2)G;").unwrap();
+        let layout = rectangular_layout(&phylogram).unwrap();
+        let depths: Vec<_> = layout.nodes.iter().map(|node| node.x).collect();
+        assert_eq!(depths, vec![0., 1., 2., 4., 2., 3., 3.]);
+        // One horizontal branch per non-root node and one vertical per internal node
+        assert_eq!(layout.branches.len(), 6 + 3);
+
+        // Without branch lengths all branches have the same width
+        let cladogram = Tree::from_newick("((A,B:3)E,(C,D)F)G;").unwrap();
+        let layout = rectangular_layout(&cladogram).unwrap();
+        let depths: Vec<_> = layout.nodes.iter().map(|node| node.x).collect();
+        assert_eq!(depths, vec![0., 1., 2., 2., 1., 2., 2.]);
+    }
+
+    #[test]
+    fn svg_drawing() {
+        assert!(matches!(
+            Tree::new().to_svg(100, 100),
+            Err(TreeError::IsEmpty)
+        ));
+
+        let phylogram = Tree::from_newick("((A:1,B:3)E:1,(C:1,D<&>:1)F:2)G;").unwrap();
+        let svg = phylogram.to_svg(200, 100).unwrap();
+
+        assert!(svg.starts_with("<svg"));
+        assert!(svg.contains("width=\"200\""));
+        assert!(svg.trim_end().ends_with("</svg>"));
+        assert_eq!(svg.matches("<line").count(), 6 + 3);
+        assert_eq!(svg.matches("<circle").count(), 3);
+        assert_eq!(svg.matches("<text").count(), 4);
+        assert!(svg.contains(">D&lt;&amp;&gt;</text>"), "{svg}");
+        // Internal node names are not written
+        assert!(!svg.contains(">E</text>"));
+
+        // The deepest leaf B reaches the label column, the root is on the margin
+        let round = |coord: f64| (coord * 100.).round() / 100.;
+        let label_width = 4. * SVG_FONT_SIZE * SVG_CHAR_WIDTH;
+        let tree_width = 200. - SVG_MARGIN - label_width - 2. * SVG_MARGIN;
+        let xmax = round(SVG_MARGIN + tree_width);
+        assert!(svg.contains(&format!("x2=\"{xmax}\"")), "{svg}");
+        assert!(svg.contains(&format!("cx=\"{SVG_MARGIN}\"")), "{svg}");
+        // Labels are fitted to the room reserved for them
+        let label_width = round(label_width);
+        assert!(
+            svg.contains(&format!("textLength=\"{label_width}\"")),
+            "{svg}"
+        );
+
+        // Leaf labels are evenly spaced from top to bottom
+        assert_eq!(svg.matches(" x=\"190\"").count(), 4);
+        for y in ["10", "36.67", "63.33", "90"] {
+            assert_eq!(svg.matches(&format!(" y=\"{y}\"")).count(), 1, "{y}: {svg}");
+        }
+    }
+}
@@ -0,0 +1,157 @@
+//! Read and write trees in the extended newick format used by the ETE3 toolkit
+use super::{NewickParseError, NodeId, Tree, TreeError};
+
+impl Tree {
+    /// Read a tree written by the ETE3 toolkit. Internal node labels that are
+    /// numbers *(e.g. `(A:0.1,B:0.2)90:0.5`)* are read as support values, as
+    /// well as numeric comments following the branch length *(e.g. `:0.5[90]`)*
+    /// and bootstrap values stored in NHX annotations *(e.g. `[&&NHX:B=90]`)*.
+    /// Support values are stored in the [`Node::support`](super::Node::support) field.
+    /// # Example
+    /// ```
+    /// use phylotree::tree::Tree;
+    ///
+    /// let tree = Tree::from_ete3_format("(A:0.1,B:0.2,(C:0.3,D:0.4)90:0.5);").unwrap();
+    /// let ancestor = tree
+    ///     .get_common_ancestor(
+    ///         &tree.get_by_name("C").unwrap().id,
+    ///         &tree.get_by_name("D").unwrap().id,
+    ///     )
+    ///     .unwrap();
+    ///
+    /// assert_eq!(tree.get(&ancestor).unwrap().support, Some(90.0));
+    /// assert_eq!(tree.get(&ancestor).unwrap().name, None);
+    /// ```
+    pub fn from_ete3_format(newick: &str) -> Result<Self, NewickParseError> {
+        let mut tree = Self::from_newick(newick)?;
+
+        let ids: Vec<NodeId> = tree.preorder(&tree.get_root()?)?;
+        for id in ids {
+            let node = tree.get_mut(&id)?;
+            if node.is_tip() {
+                continue;
+            }
+
+            if let Some(support) = node.name.as_ref().and_then(|n| n.parse().ok()) {
+                node.support = Some(support);
+                node.name = None;
+            }
+            if let Some(support) = node.comment.as_ref().and_then(|c| c.parse().ok()) {
+                node.support = Some(support);
+                node.comment = None;
+            }
+            if node.support.is_none() {
+                node.support = node.nhx.as_ref().and_then(|nhx| nhx.bootstrap());
+            }
+        }
+
+        Ok(tree)
+    }
+
+    // Writes the subtree rooted at `root` in ETE3 format
+    fn to_ete3_impl(&self, root: &NodeId) -> Result<String, TreeError> {
+        let node = self.get(root)?;
+
+        let mut repr = if node.is_tip() {
+            node.name.clone().unwrap_or_default()
+        } else {
+            let children = node
+                .children
+                .iter()
+                .map(|child| self.to_ete3_impl(child))
+                .collect::<Result<Vec<_>, _>>()?;
+            let support = node.support.map(|s| s.to_string()).unwrap_or_default();
+
+            format!("({}){support}", children.join(","))
+        };
+
+        if let Some(length) = node.parent_edge {
+            repr += &format!(":{length}");
+        }
+        if let Some(nhx) = &node.nhx {
+            repr += &format!("[{nhx}]");
+        }
+
+        Ok(repr)
+    }
+
+    /// Writes the tree in the default newick format of the ETE3 toolkit *(format 0)*,
+    /// *i.e.* with leaf names, branch lengths and support values written as internal
+    /// node labels. Internal node names and plain comments are not written, NHX
+    /// annotations are kept.
+    /// # Example
+    /// ```
+    /// use phylotree::tree::Tree;
+    ///
+    /// let newick = "(A:0.1,B:0.2,(C:0.3,D:0.4)90:0.5);";
+    /// let tree = Tree::from_ete3_format(newick).unwrap();
+    ///
+    /// assert_eq!(tree.to_ete3_format().unwrap(), newick);
+    /// ```
+    pub fn to_ete3_format(&self) -> Result<String, TreeError> {
+        let root = self.get_root()?;
+        Ok(self.to_ete3_impl(&root)? + ";")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn supports(tree: &Tree) -> Vec<Option<f64>> {
+        let root = tree.get_root().unwrap();
+        tree.preorder(&root)
+            .unwrap()
+            .into_iter()
+            .map(|id| tree.get(&id).unwrap().support)
+            .collect()
+    }
+
+    #[test]
+    fn read_supports() {
+        let cases = [
+            "((A:0.1,B:0.2)90:0.5,(C:0.3,D:0.4)0.75:0.5);",
+            "((A:0.1,B:0.2):0.5[90],(C:0.3,D:0.4):0.5[0.75]);",
+            "((A:0.1,B:0.2):0.5[&&NHX:B=90],(C:0.3,D:0.4):0.5[&&NHX:B=0.75]);",
+        ];
+
+        for newick in cases {
+            let tree = Tree::from_ete3_format(newick).unwrap();
+            assert_eq!(
+                supports(&tree),
+                vec![None, Some(90.0), None, None, Some(0.75), None, None]
+            );
+            assert!(tree.get_leaf_names().iter().all(|name| name.is_some()));
+        }
+    }
+
+    #[test]
+    fn keep_internal_names() {
+        let tree = Tree::from_ete3_format("((A,B)AB:1,C)90;").unwrap();
+        let root = tree.get_root().unwrap();
+        let root = tree.get(&root).unwrap();
+        assert_eq!(root.support, Some(90.0));
+
+        let ab = tree.get_by_name("AB").unwrap();
+        assert_eq!(ab.support, None);
+        assert_eq!(ab.parent_edge, Some(1.0));
+    }
+
+    #[test]
+    fn ete3_roundtrip() {
+        let newicks = [
+            "(A:0.1,B:0.2,(C:0.3,D:0.4)90:0.5);",
+            "((A:1,B:1)1:0.5,(C:1,(D:1,E:1)0.5:0.25)0.9:0.5);",
+            "((A:1[&&NHX:S=human],B:1)100:0.5[&&NHX:B=100:D=N],C:2);",
+        ];
+
+        for newick in newicks {
+            let tree = Tree::from_ete3_format(newick).unwrap();
+            let written = tree.to_ete3_format().unwrap();
+            assert_eq!(written, newick);
+
+            let reread = Tree::from_ete3_format(&written).unwrap();
+            assert_eq!(supports(&reread), supports(&tree));
+        }
+    }
+}
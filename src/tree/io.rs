@@ -0,0 +1,208 @@
+//! Read files containing several newick trees
+use std::{
+    collections::VecDeque,
+    fs::File,
+    io::{BufRead, BufReader},
+    path::Path,
+};
+
+use super::{NewickParseError, Tree};
+
+/// A streaming reader for files with several newick trees, such as samples of
+/// trees from an MCMC posterior. Trees are separated by semicolons and may span
+/// several lines, several trees may also be written on the same line. Lines that
+/// start with `#` outside of a tree are treated as comments and skipped.
+///
+/// Trees are read lazily, one at a time, so that large files do not need to
+/// be loaded in memory at once.
+/// ```
+/// use phylotree::tree::TreeFileReader;
+///
+/// let content = "# Sampled trees\n((A,B),C);\n\n((A,C),B); ((B,C),A);\n";
+/// let trees: Vec<_> = TreeFileReader::new(content.as_bytes())
+///     .collect::<Result<_, _>>()
+///     .unwrap();
+///
+/// assert_eq!(trees.len(), 3);
+/// assert_eq!(trees[1].to_newick().unwrap(), "((A,C),B);");
+/// ```
+pub struct TreeFileReader<R: BufRead> {
+    reader: R,
+    buffer: String,
+    pending: VecDeque<String>,
+    in_comment: bool,
+    in_quotes: bool,
+    done: bool,
+}
+
+impl<R: BufRead> TreeFileReader<R> {
+    /// Creates a new reader from a buffered source
+    pub fn new(reader: R) -> Self {
+        Self {
+            reader,
+            buffer: String::new(),
+            pending: VecDeque::new(),
+            in_comment: false,
+            in_quotes: false,
+            done: false,
+        }
+    }
+
+    // Splits a line into newick strings, the end of the line is kept in the
+    // buffer if the last tree is not complete
+    fn split_line(&mut self, line: &str) {
+        if self.buffer.trim().is_empty() && line.trim_start().starts_with('#') {
+            return;
+        }
+
+        for c in line.chars() {
+            self.buffer.push(c);
+            match c {
+                '"' if !self.in_comment => self.in_quotes = !self.in_quotes,
+                '[' if !self.in_quotes => self.in_comment = true,
+                ']' if !self.in_quotes => self.in_comment = false,
+                ';' if !(self.in_quotes || self.in_comment) => {
+                    let newick = std::mem::take(&mut self.buffer);
+                    self.pending.push_back(newick.trim().to_string());
+                }
+                _ => {}
+            }
+        }
+    }
+}
+
+impl TreeFileReader<BufReader<File>> {
+    /// Opens a file to read trees from
+    pub fn open(path: &Path) -> Result<Self, NewickParseError> {
+        Ok(Self::new(BufReader::new(File::open(path)?)))
+    }
+}
+
+impl<R: BufRead> Iterator for TreeFileReader<R> {
+    type Item = Result<Tree, NewickParseError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let mut line = String::new();
+        while self.pending.is_empty() && !self.done {
+            line.clear();
+            match self.reader.read_line(&mut line) {
+                Ok(0) => {
+                    self.done = true;
+                    // Trailing tree without a closing semicolon
+                    if !self.buffer.trim().is_empty() {
+                        let newick = std::mem::take(&mut self.buffer);
+                        self.pending.push_back(newick.trim().to_string());
+                    }
+                }
+                Ok(_) => self.split_line(&line),
+                Err(e) => {
+                    self.done = true;
+                    return Some(Err(e.into()));
+                }
+            }
+        }
+
+        self.pending
+            .pop_front()
+            .map(|newick| Tree::from_newick(&newick))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn read_all(content: &str) -> Vec<Result<Tree, NewickParseError>> {
+        TreeFileReader::new(content.as_bytes()).collect()
+    }
+
+    #[test]
+    fn read_several_trees() {
+        let content = "#NEXUS-like comment\n\
+            ((A:1,B:1):1,C:2);\n\
+            \n\
+            # Another comment\n\
+            ((A:1,C:1):1,\n  B:2);((B:1,C:1):1,A:2);\n\
+            ((A:1,B:1)[a comment; with a semicolon]:1,\"C;D\":2);\n";
+
+        let newicks: Vec<_> = read_all(content)
+            .into_iter()
+            .map(|tree| tree.unwrap().to_newick().unwrap())
+            .collect();
+
+        assert_eq!(
+            newicks,
+            vec![
+                "((A:1,B:1):1,C:2);",
+                "((A:1,C:1):1,B:2);",
+                "((B:1,C:1):1,A:2);",
+                "((A:1,B:1):1[a comment; with a semicolon],\"C;D\":2);",
+            ]
+        );
+    }
+
+    #[test]
+    fn read_errors() {
+        assert!(read_all("").is_empty());
+        assert!(read_all("\n# only comments\n\n").is_empty());
+
+        let trees = read_all("((A,B),C);\n((A,B,C);\n(A,B);\n(A,C)");
+        assert_eq!(trees.len(), 4);
+        assert!(trees[0].is_ok());
+        assert!(matches!(
+            trees[1],
+            Err(NewickParseError::UnclosedBracket(0))
+        ));
+        assert!(trees[2].is_ok());
+        assert!(matches!(
+            trees[3],
+            Err(NewickParseError::NoClosingSemicolon(_))
+        ));
+
+        // Invalid UTF-8 cannot be read
+        let content: &[u8] = b"((A,B),C);\n((A,\xff),C);\n";
+        let trees: Vec<_> = TreeFileReader::new(content).collect();
+        assert_eq!(trees.len(), 2);
+        assert!(trees[0].is_ok());
+        assert!(matches!(trees[1], Err(NewickParseError::IoError(_))));
+
+        let path = std::env::temp_dir().join("phylotree_missing_trees_file.nwk");
+        assert!(matches!(
+            TreeFileReader::open(&path),
+            Err(NewickParseError::IoError(_))
+        ));
+    }
+
+    #[test]
+    fn read_large_file() {
+        let mut content = String::from("# 1000 sampled trees\n");
+        for i in 0..1000 {
+            // Every other tree spans several lines
+            if i % 2 == 0 {
+                content += &format!("((A:{i},B:1):1,(C:1,D:1):1);\n");
+            } else {
+                content += &format!("((A:{i},C:1):1,\n(B:1,D:1):1);\n");
+            }
+        }
+
+        let path = std::env::temp_dir().join("phylotree_read_large_file.trees");
+        std::fs::write(&path, content).unwrap();
+
+        let mut n_trees = 0;
+        for (i, tree) in TreeFileReader::open(&path).unwrap().enumerate() {
+            let tree = tree.unwrap();
+            let a = tree.get_by_name("A").unwrap();
+            assert_eq!(a.parent_edge, Some(i as f64));
+            assert_eq!(tree.n_leaves(), 4);
+            n_trees += 1;
+        }
+        assert_eq!(n_trees, 1000);
+
+        let trees = Tree::many_from_file(&path).unwrap();
+        assert_eq!(trees.len(), 1000);
+        assert_eq!(trees[0].robinson_foulds(&trees[2]).unwrap(), 0);
+        assert_ne!(trees[0].robinson_foulds(&trees[1]).unwrap(), 0);
+
+        std::fs::remove_file(&path).unwrap();
+    }
+}
@@ -5,13 +5,32 @@
 //!  - The [`Tree`] struct that holds a collection of [`Node`] objects.
 //!
 
+/// A module to read trees and node annotations from BEAST output files
+pub mod beast;
+mod builder;
+/// A module to build consensus trees
+pub mod consensus;
 /// A module to draw phylogenetic trees
 pub mod draw;
+mod ete3;
+mod io;
 mod node;
+/// A module to read and write trees in the PhyloXML format
+#[cfg(feature = "phyloxml")]
+pub mod phyloxml;
+/// A module to estimate the posterior probabilities of topologies from samples of trees
+pub mod statistics;
 mod tree_impl;
+mod treeset;
 
-pub use self::node::{Node, NodeError};
-pub use self::tree_impl::{Comparison, NewickParseError, Tree, TreeError};
+pub use self::builder::TreeBuilder;
+pub use self::io::TreeFileReader;
+pub use self::node::{Calibration, NhxAnnotation, Node, NodeError, NodeIdentity};
+pub use self::tree_impl::{
+    BalanceStats, BranchLengthStats, Comparison, NewickParseError, Partition, PartitionSet,
+    RttResult, Tree, TreeError,
+};
+pub use self::treeset::TreeSet;
 
 /// A type that represents Identifiers of [`Node`] objects
 /// within phylogenetic [`Tree`] object.
@@ -25,6 +44,16 @@ pub type EdgeLength = f64;
 /// given edge within a phylogenetic [`Tree`] object.
 pub type EdgeDepth = usize;
 
+/// Subtree to exchange when performing a nearest neighbour interchange
+/// with [`Tree::nni()`]
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum NniSwap {
+    /// Swap the sibling subtree with the first child of the lower node
+    Left,
+    /// Swap the sibling subtree with the second child of the lower node
+    Right,
+}
+
 /// Newick output format
 #[derive(Debug, Copy, Clone)]
 pub enum NewickFormat {
@@ -2,6 +2,7 @@ use std::{
     cell::RefCell,
     collections::HashMap,
     fmt::{Debug, Display},
+    hash::{Hash, Hasher},
 };
 
 use thiserror::Error;
@@ -32,10 +33,160 @@ pub enum NodeError {
     },
 }
 
+/// How branch lengths are formatted when writing newick strings
+#[derive(Debug, Clone, Copy)]
+pub(crate) enum LengthPrecision {
+    /// Shortest representation that reads back to the same value
+    Shortest,
+    /// Fixed number of digits after the decimal point
+    Decimals(usize),
+    /// Fixed number of significant digits
+    Significant(usize),
+}
+
+impl LengthPrecision {
+    fn format(&self, length: EdgeLength) -> String {
+        match *self {
+            Self::Shortest => format!("{length}"),
+            Self::Decimals(precision) => format!("{length:.precision$}"),
+            Self::Significant(digits) => {
+                let digits = digits.max(1);
+                if length == 0.0 || !length.is_finite() {
+                    let decimals = digits - 1;
+                    return format!("{length:.decimals$}");
+                }
+                // Rounding can carry over to the next power of 10 (e.g. 9.99 -> 10.0)
+                let magnitude = length.abs().log10().floor() as i64;
+                let decimals = (digits as i64 - 1 - magnitude).max(0) as usize;
+                let rounded: f64 = format!("{length:.decimals$}").parse().unwrap();
+                let magnitude = rounded.abs().log10().floor() as i64;
+                let decimals = (digits as i64 - 1 - magnitude).max(0) as usize;
+                format!("{length:.decimals$}")
+            }
+        }
+    }
+}
+
+/// Annotations stored in a New Hampshire eXtended (NHX) comment,
+/// *i.e.* `[&&NHX:key=value:key=value]`. The key/value pairs are kept in the
+/// order they were read so that writing the annotation back gives the same comment.
+/// ```
+/// use phylotree::tree::NhxAnnotation;
+///
+/// let nhx = NhxAnnotation::from_comment("&&NHX:S=human:D=N:B=100:flag=Red:X").unwrap();
+///
+/// assert_eq!(nhx.species(), Some("human"));
+/// assert_eq!(nhx.duplication(), Some(false));
+/// assert_eq!(nhx.bootstrap(), Some(100.0));
+/// assert_eq!(nhx.get("flag"), Some("Red"));
+///
+/// assert_eq!(nhx.to_string(), "&&NHX:S=human:D=N:B=100:flag=Red:X");
+/// ```
+#[derive(Debug, Clone, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct NhxAnnotation {
+    /// Key/value pairs in the order they were read, keys without a value have `None`
+    pub pairs: Vec<(String, Option<String>)>,
+}
+
+impl NhxAnnotation {
+    /// Parses the content of a NHX comment *(without the square brackets)*.
+    /// Returns `None` if the comment does not start with `&&NHX`.
+    pub fn from_comment(comment: &str) -> Option<Self> {
+        let fields = comment.strip_prefix("&&NHX")?;
+        let pairs = fields
+            .split(':')
+            .filter(|f| !f.is_empty())
+            .map(|field| match field.split_once('=') {
+                Some((key, value)) => (key.into(), Some(value.into())),
+                None => (field.into(), None),
+            })
+            .collect();
+
+        Some(Self { pairs })
+    }
+
+    /// Returns the value of the first pair with the given key, `None` if the
+    /// key is missing or has no value.
+    pub fn get(&self, key: &str) -> Option<&str> {
+        self.pairs
+            .iter()
+            .find(|(k, _)| k == key)
+            .and_then(|(_, value)| value.as_deref())
+    }
+
+    /// Sets the value of the first pair with the given key, or adds a new pair
+    /// at the end of the annotation if the key is missing.
+    pub fn set(&mut self, key: &str, value: Option<String>) {
+        match self.pairs.iter_mut().find(|(k, _)| k == key) {
+            Some((_, old)) => *old = value,
+            None => self.pairs.push((key.into(), value)),
+        }
+    }
+
+    /// Bootstrap value *(`B` key)*
+    pub fn bootstrap(&self) -> Option<f64> {
+        self.get("B").and_then(|v| v.parse().ok())
+    }
+
+    /// Species name *(`S` key)*
+    pub fn species(&self) -> Option<&str> {
+        self.get("S")
+    }
+
+    /// Whether the node is a duplication event *(`D` key)*
+    pub fn duplication(&self) -> Option<bool> {
+        match self.get("D")? {
+            "Y" | "T" => Some(true),
+            "N" | "F" => Some(false),
+            _ => None,
+        }
+    }
+
+    /// EC number *(`E` key)*
+    pub fn ec_number(&self) -> Option<&str> {
+        self.get("E")
+    }
+}
+
+impl Display for NhxAnnotation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "&&NHX")?;
+        for (key, value) in self.pairs.iter() {
+            match value {
+                Some(value) => write!(f, ":{key}={value}")?,
+                None => write!(f, ":{key}")?,
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Age constraint of a node used for fossil-calibrated molecular dating
+/// *(see [`Tree::set_calibration()`](super::Tree::set_calibration))*
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Calibration {
+    /// Minimum age of the node
+    pub min: f64,
+    /// Maximum age of the node, `None` if the age is not bounded
+    pub max: Option<f64>,
+}
+
+impl Display for Calibration {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.max {
+            Some(max) => write!(f, "{{{},{max}}}", self.min),
+            None => write!(f, "{{{}}}", self.min),
+        }
+    }
+}
+
 use crate::tree::tree_impl::IdentityHasher;
 type BuildIdentityHasher = core::hash::BuildHasherDefault<IdentityHasher>;
 
 #[derive(Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 /// A node of the Tree
 pub struct Node {
     /// Index of the node
@@ -50,14 +201,64 @@ pub struct Node {
     pub parent_edge: Option<EdgeLength>,
     /// Optional comment attached to node
     pub comment: Option<String>,
+    /// Optional support value of the branch between parent and node
+    pub support: Option<f64>,
+    /// Optional aligned sequence of the node
+    /// *(see [`Tree::annotate_sequences()`](super::Tree::annotate_sequences))*
+    pub sequence: Option<Vec<char>>,
+    // Number of edges to root
+    depth: usize,
+    // Whether the node is deleted or not
+    pub(crate) deleted: bool,
+    // Table-like fields are declared last so that nodes can be serialized to TOML
+    /// Optional NHX annotations attached to node
+    pub nhx: Option<NhxAnnotation>,
+    /// Optional age calibration of the node
+    pub calibration: Option<Calibration>,
     /// lenght of branches between node and children
+    #[cfg_attr(feature = "serde", serde(default, with = "child_edges_serde"))]
     pub(crate) child_edges: Option<HashMap<NodeId, EdgeLength>>,
     /// Distance to descendants of this node
+    #[cfg_attr(feature = "serde", serde(skip))]
     pub(crate) subtree_distances: RefCell<Option<HashMap<NodeId, EdgeLength, BuildIdentityHasher>>>,
-    /// Number of edges to root
-    pub(crate) depth: usize,
-    // Whether the node is deleted or not
-    pub(crate) deleted: bool,
+}
+
+/// Child edges are (de)serialized with string keys so that
+/// they can be written to formats like TOML
+#[cfg(feature = "serde")]
+mod child_edges_serde {
+    use super::{EdgeLength, NodeId};
+    use serde::{de::Error, Deserialize, Deserializer, Serialize, Serializer};
+    use std::collections::{BTreeMap, HashMap};
+
+    pub fn serialize<S: Serializer>(
+        edges: &Option<HashMap<NodeId, EdgeLength>>,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        edges
+            .as_ref()
+            .map(|edges| {
+                edges
+                    .iter()
+                    .map(|(k, v)| (k.to_string(), *v))
+                    .collect::<BTreeMap<_, _>>()
+            })
+            .serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Option<HashMap<NodeId, EdgeLength>>, D::Error> {
+        let edges: Option<HashMap<String, EdgeLength>> = Option::deserialize(deserializer)?;
+        edges
+            .map(|edges| {
+                edges
+                    .into_iter()
+                    .map(|(k, v)| k.parse().map(|k| (k, v)).map_err(D::Error::custom))
+                    .collect()
+            })
+            .transpose()
+    }
 }
 
 impl Node {
@@ -72,6 +273,10 @@ impl Node {
             child_edges: None,
             subtree_distances: RefCell::new(None),
             comment: None,
+            nhx: None,
+            calibration: None,
+            support: None,
+            sequence: None,
             depth: 0,
             deleted: false,
         }
@@ -88,6 +293,10 @@ impl Node {
             child_edges: None,
             subtree_distances: RefCell::new(None),
             comment: None,
+            nhx: None,
+            calibration: None,
+            support: None,
+            sequence: None,
             depth: 0,
             deleted: false,
         }
@@ -98,6 +307,19 @@ impl Node {
         self.name = Some(name);
     }
 
+    /// Sets the comment of the node. If the comment is a NHX comment
+    /// *(i.e. it starts with `&&NHX`)* then it is parsed and stored in [`Node::nhx`]
+    /// instead.
+    pub fn set_comment(&mut self, comment: Option<String>) {
+        match comment.as_deref().and_then(NhxAnnotation::from_comment) {
+            Some(nhx) => {
+                self.nhx = Some(nhx);
+                self.comment = None;
+            }
+            None => self.comment = comment,
+        }
+    }
+
     /// Sets the internal Node id
     pub fn set_id(&mut self, id: NodeId) {
         self.id = id;
@@ -110,12 +332,13 @@ impl Node {
         self.parent_edge = parent_edge;
     }
 
-    /// Sets the depth of the node
+    /// Sets the depth of the node. Depths are set when nodes are added to a tree
+    /// and can be recomputed with [`Tree::reset_depths()`](super::Tree::reset_depths)
     pub fn set_depth(&mut self, depth: usize) {
         self.depth = depth;
     }
 
-    /// Gets the depth of the node
+    /// Gets the depth of the node, *i.e.* the number of edges between the node and the root
     pub fn get_depth(&self) -> usize {
         self.depth
     }
@@ -187,6 +410,30 @@ impl Node {
         Ok(())
     }
 
+    /// Replaces a child of the node by another one, keeping its position
+    /// in the children vector.
+    pub(crate) fn replace_child(
+        &mut self,
+        old: &NodeId,
+        new: NodeId,
+        edge: Option<EdgeLength>,
+    ) -> Result<(), NodeError> {
+        let vec_index = self
+            .children
+            .iter()
+            .position(|node_id| node_id == old)
+            .ok_or(NodeError::HasNoChild {
+                parent: self.id,
+                child: *old,
+            })?;
+
+        self.children[vec_index] = new;
+        self.child_edges.as_mut().map(|edges| edges.remove(old));
+        self.set_child_edge(&new, edge);
+
+        Ok(())
+    }
+
     /// Rescales parent and child edges by a factor.
     pub(crate) fn rescale_edges(&mut self, factor: f64) {
         self.parent_edge = self.parent_edge.map(|edge| edge * factor);
@@ -195,6 +442,13 @@ impl Node {
                 *v *= factor;
             }
         }
+        *self.subtree_distances.borrow_mut() = None;
+    }
+
+    /// Returns a wrapper around the node that compares and hashes it
+    /// by name, branch length and comment only (see [`NodeIdentity`]).
+    pub fn identity(&self) -> NodeIdentity<'_> {
+        NodeIdentity(self)
     }
 
     /// Check if the node is a tip node
@@ -208,24 +462,46 @@ impl Node {
     }
 
     fn format_name(&self) -> String {
-        self.name.clone().unwrap_or_default()
+        match (&self.name, self.support) {
+            (Some(name), _) => name.clone(),
+            (None, Some(support)) if !self.is_tip() => support.to_string(),
+            _ => String::new(),
+        }
     }
 
-    fn format_length(&self) -> String {
+    fn format_length(&self, precision: LengthPrecision) -> String {
         self.parent_edge
-            .map(|v| format!(":{v}"))
+            .map(|v| format!(":{}", precision.format(v)))
             .unwrap_or_default()
     }
 
     fn format_comment(&self) -> String {
-        self.comment
+        let comment = self
+            .comment
             .clone()
             .map(|v| format!("[{v}]"))
-            .unwrap_or_default()
+            .unwrap_or_default();
+        let nhx = self
+            .nhx
+            .as_ref()
+            .map(|v| format!("[{v}]"))
+            .unwrap_or_default();
+
+        comment + &nhx
     }
 
     /// Returns String with node in newick format
     pub fn to_newick(&self, format: NewickFormat) -> String {
+        self.to_newick_with_precision(format, LengthPrecision::Shortest)
+    }
+
+    /// Returns String with node in newick format, with branch
+    /// lengths formatted according to `precision`
+    pub(crate) fn to_newick_with_precision(
+        &self,
+        format: NewickFormat,
+        precision: LengthPrecision,
+    ) -> String {
         let mut repr = String::new();
 
         match format {
@@ -247,15 +523,15 @@ impl Node {
             NewickFormat::AllFields
             | NewickFormat::NoComments
             | NewickFormat::OnlyLengths
-            | NewickFormat::AllLengthsLeafNames => repr += &self.format_length(),
+            | NewickFormat::AllLengthsLeafNames => repr += &self.format_length(precision),
             NewickFormat::InternalLengthsLeafNames => {
                 if !self.is_tip() {
-                    repr += &self.format_length()
+                    repr += &self.format_length(precision)
                 }
             }
             NewickFormat::LeafLengthsLeafNames | NewickFormat::LeafLengthsAllNames => {
                 if self.is_tip() {
-                    repr += &self.format_length()
+                    repr += &self.format_length(precision)
                 }
             }
             _ => (),
@@ -324,3 +600,55 @@ impl Debug for Node {
         )
     }
 }
+
+/// A wrapper around a [`Node`] reference that compares and hashes nodes using only
+/// their name, parent branch length and comment. Unlike [`Node`] equality, the
+/// position of the node in its tree *(id, depth, parent and children)* is ignored,
+/// so the same node can be matched across differently parsed trees, or used as a
+/// [`HashMap`] key.
+/// ```
+/// use std::collections::HashSet;
+/// use phylotree::tree::Tree;
+///
+/// let tree1 = Tree::from_newick("((A:1,B:2)C:1,D:3)E;").unwrap();
+/// let tree2 = Tree::from_newick("(D:3,(B:2,A:1)C:1)E;").unwrap();
+///
+/// let a1 = tree1.get_by_name("A").unwrap();
+/// let a2 = tree2.get_by_name("A").unwrap();
+/// assert_ne!(a1.id, a2.id);
+/// assert_eq!(a1.identity(), a2.identity());
+///
+/// let nodes1: HashSet<_> = tree1.node_iter().map(|n| n.identity()).collect();
+/// let nodes2: HashSet<_> = tree2.node_iter().map(|n| n.identity()).collect();
+/// assert_eq!(nodes1, nodes2);
+/// ```
+#[derive(Debug, Clone, Copy)]
+pub struct NodeIdentity<'a>(pub &'a Node);
+
+impl NodeIdentity<'_> {
+    // Branch lengths are compared bitwise so that equality is consistent with hashing,
+    // `-0.0` is normalized to `0.0` to match float equality.
+    fn length_bits(&self) -> Option<u64> {
+        self.0
+            .parent_edge
+            .map(|l| if l == 0.0 { 0.0f64 } else { l }.to_bits())
+    }
+}
+
+impl PartialEq for NodeIdentity<'_> {
+    fn eq(&self, other: &Self) -> bool {
+        self.0.name == other.0.name
+            && self.length_bits() == other.length_bits()
+            && self.0.comment == other.0.comment
+    }
+}
+
+impl Eq for NodeIdentity<'_> {}
+
+impl Hash for NodeIdentity<'_> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.0.name.hash(state);
+        self.length_bits().hash(state);
+        self.0.comment.hash(state);
+    }
+}
@@ -0,0 +1,382 @@
+//! Read and write trees in the [PhyloXML](http://www.phyloxml.org) format.
+//!
+//! Clade names, branch lengths and confidence values are stored in the [`Node::name`],
+//! [`Node::parent_edge`] and [`Node::support`] fields. Other clade elements
+//! *(e.g. `<taxonomy>` or `<sequence>`)* are stored verbatim in [`Node::comment`], and
+//! written back as is if the comment starts with `<`.
+//!
+//! This module is only available with the `phyloxml` feature.
+use std::num::ParseFloatError;
+
+use quick_xml::events::{BytesDecl, BytesText, Event};
+use quick_xml::{Reader, Writer};
+use thiserror::Error;
+
+use super::{Node, NodeId, Tree, TreeError};
+
+/// Errors that can occur when reading a PhyloXML document
+#[derive(Error, Debug)]
+pub enum PhyloXmlError {
+    /// The document is not valid XML
+    #[error("Could not parse the XML document: {0}")]
+    XmlError(#[from] quick_xml::Error),
+    /// There was a [`TreeError`] when building the tree
+    #[error("Problem with building the tree.")]
+    TreeError(#[from] TreeError),
+    /// A branch length or confidence value is not a number
+    #[error("Could not parse a numeric value")]
+    FloatError(#[from] ParseFloatError),
+    /// The document does not contain a `<phylogeny>` element
+    #[error("The document does not contain a phylogeny")]
+    NoPhylogeny,
+}
+
+// Reads the text content of the element that was just opened
+fn read_text(reader: &mut Reader<&[u8]>, end: &[u8]) -> Result<String, PhyloXmlError> {
+    let mut text = String::new();
+    loop {
+        match reader.read_event()? {
+            Event::Text(t) => text += &t.unescape()?,
+            Event::CData(t) => text += &String::from_utf8_lossy(&t.into_inner()),
+            Event::End(e) if e.name().as_ref() == end => return Ok(text),
+            Event::Eof => {
+                return Err(quick_xml::Error::UnexpectedEof(end.escape_ascii().to_string()).into())
+            }
+            _ => {}
+        }
+    }
+}
+
+impl Tree {
+    /// Reads the first phylogeny of a PhyloXML document.
+    /// # Example
+    /// ```
+    /// use phylotree::tree::Tree;
+    ///
+    /// let xml = r#"<?xml version="1.0" encoding="UTF-8"?>
+    /// <phyloxml xmlns="http://www.phyloxml.org">
+    ///   <phylogeny rooted="true">
+    ///     <clade>
+    ///       <clade>
+    ///         <branch_length>0.5</branch_length>
+    ///         <confidence type="bootstrap">90</confidence>
+    ///         <clade><name>A</name><branch_length>0.1</branch_length></clade>
+    ///         <clade><name>B</name><branch_length>0.2</branch_length></clade>
+    ///       </clade>
+    ///       <clade>
+    ///         <name>C</name>
+    ///         <branch_length>0.3</branch_length>
+    ///         <taxonomy><scientific_name>Homo sapiens</scientific_name></taxonomy>
+    ///       </clade>
+    ///     </clade>
+    ///   </phylogeny>
+    /// </phyloxml>"#;
+    ///
+    /// let tree = Tree::from_phyloxml(xml).unwrap();
+    /// assert_eq!(
+    ///     tree.to_newick().unwrap(),
+    ///     "((A:0.1,B:0.2)90:0.5,C:0.3[<taxonomy><scientific_name>Homo sapiens</scientific_name></taxonomy>]);"
+    /// );
+    ///
+    /// let ancestor = tree.get(&tree.get_by_name("A").unwrap().parent.unwrap()).unwrap();
+    /// assert_eq!(ancestor.support, Some(90.0));
+    /// ```
+    pub fn from_phyloxml(xml: &str) -> Result<Self, PhyloXmlError> {
+        let mut reader = Reader::from_str(xml);
+        reader.trim_text(true);
+
+        let mut tree = Tree::new();
+        let mut stack: Vec<NodeId> = vec![];
+        let mut in_phylogeny = false;
+
+        loop {
+            let start = reader.buffer_position();
+            let event = reader.read_event()?;
+
+            match &event {
+                Event::Start(e) if !in_phylogeny => {
+                    in_phylogeny = e.name().as_ref() == b"phylogeny";
+                }
+                Event::Start(e) | Event::Empty(e) if e.name().as_ref() == b"clade" => {
+                    let id = match stack.last() {
+                        None => tree.add(Node::new()),
+                        Some(parent) => tree.add_child(Node::new(), *parent, None)?,
+                    };
+                    if let Some(length) = e.try_get_attribute("branch_length")? {
+                        let length = length.unescape_value()?.parse()?;
+                        set_branch_length(&mut tree, &id, length)?;
+                    }
+                    if matches!(event, Event::Start(_)) {
+                        stack.push(id);
+                    }
+                }
+                Event::Start(e) => {
+                    let name = e.name();
+                    let Some(id) = stack.last() else {
+                        // Phylogeny level elements are ignored
+                        reader.read_to_end(name)?;
+                        continue;
+                    };
+                    match name.as_ref() {
+                        b"name" => {
+                            let text = read_text(&mut reader, b"name")?;
+                            tree.get_mut(id)?.name = Some(text);
+                        }
+                        b"branch_length" => {
+                            let length = read_text(&mut reader, b"branch_length")?;
+                            set_branch_length(&mut tree, id, length.trim().parse()?)?;
+                        }
+                        b"confidence" => {
+                            let value = read_text(&mut reader, b"confidence")?;
+                            tree.get_mut(id)?.support = Some(value.trim().parse()?);
+                        }
+                        _ => {
+                            reader.read_to_end(name)?;
+                            let raw = &xml[start..reader.buffer_position()];
+                            append_comment(tree.get_mut(id)?, raw.trim());
+                        }
+                    }
+                }
+                Event::Empty(_) => {
+                    if let Some(id) = stack.last() {
+                        let raw = &xml[start..reader.buffer_position()];
+                        append_comment(tree.get_mut(id)?, raw.trim());
+                    }
+                }
+                Event::End(e) if e.name().as_ref() == b"clade" => {
+                    stack.pop();
+                }
+                Event::End(e) if e.name().as_ref() == b"phylogeny" => break,
+                Event::Eof => break,
+                _ => {}
+            }
+        }
+
+        if tree.size() == 0 {
+            return Err(PhyloXmlError::NoPhylogeny);
+        }
+        tree.reset_depths()?;
+
+        Ok(tree)
+    }
+
+    // Writes the clade rooted at a given node
+    fn write_clade(&self, writer: &mut Writer<Vec<u8>>, id: &NodeId) -> Result<(), PhyloXmlError> {
+        let node = self.get(id)?;
+
+        writer.create_element("clade").write_inner_content(
+            |writer| -> Result<(), PhyloXmlError> {
+                if let Some(name) = &node.name {
+                    writer
+                        .create_element("name")
+                        .write_text_content(BytesText::new(name))?;
+                }
+                if let Some(length) = node.parent_edge {
+                    writer
+                        .create_element("branch_length")
+                        .write_text_content(BytesText::new(&length.to_string()))?;
+                }
+                if let Some(support) = node.support {
+                    writer
+                        .create_element("confidence")
+                        .with_attribute(("type", "support"))
+                        .write_text_content(BytesText::new(&support.to_string()))?;
+                }
+                if let Some(comment) = node.comment.as_ref().filter(|c| c.starts_with('<')) {
+                    writer.write_event(Event::Text(BytesText::from_escaped(comment.as_str())))?;
+                }
+                for child in node.children.iter() {
+                    self.write_clade(writer, child)?;
+                }
+                Ok(())
+            },
+        )?;
+
+        Ok(())
+    }
+
+    /// Writes the tree as a PhyloXML document.
+    /// # Example
+    /// ```
+    /// use phylotree::tree::Tree;
+    ///
+    /// let tree = Tree::from_newick("((A:0.1,B:0.2)E:0.5,C:0.3);").unwrap();
+    /// let xml = tree.to_phyloxml().unwrap();
+    ///
+    /// assert!(xml.contains("<phylogeny rooted=\"true\">"));
+    /// assert!(xml.contains("<name>E</name>"));
+    ///
+    /// let read = Tree::from_phyloxml(&xml).unwrap();
+    /// assert_eq!(read.to_newick().unwrap(), tree.to_newick().unwrap());
+    /// ```
+    pub fn to_phyloxml(&self) -> Result<String, TreeError> {
+        let root = self.get_root()?;
+        let rooted = if self.is_rooted()? { "true" } else { "false" };
+
+        let mut writer = Writer::new_with_indent(Vec::new(), b' ', 2);
+        let result = writer
+            .write_event(Event::Decl(BytesDecl::new("1.0", Some("UTF-8"), None)))
+            .map_err(PhyloXmlError::from)
+            .and_then(|_| {
+                writer
+                    .create_element("phyloxml")
+                    .with_attributes([
+                        ("xmlns:xsi", "http://www.w3.org/2001/XMLSchema-instance"),
+                        (
+                            "xsi:schemaLocation",
+                            "http://www.phyloxml.org http://www.phyloxml.org/1.10/phyloxml.xsd",
+                        ),
+                        ("xmlns", "http://www.phyloxml.org"),
+                    ])
+                    .write_inner_content(|writer| -> Result<(), PhyloXmlError> {
+                        writer
+                            .create_element("phylogeny")
+                            .with_attribute(("rooted", rooted))
+                            .write_inner_content(|writer| self.write_clade(writer, &root))?;
+                        Ok(())
+                    })?;
+                Ok(())
+            });
+
+        match result {
+            Ok(()) => Ok(String::from_utf8_lossy(&writer.into_inner()).into_owned()),
+            Err(PhyloXmlError::TreeError(e)) => Err(e),
+            Err(_) => Err(TreeError::GeneralError(
+                "Could not write the PhyloXML document",
+            )),
+        }
+    }
+}
+
+// Sets the length of the branch above a node, in the node and its parent
+fn set_branch_length(tree: &mut Tree, id: &NodeId, length: f64) -> Result<(), TreeError> {
+    let node = tree.get_mut(id)?;
+    node.parent_edge = Some(length);
+    if let Some(parent) = node.parent {
+        tree.get_mut(&parent)?.set_child_edge(id, Some(length));
+    }
+
+    Ok(())
+}
+
+// Stores unrecognised clade elements verbatim in the node comment
+fn append_comment(node: &mut Node, raw: &str) {
+    match node.comment.as_mut() {
+        Some(comment) => comment.push_str(raw),
+        None => node.comment = Some(raw.to_string()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Adapted from the examples of the PhyloXML documentation
+    // http://www.phyloxml.org/examples/phyloxml_examples.xml
+    const SAMPLE: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<phyloxml xmlns:xsi="http://www.w3.org/2001/XMLSchema-instance" xsi:schemaLocation="http://www.phyloxml.org http://www.phyloxml.org/1.10/phyloxml.xsd" xmlns="http://www.phyloxml.org">
+   <phylogeny rooted="true">
+      <name>example from Prof. Joe Felsenstein's book "Inferring Phylogenies"</name>
+      <description>phyloXML allows to use either a "branch_length" attribute or element to indicate branch lengths.</description>
+      <clade>
+         <clade branch_length="0.06">
+            <confidence type="bootstrap">89</confidence>
+            <clade branch_length="0.102">
+               <name>A</name>
+               <taxonomy>
+                  <id provider="ncbi">6645</id>
+                  <scientific_name>Octopus vulgaris</scientific_name>
+               </taxonomy>
+            </clade>
+            <clade branch_length="0.23">
+               <name>B &amp; C</name>
+               <sequence type="protein">
+                  <mol_seq is_aligned="false">MNGTEGPNFYVPFSNKTGVV</mol_seq>
+               </sequence>
+            </clade>
+         </clade>
+         <clade>
+            <name>D</name>
+            <branch_length>0.4</branch_length>
+            <color><red>255</red><green>0</green><blue>0</blue></color>
+         </clade>
+      </clade>
+   </phylogeny>
+</phyloxml>"#;
+
+    #[test]
+    fn read_sample() {
+        let tree = Tree::from_phyloxml(SAMPLE).unwrap();
+
+        assert_eq!(tree.n_leaves(), 3);
+        assert!(tree.is_rooted().unwrap());
+        assert_eq!(
+            tree.to_formatted_newick(crate::tree::NewickFormat::NoComments)
+                .unwrap(),
+            "((A:0.102,B & C:0.23)89:0.06,D:0.4);"
+        );
+
+        let a = tree.get_by_name("A").unwrap();
+        let comment = a.comment.as_ref().unwrap();
+        assert!(comment.starts_with("<taxonomy>"));
+        assert!(comment.contains("<scientific_name>Octopus vulgaris</scientific_name>"));
+        assert!(comment.ends_with("</taxonomy>"));
+
+        let parent = tree.get(&a.parent.unwrap()).unwrap();
+        assert_eq!(parent.support, Some(89.0));
+        assert_eq!(parent.get_child_edge(&a.id), Some(0.102));
+
+        let bc = tree.get_by_name("B & C").unwrap();
+        assert!(bc
+            .comment
+            .as_ref()
+            .unwrap()
+            .contains("MNGTEGPNFYVPFSNKTGVV"));
+    }
+
+    #[test]
+    fn phyloxml_roundtrip() {
+        let tree = Tree::from_phyloxml(SAMPLE).unwrap();
+        let xml = tree.to_phyloxml().unwrap();
+        let reread = Tree::from_phyloxml(&xml).unwrap();
+
+        assert_eq!(reread.to_newick().unwrap(), tree.to_newick().unwrap());
+        let nodes = |tree: &Tree| -> Vec<Node> {
+            tree.preorder(&tree.get_root().unwrap())
+                .unwrap()
+                .iter()
+                .map(|id| tree.get(id).unwrap().clone())
+                .collect()
+        };
+        for (n1, n2) in nodes(&tree).iter().zip(nodes(&reread).iter()) {
+            assert_eq!(n1.name, n2.name);
+            assert_eq!(n1.parent_edge, n2.parent_edge);
+            assert_eq!(n1.support, n2.support);
+            assert_eq!(n1.comment, n2.comment);
+            assert_eq!(n1.get_depth(), n2.get_depth());
+        }
+
+        // Plain newick comments are not written
+        let tree = Tree::from_newick("((A:1[plain comment],B:1)90:1,C:2);").unwrap();
+        let reread = Tree::from_phyloxml(&tree.to_phyloxml().unwrap()).unwrap();
+        assert_eq!(reread.to_newick().unwrap(), "((A:1,B:1)90:1,C:2);");
+    }
+
+    #[test]
+    fn phyloxml_errors() {
+        assert!(matches!(
+            Tree::from_phyloxml("<phyloxml></phyloxml>"),
+            Err(PhyloXmlError::NoPhylogeny)
+        ));
+        assert!(matches!(
+            Tree::from_phyloxml(
+                "<phyloxml><phylogeny><clade><branch_length>abc</branch_length></clade></phylogeny></phyloxml>"
+            ),
+            Err(PhyloXmlError::FloatError(_))
+        ));
+        assert!(matches!(
+            Tree::from_phyloxml("<phyloxml><phylogeny><clade><name>A</clade>"),
+            Err(PhyloXmlError::XmlError(_))
+        ));
+    }
+}
@@ -0,0 +1,200 @@
+//! A module to estimate the posterior probabilities of topologies from samples of trees
+use std::collections::HashMap;
+
+use fixedbitset::FixedBitSet;
+
+use super::consensus::to_cluster;
+use super::{Tree, TreeError};
+
+/// Counts the trees of a posterior sample that have the same topology as `query`
+/// *(see [`Tree::topology_equal()`])*, and returns this count with the corresponding
+/// proportion of the sample, *i.e.* the estimated posterior probability of the topology.
+/// All the trees must have the same set of uniquely named leaves.
+/// ```
+/// use phylotree::tree::Tree;
+/// use phylotree::tree::statistics::count_topologies_matching;
+///
+/// let posterior: Vec<_> = ["((A,B),(C,D));", "((B,A),(D,C));", "((A,C),(B,D));", "(A,B,(C,D));"]
+///     .iter()
+///     .map(|newick| Tree::from_newick(newick).unwrap())
+///     .collect();
+/// let query = Tree::from_newick("((C,D),(A,B));").unwrap();
+///
+/// assert_eq!(count_topologies_matching(&query, &posterior).unwrap(), (3, 0.75));
+/// ```
+pub fn count_topologies_matching(
+    query: &Tree,
+    posterior: &[Tree],
+) -> Result<(usize, f64), TreeError> {
+    if posterior.is_empty() {
+        return Err(TreeError::IsEmpty);
+    }
+
+    let mut count = 0;
+    for tree in posterior {
+        if query.topology_equal(tree)? {
+            count += 1;
+        }
+    }
+
+    Ok((count, count as f64 / posterior.len() as f64))
+}
+
+/// Groups the trees of a posterior sample by topology, and returns the frequency of each
+/// topology keyed by a canonical newick string. In this string, the first leaf in
+/// alphabetical order is a child of the root and children are sorted alphabetically by
+/// their own newick representation, so that trees with the same unrooted topology have
+/// the same string. All the trees must have the same set of uniquely named leaves.
+/// ```
+/// use phylotree::tree::Tree;
+/// use phylotree::tree::statistics::topology_frequency_map;
+///
+/// let posterior: Vec<_> = ["((A,B),(C,D));", "((B,A),(D,C));", "((A,C),(B,D));", "(A,B,(C,D));"]
+///     .iter()
+///     .map(|newick| Tree::from_newick(newick).unwrap())
+///     .collect();
+///
+/// let frequencies = topology_frequency_map(&posterior).unwrap();
+///
+/// assert_eq!(frequencies.len(), 2);
+/// assert_eq!(frequencies["((C,D),A,B);"], 0.75);
+/// assert_eq!(frequencies["((B,D),A,C);"], 0.25);
+/// ```
+pub fn topology_frequency_map(posterior: &[Tree]) -> Result<HashMap<String, f64>, TreeError> {
+    let first = posterior.first().ok_or(TreeError::IsEmpty)?;
+    let leaf_names = first.get_leaf_index()?;
+
+    let mut counts: HashMap<String, usize> = HashMap::new();
+    for tree in posterior {
+        if tree.get_leaf_index()? != leaf_names {
+            return Err(TreeError::DifferentTipIndices);
+        }
+        *counts
+            .entry(canonical_newick(tree, &leaf_names)?)
+            .or_default() += 1;
+    }
+
+    Ok(counts
+        .into_iter()
+        .map(|(newick, count)| (newick, count as f64 / posterior.len() as f64))
+        .collect())
+}
+
+/// Writes the unrooted topology of a tree as a newick string where the first leaf is
+/// a child of the root, and children are sorted by their own newick strings
+fn canonical_newick(tree: &Tree, leaf_names: &[String]) -> Result<String, TreeError> {
+    let n_leaves = leaf_names.len();
+    let clusters: Vec<_> = tree
+        .get_partitions()?
+        .iter()
+        .map(|partition| to_cluster(partition, n_leaves))
+        .filter(|cluster| cluster.count_ones(..) >= 2 && cluster.count_ones(..) + 1 < n_leaves)
+        .collect();
+
+    let mut all_leaves = FixedBitSet::with_capacity(n_leaves);
+    all_leaves.insert_range(..);
+
+    Ok(format!(
+        "{};",
+        cluster_newick(&all_leaves, &clusters, leaf_names)
+    ))
+}
+
+/// Writes the subtree of the leaves in `cluster` as a newick string
+fn cluster_newick(
+    cluster: &FixedBitSet,
+    clusters: &[FixedBitSet],
+    leaf_names: &[String],
+) -> String {
+    let is_strict_subset = |sub: &FixedBitSet, sup: &FixedBitSet| sub != sup && sub.is_subset(sup);
+
+    // Children are the largest clusters strictly contained in this one
+    let children = clusters.iter().filter(|child| {
+        is_strict_subset(child, cluster)
+            && !clusters
+                .iter()
+                .any(|other| is_strict_subset(child, other) && is_strict_subset(other, cluster))
+    });
+
+    let mut covered = FixedBitSet::with_capacity(leaf_names.len());
+    let mut labels = vec![];
+    for child in children {
+        covered.union_with(child);
+        labels.push(cluster_newick(child, clusters, leaf_names));
+    }
+    labels.extend(cluster.difference(&covered).map(|i| leaf_names[i].clone()));
+    labels.sort();
+
+    format!("({})", labels.join(","))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn trees(newicks: &[&str]) -> Vec<Tree> {
+        newicks
+            .iter()
+            .map(|newick| Tree::from_newick(newick).unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn topology_frequencies() {
+        let posterior = trees(&[
+            "(((A,B),C),(D,E));",
+            "((E,D),(C,(B,A)));",
+            "((A,B),C,(D,E));",
+            "(((A,C),B),(D,E));",
+            "((A,(B,C)),D,E);",
+        ]);
+
+        let frequencies = topology_frequency_map(&posterior).unwrap();
+        let mut sorted: Vec<_> = frequencies.iter().collect();
+        sorted.sort_by(|a, b| a.0.cmp(b.0));
+        assert_eq!(
+            sorted,
+            vec![
+                (&"(((D,E),B),A,C);".to_string(), &0.2),
+                (&"(((D,E),C),A,B);".to_string(), &0.6),
+                (&"((B,C),(D,E),A);".to_string(), &0.2),
+            ]
+        );
+
+        // Canonical newick strings have the same topology as the trees
+        for tree in posterior.iter() {
+            let canonical = canonical_newick(tree, &tree.get_leaf_index().unwrap()).unwrap();
+            let canonical = Tree::from_newick(&canonical).unwrap();
+            assert!(canonical.topology_equal(tree).unwrap());
+        }
+
+        for (query, expected) in [(&posterior[0], 3), (&posterior[3], 1)] {
+            let (count, proportion) = count_topologies_matching(query, &posterior).unwrap();
+            assert_eq!(count, expected);
+            assert_eq!(proportion, expected as f64 / 5.0);
+        }
+    }
+
+    #[test]
+    fn topology_frequency_errors() {
+        assert!(matches!(
+            topology_frequency_map(&[]),
+            Err(TreeError::IsEmpty)
+        ));
+        let query = Tree::from_newick("((A,B),C);").unwrap();
+        assert!(matches!(
+            count_topologies_matching(&query, &[]),
+            Err(TreeError::IsEmpty)
+        ));
+
+        let posterior = trees(&["((A,B),(C,D));", "((A,B),(C,E));"]);
+        assert!(matches!(
+            topology_frequency_map(&posterior),
+            Err(TreeError::DifferentTipIndices)
+        ));
+        assert!(matches!(
+            count_topologies_matching(&posterior[0], &posterior),
+            Err(TreeError::DifferentTipIndices)
+        ));
+    }
+}
@@ -3,22 +3,27 @@ use accurate::traits::*;
 use fixedbitset::FixedBitSet;
 use itertools::Itertools;
 use ptree::{print_tree, TreeBuilder};
-use rand::seq::SliceRandom;
+use rand::{seq::SliceRandom, Rng};
+use rayon::prelude::*;
 use std::collections::VecDeque;
 use std::iter::zip;
 use std::{
     cell::RefCell,
     collections::{HashMap, HashSet},
     fs,
+    io::{BufWriter, Write},
     path::Path,
 };
 
 use thiserror::Error;
 
-use super::node::{Node, NodeError};
-use super::{EdgeDepth, EdgeLength, NewickFormat, NodeId};
+use super::io::TreeFileReader;
+use super::node::{Calibration, LengthPrecision, Node, NodeError};
+use super::{EdgeDepth, EdgeLength, NewickFormat, NniSwap, NodeId};
 
-use crate::distance::{tril_to_rowvec_index, DistanceMatrix, MatrixError};
+use crate::alignment::AlignmentError;
+use crate::distance::{tril_to_rowvec_index, DistanceMatrix, MatrixError, PairwiseDist};
+use crate::distr::DistrError;
 
 /// Errors that can occur when reading, writing and manipulating [`Tree`] structs.
 #[derive(Error, Debug)]
@@ -50,6 +55,12 @@ pub enum TreeError {
     /// Some branches of the tree have no length
     #[error("The tree must have all branch lengths.")]
     MissingBranchLengths,
+    /// A branch of the tree has a negative length
+    #[error("Invalid negative branch length: {0}")]
+    InvalidBranchLength(EdgeLength),
+    /// Some leaves of the tree have no sequence, or sequences of different lengths
+    #[error("All the leaves must have sequences of the same length.")]
+    MissingSequences,
     /// The trees we want to compare have different tips
     #[error("The trees have different tips indices.")]
     DifferentTipIndices,
@@ -62,6 +73,29 @@ pub enum TreeError {
     /// The two nodes could not be merged into a single parent
     #[error("Cound not merge nodes {0} and {1} since they are not siblings")]
     MergingNonSiblingNodes(NodeId, NodeId),
+    /// The trees we want to compare have different topologies
+    #[error("The trees have different topologies.")]
+    DifferentTopologies,
+    /// No subtree of the tree corresponds to the bipartition
+    #[error("No subtree of the tree corresponds to the bipartition.")]
+    PartitionNotFound,
+    /// The subtree rooted at [`NodeId`] cannot be regrafted onto one of its own branches
+    #[error("Cannot regraft the subtree rooted at {0} onto one of its own branches")]
+    RegraftInsideSubtree(NodeId),
+    /// The calibration of node [`NodeId`] has a negative minimum age, or
+    /// a maximum age lower than its minimum age
+    #[error("Invalid age calibration for node {0}")]
+    InvalidCalibration(NodeId),
+    /// The minimum age of a node is higher than the maximum age of one of its ancestors
+    #[error(
+        "The minimum age of node {node} is higher than the maximum age of its ancestor {ancestor}"
+    )]
+    InconsistentCalibrations {
+        /// Id of the node
+        node: NodeId,
+        /// Id of the ancestor
+        ancestor: NodeId,
+    },
     /// There was a [`std::io::Error`] when writin the tree to a file
     #[error("Error writing tree to file")]
     IoError(#[from] std::io::Error),
@@ -71,6 +105,12 @@ pub enum TreeError {
     /// There was a [`MatrixError`] when extracting distance matrix
     #[error("Could not convert to matrix")]
     MatrixError(#[from] MatrixError),
+    /// There was a [`DistrError`] when sampling branch lengths
+    #[error("Invalid branch length distribution")]
+    DistrError(#[from] DistrError),
+    /// There was an [`AlignmentError`] when using a sequence alignment
+    #[error("Could not use the sequence alignment")]
+    AlignmentError(#[from] AlignmentError),
     /// General error
     #[error("Encountered an error: {0}")]
     GeneralError(&'static str),
@@ -80,23 +120,43 @@ pub enum TreeError {
 #[derive(Error, Debug)]
 pub enum NewickParseError {
     /// There is whitespace in one of the branch lengths
-    #[error("Cannot have whitespace in number field.")]
-    WhiteSpaceInNumber,
-    /// There is an unclosed bracket in the newick String
-    #[error("Missing a closing bracket.")]
-    UnclosedBracket,
-    /// The newick string is missing a final semi-colon
-    #[error("The tree is missin a semi colon at the end.")]
-    NoClosingSemicolon,
-    /// We are trying to close a subtre but have no parent node.
-    #[error("Parent node of subtree not found")]
-    NoSubtreeParent,
+    #[error("at offset {pos}: cannot have whitespace in number field: `{context}`")]
+    WhiteSpaceInNumber {
+        /// Character offset of the whitespace in the newick string
+        pos: usize,
+        /// Newick string around the whitespace
+        context: String,
+    },
+    /// There is an unclosed bracket in the newick String, opened at the given offset
+    #[error("at offset {0}: missing a closing bracket")]
+    UnclosedBracket(usize),
+    /// The newick string is missing a final semi-colon, the offset is the end of the string
+    #[error("at offset {0}: the tree is missing a semi colon at the end")]
+    NoClosingSemicolon(usize),
+    /// We are trying to close a subtree at the given offset but have no parent node.
+    #[error("at offset {0}: parent node of subtree not found")]
+    NoSubtreeParent(usize),
     /// There was a [`TreeError`] when building a tree fromthe newick string
     #[error("Problem with building the tree.")]
     TreeError(#[from] TreeError),
-    /// There was a [`std::num::ParseFloatError`] when parsing branch lengths
-    #[error("Could not parse a branch length")]
-    FloatError(#[from] std::num::ParseFloatError),
+    /// There was a [`std::num::ParseFloatError`] when parsing a branch length
+    #[error("at offset {pos}: could not parse branch length `{raw}`")]
+    FloatError {
+        /// Error raised when parsing the branch length
+        source: std::num::ParseFloatError,
+        /// Character offset of the branch length in the newick string
+        pos: usize,
+        /// Branch length as written in the newick string
+        raw: String,
+    },
+    /// A branch length in the newick string is negative
+    #[error("at offset {pos}: invalid negative branch length: {length}")]
+    InvalidBranchLength {
+        /// Character offset of the branch length in the newick string
+        pos: usize,
+        /// The negative branch length
+        length: EdgeLength,
+    },
     /// There was a [`std::io::Error`] when reading a newick file
     #[error("Problem reading file")]
     IoError(#[from] std::io::Error),
@@ -115,6 +175,70 @@ pub struct Comparison {
     pub branch_score: f64,
 }
 
+/// Summary statistics of the branch lengths of a tree,
+/// computed with [`Tree::branch_length_stats()`]
+#[derive(Debug, Clone, PartialEq)]
+pub struct BranchLengthStats {
+    /// Shortest branch length
+    pub min: f64,
+    /// Longest branch length
+    pub max: f64,
+    /// Mean branch length
+    pub mean: f64,
+    /// Median branch length
+    pub median: f64,
+    /// Population variance of branch lengths
+    pub variance: f64,
+    /// Sum of branch lengths
+    pub total: f64,
+}
+
+/// Tree balance and shape statistics, computed in a single traversal
+/// with [`Tree::balance_stats()`]
+#[derive(Debug, Clone, PartialEq)]
+pub struct BalanceStats {
+    /// Number of leaves
+    pub n_leaves: usize,
+    /// Number of nodes
+    pub n_nodes: usize,
+    /// Height of the tree, `None` if some branches do not have a length
+    pub height: Option<f64>,
+    /// Diameter of the tree, `None` if some branches do not have a length
+    pub diameter: Option<f64>,
+    /// Number of cherries *(see [`Tree::cherries()`])*
+    pub cherries: usize,
+    /// Sackin index *(see [`Tree::sackin()`])*
+    pub sackin: usize,
+    /// Sackin index normalized with a Yule null model *(see [`Tree::sackin_yule()`])*
+    pub sackin_yule: f64,
+    /// Sackin index normalized with a PDA null model *(see [`Tree::sackin_pda()`])*
+    pub sackin_pda: f64,
+    /// Colless index *(see [`Tree::colless()`])*
+    pub colless: usize,
+    /// Colless index normalized with a Yule null model *(see [`Tree::colless_yule()`])*
+    pub colless_yule: f64,
+    /// Colless index normalized with a PDA null model *(see [`Tree::colless_pda()`])*
+    pub colless_pda: f64,
+}
+
+/// Root-to-tip regression of the distances between the root and the tips against
+/// their sampling dates, computed with [`Tree::rtt_regression()`]
+#[derive(Debug, Clone, PartialEq)]
+pub struct RttResult {
+    /// Slope of the regression, an estimate of the substitution rate
+    pub slope: f64,
+    /// Intercept of the regression, *i.e.* the root-to-tip distance at date 0
+    pub intercept: f64,
+    /// Coefficient of determination of the regression
+    pub r_squared: f64,
+    /// Difference between the observed and predicted root-to-tip distance of each tip
+    pub residuals: Vec<(String, f64)>,
+}
+
+/// Tolerance on root to tip distances when checking that trees are ultrametric
+/// *(see [`Tree::is_ultrametric()`])*
+const ULTRAMETRIC_TOLERANCE: f64 = 1e-6;
+
 /// Used to hold compared tree edges
 type EdgeCompare = (
     Vec<(EdgeDepth, EdgeLength)>,
@@ -122,17 +246,29 @@ type EdgeCompare = (
     Vec<((EdgeDepth, EdgeLength), (EdgeDepth, EdgeLength))>,
 );
 
-type Partition = FixedBitSet;
+/// Used to hold node labels, leftmost leaves and keyroots for the tree edit distance
+type KeyrootDecomposition<'a> = (Vec<Option<&'a str>>, Vec<usize>, Vec<usize>);
+
+/// A bipartition of the leaves of a [`Tree`], induced by one of its branches.
+/// Bit `i` is set if the `i`-th leaf of the leaf index
+/// *(see [`Tree::init_leaf_index()`])* is on one side of the branch.
+pub type Partition = FixedBitSet;
 type WrappedPartitionMap = HashMap<Partition, (usize, Option<EdgeLength>)>;
 type PartitionMap = HashMap<Partition, (EdgeDepth, EdgeLength)>;
-type PartitionSet = HashSet<Partition>;
+/// The set of bipartitions of a [`Tree`]
+pub type PartitionSet = HashSet<Partition>;
 
 /// A Phylogenetic tree
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Tree {
     nodes: Vec<Node>,
+    #[cfg_attr(feature = "serde", serde(skip))]
     leaf_index: RefCell<Option<Vec<String>>>,
+    #[cfg_attr(feature = "serde", serde(skip))]
     partitions: RefCell<Option<WrappedPartitionMap>>,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    root_paths: RefCell<HashMap<NodeId, Vec<NodeId>>>,
 }
 
 /// Base methods to add and get [`Node`] objects to and from the [`Tree`].
@@ -146,6 +282,7 @@ impl Tree {
             nodes: Vec::new(),
             leaf_index: RefCell::new(None),
             partitions: RefCell::new(None),
+            root_paths: RefCell::new(HashMap::new()),
         }
     }
 
@@ -159,6 +296,7 @@ impl Tree {
         let mut node = node;
         node.id = idx;
         self.nodes.push(node);
+        self.reset_root_paths();
 
         idx
     }
@@ -200,7 +338,7 @@ impl Tree {
         let mut node = node;
 
         node.set_parent(parent, edge);
-        node.set_depth(self.get(&parent)?.depth + 1);
+        node.set_depth(self.get(&parent)?.get_depth() + 1);
 
         let id = self.add(node);
 
@@ -210,7 +348,16 @@ impl Tree {
         Ok(id)
     }
 
-    /// Get a reference to a specific Node of the tree
+    /// Get a reference to a specific Node of the tree. Returns
+    /// [`TreeError::NodeNotFound`] if the id is out of bounds or the node was deleted.
+    /// ```
+    /// use phylotree::tree::{Tree, TreeError};
+    ///
+    /// let tree = Tree::from_newick("(A,B)C;").unwrap();
+    ///
+    /// assert_eq!(tree.get(&0).unwrap().name, Some("C".to_string()));
+    /// assert!(matches!(tree.get(&3), Err(TreeError::NodeNotFound(3))));
+    /// ```
     pub fn get(&self, id: &NodeId) -> Result<&Node, TreeError> {
         if *id >= self.nodes.len() {
             return Err(TreeError::NodeNotFound(*id));
@@ -223,11 +370,13 @@ impl Tree {
         Ok(node)
     }
 
-    /// Get a mutable reference to a specific Node of the tree
+    /// Get a mutable reference to a specific Node of the tree. Returns
+    /// [`TreeError::NodeNotFound`] if the id is out of bounds or the node was deleted.
     pub fn get_mut(&mut self, id: &NodeId) -> Result<&mut Node, TreeError> {
         if *id >= self.nodes.len() {
             return Err(TreeError::NodeNotFound(*id));
         }
+        self.reset_root_paths();
         let node = &mut self.nodes[*id];
         if node.deleted {
             return Err(TreeError::NodeNotFound(*id));
@@ -236,6 +385,12 @@ impl Tree {
         Ok(node)
     }
 
+    // Empties the cache of paths from the root, must be called
+    // whenever nodes are added or can be modified
+    fn reset_root_paths(&mut self) {
+        self.root_paths.get_mut().clear();
+    }
+
     /// Get a reference to a node in the tree by name.
     /// Note that this does not check for name unicity, if several nodes
     /// match a name this funciton will return the first match in the tree.
@@ -258,6 +413,7 @@ impl Tree {
 
     /// Get a mutable reference to a node in the tree by name
     pub fn get_by_name_mut(&mut self, name: &str) -> Option<&mut Node> {
+        self.reset_root_paths();
         self.nodes
             .iter_mut()
             .find(|node| node.name.is_some() && node.name == Some(String::from(name)))
@@ -292,12 +448,50 @@ impl Tree {
     pub fn get_root(&self) -> Result<NodeId, TreeError> {
         self.nodes
             .iter()
-            .filter(|&node| node.parent.is_none())
+            .filter(|&node| !node.deleted && node.parent.is_none())
             .map(|node| node.id)
             .next()
             .ok_or(TreeError::RootNotFound)
     }
 
+    /// Returns the length of the root edge, *i.e.* the branch above the root node
+    /// that is written after the closing parenthesis in newick strings such as
+    /// `(A,B):0.1;`. Returns [`None`] if the root has no edge length or if the tree is empty.
+    /// ```
+    /// use phylotree::tree::Tree;
+    ///
+    /// let tree = Tree::from_newick("(A:1,B:2)C:0.5;").unwrap();
+    /// assert_eq!(tree.root_edge(), Some(0.5));
+    ///
+    /// let tree = Tree::from_newick("(A:1,B:2)C;").unwrap();
+    /// assert_eq!(tree.root_edge(), None);
+    /// ```
+    pub fn root_edge(&self) -> Option<EdgeLength> {
+        let root = self.get_root().ok()?;
+        self.get(&root).ok()?.parent_edge
+    }
+
+    /// Sets the length of the root edge, or removes it if `length` is [`None`].
+    /// The root edge is written after the closing parenthesis of newick strings
+    /// but is not counted in the length or height of the tree.
+    /// ```
+    /// use phylotree::tree::Tree;
+    ///
+    /// let mut tree = Tree::from_newick("(A:1,B:2)C;").unwrap();
+    ///
+    /// tree.set_root_edge(Some(0.5)).unwrap();
+    /// assert_eq!(tree.to_newick().unwrap(), "(A:1,B:2)C:0.5;");
+    ///
+    /// tree.set_root_edge(None).unwrap();
+    /// assert_eq!(tree.to_newick().unwrap(), "(A:1,B:2)C;");
+    /// ```
+    pub fn set_root_edge(&mut self, length: Option<EdgeLength>) -> Result<(), TreeError> {
+        let root = self.get_root()?;
+        self.get_mut(&root)?.parent_edge = length;
+
+        Ok(())
+    }
+
     /// Returns a [`Vec`] containing the Node IDs of leaf nodes of the tree
     /// ```
     /// use phylotree::tree::{Tree, Node};
@@ -317,6 +511,28 @@ impl Tree {
             .collect()
     }
 
+    /// Returns a [`Vec`] containing the Node IDs of the internal nodes of the tree,
+    /// i.e. nodes that are neither leaves nor the root.
+    /// ```
+    /// use phylotree::tree::Tree;
+    ///
+    /// let tree = Tree::from_newick("((A,B)E,(C,D)F)G;").unwrap();
+    /// let names: Vec<_> = tree
+    ///     .get_internal_nodes()
+    ///     .iter()
+    ///     .map(|id| tree.get(id).unwrap().name.clone().unwrap())
+    ///     .collect();
+    ///
+    /// assert_eq!(names, vec!["E", "F"]);
+    /// ```
+    pub fn get_internal_nodes(&self) -> Vec<NodeId> {
+        self.nodes
+            .iter()
+            .filter(|&node| !node.deleted && !node.is_tip() && !node.is_root())
+            .map(|node| node.id)
+            .collect()
+    }
+
     /// Returns a [`Vec`] containing the Names of the leaf nodes of the tree
     /// ```
     /// use phylotree::tree::{Tree, Node};
@@ -339,6 +555,49 @@ impl Tree {
             .collect()
     }
 
+    /// Returns the Node IDs of the leaves of the tree sorted by leaf name,
+    /// this order does not depend on the order of the leaves in the newick string.
+    /// Returns [`TreeError::UnnamedLeaves`] if some leaves are not named.
+    /// ```
+    /// use phylotree::tree::Tree;
+    ///
+    /// let tree = Tree::from_newick("((C,A),(D,B));").unwrap();
+    /// let names: Vec<_> = tree
+    ///     .get_leaves_sorted()
+    ///     .unwrap()
+    ///     .iter()
+    ///     .map(|id| tree.get(id).unwrap().name.clone().unwrap())
+    ///     .collect();
+    ///
+    /// assert_eq!(names, vec!["A", "B", "C", "D"]);
+    /// ```
+    pub fn get_leaves_sorted(&self) -> Result<Vec<NodeId>, TreeError> {
+        let mut leaves = self.get_leaves();
+        if leaves.iter().any(|id| self.nodes[*id].name.is_none()) {
+            return Err(TreeError::UnnamedLeaves);
+        }
+        leaves.sort_by(|a, b| self.nodes[*a].name.cmp(&self.nodes[*b].name));
+
+        Ok(leaves)
+    }
+
+    /// Returns the names of the leaves of the tree in alphabetical order.
+    /// Returns [`TreeError::UnnamedLeaves`] if some leaves are not named.
+    /// ```
+    /// use phylotree::tree::Tree;
+    ///
+    /// let tree = Tree::from_newick("((C,A),(D,B));").unwrap();
+    ///
+    /// assert_eq!(tree.get_leaf_names_sorted().unwrap(), vec!["A", "B", "C", "D"]);
+    /// ```
+    pub fn get_leaf_names_sorted(&self) -> Result<Vec<String>, TreeError> {
+        Ok(self
+            .get_leaves_sorted()?
+            .into_iter()
+            .map(|id| self.nodes[id].name.clone().unwrap())
+            .collect())
+    }
+
     /// Gets the node ids of all the nodes in the subtree rooted at the specified node
     /// ```
     /// use phylotree::tree::Tree;
@@ -364,29 +623,28 @@ impl Tree {
         Ok(indices)
     }
 
-    /// Gets the node ids of all the nodes in the subtree rooted at the specified node, except the root
+    /// Gets the node ids of all the nodes in the subtree rooted at the specified node, except the root.
+    /// Both internal nodes and leaves are returned, in preorder.
     /// ```
     /// use phylotree::tree::Tree;
     ///
-    /// let tree = Tree::from_newick("(A:0.1,B:0.2,(C:0.3,D:0.4)E:0.5)F;").unwrap();
-    /// let sub_root = tree.get_by_name("E").unwrap();
-    /// let subtree: Vec<_> = tree.get_descendants(&sub_root.id)
+    /// let tree = Tree::from_newick("((A,(C,E)D)B,((H)I)G)F;").unwrap();
+    /// let sub_root = tree.get_by_name("B").unwrap();
+    /// let descendants: Vec<_> = tree.get_descendants(&sub_root.id)
     ///     .unwrap()
     ///     .iter()
     ///     .map(|id| tree.get(id).unwrap().name.clone())
     ///     .flatten()
     ///     .collect();
     ///
-    /// assert_eq!(subtree, vec!["C", "D"])
+    /// assert_eq!(descendants, vec!["A", "D", "C", "E"])
     /// ```
     pub fn get_descendants(&self, root: &NodeId) -> Result<Vec<NodeId>, TreeError> {
-        let mut indices = vec![];
-
-        for child in self.get(root)?.children.iter() {
-            indices.extend(self.get_subtree(child)?);
-        }
-
-        Ok(indices)
+        Ok(self
+            .iter_preorder(root)?
+            .skip(1)
+            .map(|node| node.id)
+            .collect())
     }
 
     /// Gets the node ids of all the leaves in the subtree rooted at the specified node
@@ -411,6 +669,183 @@ impl Tree {
             .filter(|id| self.get(id).unwrap().is_tip())
             .collect())
     }
+
+    /// Gets the node ids of the sister clades of a node, *i.e.* the other children
+    /// of its parent. Returns an empty [`Vec`] for the root node.
+    /// ```
+    /// use phylotree::tree::Tree;
+    ///
+    /// let tree = Tree::from_newick("(A,B,(C,D)E)F;").unwrap();
+    /// let e = tree.get_by_name("E").unwrap().id;
+    /// let sisters: Vec<_> = tree
+    ///     .get_sister_clades(&e)
+    ///     .unwrap()
+    ///     .iter()
+    ///     .map(|id| tree.get(id).unwrap().name.clone().unwrap())
+    ///     .collect();
+    ///
+    /// assert_eq!(sisters, vec!["A", "B"]);
+    /// assert!(tree.get_sister_clades(&tree.get_root().unwrap()).unwrap().is_empty());
+    /// ```
+    pub fn get_sister_clades(&self, node_id: &NodeId) -> Result<Vec<NodeId>, TreeError> {
+        let Some(parent) = self.get(node_id)?.parent else {
+            return Ok(vec![]);
+        };
+
+        Ok(self
+            .get(&parent)?
+            .children
+            .iter()
+            .filter(|&child| child != node_id)
+            .copied()
+            .collect())
+    }
+
+    /// Gets the node ids of the siblings of a node, *i.e.* the other children
+    /// of its parent. This is the same as [`Tree::get_sister_clades`].
+    /// ```
+    /// use phylotree::tree::Tree;
+    ///
+    /// let tree = Tree::from_newick("((A,B)E,C)F;").unwrap();
+    /// let a = tree.get_by_name("A").unwrap().id;
+    /// let b = tree.get_by_name("B").unwrap().id;
+    ///
+    /// assert_eq!(tree.get_siblings(&a).unwrap(), vec![b]);
+    /// ```
+    pub fn get_siblings(&self, node_id: &NodeId) -> Result<Vec<NodeId>, TreeError> {
+        self.get_sister_clades(node_id)
+    }
+
+    /// Checks if one of the siblings of a node is named `name`. Returns `false`
+    /// if the node does not exist in the tree.
+    /// ```
+    /// use phylotree::tree::Tree;
+    ///
+    /// let tree = Tree::from_newick("((A,B)E,C)F;").unwrap();
+    /// let a = tree.get_by_name("A").unwrap().id;
+    ///
+    /// assert!(tree.has_sibling_named(&a, "B"));
+    /// assert!(!tree.has_sibling_named(&a, "C"));
+    /// ```
+    pub fn has_sibling_named(&self, node_id: &NodeId, name: &str) -> bool {
+        self.get_siblings(node_id)
+            .unwrap_or_default()
+            .iter()
+            .filter_map(|id| self.get(id).ok())
+            .any(|sibling| sibling.name.as_deref() == Some(name))
+    }
+
+    /// Gets the node ids of all the leaves in the sister clades of a node
+    /// *(see [`Tree::get_sister_clades()`])*.
+    /// ```
+    /// use phylotree::tree::Tree;
+    ///
+    /// let tree = Tree::from_newick("((A,B)E,(C,D)F)G;").unwrap();
+    /// let e = tree.get_by_name("E").unwrap().id;
+    /// let leaves: Vec<_> = tree
+    ///     .get_sister_leaves(&e)
+    ///     .unwrap()
+    ///     .iter()
+    ///     .map(|id| tree.get(id).unwrap().name.clone().unwrap())
+    ///     .collect();
+    ///
+    /// assert_eq!(leaves, vec!["C", "D"]);
+    /// ```
+    pub fn get_sister_leaves(&self, node_id: &NodeId) -> Result<Vec<NodeId>, TreeError> {
+        let mut leaves = vec![];
+        for sister in self.get_sister_clades(node_id)? {
+            leaves.extend(self.get_subtree_leaves(&sister)?);
+        }
+
+        Ok(leaves)
+    }
+
+    /// Copies the subtree rooted at the specified node into a new [`Tree`].
+    /// Node ids are compacted in the new tree and the specified node becomes
+    /// its root *(with no parent edge)*.
+    /// ```
+    /// use phylotree::tree::Tree;
+    ///
+    /// let tree = Tree::from_newick("(A:0.1,B:0.2,(C:0.3,D:0.4)E:0.5)F;").unwrap();
+    /// let sub_root = tree.get_by_name("E").unwrap();
+    /// let subtree = tree.extract_subtree(&sub_root.id).unwrap();
+    ///
+    /// assert_eq!(subtree.size(), 3);
+    /// assert_eq!(subtree.to_newick().unwrap(), "(C:0.3,D:0.4)E;");
+    /// ```
+    pub fn extract_subtree(&self, root_id: &NodeId) -> Result<Tree, TreeError> {
+        let mut subtree = Tree::new();
+        let mut new_ids = HashMap::new();
+
+        for old_id in self.get_subtree(root_id)? {
+            let old_node = self.get(&old_id)?;
+
+            let mut node = old_node.clone();
+            node.children.clear();
+            node.child_edges = None;
+            node.subtree_distances = RefCell::new(None);
+
+            let new_id = if old_id == *root_id {
+                node.parent = None;
+                node.parent_edge = None;
+                node.set_depth(0);
+                subtree.add(node)
+            } else {
+                let parent = new_ids[&old_node.parent.unwrap()];
+                subtree.add_child(node, parent, old_node.parent_edge)?
+            };
+            new_ids.insert(old_id, new_id);
+        }
+
+        Ok(subtree)
+    }
+
+    /// Returns the id of the first node named `name`, to be used as the root of a clade
+    /// *(e.g. with [`Tree::extract_subtree()`])*. Returns `None` if no node has this name.
+    /// ```
+    /// use phylotree::tree::Tree;
+    ///
+    /// let tree = Tree::from_newick("((Human,Chimp)Primates,(Mouse,Rat)Rodentia)Mammals;").unwrap();
+    /// let primates = tree.get_clade_by_name("Primates").unwrap();
+    ///
+    /// assert_eq!(
+    ///     tree.extract_subtree(&primates).unwrap().to_newick().unwrap(),
+    ///     "(Human,Chimp)Primates;"
+    /// );
+    /// assert_eq!(tree.get_clade_by_name("Birds"), None);
+    /// ```
+    pub fn get_clade_by_name(&self, name: &str) -> Option<NodeId> {
+        self.nodes
+            .iter()
+            .find(|node| !node.deleted && node.name.as_deref() == Some(name))
+            .map(|node| node.id)
+    }
+
+    /// Returns the ids of all the named internal nodes of the tree *(including the root)*,
+    /// keyed by name. If several internal nodes have the same name, the first one is kept.
+    /// ```
+    /// use phylotree::tree::Tree;
+    ///
+    /// let tree = Tree::from_newick("((Human,Chimp)Primates,(Mouse,Rat)Rodentia,(Cow,Pig));").unwrap();
+    /// let clades = tree.named_clades();
+    ///
+    /// assert_eq!(clades.len(), 2);
+    /// assert_eq!(tree.get(&clades["Rodentia"]).unwrap().children.len(), 2);
+    /// ```
+    pub fn named_clades(&self) -> HashMap<String, NodeId> {
+        let mut clades = HashMap::new();
+        for node in self
+            .nodes
+            .iter()
+            .filter(|node| !node.deleted && !node.is_tip())
+        {
+            if let Some(name) = node.name.as_ref() {
+                clades.entry(name.clone()).or_insert(node.id);
+            }
+        }
+
+        clades
+    }
 }
 
 /// Methods to traverse the [`Tree`]
@@ -544,6 +979,216 @@ impl Tree {
 
         Ok(indices)
     }
+
+    /// Returns a lazy iterator over the nodes of the subtree rooted at `root`, in the same
+    /// order as the [`Tree::preorder()`] traversal. Unlike [`Tree::preorder()`], node
+    /// references are yielded directly and no vector of ids is built.
+    /// ```
+    /// use phylotree::tree::Tree;
+    ///
+    /// let tree = Tree::from_newick("((A,(C,E)D)B,((H)I)G)F;").unwrap();
+    /// let preorder: Vec<_> = tree
+    ///     .iter_preorder(&tree.get_root().unwrap())
+    ///     .unwrap()
+    ///     .filter_map(|node| node.name.as_deref())
+    ///     .collect();
+    ///
+    /// assert_eq!(preorder, vec!["F", "B", "A", "D", "C", "E", "G", "I", "H"])
+    /// ```
+    pub fn iter_preorder(
+        &self,
+        root: &NodeId,
+    ) -> Result<impl Iterator<Item = &Node> + '_, TreeError> {
+        let mut stack = vec![self.get(root)?];
+
+        Ok(std::iter::from_fn(move || {
+            let node = stack.pop()?;
+            stack.extend(node.children.iter().rev().map(|id| &self.nodes[*id]));
+            Some(node)
+        }))
+    }
+
+    /// Returns a lazy iterator over the nodes of the subtree rooted at `root`, in the same
+    /// order as the [`Tree::postorder()`] traversal.
+    /// ```
+    /// use phylotree::tree::Tree;
+    ///
+    /// let tree = Tree::from_newick("((A,(C,E)D)B,((H)I)G)F;").unwrap();
+    /// let postorder: Vec<_> = tree
+    ///     .iter_postorder(&tree.get_root().unwrap())
+    ///     .unwrap()
+    ///     .filter_map(|node| node.name.as_deref())
+    ///     .collect();
+    ///
+    /// assert_eq!(postorder, vec!["A", "C", "E", "D", "B", "H", "I", "G", "F"])
+    /// ```
+    pub fn iter_postorder(
+        &self,
+        root: &NodeId,
+    ) -> Result<impl Iterator<Item = &Node> + '_, TreeError> {
+        // Nodes are yielded once all their children have been visited
+        let mut stack = vec![(self.get(root)?, false)];
+
+        Ok(std::iter::from_fn(move || loop {
+            let (node, visited) = stack.pop()?;
+            if visited || node.children.is_empty() {
+                return Some(node);
+            }
+            stack.push((node, true));
+            stack.extend(
+                node.children
+                    .iter()
+                    .rev()
+                    .map(|id| (&self.nodes[*id], false)),
+            );
+        }))
+    }
+
+    /// Returns a lazy iterator over the nodes of the subtree rooted at `root`, in the same
+    /// order as the [`Tree::inorder()`] traversal. The subtree must be binary, this is
+    /// checked before any node is yielded.
+    /// ```
+    /// use phylotree::tree::Tree;
+    ///
+    /// let tree = Tree::from_newick("((A,(C,E)D)B,((H)I)G)F;").unwrap();
+    /// let inorder: Vec<_> = tree
+    ///     .iter_inorder(&tree.get_root().unwrap())
+    ///     .unwrap()
+    ///     .filter_map(|node| node.name.as_deref())
+    ///     .collect();
+    ///
+    /// assert_eq!(inorder, vec!["A", "B", "C", "D", "E", "F", "H", "I", "G"])
+    /// ```
+    pub fn iter_inorder(
+        &self,
+        root: &NodeId,
+    ) -> Result<impl Iterator<Item = &Node> + '_, TreeError> {
+        if self
+            .iter_preorder(root)?
+            .any(|node| node.children.len() > 2)
+        {
+            return Err(TreeError::IsNotBinary);
+        }
+
+        let mut current = Some(self.get(root)?);
+        let mut stack = vec![];
+
+        Ok(std::iter::from_fn(move || {
+            // Go down the left-most path before yielding nodes
+            while let Some(node) = current {
+                stack.push(node);
+                current = node.children.first().map(|id| &self.nodes[*id]);
+            }
+            let node = stack.pop()?;
+            current = node.children.get(1).map(|id| &self.nodes[*id]);
+            Some(node)
+        }))
+    }
+
+    /// Returns a lazy iterator over the nodes of the subtree rooted at `root`, in the same
+    /// order as the [`Tree::levelorder()`] traversal.
+    /// ```
+    /// use phylotree::tree::Tree;
+    ///
+    /// let tree = Tree::from_newick("((A,(C,E)D)B,((H)I)G)F;").unwrap();
+    /// let levelorder: Vec<_> = tree
+    ///     .iter_levelorder(&tree.get_root().unwrap())
+    ///     .unwrap()
+    ///     .filter_map(|node| node.name.as_deref())
+    ///     .collect();
+    ///
+    /// assert_eq!(levelorder, vec!["F", "B", "G", "A", "D", "I", "C", "E", "H"])
+    /// ```
+    pub fn iter_levelorder(
+        &self,
+        root: &NodeId,
+    ) -> Result<impl Iterator<Item = &Node> + '_, TreeError> {
+        let mut queue = VecDeque::from([self.get(root)?]);
+
+        Ok(std::iter::from_fn(move || {
+            let node = queue.pop_front()?;
+            queue.extend(node.children.iter().map(|id| &self.nodes[*id]));
+            Some(node)
+        }))
+    }
+
+    /// Returns an iterator over mutable references to the nodes of the tree, in
+    /// the same order as the [`Tree::preorder()`] traversal from the root. This makes
+    /// it possible to update nodes during a single traversal. The traversal order is
+    /// computed beforehand so changing the topology during iteration has no effect on
+    /// which nodes are visited.
+    ///
+    /// The cached leaf index and bipartitions are reset, so nodes can be renamed.
+    ///
+    /// *N.B.: branch lengths should not be changed through this iterator, they are also
+    /// stored in parent nodes (see [`Node::get_child_edge()`]) which are not updated.
+    /// Use [`Node::set_parent()`] and [`Node::set_child_edge()`] instead.*
+    /// ```
+    /// use phylotree::tree::Tree;
+    ///
+    /// let mut tree = Tree::from_newick("((A:1,B:2)C:1,D:3)E;").unwrap();
+    /// for node in tree.iter_preorder_mut() {
+    ///     if let Some(name) = node.name.as_mut() {
+    ///         name.insert_str(0, "sp_");
+    ///     }
+    /// }
+    ///
+    /// assert_eq!(tree.to_newick().unwrap(), "((sp_A:1,sp_B:2)sp_C:1,sp_D:3)sp_E;");
+    /// ```
+    pub fn iter_preorder_mut(&mut self) -> impl Iterator<Item = &mut Node> + '_ {
+        let ids = self
+            .get_root()
+            .and_then(|root| self.preorder(&root))
+            .unwrap_or_default();
+        self.reset_root_paths();
+        self.reset_bipartition_cache();
+
+        NodeIterMut {
+            nodes: self.nodes.as_mut_ptr(),
+            len: self.nodes.len(),
+            ids: ids.into_iter(),
+            _marker: std::marker::PhantomData,
+        }
+    }
+
+    /// Returns an iterator over the nodes of the tree in ascending [`NodeId`] order,
+    /// *i.e.* in the order they were added to the tree. Deleted nodes are skipped.
+    /// ```
+    /// use phylotree::tree::Tree;
+    ///
+    /// let tree = Tree::from_newick("((A,B)C,D)E;").unwrap();
+    /// let names: Vec<_> = tree.node_iter().filter_map(|node| node.name.as_deref()).collect();
+    /// let tips = tree.node_iter().filter(|node| node.is_tip()).count();
+    ///
+    /// assert_eq!(names, vec!["E", "C", "A", "B", "D"]);
+    /// assert_eq!(tips, 3);
+    /// ```
+    pub fn node_iter(&self) -> impl Iterator<Item = &Node> + '_ {
+        self.nodes.iter().filter(|node| !node.deleted)
+    }
+
+    /// Returns an iterator over mutable references to the nodes of the tree in
+    /// ascending [`NodeId`] order. Deleted nodes are skipped. The cached leaf index and
+    /// bipartitions are reset, so nodes can be renamed.
+    ///
+    /// *N.B.: branch lengths should not be changed through this iterator, they are also
+    /// stored in parent nodes (see [`Node::get_child_edge()`]) which are not updated.
+    /// Use [`Node::set_parent()`] and [`Node::set_child_edge()`] instead.*
+    /// ```
+    /// use phylotree::tree::Tree;
+    ///
+    /// let mut tree = Tree::from_newick("((A,B)C,D)E;").unwrap();
+    /// for node in tree.node_iter_mut().filter(|node| node.is_tip()) {
+    ///     node.name = node.name.as_ref().map(|name| name.to_lowercase());
+    /// }
+    ///
+    /// assert_eq!(tree.to_newick().unwrap(), "((a,b)C,d)E;");
+    /// ```
+    pub fn node_iter_mut(&mut self) -> impl Iterator<Item = &mut Node> + '_ {
+        self.reset_root_paths();
+        self.reset_bipartition_cache();
+        self.nodes.iter_mut().filter(|node| !node.deleted)
+    }
 }
 
 /// Methods that compute characteristics and measures to describe the [`Tree`]
@@ -580,12 +1225,59 @@ impl Tree {
         Ok(!self.nodes.is_empty() && self.get(&root_id)?.children.len() == 2)
     }
 
-    /// Checks if all the tips have unique names (This check assumes that all tips have a name)
-    pub fn has_unique_tip_names(&self) -> Result<bool, TreeError> {
-        let mut names = HashSet::new();
-        for name in self.get_leaf_names() {
-            if let Some(name) = name {
-                names.insert(name);
+    /// Returns `true` if the tree has a polytomy, *i.e.* a node with more children than
+    /// allowed in a binary tree: more than 2 for internal nodes and more than 3 for the
+    /// root *(the virtual root of an unrooted binary tree has 3 children)*.
+    /// ```
+    /// use phylotree::tree::Tree;
+    ///
+    /// assert!(!Tree::from_newick("((A,B),(C,D));").unwrap().has_polytomy());
+    /// assert!(!Tree::from_newick("(A,B,(C,D));").unwrap().has_polytomy());
+    /// assert!(Tree::from_newick("(A,(B,C,D));").unwrap().has_polytomy());
+    /// assert!(Tree::from_newick("(A,B,C,D);").unwrap().has_polytomy());
+    /// ```
+    pub fn has_polytomy(&self) -> bool {
+        self.polytomies().next().is_some()
+    }
+
+    /// Returns the number of polytomies in the tree *(see [`Tree::has_polytomy()`])*.
+    /// ```
+    /// use phylotree::tree::Tree;
+    ///
+    /// let tree = Tree::from_newick("((A,B,C),(D,E,F),G);").unwrap();
+    /// assert_eq!(tree.count_polytomies(), 2);
+    /// ```
+    pub fn count_polytomies(&self) -> usize {
+        self.polytomies().count()
+    }
+
+    /// Returns the IDs of the nodes that are polytomies *(see [`Tree::has_polytomy()`])*.
+    /// ```
+    /// use phylotree::tree::Tree;
+    ///
+    /// let tree = Tree::from_newick("((A,B,C)D,(E,F)G)H;").unwrap();
+    /// let polytomies = tree.polytomy_nodes();
+    ///
+    /// assert_eq!(polytomies, vec![tree.get_by_name("D").unwrap().id]);
+    /// ```
+    pub fn polytomy_nodes(&self) -> Vec<NodeId> {
+        self.polytomies().map(|node| node.id).collect()
+    }
+
+    // Iterates over the nodes with more children than in a binary tree
+    fn polytomies(&self) -> impl Iterator<Item = &Node> + '_ {
+        self.nodes.iter().filter(|node| {
+            let max_children = if node.is_root() { 3 } else { 2 };
+            !node.deleted && node.children.len() > max_children
+        })
+    }
+
+    /// Checks if all the tips have unique names (This check assumes that all tips have a name)
+    pub fn has_unique_tip_names(&self) -> Result<bool, TreeError> {
+        let mut names = HashSet::new();
+        for name in self.get_leaf_names() {
+            if let Some(name) = name {
+                names.insert(name);
             } else {
                 return Err(TreeError::UnnamedLeaves);
             }
@@ -594,6 +1286,44 @@ impl Tree {
         Ok(names.len() == self.n_leaves())
     }
 
+    /// Returns `true` if no two tips of the tree share the same name. Unnamed tips are
+    /// ignored, use [`Tree::check_tip_names()`] to also check that all tips are named.
+    /// ```
+    /// use phylotree::tree::Tree;
+    ///
+    /// assert!(Tree::from_newick("((A,B),C);").unwrap().are_tip_names_unique());
+    /// assert!(Tree::from_newick("((A,),C);").unwrap().are_tip_names_unique());
+    /// assert!(!Tree::from_newick("((A,B),A);").unwrap().are_tip_names_unique());
+    /// ```
+    pub fn are_tip_names_unique(&self) -> bool {
+        let names: Vec<_> = self.get_leaf_names().into_iter().flatten().collect();
+        names.iter().all_unique()
+    }
+
+    /// Checks that all the tips of the tree have a name, and that these names are unique.
+    /// Returns [`TreeError::UnnamedLeaves`] or [`TreeError::DuplicateLeafNames`] otherwise.
+    /// This must hold for bipartitions to be computed, *e.g.* when comparing trees.
+    /// ```
+    /// use phylotree::tree::{Tree, TreeError};
+    ///
+    /// assert!(Tree::from_newick("((A,B),C);").unwrap().check_tip_names().is_ok());
+    /// assert!(matches!(
+    ///     Tree::from_newick("((A,),C);").unwrap().check_tip_names(),
+    ///     Err(TreeError::UnnamedLeaves)
+    /// ));
+    /// assert!(matches!(
+    ///     Tree::from_newick("((A,B),A);").unwrap().check_tip_names(),
+    ///     Err(TreeError::DuplicateLeafNames)
+    /// ));
+    /// ```
+    pub fn check_tip_names(&self) -> Result<(), TreeError> {
+        if !self.has_unique_tip_names()? {
+            return Err(TreeError::DuplicateLeafNames);
+        }
+
+        Ok(())
+    }
+
     /// Returns the number of nodes in the tree
     pub fn size(&self) -> usize {
         self.nodes.len()
@@ -601,7 +1331,80 @@ impl Tree {
 
     /// Returns the number of leaves in the tree
     pub fn n_leaves(&self) -> usize {
-        self.nodes.iter().filter(|&node| node.is_tip()).count()
+        self.nodes
+            .iter()
+            .filter(|&node| !node.deleted && node.is_tip())
+            .count()
+    }
+
+    /// Returns the number of internal nodes in the tree, excluding the root.
+    /// A rooted binary tree with `n` leaves has `n-1` non-leaf nodes, so `n-2`
+    /// internal nodes once the root is excluded.
+    /// ```
+    /// use phylotree::tree::Tree;
+    ///
+    /// let tree = Tree::from_newick("((A,B),(C,D));").unwrap();
+    /// assert_eq!(tree.n_internal_nodes(), tree.n_leaves() - 2);
+    /// ```
+    pub fn n_internal_nodes(&self) -> usize {
+        self.nodes
+            .iter()
+            .filter(|&node| !node.deleted && !node.is_tip() && !node.is_root())
+            .count()
+    }
+
+    /// Returns all the edges of the tree as `(parent, child, length)` triples, with
+    /// children visited in preorder from the root. The length is `None` if the branch
+    /// between the two nodes has no length.
+    /// ```
+    /// use phylotree::tree::Tree;
+    ///
+    /// let tree = Tree::from_newick("((A:1,B:2)C,D:3)E;").unwrap();
+    ///
+    /// assert_eq!(
+    ///     tree.edge_list(),
+    ///     vec![(0, 1, None), (1, 2, Some(1.)), (1, 3, Some(2.)), (0, 4, Some(3.))]
+    /// );
+    /// ```
+    pub fn edge_list(&self) -> Vec<(NodeId, NodeId, Option<EdgeLength>)> {
+        let Ok(nodes) = self.get_root().and_then(|root| self.iter_preorder(&root)) else {
+            return vec![];
+        };
+
+        nodes
+            .filter_map(|node| {
+                node.parent
+                    .map(|parent| (parent, node.id, node.parent_edge))
+            })
+            .collect()
+    }
+
+    /// Returns the number of edges in the tree, *i.e.* `n-1` for a tree with `n` nodes.
+    /// ```
+    /// use phylotree::tree::Tree;
+    ///
+    /// let tree = Tree::from_newick("((A:1,B:2)C,D:3)E;").unwrap();
+    /// assert_eq!(tree.edge_count(), 4);
+    /// ```
+    pub fn edge_count(&self) -> usize {
+        self.nodes
+            .iter()
+            .filter(|&node| !node.deleted && !node.is_root())
+            .count()
+    }
+
+    /// Returns the number of edges in the tree that have a branch length.
+    /// ```
+    /// use phylotree::tree::Tree;
+    ///
+    /// let tree = Tree::from_newick("((A:1,B:2)C,D:3)E;").unwrap();
+    /// assert_eq!(tree.edges_with_lengths(), 3);
+    /// ```
+    pub fn edges_with_lengths(&self) -> usize {
+        self.nodes
+            .iter()
+            .filter(|&node| !node.deleted && !node.is_root() && node.parent_edge.is_some())
+            .count()
     }
 
     /// Returns the height of the tree
@@ -673,7 +1476,7 @@ impl Tree {
         let s = self
             .nodes
             .iter()
-            .filter(|n| !n.is_root())
+            .filter(|n| !n.deleted && !n.is_root())
             .map(|n| n.parent_edge)
             .collect::<Option<Vec<_>>>();
         match s {
@@ -682,6 +1485,438 @@ impl Tree {
         }
     }
 
+    /// Checks that no branch of the tree has a negative length. Missing branch
+    /// lengths are allowed. Returns [`TreeError::InvalidBranchLength`] for the
+    /// first negative length found.
+    /// ```
+    /// use phylotree::tree::{Tree, TreeError};
+    ///
+    /// let mut tree = Tree::from_newick("((A:0.1,B:0.2)C:0.3,D)E;").unwrap();
+    /// assert!(tree.validate_branch_lengths().is_ok());
+    ///
+    /// tree.rescale(-1.0);
+    /// assert!(matches!(
+    ///     tree.validate_branch_lengths(),
+    ///     Err(TreeError::InvalidBranchLength(_))
+    /// ));
+    /// ```
+    pub fn validate_branch_lengths(&self) -> Result<(), TreeError> {
+        for node in self.nodes.iter().filter(|node| !node.deleted) {
+            if let Some(length) = node.parent_edge.filter(|&length| length < 0.0) {
+                return Err(TreeError::InvalidBranchLength(length));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Checks that the age calibrations of the tree *(see [`Tree::set_calibration()`])*
+    /// are consistent with each other, *i.e.* that no node has a minimum age higher than
+    /// the maximum age of one of its ancestors. Returns
+    /// [`TreeError::InconsistentCalibrations`] for the first inconsistency found.
+    /// ```
+    /// use phylotree::tree::{Tree, TreeError};
+    ///
+    /// let mut tree = Tree::from_newick("((A,B)C,D)E;").unwrap();
+    /// let root = tree.get_root().unwrap();
+    /// let c = tree.get_by_name("C").unwrap().id;
+    ///
+    /// tree.set_calibration(&root, 50.0, Some(60.0)).unwrap();
+    /// tree.set_calibration(&c, 20.0, None).unwrap();
+    /// assert!(tree.validate_calibrations().is_ok());
+    ///
+    /// tree.set_calibration(&c, 70.0, None).unwrap();
+    /// assert!(matches!(
+    ///     tree.validate_calibrations(),
+    ///     Err(TreeError::InconsistentCalibrations { .. })
+    /// ));
+    /// ```
+    pub fn validate_calibrations(&self) -> Result<(), TreeError> {
+        for node in self.nodes.iter().filter(|node| !node.deleted) {
+            let Some(calibration) = node.calibration else {
+                continue;
+            };
+
+            let mut parent = node.parent;
+            while let Some(ancestor) = parent {
+                let ancestor = self.get(&ancestor)?;
+                if let Some(max) = ancestor.calibration.and_then(|c| c.max) {
+                    if calibration.min > max {
+                        return Err(TreeError::InconsistentCalibrations {
+                            node: node.id,
+                            ancestor: ancestor.id,
+                        });
+                    }
+                }
+                parent = ancestor.parent;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Returns the total length of the tree, *i.e.* the sum of all branch lengths,
+    /// each branch being counted once. This is the same as [`Tree::length`].
+    /// Returns [`TreeError::MissingBranchLengths`] if a branch does not have a length.
+    /// ```
+    /// use phylotree::tree::Tree;
+    ///
+    /// let rooted = Tree::from_newick("((A:0.1,B:0.2)E:0.5,(C:0.3,D:0.4)F:0.5)G;").unwrap();
+    /// let unrooted = Tree::from_newick("(A:0.1,B:0.2,(C:0.3,D:0.4)E:1)F;").unwrap();
+    ///
+    /// assert_eq!(rooted.total_tree_length().unwrap(), 2.0);
+    /// assert_eq!(unrooted.total_tree_length().unwrap(), 2.0);
+    /// ```
+    pub fn total_tree_length(&self) -> Result<EdgeLength, TreeError> {
+        self.length()
+    }
+
+    /// Returns the mean branch length of the tree, *i.e.* the total length of the
+    /// tree divided by its number of branches.
+    /// Returns [`TreeError::MissingBranchLengths`] if a branch does not have a length
+    /// or if the tree has no branches.
+    /// ```
+    /// use phylotree::tree::Tree;
+    ///
+    /// let tree = Tree::from_newick("(A:0.1,B:0.2,(C:0.3,D:0.4)E:1)F;").unwrap();
+    ///
+    /// assert_eq!(tree.average_branch_length().unwrap(), 0.4);
+    /// ```
+    pub fn average_branch_length(&self) -> Result<EdgeLength, TreeError> {
+        let n_edges = self
+            .nodes
+            .iter()
+            .filter(|n| !n.deleted && !n.is_root())
+            .count();
+        if n_edges == 0 {
+            return Err(TreeError::MissingBranchLengths);
+        }
+
+        Ok(self.total_tree_length()? / n_edges as f64)
+    }
+
+    // Computes summary statistics over the lengths of branches above nodes matching a filter
+    fn branch_length_stats_impl<F>(&self, filter: F) -> Result<BranchLengthStats, TreeError>
+    where
+        F: Fn(&Node) -> bool,
+    {
+        let lengths: Vec<_> = self
+            .nodes
+            .iter()
+            .filter(|n| !n.deleted && !n.is_root() && filter(n))
+            .filter_map(|n| n.parent_edge)
+            .sorted_by(|a, b| a.total_cmp(b))
+            .collect();
+
+        if lengths.is_empty() {
+            return Err(TreeError::MissingBranchLengths);
+        }
+
+        let n = lengths.len();
+        let total: f64 = lengths.iter().sum();
+        let mean = total / n as f64;
+        let variance = lengths.iter().map(|l| (l - mean).powi(2)).sum::<f64>() / n as f64;
+        let median = if n % 2 == 0 {
+            (lengths[n / 2 - 1] + lengths[n / 2]) / 2.0
+        } else {
+            lengths[n / 2]
+        };
+
+        Ok(BranchLengthStats {
+            min: lengths[0],
+            max: lengths[n - 1],
+            mean,
+            median,
+            variance,
+            total,
+        })
+    }
+
+    /// Computes summary statistics *(min, max, mean, median, variance and total)* over
+    /// all the branches of the tree that have a length. Returns
+    /// [`TreeError::MissingBranchLengths`] if no branch has a length.
+    /// ```
+    /// use phylotree::tree::Tree;
+    ///
+    /// let tree = Tree::from_newick("((A:1,B:2)E:3,(C:4,D:5)F:6)G;").unwrap();
+    /// let stats = tree.branch_length_stats().unwrap();
+    ///
+    /// assert_eq!(stats.min, 1.0);
+    /// assert_eq!(stats.max, 6.0);
+    /// assert_eq!(stats.mean, 3.5);
+    /// assert_eq!(stats.median, 3.5);
+    /// assert_eq!(stats.total, 21.0);
+    /// ```
+    pub fn branch_length_stats(&self) -> Result<BranchLengthStats, TreeError> {
+        self.branch_length_stats_impl(|_| true)
+    }
+
+    /// Computes summary statistics over the internal branches of the tree that have a
+    /// length *(see [`Tree::branch_length_stats()`])*.
+    /// ```
+    /// use phylotree::tree::Tree;
+    ///
+    /// let tree = Tree::from_newick("((A:1,B:2)E:3,(C:4,D:5)F:6)G;").unwrap();
+    /// let stats = tree.internal_branch_length_stats().unwrap();
+    ///
+    /// assert_eq!(stats.mean, 4.5);
+    /// assert_eq!(stats.variance, 2.25);
+    /// ```
+    pub fn internal_branch_length_stats(&self) -> Result<BranchLengthStats, TreeError> {
+        self.branch_length_stats_impl(|n| !n.is_tip())
+    }
+
+    /// Computes summary statistics over the terminal branches of the tree that have a
+    /// length *(see [`Tree::branch_length_stats()`])*.
+    /// ```
+    /// use phylotree::tree::Tree;
+    ///
+    /// let tree = Tree::from_newick("((A:1,B:2)E:3,(C:4,D:5)F:6)G;").unwrap();
+    /// let stats = tree.external_branch_length_stats().unwrap();
+    ///
+    /// assert_eq!(stats.median, 3.0);
+    /// assert_eq!(stats.total, 12.0);
+    /// ```
+    pub fn external_branch_length_stats(&self) -> Result<BranchLengthStats, TreeError> {
+        self.branch_length_stats_impl(|n| n.is_tip())
+    }
+
+    /// Performs a linear regression of the root-to-tip distances against the sampling
+    /// dates of the tips *(e.g. in years)*, to assess the temporal signal of the tree as
+    /// in TempEst. The slope estimates the substitution rate under a strict molecular
+    /// clock, and the date at which the regression line crosses 0 that of the root.
+    ///
+    /// Returns [`TreeError::UnnamedLeaves`] if a tip has no name or no date in `tip_dates`,
+    /// [`TreeError::MissingBranchLengths`] if a branch between the root and a tip has no
+    /// length, and an error if all the tips have the same date.
+    /// ```
+    /// use std::collections::HashMap;
+    /// use phylotree::tree::Tree;
+    ///
+    /// let tree = Tree::from_newick("((A:1,B:2):1,(C:3,D:1):2);").unwrap();
+    /// let tip_dates = HashMap::from([
+    ///     ("A".to_string(), 2004.0),
+    ///     ("B".to_string(), 2006.0),
+    ///     ("C".to_string(), 2010.0),
+    ///     ("D".to_string(), 2006.0),
+    /// ]);
+    ///
+    /// let rtt = tree.rtt_regression(&tip_dates).unwrap();
+    ///
+    /// assert!((rtt.slope - 0.5).abs() < 1e-10);
+    /// assert!((rtt.intercept + 1000.0).abs() < 1e-8);
+    /// assert!((rtt.r_squared - 1.0).abs() < 1e-10);
+    /// ```
+    pub fn rtt_regression(&self, tip_dates: &HashMap<String, f64>) -> Result<RttResult, TreeError> {
+        let root = self.get_root()?;
+
+        let mut tips = vec![];
+        for leaf in self.get_leaves() {
+            let name = self
+                .get(&leaf)?
+                .name
+                .as_ref()
+                .ok_or(TreeError::UnnamedLeaves)?;
+            let date = *tip_dates.get(name).ok_or(TreeError::UnnamedLeaves)?;
+            let distance = self
+                .get_distance(&root, &leaf)?
+                .0
+                .ok_or(TreeError::MissingBranchLengths)?;
+            tips.push((name, date, distance));
+        }
+
+        let n = tips.len() as f64;
+        let mean_date = tips.iter().map(|(_, date, _)| date).sum::<f64>() / n;
+        let mean_distance = tips.iter().map(|(_, _, distance)| distance).sum::<f64>() / n;
+
+        let (mut sxx, mut sxy, mut syy) = (0.0, 0.0, 0.0);
+        for (_, date, distance) in tips.iter() {
+            let (dx, dy) = (date - mean_date, distance - mean_distance);
+            sxx += dx * dx;
+            sxy += dx * dy;
+            syy += dy * dy;
+        }
+
+        if sxx == 0.0 {
+            return Err(TreeError::GeneralError(
+                "The tips must have at least two different sampling dates",
+            ));
+        }
+
+        let slope = sxy / sxx;
+        let intercept = mean_distance - slope * mean_date;
+        // All the tips are at the same distance from the root and on the regression line
+        let r_squared = if syy == 0.0 {
+            1.0
+        } else {
+            sxy * sxy / (sxx * syy)
+        };
+
+        let residuals = tips
+            .into_iter()
+            .map(|(name, date, distance)| (name.clone(), distance - (intercept + slope * date)))
+            .collect();
+
+        Ok(RttResult {
+            slope,
+            intercept,
+            r_squared,
+            residuals,
+        })
+    }
+
+    /// Computes Faith's phylogenetic diversity of a set of tips, *i.e.* the sum
+    /// of the branch lengths of the smallest subtree connecting all the tips.
+    /// ```
+    /// use phylotree::tree::Tree;
+    ///
+    /// let tree = Tree::from_newick("((A:1,B:2)E:3,(C:4,D:5)F:6)G;").unwrap();
+    /// let tips: Vec<_> = ["A", "B", "C"]
+    ///     .iter()
+    ///     .map(|name| tree.get_by_name(name).unwrap().id)
+    ///     .collect();
+    ///
+    /// assert_eq!(tree.phylogenetic_diversity(&tips).unwrap(), 16.0);
+    /// ```
+    pub fn phylogenetic_diversity(&self, tip_set: &[NodeId]) -> Result<EdgeLength, TreeError> {
+        if tip_set.is_empty() {
+            return Ok(0.0);
+        }
+        let ancestor = self.get_mrca(tip_set)?;
+
+        // Collect the branches between the tips and their common ancestor
+        let mut branches = HashSet::new();
+        for tip in tip_set {
+            let mut current = *tip;
+            while current != ancestor && branches.insert(current) {
+                current = self
+                    .get(&current)?
+                    .parent
+                    .ok_or(NodeError::HasNoParent(current))?;
+            }
+        }
+
+        branches
+            .iter()
+            .map(|id| self.get(id).map(|node| node.parent_edge))
+            .try_fold(0.0, |sum, edge| match edge? {
+                Some(edge) => Ok(sum + edge),
+                None => Err(TreeError::MissingBranchLengths),
+            })
+    }
+
+    /// Computes the "fair proportion" evolutionary distinctiveness of each leaf
+    /// [(Isaac et al., 2007)](https://doi.org/10.1371/journal.pone.0000296):
+    /// the length of each branch is divided equally between the leaves of its
+    /// subtree, and the distinctiveness of a leaf is the sum of these shares on
+    /// the path from the root to the leaf. Returns a map from leaf ids to their
+    /// distinctiveness, or [`TreeError::MissingBranchLengths`] if some branches
+    /// have no length.
+    /// ```
+    /// use phylotree::tree::Tree;
+    ///
+    /// let tree = Tree::from_newick("((A:1,B:2)E:3,(C:4,D:5)F:6)G;").unwrap();
+    /// let ed = tree.evolutionary_distinctiveness().unwrap();
+    ///
+    /// assert_eq!(ed[&tree.get_by_name("A").unwrap().id], 2.5);
+    /// assert_eq!(ed[&tree.get_by_name("D").unwrap().id], 8.0);
+    /// ```
+    pub fn evolutionary_distinctiveness(&self) -> Result<HashMap<NodeId, f64>, TreeError> {
+        let root = self.get_root()?;
+
+        let mut n_leaves = vec![0usize; self.nodes.len()];
+        for node_id in self.postorder(&root)? {
+            let node = &self.nodes[node_id];
+            n_leaves[node_id] = if node.is_tip() {
+                1
+            } else {
+                node.children.iter().map(|child| n_leaves[*child]).sum()
+            };
+        }
+
+        let mut shares = vec![0.0; self.nodes.len()];
+        let mut distinctiveness = HashMap::new();
+        for node_id in self.preorder(&root)?.into_iter().skip(1) {
+            let node = &self.nodes[node_id];
+            let edge = node.parent_edge.ok_or(TreeError::MissingBranchLengths)?;
+            shares[node_id] = shares[node.parent.unwrap()] + edge / n_leaves[node_id] as f64;
+            if node.is_tip() {
+                distinctiveness.insert(node_id, shares[node_id]);
+            }
+        }
+
+        Ok(distinctiveness)
+    }
+
+    // Pairwise path lengths between named leaves
+    fn named_leaf_distances(&self) -> Result<Vec<Vec<EdgeLength>>, TreeError> {
+        let leaves: Vec<_> = self
+            .get_leaves()
+            .into_iter()
+            .filter(|id| self.nodes[*id].name.is_some())
+            .collect();
+        if leaves.len() < 2 {
+            return Err(TreeError::GeneralError(
+                "At least 2 named leaves are needed to compute distances",
+            ));
+        }
+
+        let mut distances = vec![vec![0.0; leaves.len()]; leaves.len()];
+        for (i, j) in (0..leaves.len()).tuple_combinations() {
+            let (dist, _) = self.get_distance(&leaves[i], &leaves[j])?;
+            let dist = dist.ok_or(TreeError::MissingBranchLengths)?;
+            distances[i][j] = dist;
+            distances[j][i] = dist;
+        }
+
+        Ok(distances)
+    }
+
+    /// Computes the mean pairwise distance *(MPD)* between named leaves of the tree,
+    /// as defined by [Webb et al. 2002](https://doi.org/10.1146/annurev.ecolsys.33.010802.150448).
+    /// ```
+    /// use phylotree::tree::Tree;
+    ///
+    /// let tree = Tree::from_newick("((A:1,B:1):1,C:2);").unwrap();
+    ///
+    /// assert_eq!(tree.mpd().unwrap(), (2.0 + 4.0 + 4.0) / 3.0);
+    /// ```
+    pub fn mpd(&self) -> Result<EdgeLength, TreeError> {
+        let distances = self.named_leaf_distances()?;
+        let n = distances.len();
+        let total: f64 = distances.iter().flatten().sum();
+
+        Ok(total / (n * (n - 1)) as f64)
+    }
+
+    /// Computes the mean nearest taxon distance *(MNTD)* of the tree, *i.e.* the mean
+    /// over all named leaves of the distance to the closest other named leaf, as defined by
+    /// [Webb et al. 2002](https://doi.org/10.1146/annurev.ecolsys.33.010802.150448).
+    /// ```
+    /// use phylotree::tree::Tree;
+    ///
+    /// let tree = Tree::from_newick("((A:1,B:1):1,C:2);").unwrap();
+    ///
+    /// assert_eq!(tree.mntd().unwrap(), (2.0 + 2.0 + 4.0) / 3.0);
+    /// ```
+    pub fn mntd(&self) -> Result<EdgeLength, TreeError> {
+        let distances = self.named_leaf_distances()?;
+        let n = distances.len();
+        let total: f64 = distances
+            .iter()
+            .enumerate()
+            .map(|(i, row)| {
+                row.iter()
+                    .enumerate()
+                    .filter(|(j, _)| *j != i)
+                    .map(|(_, d)| *d)
+                    .fold(f64::INFINITY, f64::min)
+            })
+            .sum();
+
+        Ok(total / n as f64)
+    }
+
     /// Checks if the tree is rooted and binary
     fn check_rooted_binary(&self) -> Result<(), TreeError> {
         if !self.is_rooted()? {
@@ -780,7 +2015,7 @@ impl Tree {
         Ok(self
             .get_leaves()
             .iter()
-            .map(|tip_idx| self.get(tip_idx).unwrap().depth)
+            .map(|tip_idx| self.get(tip_idx).unwrap().get_depth())
             .sum())
     }
 
@@ -811,40 +2046,496 @@ impl Tree {
         self.sackin()
             .map(|i_n| i_n as f64 / f64::powf(self.n_leaves() as f64, 3.0 / 2.0))
     }
-}
 
-/// Methods that compute edge bipartitions and compare [`Tree`] objects with each other.
-///   
-/// ----
-/// ----
-impl Tree {
-    // #########################
-    // # GET EDGES IN THE TREE #
-    // #########################
+    /// Computes all the balance statistics of the tree *(number of cherries, Sackin
+    /// and Colless indices and their normalized versions)* as well as its size,
+    /// height and diameter in a single postorder traversal.
+    /// The tree must be rooted and binary.
+    /// ```
+    /// use phylotree::tree::Tree;
+    ///
+    /// let tree = Tree::from_newick("(((A:1,B:1):1,C:2):1,D:3);").unwrap();
+    /// let stats = tree.balance_stats().unwrap();
+    ///
+    /// assert_eq!(stats.n_leaves, 4);
+    /// assert_eq!(stats.n_nodes, 7);
+    /// assert_eq!(stats.cherries, 1);
+    /// assert_eq!(stats.sackin, tree.sackin().unwrap());
+    /// assert_eq!(stats.colless, tree.colless().unwrap());
+    /// assert_eq!(stats.height, Some(3.0));
+    /// assert_eq!(stats.diameter, Some(6.0));
+    /// ```
+    pub fn balance_stats(&self) -> Result<BalanceStats, TreeError> {
+        self.check_rooted_binary()?;
+
+        let root = self.get_root()?;
+        let mut n_leaves: Vec<usize> = vec![0; self.nodes.len()];
+        // Longest path from each node to its descendant leaves
+        let mut heights: Vec<Option<f64>> = vec![Some(0.0); self.nodes.len()];
+        let mut diameter: Option<f64> = Some(0.0);
+        let (mut n_nodes, mut cherries, mut sackin, mut colless) = (0, 0, 0, 0);
+
+        for id in self.postorder(&root)? {
+            n_nodes += 1;
+            let node = self.get(&id)?;
+            if node.is_tip() {
+                n_leaves[id] = 1;
+                continue;
+            }
+
+            let (left, right) = (node.children[0], node.children[1]);
+            n_leaves[id] = n_leaves[left] + n_leaves[right];
+            sackin += n_leaves[id];
+            colless += n_leaves[left].abs_diff(n_leaves[right]);
+            if n_leaves[left] == 1 && n_leaves[right] == 1 {
+                cherries += 1;
+            }
+
+            let to_left = heights[left].zip(self.get(&left)?.parent_edge);
+            let to_right = heights[right].zip(self.get(&right)?.parent_edge);
+            let (to_left, to_right) = match (to_left, to_right) {
+                (Some((h1, l1)), Some((h2, l2))) => (Some(h1 + l1), Some(h2 + l2)),
+                _ => (None, None),
+            };
+            heights[id] = to_left.zip(to_right).map(|(l, r)| l.max(r));
+            diameter = diameter
+                .zip(to_left.zip(to_right))
+                .map(|(d, (l, r))| d.max(l + r));
+        }
+
+        let n = n_leaves[root] as f64;
+        let harmonic: f64 = (2..=n_leaves[root]).map(|i| 1.0 / (i as f64)).sum();
+        let e_colless = n * n.ln() + (0.57721566 - 1. - f64::ln(2.0)) * n;
+
+        Ok(BalanceStats {
+            n_leaves: n_leaves[root],
+            n_nodes,
+            height: heights[root],
+            diameter,
+            cherries,
+            sackin,
+            sackin_yule: (sackin as f64 - 2.0 * n * harmonic) / n,
+            sackin_pda: sackin as f64 / f64::powf(n, 3.0 / 2.0),
+            colless,
+            colless_yule: (colless as f64 - e_colless) / n,
+            colless_pda: colless as f64 / f64::powf(n, 3.0 / 2.0),
+        })
+    }
 
-    /// Initializes the leaf index
-    fn init_leaf_index(&self) -> Result<(), TreeError> {
+    /// Computes the B1 balance index of Shao and Sokal (1990):
+    /// $$
+    /// B_1 = \sum_{i} 1/M_i
+    /// $$
+    /// With the sum over all internal nodes $i$ except the root, and $M_i$ the
+    /// maximum number of edges between node $i$ and a leaf of its subtree.
+    /// A larger B1 index means a more balanced tree.
+    /// ```
+    /// use phylotree::tree::Tree;
+    ///
+    /// let tree = Tree::from_newick("((A,B),(C,D));").unwrap();
+    /// assert_eq!(tree.b1().unwrap(), 2.0);
+    ///
+    /// let tree = Tree::from_newick("(((A,B),C),D);").unwrap();
+    /// assert_eq!(tree.b1().unwrap(), 1.5);
+    /// ```
+    pub fn b1(&self) -> Result<f64, TreeError> {
         if self.nodes.is_empty() {
             return Err(TreeError::IsEmpty);
         }
-        if self.leaf_index.borrow().is_some() {
-            return Ok(());
+        if !self.is_rooted()? {
+            return Err(TreeError::IsNotRooted);
         }
 
-        let names = self.get_leaf_names();
-        if names.len() != self.n_leaves() {
-            return Err(TreeError::UnnamedLeaves);
+        let root = self.get_root()?;
+        let mut heights = vec![0usize; self.nodes.len()];
+        let mut b1 = 0.0;
+        for node_id in self.postorder(&root)? {
+            let node = self.get(&node_id)?;
+            if node.is_tip() {
+                continue;
+            }
+            heights[node_id] = 1 + node
+                .children
+                .iter()
+                .map(|child| heights[*child])
+                .max()
+                .unwrap();
+            if node_id != root {
+                b1 += 1.0 / heights[node_id] as f64;
+            }
         }
 
-        if !self.has_unique_tip_names()? {
-            return Err(TreeError::DuplicateLeafNames);
+        Ok(b1)
+    }
+
+    /// Computes the B2 balance index of Shao and Sokal (1990):
+    /// $$
+    /// B_2 = -\sum_{i=1}^n p_i \log_2 p_i
+    /// $$
+    /// With $p_i$ the probability of reaching leaf $i$ when going down from the
+    /// root and choosing each child with equal probability at every internal node.
+    /// For binary trees $p_i = 1/2^{d_i}$ with $d_i$ the depth of leaf $i$.
+    /// A larger B2 index means a more balanced tree.
+    /// ```
+    /// use phylotree::tree::Tree;
+    ///
+    /// let tree = Tree::from_newick("((A,B),(C,D));").unwrap();
+    /// assert_eq!(tree.b2().unwrap(), 2.0);
+    ///
+    /// let tree = Tree::from_newick("(((A,B),C),D);").unwrap();
+    /// assert_eq!(tree.b2().unwrap(), 1.75);
+    /// ```
+    pub fn b2(&self) -> Result<f64, TreeError> {
+        if self.nodes.is_empty() {
+            return Err(TreeError::IsEmpty);
+        }
+        if !self.is_rooted()? {
+            return Err(TreeError::IsNotRooted);
+        }
+
+        let root = self.get_root()?;
+        let mut probabilities = vec![1.0; self.nodes.len()];
+        let mut b2 = 0.0;
+        for node_id in self.preorder(&root)? {
+            let node = self.get(&node_id)?;
+            if node.is_tip() {
+                let p: f64 = probabilities[node_id];
+                b2 -= p * p.log2();
+            }
+            for child in node.children.iter() {
+                probabilities[*child] = probabilities[node_id] / node.children.len() as f64;
+            }
+        }
+
+        Ok(b2)
+    }
+
+    // Sum of branch lengths from the root to each node, indexed by node id
+    fn root_distances(&self) -> Result<Vec<EdgeLength>, TreeError> {
+        let root = self.get_root()?;
+        let mut root_dists = vec![0.0; self.nodes.len()];
+        for node_id in self.preorder(&root)?.into_iter().skip(1) {
+            let node = self.get(&node_id)?;
+            let edge = node.parent_edge.ok_or(TreeError::MissingBranchLengths)?;
+            root_dists[node_id] = root_dists[node.parent.unwrap()] + edge;
+        }
+
+        Ok(root_dists)
+    }
+
+    /// Returns the topological depth *(i.e. number of edges from the root)* of every
+    /// node in the tree, indexed by node id.
+    /// ```
+    /// use phylotree::tree::Tree;
+    ///
+    /// let tree = Tree::from_newick("((A,B)E,(C,D)F)G;").unwrap();
+    ///
+    /// assert_eq!(tree.node_depths_vector(), vec![0, 1, 2, 2, 1, 2, 2]);
+    /// ```
+    pub fn node_depths_vector(&self) -> Vec<usize> {
+        self.nodes.iter().map(|node| node.get_depth()).collect()
+    }
+
+    /// Returns the topological depth of each named tip of the tree, sorted by tip name.
+    /// ```
+    /// use phylotree::tree::Tree;
+    ///
+    /// let tree = Tree::from_newick("((C,(B,A)),D);").unwrap();
+    ///
+    /// assert_eq!(
+    ///     tree.tip_depths_vector(),
+    ///     vec![
+    ///         ("A".to_string(), 3),
+    ///         ("B".to_string(), 3),
+    ///         ("C".to_string(), 2),
+    ///         ("D".to_string(), 1)
+    ///     ]
+    /// );
+    /// ```
+    pub fn tip_depths_vector(&self) -> Vec<(String, usize)> {
+        self.nodes
+            .iter()
+            .filter(|node| !node.deleted && node.is_tip())
+            .filter_map(|node| node.name.clone().map(|name| (name, node.get_depth())))
+            .sorted()
+            .collect()
+    }
+
+    /// Returns the number of nodes at each topological depth of the tree, as
+    /// `(depth, count)` pairs sorted by depth.
+    /// ```
+    /// use phylotree::tree::Tree;
+    ///
+    /// let tree = Tree::from_newick("((A,(B,C)),D);").unwrap();
+    ///
+    /// assert_eq!(tree.node_count_by_depth(), vec![(0, 1), (1, 2), (2, 2), (3, 2)]);
+    /// ```
+    pub fn node_count_by_depth(&self) -> Vec<(usize, usize)> {
+        self.nodes
+            .iter()
+            .filter(|node| !node.deleted)
+            .map(|node| node.get_depth())
+            .counts()
+            .into_iter()
+            .sorted()
+            .collect()
+    }
+
+    /// Returns the number of leaves at each topological depth of the tree, as
+    /// `(depth, count)` pairs sorted by depth. Depths without leaves are omitted.
+    /// ```
+    /// use phylotree::tree::Tree;
+    ///
+    /// let tree = Tree::from_newick("((A,(B,C)),D);").unwrap();
+    ///
+    /// assert_eq!(tree.leaf_count_by_depth(), vec![(1, 1), (2, 1), (3, 2)]);
+    /// ```
+    pub fn leaf_count_by_depth(&self) -> Vec<(usize, usize)> {
+        self.nodes
+            .iter()
+            .filter(|node| !node.deleted && node.is_tip())
+            .map(|node| node.get_depth())
+            .counts()
+            .into_iter()
+            .sorted()
+            .collect()
+    }
+
+    /// Returns the sum of branch lengths on the path from the root to each named tip
+    /// of the tree, sorted by tip name.
+    /// Returns [`TreeError::MissingBranchLengths`] if some branches do not have a length.
+    /// ```
+    /// use phylotree::tree::Tree;
+    ///
+    /// let tree = Tree::from_newick("((C:1,(B:1,A:2):0.5):1,D:3);").unwrap();
+    ///
+    /// assert_eq!(
+    ///     tree.root_to_tip_lengths().unwrap(),
+    ///     vec![
+    ///         ("A".to_string(), 3.5),
+    ///         ("B".to_string(), 2.5),
+    ///         ("C".to_string(), 2.0),
+    ///         ("D".to_string(), 3.0)
+    ///     ]
+    /// );
+    /// ```
+    pub fn root_to_tip_lengths(&self) -> Result<Vec<(String, EdgeLength)>, TreeError> {
+        let root_dists = self.root_distances()?;
+        let mut lengths: Vec<_> = self
+            .get_leaves()
+            .into_iter()
+            .filter_map(|id| {
+                let name = self.nodes[id].name.clone()?;
+                Some((name, root_dists[id]))
+            })
+            .collect();
+        lengths.sort_by(|(n1, _), (n2, _)| n1.cmp(n2));
+
+        Ok(lengths)
+    }
+
+    /// Checks if the tree is ultrametric, *i.e.* if all the root to tip distances
+    /// deviate from their mean by at most `tolerance`. The tree must be rooted
+    /// and have all branch lengths.
+    /// ```
+    /// use phylotree::tree::Tree;
+    ///
+    /// let tree = Tree::from_newick("((A:1,B:1):2,(C:2,D:2.01):1);").unwrap();
+    ///
+    /// assert!(tree.is_ultrametric(0.01).unwrap());
+    /// assert!(!tree.is_ultrametric(0.001).unwrap());
+    /// ```
+    pub fn is_ultrametric(&self, tolerance: f64) -> Result<bool, TreeError> {
+        if !self.is_rooted()? {
+            return Err(TreeError::IsNotRooted);
+        }
+
+        let root_dists = self.root_distances()?;
+        let tip_dists: Vec<_> = self.get_leaves().iter().map(|id| root_dists[*id]).collect();
+        let mean = tip_dists.iter().sum::<f64>() / tip_dists.len() as f64;
+
+        Ok(tip_dists.iter().all(|d| (d - mean).abs() <= tolerance))
+    }
+
+    /// Computes the gamma statistic of Pybus and Harvey (2000) from the
+    /// internode intervals of the tree. Under a constant rate pure-birth process
+    /// the statistic follows a standard normal distribution, negative values indicate
+    /// that internal nodes are closer to the root than expected. The tree must be
+    /// rooted, binary and ultrametric *(up to a tolerance of $10^{-6}$, see
+    /// [`Tree::is_ultrametric()`])* with all branch lengths.
+    /// ```
+    /// use phylotree::tree::Tree;
+    ///
+    /// let tree = Tree::from_newick("((A:1,B:1):2,(C:2,D:2):1);").unwrap();
+    /// let gamma = tree.gamma_statistic().unwrap();
+    ///
+    /// assert!((gamma - -0.544331).abs() < 1e-6);
+    /// ```
+    pub fn gamma_statistic(&self) -> Result<f64, TreeError> {
+        self.check_rooted_binary()?;
+        if !self.is_ultrametric(ULTRAMETRIC_TOLERANCE)? {
+            return Err(TreeError::GeneralError(
+                "The gamma statistic can only be computed on ultrametric trees",
+            ));
+        }
+
+        let n = self.n_leaves();
+        if n < 3 {
+            return Err(TreeError::GeneralError(
+                "The gamma statistic requires at least 3 leaves",
+            ));
+        }
+
+        let root_dists = self.root_distances()?;
+        let height = self
+            .get_leaves()
+            .iter()
+            .map(|leaf| root_dists[*leaf])
+            .fold(f64::MIN, f64::max);
+        let branching_times: Vec<_> = self
+            .search_nodes(|node| !node.is_tip())
+            .iter()
+            .map(|id| root_dists[*id])
+            .chain(std::iter::once(height))
+            .sorted_by(|a, b| a.partial_cmp(b).unwrap_or(std::cmp::Ordering::Equal))
+            .collect();
+
+        // g[k] is the interval during which there are k+2 lineages
+        let intervals: Vec<_> = branching_times.windows(2).map(|w| w[1] - w[0]).collect();
+
+        let weighted: Vec<_> = intervals
+            .iter()
+            .enumerate()
+            .map(|(k, g)| (k + 2) as f64 * g)
+            .collect();
+        let total: f64 = weighted.iter().sum();
+        let inner: f64 = weighted[..n - 2]
+            .iter()
+            .scan(0.0, |cumsum, v| {
+                *cumsum += v;
+                Some(*cumsum)
+            })
+            .sum();
+
+        let mean = inner / (n - 2) as f64 - total / 2.0;
+        let std = total * (1.0 / (12.0 * (n - 2) as f64)).sqrt();
+
+        Ok(mean / std)
+    }
+
+    /// Computes the age of every node in a time-calibrated tree, *i.e.* the distance
+    /// between the node and the tips descending from it. Tips have an age of 0 and
+    /// the root is the oldest node. The tree must be rooted and ultrametric
+    /// *(up to a tolerance of $10^{-6}$, see [`Tree::is_ultrametric()`])*.
+    /// ```
+    /// use phylotree::tree::Tree;
+    ///
+    /// let tree = Tree::from_newick("((A:1,B:1)E:2,(C:2,D:2)F:1)G;").unwrap();
+    /// let ages = tree.node_ages().unwrap();
+    ///
+    /// assert_eq!(ages[&tree.get_by_name("G").unwrap().id], 3.0);
+    /// assert_eq!(ages[&tree.get_by_name("E").unwrap().id], 1.0);
+    /// assert_eq!(ages[&tree.get_by_name("F").unwrap().id], 2.0);
+    /// assert_eq!(ages[&tree.get_by_name("A").unwrap().id], 0.0);
+    /// ```
+    pub fn node_ages(&self) -> Result<HashMap<NodeId, f64>, TreeError> {
+        if !self.is_ultrametric(ULTRAMETRIC_TOLERANCE)? {
+            return Err(TreeError::GeneralError(
+                "Node ages can only be computed on ultrametric trees",
+            ));
+        }
+
+        let mut ages = HashMap::new();
+        for id in self.postorder(&self.get_root()?)? {
+            let node = self.get(&id)?;
+            let age = node
+                .children
+                .iter()
+                .map(|child| ages[child] + self.nodes[*child].parent_edge.unwrap_or_default())
+                .fold(0.0, f64::max);
+            ages.insert(id, age);
+        }
+
+        Ok(ages)
+    }
+
+    /// Computes the age of a single node in a time-calibrated tree
+    /// *(see [`Tree::node_ages()`])*.
+    /// ```
+    /// use phylotree::tree::Tree;
+    ///
+    /// let tree = Tree::from_newick("((A:1,B:1)E:2,(C:2,D:2)F:1)G;").unwrap();
+    ///
+    /// assert_eq!(tree.age_of(&tree.get_by_name("F").unwrap().id).unwrap(), 2.0);
+    /// ```
+    pub fn age_of(&self, id: &NodeId) -> Result<f64, TreeError> {
+        self.get(id)?;
+        self.node_ages()?
+            .remove(id)
+            .ok_or(TreeError::NodeNotFound(*id))
+    }
+}
+
+/// Methods that compute edge bipartitions and compare [`Tree`] objects with each other.
+///   
+/// ----
+/// ----
+impl Tree {
+    // #########################
+    // # GET EDGES IN THE TREE #
+    // #########################
+
+    /// Initializes the leaf index of the tree, *i.e.* the sorted list of leaf names
+    /// used to represent bipartitions as bitsets *(see [`Partition`])*. The index is
+    /// cached until [`Tree::reset_bipartition_cache()`] is called, it is initialized
+    /// automatically by the methods that use bipartitions.
+    ///
+    /// All the leaves must have a name, and leaf names must be unique.
+    /// ```
+    /// use phylotree::tree::Tree;
+    ///
+    /// let tree = Tree::from_newick("((C,A),(B,D));").unwrap();
+    /// tree.init_leaf_index().unwrap();
+    ///
+    /// assert_eq!(tree.get_leaf_index().unwrap(), vec!["A", "B", "C", "D"]);
+    /// assert!(Tree::from_newick("((C,A),(B,));").unwrap().init_leaf_index().is_err());
+    /// ```
+    pub fn init_leaf_index(&self) -> Result<(), TreeError> {
+        if self.nodes.is_empty() {
+            return Err(TreeError::IsEmpty);
+        }
+        if self.leaf_index.borrow().is_some() {
+            return Ok(());
         }
 
+        self.check_tip_names()?;
+        let names = self.get_leaf_names();
+
         (*self.leaf_index.borrow_mut()) = Some(names.into_iter().flatten().sorted().collect());
 
         Ok(())
     }
 
+    /// Returns the leaf index of the tree, initializing it if needed
+    /// *(see [`Tree::init_leaf_index()`])*.
+    pub fn get_leaf_index(&self) -> Result<Vec<String>, TreeError> {
+        self.init_leaf_index()?;
+
+        Ok(self.leaf_index.borrow().clone().unwrap())
+    }
+
+    /// Initializes the leaf indices of both trees and checks that they are identical
+    fn check_same_leaf_index(&self, other: &Self) -> Result<(), TreeError> {
+        self.init_leaf_index()?;
+        other.init_leaf_index()?;
+
+        if *(self.leaf_index.borrow()) != *(other.leaf_index.borrow()) {
+            return Err(TreeError::DifferentTipIndices);
+        }
+
+        Ok(())
+    }
+
     /// Get the partition corresponding to the branch associated to the node at index
     fn get_partition(&self, index: &NodeId) -> Result<Partition, TreeError> {
         self.init_leaf_index()?;
@@ -867,7 +2558,7 @@ impl Tree {
         Ok(toggled.min(bitset))
     }
 
-    /// Helper function to view a partition as
+    /// Helper function to view a partition as the concatenated names of the leaves it contains
     pub fn partition_to_leaves(&self, partition: &Partition) -> Result<String, TreeError> {
         self.init_leaf_index()?;
 
@@ -875,6 +2566,73 @@ impl Tree {
         Ok(partition.ones().map(|i| v[i].clone()).collect())
     }
 
+    /// Writes the clade corresponding to a bipartition as a newick string, which is
+    /// easier to read than the bitset when reporting partition-based comparisons.
+    /// The clade is the subtree whose leaves are exactly those of the partition or,
+    /// since bipartitions are unordered, of its complement.
+    ///
+    /// Returns [`TreeError::LeafIndexNotInitialized`] if the leaf index of the tree is not
+    /// initialized *(see [`Tree::init_leaf_index()`])* and [`TreeError::PartitionNotFound`]
+    /// if no subtree corresponds to the bipartition.
+    /// ```
+    /// use phylotree::tree::Tree;
+    ///
+    /// let tree = Tree::from_newick("((A:1,B:1)E:0.5,(C:1,D:1)F:0.5,G:2)H;").unwrap();
+    ///
+    /// let mut clades: Vec<_> = tree
+    ///     .get_partitions()
+    ///     .unwrap()
+    ///     .iter()
+    ///     .map(|p| tree.bipartition_to_newick(p).unwrap())
+    ///     .collect();
+    /// clades.sort();
+    ///
+    /// assert_eq!(clades, vec!["(A:1,B:1)E:0.5;", "(C:1,D:1)F:0.5;"]);
+    /// ```
+    pub fn bipartition_to_newick(&self, partition: &Partition) -> Result<String, TreeError> {
+        let leaf_index = self
+            .leaf_index
+            .borrow()
+            .clone()
+            .ok_or(TreeError::LeafIndexNotInitialized)?;
+
+        let mut clade = partition.clone();
+        clade.grow(leaf_index.len());
+        let mut complement = clade.clone();
+        complement.toggle_range(..);
+
+        let mut clade_root = None;
+        for node in self.nodes.iter().filter(|node| !node.deleted) {
+            let mut leaves = FixedBitSet::with_capacity(leaf_index.len());
+            for leaf in self.get_subtree_leaves(&node.id)? {
+                if let Some(name) = self.get(&leaf)?.name.as_ref() {
+                    if let Ok(index) = leaf_index.binary_search(name) {
+                        leaves.insert(index);
+                    }
+                }
+            }
+
+            if leaves == clade {
+                clade_root = Some(node);
+                break;
+            } else if leaves == complement && clade_root.is_none() {
+                clade_root = Some(node);
+            }
+        }
+
+        let root = clade_root.ok_or(TreeError::PartitionNotFound)?;
+        let mut buffer = Vec::new();
+        self.write_newick_impl(
+            &mut buffer,
+            root,
+            NewickFormat::AllFields,
+            LengthPrecision::Shortest,
+        )?;
+        buffer.push(b';');
+
+        Ok(String::from_utf8(buffer).expect("Newick strings are written from valid UTF-8"))
+    }
+
     /// Caches partitions for distance computation
     fn init_partitions(&self) -> Result<(), TreeError> {
         self.init_leaf_index()?;
@@ -906,7 +2664,7 @@ impl Tree {
                 (None, Some((_, old_len))) => *old_len,
             };
 
-            partitions.insert(part, (node.depth, len));
+            partitions.insert(part, (node.get_depth(), len));
         }
 
         (*self.partitions.borrow_mut()) = Some(partitions);
@@ -914,7 +2672,26 @@ impl Tree {
         Ok(())
     }
 
-    /// Get all partitions of a tree
+    /// Get all the non-trivial bipartitions of a tree. Each bipartition is stored as a
+    /// bitset over the leaf index of the tree, which is initialized by this method if
+    /// needed *(see [`Tree::init_leaf_index()`])*. Of the two complementary bitsets
+    /// describing a bipartition, the smallest one is returned so that bipartitions of
+    /// trees sharing the same leaf index can be compared directly.
+    /// ```
+    /// use phylotree::tree::Tree;
+    ///
+    /// let tree = Tree::from_newick("((A,B),(C,D),(E,F));").unwrap();
+    /// let other = Tree::from_newick("((A,B),(C,E),(D,F));").unwrap();
+    ///
+    /// let partitions = tree.get_partitions().unwrap();
+    /// let shared: Vec<_> = partitions
+    ///     .intersection(&other.get_partitions().unwrap())
+    ///     .map(|p| tree.partition_to_leaves(p).unwrap())
+    ///     .collect();
+    ///
+    /// assert_eq!(partitions.len(), 3);
+    /// assert_eq!(shared, vec!["AB"]);
+    /// ```
     pub fn get_partitions(&self) -> Result<PartitionSet, TreeError> {
         self.init_leaf_index()?;
         self.init_partitions()?;
@@ -974,14 +2751,24 @@ impl Tree {
     /// $$
     /// Where $A$ and $B$ are the sets of bipartitions of the first and second trees.  
     /// See also [Tree::compare_topologies()]
+    ///
+    /// Both trees must have the same set of uniquely named leaves, otherwise
+    /// [`TreeError::DifferentTipIndices`] is returned.
+    /// ```
+    /// use phylotree::tree::Tree;
+    ///
+    /// let tree1 = Tree::from_newick("((A,B),(C,D),(E,F));").unwrap();
+    /// let tree2 = Tree::from_newick("((A,B),(C,E),(D,F));").unwrap();
+    ///
+    /// assert_eq!(tree1.robinson_foulds(&tree1).unwrap(), 0);
+    /// assert_eq!(tree1.robinson_foulds(&tree2).unwrap(), 4);
+    /// ```
     pub fn robinson_foulds(&self, other: &Self) -> Result<usize, TreeError> {
+        self.check_same_leaf_index(other)?;
+
         let partitions_s = self.get_partitions()?;
         let partitions_o = other.get_partitions()?;
 
-        if *(self.leaf_index.borrow()) != *(other.leaf_index.borrow()) {
-            return Err(TreeError::DifferentTipIndices);
-        }
-
         let mut root_s = HashSet::new();
         for i in self.get(&self.get_root()?)?.children.iter() {
             root_s.insert(self.get_partition(i)?);
@@ -1004,6 +2791,108 @@ impl Tree {
         }
     }
 
+    /// Checks if two trees have the same topology, *i.e.* the same set of bipartitions,
+    /// regardless of branch lengths, internal node names or the order of children.
+    /// This is equivalent to a [Robinson-Foulds distance](Tree::robinson_foulds())
+    /// of 0 but cheaper to compute.
+    ///
+    /// Both trees must have the same set of uniquely named leaves, otherwise
+    /// [`TreeError::DifferentTipIndices`] is returned.
+    /// ```
+    /// use phylotree::tree::Tree;
+    ///
+    /// let tree1 = Tree::from_newick("((A:1,B:2)E:1,(C:3,D:1)F:2);").unwrap();
+    /// let tree2 = Tree::from_newick("((D,C),(B,A));").unwrap();
+    /// let tree3 = Tree::from_newick("((A,C),(B,D));").unwrap();
+    ///
+    /// assert!(tree1.topology_equal(&tree2).unwrap());
+    /// assert!(!tree1.topology_equal(&tree3).unwrap());
+    /// ```
+    pub fn topology_equal(&self, other: &Self) -> Result<bool, TreeError> {
+        self.check_same_leaf_index(other)?;
+
+        Ok(self.get_partitions()? == other.get_partitions()?)
+    }
+
+    /// Checks if two trees are compatible on their common leaves, *i.e.* if both trees,
+    /// restricted to the leaves they share, could be refinements of the same tree.
+    /// This is the case if every bipartition of one tree is compatible with every
+    /// bipartition of the other: two bipartitions are compatible if one side of the
+    /// first does not overlap with one side of the second.
+    ///
+    /// Trees sharing fewer than 4 leaves are always compatible.
+    /// Returns [`TreeError::UnnamedLeaves`] if any leaf of either tree has no name.
+    /// ```
+    /// use phylotree::tree::Tree;
+    ///
+    /// let reference = Tree::from_newick("((A,B),(C,D),(E,F));").unwrap();
+    /// let refined = Tree::from_newick("(((A,B),X),((C,D),(E,F)));").unwrap();
+    /// let conflicting = Tree::from_newick("((A,C),(B,D),Y);").unwrap();
+    ///
+    /// assert!(reference.is_consistent_with(&refined).unwrap());
+    /// assert!(!reference.is_consistent_with(&conflicting).unwrap());
+    /// ```
+    pub fn is_consistent_with(&self, other: &Self) -> Result<bool, TreeError> {
+        let names_s = self.get_leaf_names_sorted()?;
+        let names_o: HashSet<_> = other.get_leaf_names_sorted()?.into_iter().collect();
+        let common: Vec<&str> = names_s
+            .iter()
+            .filter(|name| names_o.contains(*name))
+            .map(String::as_str)
+            .collect();
+
+        if common.len() < 4 {
+            return Ok(true);
+        }
+
+        let mut restricted_s = self.clone();
+        let mut restricted_o = other.clone();
+        restricted_s.prune_to_taxa(&common)?;
+        restricted_o.prune_to_taxa(&common)?;
+        restricted_s.check_same_leaf_index(&restricted_o)?;
+
+        let partitions_s = restricted_s.get_partitions()?;
+        let partitions_o = restricted_o.get_partitions()?;
+
+        let compatible = |a: &Partition, b: &Partition| {
+            a.is_disjoint(b)
+                || a.is_subset(b)
+                || b.is_subset(a)
+                || a.union(b).count() == common.len()
+        };
+
+        Ok(partitions_s
+            .iter()
+            .all(|a| partitions_o.iter().all(|b| compatible(a, b))))
+    }
+
+    /// Returns the number of distinct unrooted binary tree topologies with `n_leaves`
+    /// labelled leaves: $(2n-5)!! = 1 \times 3 \times 5 \times \dots \times (2n-5)$.
+    /// Returns `None` if the number does not fit in a [`u128`] *(from 31 leaves)*.
+    /// ```
+    /// use phylotree::tree::Tree;
+    ///
+    /// assert_eq!(Tree::number_of_topologies(4), Some(3));
+    /// assert_eq!(Tree::number_of_topologies(10), Some(2_027_025));
+    /// assert_eq!(Tree::number_of_topologies(100), None);
+    /// ```
+    pub fn number_of_topologies(n_leaves: usize) -> Option<u128> {
+        double_factorial((2 * n_leaves).saturating_sub(5))
+    }
+
+    /// Returns the number of distinct rooted binary tree topologies with `n_leaves`
+    /// labelled leaves: $(2n-3)!! = 1 \times 3 \times 5 \times \dots \times (2n-3)$.
+    /// Returns `None` if the number does not fit in a [`u128`] *(from 30 leaves)*.
+    /// ```
+    /// use phylotree::tree::Tree;
+    ///
+    /// assert_eq!(Tree::number_of_rooted_topologies(4), Some(15));
+    /// assert_eq!(Tree::number_of_rooted_topologies(10), Some(34_459_425));
+    /// ```
+    pub fn number_of_rooted_topologies(n_leaves: usize) -> Option<u128> {
+        double_factorial((2 * n_leaves).saturating_sub(3))
+    }
+
     /// Computes the normalized Robinson Foulds distance between two trees
     /// [(Robinson & Foulds, 1981)](https://doi.org/10.1016/0025-5564(81)90043-2).
     /// The RF distance is normalized by the maximum possible RF distance for both trees
@@ -1025,6 +2914,65 @@ impl Tree {
         Ok((rf as f64) / (tot as f64))
     }
 
+    /// Computes the support of each internal branch of the tree, defined as the fraction
+    /// of `reference_trees` that contain the bipartition of that branch. The result maps
+    /// the id of each internal non-root node to the support of the branch above it.
+    /// This can be used to annotate a consensus tree with posterior probabilities from
+    /// a sample of trees. All the trees must have the same set of uniquely named leaves.
+    /// ```
+    /// use phylotree::tree::Tree;
+    ///
+    /// let tree = Tree::from_newick("((A,B)AB,(C,D)CD,E);").unwrap();
+    /// let references = vec![
+    ///     Tree::from_newick("((A,B),(C,D),E);").unwrap(),
+    ///     Tree::from_newick("((A,B),(C,E),D);").unwrap(),
+    ///     Tree::from_newick("((A,C),(B,D),E);").unwrap(),
+    ///     Tree::from_newick("(((A,B),C),D,E);").unwrap(),
+    /// ];
+    ///
+    /// let supports = tree.bipartition_support(&references).unwrap();
+    ///
+    /// assert_eq!(supports[&tree.get_by_name("AB").unwrap().id], 0.75);
+    /// assert_eq!(supports[&tree.get_by_name("CD").unwrap().id], 0.25);
+    /// ```
+    pub fn bipartition_support(
+        &self,
+        reference_trees: &[Tree],
+    ) -> Result<HashMap<NodeId, f64>, TreeError> {
+        if reference_trees.is_empty() {
+            return Err(TreeError::IsEmpty);
+        }
+
+        let mut reference_partitions = vec![];
+        for tree in reference_trees {
+            self.check_same_leaf_index(tree)?;
+            reference_partitions.push(tree.get_partitions()?);
+        }
+
+        let mut supports = HashMap::new();
+        for node in self
+            .nodes
+            .iter()
+            .filter(|n| !(n.deleted || n.parent.is_none() || n.is_tip()))
+        {
+            let part = self.get_partition(&node.id)?;
+
+            // Trivial bipartitions are present in all trees
+            let count = if part.count_ones(..) <= 1 {
+                reference_trees.len()
+            } else {
+                reference_partitions
+                    .iter()
+                    .filter(|partitions| partitions.contains(&part))
+                    .count()
+            };
+
+            supports.insert(node.id, count as f64 / reference_trees.len() as f64);
+        }
+
+        Ok(supports)
+    }
+
     /// Computes the weighted Robinson Foulds distance between two trees
     /// [(Robinson & Foulds, 1979)](https://doi.org/10.1007/BFb0102690).
     /// This distance is equal to the absolute difference of branch lengths for
@@ -1037,7 +2985,18 @@ impl Tree {
     /// Where $A$ and $B$ are the sets of bipartitions of the first and second trees,
     /// and $d_{(e,A)}$ the branch length of bipartition $e$ in the first tree ($A$).  
     /// See also [Tree::compare_topologies()]
+    /// ```
+    /// use phylotree::tree::Tree;
+    ///
+    /// let tree1 = Tree::from_newick("((A:1,B:1):0.5,(C:1,D:1):1,(E:1,F:1):2);").unwrap();
+    /// let tree2 = Tree::from_newick("((A:1,B:1):1,(C:1,E:1):1,(D:1,F:1):1);").unwrap();
+    ///
+    /// // |0.5 - 1| for (A,B), plus the lengths of (C,D), (E,F), (C,E) and (D,F)
+    /// assert_eq!(tree1.weighted_robinson_foulds(&tree2).unwrap(), 5.5);
+    /// ```
     pub fn weighted_robinson_foulds(&self, other: &Self) -> Result<f64, TreeError> {
+        self.check_same_leaf_index(other)?;
+
         let partitions_s = self.get_partitions_with_lengths()?;
         let partitions_o = other.get_partitions_with_lengths()?;
 
@@ -1073,7 +3032,18 @@ impl Tree {
     /// }
     /// $$
     /// See also [Tree::compare_topologies()]
+    /// ```
+    /// use phylotree::tree::Tree;
+    ///
+    /// let tree1 = Tree::from_newick("((A:1,B:1):0.5,(C:1,D:1):1,(E:1,F:1):2);").unwrap();
+    /// let tree2 = Tree::from_newick("((A:1,B:1):1,(C:1,E:1):1,(D:1,F:1):1);").unwrap();
+    ///
+    /// let expected = (0.5_f64.powi(2) + 1.0 + 4.0 + 1.0 + 1.0).sqrt();
+    /// assert!((tree1.khuner_felsenstein(&tree2).unwrap() - expected).abs() < 1e-10);
+    /// ```
     pub fn khuner_felsenstein(&self, other: &Self) -> Result<f64, TreeError> {
+        self.check_same_leaf_index(other)?;
+
         let partitions_s = self.get_partitions_with_lengths()?;
         let partitions_o = other.get_partitions_with_lengths()?;
 
@@ -1096,6 +3066,205 @@ impl Tree {
         Ok(dist.sqrt())
     }
 
+    /// Computes the quartet distance between two trees, *i.e.* the number of sets of
+    /// four leaves whose induced topologies differ between the two trees. A set of four
+    /// leaves that is resolved in one tree but not in the other is counted as different.
+    ///
+    /// The resolved quartets of each bipartition are enumerated, so this is only suited
+    /// to trees with up to a few hundred leaves.
+    /// ```
+    /// use phylotree::tree::Tree;
+    ///
+    /// let tree1 = Tree::from_newick("((A,B),(C,D),E);").unwrap();
+    /// let tree2 = Tree::from_newick("((A,B),C,D,E);").unwrap();
+    /// let tree3 = Tree::from_newick("((A,C),(B,D),E);").unwrap();
+    ///
+    /// assert_eq!(tree1.quartet_distance(&tree2).unwrap(), 2);
+    /// assert_eq!(tree1.quartet_distance(&tree3).unwrap(), 5);
+    /// ```
+    pub fn quartet_distance(&self, other: &Self) -> Result<usize, TreeError> {
+        self.check_same_leaf_index(other)?;
+
+        let quartets_s = self.resolved_quartets()?;
+        let quartets_o = other.resolved_quartets()?;
+
+        let different = quartets_s
+            .iter()
+            .filter(|(quartet, partner)| quartets_o.get(*quartet) != Some(partner))
+            .count();
+        let unique_o = quartets_o
+            .keys()
+            .filter(|quartet| !quartets_s.contains_key(*quartet))
+            .count();
+
+        Ok(different + unique_o)
+    }
+
+    // Maps each set of four leaves (sorted leaf indices) resolved by a bipartition of
+    // the tree to the leaf that is grouped with the first leaf of the set.
+    fn resolved_quartets(&self) -> Result<HashMap<[usize; 4], usize>, TreeError> {
+        let n_leaves = self.get_leaf_index()?.len();
+        let mut quartets = HashMap::new();
+
+        for partition in self.get_partitions()?.iter() {
+            let inside: Vec<_> = partition.ones().collect();
+            let outside: Vec<_> = (0..n_leaves).filter(|i| !partition.contains(*i)).collect();
+
+            for pair_in in inside.iter().combinations(2) {
+                for pair_out in outside.iter().combinations(2) {
+                    let (a, b, c, d) = (*pair_in[0], *pair_in[1], *pair_out[0], *pair_out[1]);
+                    let mut quartet = [a, b, c, d];
+                    quartet.sort_unstable();
+                    let partner = match quartet[0] {
+                        first if first == a => b,
+                        first if first == b => a,
+                        first if first == c => d,
+                        _ => c,
+                    };
+                    quartets.insert(quartet, partner);
+                }
+            }
+        }
+
+        Ok(quartets)
+    }
+
+    /// Computes the [tree edit distance](https://doi.org/10.1137/0218082) between two
+    /// trees with the Zhang-Shasha algorithm. This is the minimum number of node
+    /// insertions, deletions and relabelings needed to transform one tree into the other.
+    /// Unlike the RF distance, this considers the order of children and the names of
+    /// internal nodes.
+    /// ```
+    /// use phylotree::tree::Tree;
+    ///
+    /// let tree1 = Tree::from_newick("((A,(B)C)D,E)F;").unwrap();
+    /// let tree2 = Tree::from_newick("(((A,B)D)C,E)F;").unwrap();
+    ///
+    /// assert_eq!(tree1.tree_edit_distance(&tree1).unwrap(), 0);
+    /// assert_eq!(tree1.tree_edit_distance(&tree2).unwrap(), 2);
+    /// ```
+    pub fn tree_edit_distance(&self, other: &Self) -> Result<usize, TreeError> {
+        let (labels_s, leftmost_s, keyroots_s) = self.zhang_shasha_decomposition()?;
+        let (labels_o, leftmost_o, keyroots_o) = other.zhang_shasha_decomposition()?;
+
+        // Distances between the subtrees rooted at each pair of nodes
+        let mut treedist = vec![vec![0; labels_o.len()]; labels_s.len()];
+
+        for &i in keyroots_s.iter() {
+            for &j in keyroots_o.iter() {
+                let (li, lj) = (leftmost_s[i], leftmost_o[j]);
+                let (m, n) = (i - li + 2, j - lj + 2);
+
+                // Distances between forests of postorder prefixes
+                let mut forestdist = vec![vec![0; n]; m];
+                for x in 1..m {
+                    forestdist[x][0] = forestdist[x - 1][0] + 1;
+                }
+                for y in 1..n {
+                    forestdist[0][y] = forestdist[0][y - 1] + 1;
+                }
+
+                for x in 1..m {
+                    for y in 1..n {
+                        let (i1, j1) = (li + x - 1, lj + y - 1);
+                        let indel = usize::min(forestdist[x - 1][y], forestdist[x][y - 1]) + 1;
+                        if leftmost_s[i1] == li && leftmost_o[j1] == lj {
+                            let relabel = usize::from(labels_s[i1] != labels_o[j1]);
+                            forestdist[x][y] = indel.min(forestdist[x - 1][y - 1] + relabel);
+                            treedist[i1][j1] = forestdist[x][y];
+                        } else {
+                            let (p, q) = (leftmost_s[i1] - li, leftmost_o[j1] - lj);
+                            forestdist[x][y] = indel.min(forestdist[p][q] + treedist[i1][j1]);
+                        }
+                    }
+                }
+            }
+        }
+
+        Ok(treedist[labels_s.len() - 1][labels_o.len() - 1])
+    }
+
+    /// Approximates the rooted subtree prune and regraft (SPR) distance between two trees,
+    /// *i.e.* the minimum number of SPR moves needed to transform one tree into the other.
+    /// Computing this distance exactly is NP-hard, it is equal to the number of components
+    /// of a maximum agreement forest of the two trees minus one. This method builds an
+    /// agreement forest with the 3-approximation algorithm of
+    /// [Whidden *et al.* (2013)](https://doi.org/10.1137/110845045), so the returned value
+    /// is between the SPR distance and three times this distance. It runs in $O(n^2)$ time
+    /// for trees with $n$ leaves.
+    ///
+    /// Both trees must be rooted and binary, otherwise [`TreeError::IsNotRooted`] or
+    /// [`TreeError::IsNotBinary`] is returned. They must also have the same set of uniquely
+    /// named leaves, otherwise [`TreeError::DifferentTipIndices`] is returned.
+    /// ```
+    /// use phylotree::tree::Tree;
+    ///
+    /// let tree1 = Tree::from_newick("(((A,B),C),(D,E));").unwrap();
+    /// let tree2 = Tree::from_newick("(((A,C),B),(D,E));").unwrap();
+    ///
+    /// assert_eq!(tree1.spr_distance(&tree1).unwrap(), 0);
+    ///
+    /// let distance = tree1.spr_distance(&tree2).unwrap();
+    /// assert!((1..=3).contains(&distance));
+    /// ```
+    pub fn spr_distance(&self, other: &Self) -> Result<usize, TreeError> {
+        self.check_same_leaf_index(other)?;
+
+        let mut tree = self.agreement_forest()?;
+        let mut forest = other.agreement_forest()?;
+
+        // Nodes of the tree and of the forest for each remaining leaf label
+        let n_labels = self.get_leaf_index()?.len() + 1;
+        let mut labels: Vec<_> = (0..n_labels).map(|label| (label, label)).collect();
+        let mut tree_labels: HashMap<_, _> = (0..n_labels).map(|label| (label, label)).collect();
+        let mut remaining: Vec<_> = (0..n_labels).collect();
+
+        while remaining.len() > 1 {
+            let (a, c) = remaining
+                .iter()
+                .find_map(|&a| {
+                    let sibling = tree.sibling(labels[a].0)?;
+                    tree.is_leaf(sibling).then(|| (a, tree_labels[&sibling]))
+                })
+                .expect("Binary trees with several leaves have a cherry");
+            let (a_node, c_node) = (labels[a].1, labels[c].1);
+
+            if let Some(isolated) = [a, c]
+                .into_iter()
+                .find(|&label| forest.parent[labels[label].1].is_none())
+            {
+                // Leaves isolated in the forest are removed from the tree
+                tree.cut(labels[isolated].0);
+                remaining.retain(|&label| label != isolated);
+            } else if forest.sibling(a_node) == Some(c_node) {
+                // Common cherries are merged into a single leaf
+                let merged = (
+                    tree.merge(labels[a].0, labels[c].0),
+                    forest.merge(a_node, c_node),
+                );
+                tree_labels.insert(merged.0, a);
+                labels[a] = merged;
+                remaining.retain(|&label| label != c);
+            } else if forest.root_of(a_node) != forest.root_of(c_node) {
+                forest.cut(a_node);
+                forest.cut(c_node);
+            } else {
+                // Cut a subtree hanging from the path between both leaves as well
+                let sibling = forest.sibling(a_node).unwrap();
+                let pendant = if forest.is_ancestor(sibling, c_node) {
+                    forest.sibling(c_node).unwrap()
+                } else {
+                    sibling
+                };
+                for node in [a_node, c_node, pendant] {
+                    forest.cut(node);
+                }
+            }
+        }
+
+        Ok(forest.n_components() - 1)
+    }
+
     /// Compute several the RF metric, the weighted and normalized RF metrics and
     /// the KF branch score in one pass. This is more efficient than calling the
     /// different functions separately.
@@ -1118,6 +3287,8 @@ impl Tree {
     /// assert_eq!(branch_score, comparison.branch_score);
     /// ```
     pub fn compare_topologies(&self, other: &Self) -> Result<Comparison, TreeError> {
+        self.check_same_leaf_index(other)?;
+
         let partitions_s = self.get_partitions_with_lengths()?;
         let partitions_o = other.get_partitions_with_lengths()?;
 
@@ -1244,6 +3415,8 @@ impl Tree {
         other: &Self,
         include_tips: bool,
     ) -> Result<EdgeCompare, TreeError> {
+        self.check_same_leaf_index(other)?;
+
         let partitions_s = self.get_partitions_with_lengths()?;
         let partitions_o = other.get_partitions_with_lengths()?;
 
@@ -1290,6 +3463,114 @@ impl Tree {
         Ok((self_branches, other_branches, common_branches))
     }
 
+    /// Returns the lengths of homologous branches in two trees with the same topology,
+    /// as `(self_length, other_length)` pairs. Internal branches are matched by the
+    /// bipartition they define and come first, sorted by bipartition, followed by
+    /// terminal branches sorted by leaf name.
+    ///
+    /// Returns [`TreeError::DifferentTipIndices`] if the trees have different leaves,
+    /// [`TreeError::DifferentTopologies`] if they have different topologies and
+    /// [`TreeError::MissingBranchLengths`] if a branch has no length.
+    /// ```
+    /// use phylotree::tree::{Tree, TreeError};
+    ///
+    /// let tree1 = Tree::from_newick("(A:0.1,B:0.2,(C:0.3,D:0.4):0.5);").unwrap();
+    /// let tree2 = Tree::from_newick("(B:2,A:1,(D:4,C:3):5);").unwrap();
+    ///
+    /// assert_eq!(
+    ///     tree1.homologous_branch_lengths(&tree2).unwrap(),
+    ///     vec![(0.5, 5.0), (0.1, 1.0), (0.2, 2.0), (0.3, 3.0), (0.4, 4.0)]
+    /// );
+    ///
+    /// let tree3 = Tree::from_newick("(A:1,C:1,(B:1,D:1):1);").unwrap();
+    /// assert!(matches!(
+    ///     tree1.homologous_branch_lengths(&tree3),
+    ///     Err(TreeError::DifferentTopologies)
+    /// ));
+    /// ```
+    pub fn homologous_branch_lengths(
+        &self,
+        other: &Self,
+    ) -> Result<Vec<(EdgeLength, EdgeLength)>, TreeError> {
+        if !self.topology_equal(other)? {
+            return Err(TreeError::DifferentTopologies);
+        }
+
+        let partitions_s = self.get_partitions_with_lengths()?;
+        let partitions_o = other.get_partitions_with_lengths()?;
+
+        let mut pairs = vec![];
+        for (edge, (_, len_s)) in partitions_s.iter().sorted_by(|a, b| a.0.cmp(b.0)) {
+            pairs.push((*len_s, partitions_o[edge].1));
+        }
+
+        let tips_s = self.get_terminal_branches()?;
+        let tips_o = other.get_terminal_branches()?;
+        for (name, (_, len_s)) in tips_s.iter().sorted_by(|a, b| a.0.cmp(b.0)) {
+            let len_s = len_s.ok_or(TreeError::MissingBranchLengths)?;
+            let len_o = tips_o[name].1.ok_or(TreeError::MissingBranchLengths)?;
+            pairs.push((len_s, len_o));
+        }
+
+        Ok(pairs)
+    }
+
+    // Get node labels and leftmost leaf descendants in postorder, as well as the
+    // keyroots (nodes with no ancestor sharing their leftmost leaf) of the tree
+    fn zhang_shasha_decomposition(&self) -> Result<KeyrootDecomposition<'_>, TreeError> {
+        let root = self.get_root()?;
+
+        let mut labels = vec![];
+        let mut leftmost = vec![];
+        let mut postorder_index = HashMap::new();
+        for (i, node) in self.iter_postorder(&root)?.enumerate() {
+            postorder_index.insert(node.id, i);
+            labels.push(node.name.as_deref());
+            leftmost.push(match node.children.first() {
+                Some(child) => leftmost[postorder_index[child]],
+                None => i,
+            });
+        }
+
+        let mut highest = HashMap::new();
+        for (i, &left) in leftmost.iter().enumerate() {
+            highest.insert(left, i);
+        }
+        let mut keyroots: Vec<_> = highest.into_values().collect();
+        keyroots.sort_unstable();
+
+        Ok((labels, leftmost, keyroots))
+    }
+
+    // Build a binary forest with the topology of the tree for agreement forest
+    // computations. Leaves are labelled by their position in the leaf index, and
+    // an extra leaf labelled with the number of leaves is placed above the root.
+    fn agreement_forest(&self) -> Result<AgreementForest, TreeError> {
+        if !self.is_rooted()? {
+            return Err(TreeError::IsNotRooted);
+        }
+
+        let leaf_index = self.get_leaf_index()?;
+        let mut forest = AgreementForest::with_leaves(leaf_index.len() + 1);
+
+        let root = self.get_root()?;
+        let mut forest_ids = HashMap::new();
+        for node in self.iter_postorder(&root)? {
+            let id = match node.children[..] {
+                [] => leaf_index
+                    .binary_search(node.name.as_ref().unwrap())
+                    .unwrap(),
+                [child] => forest_ids[&child],
+                [left, right] => forest.join(forest_ids[&left], forest_ids[&right]),
+                _ => return Err(TreeError::IsNotBinary),
+            };
+            forest_ids.insert(node.id, id);
+        }
+        forest.join(forest_ids[&root], leaf_index.len());
+
+        Ok(forest)
+    }
+
     // Get terminal branch lengths of a tree keyed by tip name
     fn get_terminal_branches(
         &self,
@@ -1300,7 +3581,10 @@ impl Tree {
 
         Ok(HashMap::from_iter(self.get_leaves().iter().map(|idx| {
             let node = self.get(idx).unwrap();
-            (node.name.clone().unwrap(), (node.depth, node.parent_edge))
+            (
+                node.name.clone().unwrap(),
+                (node.get_depth(), node.parent_edge),
+            )
         })))
     }
 }
@@ -1314,7 +3598,8 @@ impl Tree {
     // # FIND PATHS IN THE TREE #
     // ##########################
 
-    /// Returns the path from the node to the root
+    /// Returns the path from the root to the node.
+    /// Paths are cached until the tree is modified, so repeated calls are cheap.
     /// ```
     /// use phylotree::tree::Tree;
     ///
@@ -1329,17 +3614,34 @@ impl Tree {
     /// assert_eq!(path, vec!["F", "B", "D", "E"])
     /// ```
     pub fn get_path_from_root(&self, node: &NodeId) -> Result<Vec<NodeId>, TreeError> {
+        if let Some(path) = self.root_paths.borrow().get(node) {
+            return Ok(path.clone());
+        }
+
+        // Walk up until the root or an ancestor with a cached path
+        let mut upper_path = vec![];
         let mut path = vec![];
         let mut current_node = *node;
         loop {
+            self.get(&current_node)?;
+            if let Some(cached) = self.root_paths.borrow().get(&current_node) {
+                upper_path.clone_from(cached);
+                break;
+            }
             path.push(current_node);
-            match self.get(&current_node)?.parent {
+            match self.nodes[current_node].parent {
                 Some(parent) => current_node = parent,
                 None => break,
             }
         }
+        path.reverse();
+        upper_path.extend(path);
+
+        self.root_paths
+            .borrow_mut()
+            .insert(*node, upper_path.clone());
 
-        Ok(path.into_iter().rev().collect())
+        Ok(upper_path)
     }
 
     /// Gets the most recent common ancestor between two tree nodes
@@ -1378,6 +3680,119 @@ impl Tree {
         Ok(root_to_source[cursor - 1])
     }
 
+    /// Gets the most recent common ancestor of any number of tree nodes.
+    /// Returns [`TreeError::IsEmpty`] if no nodes are given.
+    /// ```
+    /// use phylotree::tree::Tree;
+    ///
+    /// let tree = Tree::from_newick("((A,(C,E)D)B,((H)I)G)F;").unwrap();
+    /// let nodes: Vec<_> = ["A", "C", "E"]
+    ///     .iter()
+    ///     .map(|name| tree.get_by_name(name).unwrap().id)
+    ///     .collect();
+    /// let ancestor = tree.get_mrca(&nodes).unwrap();
+    ///
+    /// assert_eq!(tree.get(&ancestor).unwrap().name, Some("B".to_owned()))
+    /// ```
+    pub fn get_mrca(&self, nodes: &[NodeId]) -> Result<NodeId, TreeError> {
+        let (first, others) = nodes.split_first().ok_or(TreeError::IsEmpty)?;
+        self.get(first)?;
+
+        others.iter().try_fold(*first, |ancestor, node| {
+            self.get_common_ancestor(&ancestor, node)
+        })
+    }
+
+    /// Gets the most recent common ancestor of any number of leaves, from their names.
+    /// Returns [`TreeError::IsEmpty`] if no names are given.
+    /// ```
+    /// use phylotree::tree::Tree;
+    ///
+    /// let tree = Tree::from_newick("((A,(C,E)D)B,((H)I)G)F;").unwrap();
+    ///
+    /// let ancestor = tree.get_mrca_by_name(&["C", "E"]).unwrap();
+    /// assert_eq!(tree.get(&ancestor).unwrap().name, Some("D".to_owned()));
+    ///
+    /// let ancestor = tree.get_mrca_by_name(&["C", "H"]).unwrap();
+    /// assert_eq!(tree.get(&ancestor).unwrap().name, Some("F".to_owned()));
+    /// ```
+    pub fn get_mrca_by_name(&self, names: &[&str]) -> Result<NodeId, TreeError> {
+        self.get_mrca(&self.get_leaves_by_name(names)?)
+    }
+
+    // Gets the ids of leaves from their names
+    fn get_leaves_by_name(&self, names: &[&str]) -> Result<Vec<NodeId>, TreeError> {
+        names
+            .iter()
+            .map(|name| {
+                self.nodes
+                    .iter()
+                    .find(|node| {
+                        !node.deleted && node.is_tip() && node.name.as_deref() == Some(name)
+                    })
+                    .map(|node| node.id)
+                    .ok_or(TreeError::GeneralError(
+                        "Some of the tip names are not leaves of the tree",
+                    ))
+            })
+            .collect()
+    }
+
+    // Returns the leaves descending from the MRCA of a group of leaves
+    // that are not in the group
+    fn leaves_outside_group(&self, tips: &[NodeId]) -> Result<Vec<NodeId>, TreeError> {
+        let ancestor = self.get_mrca(tips)?;
+
+        let group: HashSet<_> = tips.iter().collect();
+        Ok(self
+            .get_subtree_leaves(&ancestor)?
+            .into_iter()
+            .filter(|leaf| !group.contains(leaf))
+            .collect())
+    }
+
+    /// Checks if a group of tips is monophyletic, *i.e.* if the subtree rooted at
+    /// their most recent common ancestor does not contain any other tips.
+    /// ```
+    /// use phylotree::tree::Tree;
+    ///
+    /// let tree = Tree::from_newick("(((A,B),C),(D,E));").unwrap();
+    ///
+    /// assert!(tree.is_monophyletic(&["A", "B"]).unwrap());
+    /// assert!(tree.is_monophyletic(&["C", "B", "A"]).unwrap());
+    /// assert!(!tree.is_monophyletic(&["A", "C"]).unwrap());
+    /// ```
+    pub fn is_monophyletic(&self, tip_names: &[&str]) -> Result<bool, TreeError> {
+        let tips = self.get_leaves_by_name(tip_names)?;
+
+        Ok(self.leaves_outside_group(&tips)?.is_empty())
+    }
+
+    /// Checks if a group of tips is paraphyletic, *i.e.* if it is not monophyletic
+    /// and the other tips in the subtree rooted at its most recent common ancestor form
+    /// a monophyletic group. In other words the group is the complement of a
+    /// monophyletic group within a clade.
+    /// ```
+    /// use phylotree::tree::Tree;
+    ///
+    /// let tree = Tree::from_newick("(((A,B),C),(D,E));").unwrap();
+    ///
+    /// // (A,C) is the (A,B,C) clade without the B clade
+    /// assert!(tree.is_paraphyletic(&["A", "C"]).unwrap());
+    /// assert!(tree.is_paraphyletic(&["C", "D", "E"]).unwrap());
+    /// assert!(!tree.is_paraphyletic(&["A", "B"]).unwrap());
+    /// assert!(!tree.is_paraphyletic(&["A", "D"]).unwrap());
+    /// ```
+    pub fn is_paraphyletic(&self, tip_names: &[&str]) -> Result<bool, TreeError> {
+        let tips = self.get_leaves_by_name(tip_names)?;
+        let excluded = self.leaves_outside_group(&tips)?;
+        if excluded.is_empty() {
+            return Ok(false);
+        }
+
+        Ok(self.leaves_outside_group(&excluded)?.is_empty())
+    }
+
     /// Gets the distance between 2 nodes, returns the sum of branch lengths (if all
     /// branches in the path have lengths) and the number of edges in the path.
     /// ```
@@ -1401,32 +3816,21 @@ impl Tree {
         let mut branches = 0;
         let mut all_dists = true;
 
-        if source == target {
-            return Ok((Some(0.0), 0));
-        }
-
-        let root_to_source = self.get_path_from_root(source)?;
-        let root_to_target = self.get_path_from_root(target)?;
-
-        let cursor = zip(root_to_source.iter(), root_to_target.iter())
-            .enumerate()
-            .filter(|(_, (s, t))| s != t)
-            .map(|(idx, _)| idx)
-            .next()
-            .unwrap_or_else(|| {
-                // One node is a child of the other
-                root_to_source.len().min(root_to_target.len())
-            });
+        for pair in self.path_between(source, target)?.windows(2) {
+            // The branch is stored in the child node
+            let (first, second) = (self.get(&pair[0])?, self.get(&pair[1])?);
+            let child = if second.parent == Some(first.id) {
+                second
+            } else {
+                first
+            };
 
-        for list in [root_to_source, root_to_target] {
-            for node in list.iter().skip(cursor) {
-                if let Some(d) = self.get(node)?.parent_edge {
-                    dist += d;
-                } else {
-                    all_dists = false;
-                }
-                branches += 1;
+            if let Some(d) = child.parent_edge {
+                dist += d;
+            } else {
+                all_dists = false;
             }
+            branches += 1;
         }
 
         if all_dists {
@@ -1436,9 +3840,74 @@ impl Tree {
         }
     }
 
-    // Implementation of recursive distance matrix computation
-    fn distance_matrix_recursive_impl(
-        &self,
+    /// Gets the distance between 2 nodes, returns the sum of branch lengths (if all
+    /// branches in the path have lengths) and the number of edges in the path.
+    /// This is the same as [`Tree::get_distance()`].
+    /// ```
+    /// use phylotree::tree::Tree;
+    ///
+    /// let tree = Tree::from_newick("((A:1,(C:2,E:3)D:4)B:5,((H:6)I:7)G:8)F;").unwrap();
+    /// let a = tree.get_by_name("A").unwrap().id;
+    /// let e = tree.get_by_name("E").unwrap().id;
+    ///
+    /// assert_eq!(tree.path_length_between(&a, &e).unwrap(), (Some(8.0), 3));
+    /// ```
+    pub fn path_length_between(
+        &self,
+        source: &NodeId,
+        target: &NodeId,
+    ) -> Result<(Option<f64>, usize), TreeError> {
+        self.get_distance(source, target)
+    }
+
+    /// Gets the nodes on the path between 2 nodes, from `source` to `target`
+    /// *(both included)*.
+    /// ```
+    /// use phylotree::tree::Tree;
+    ///
+    /// let tree = Tree::from_newick("((A,(C,E)D)B,((H)I)G)F;").unwrap();
+    /// let path: Vec<_> = tree
+    ///     .path_between(
+    ///         &tree.get_by_name("E").unwrap().id,
+    ///         &tree.get_by_name("H").unwrap().id,
+    ///     )
+    ///     .unwrap()
+    ///     .iter()
+    ///     .map(|id| tree.get(id).unwrap().name.clone().unwrap())
+    ///     .collect();
+    ///
+    /// assert_eq!(path, vec!["E", "D", "B", "F", "G", "I", "H"]);
+    /// ```
+    pub fn path_between(&self, source: &NodeId, target: &NodeId) -> Result<Vec<NodeId>, TreeError> {
+        if source == target {
+            return Ok(vec![*source]);
+        }
+
+        let root_to_source = self.get_path_from_root(source)?;
+        let root_to_target = self.get_path_from_root(target)?;
+
+        let cursor = zip(root_to_source.iter(), root_to_target.iter())
+            .enumerate()
+            .filter(|(_, (s, t))| s != t)
+            .map(|(idx, _)| idx)
+            .next()
+            .unwrap_or_else(|| {
+                // One node is a child of the other
+                root_to_source.len().min(root_to_target.len())
+            });
+
+        // Go up from the source to the common ancestor, then down to the target
+        Ok(root_to_source[(cursor - 1)..]
+            .iter()
+            .rev()
+            .chain(root_to_target[cursor..].iter())
+            .copied()
+            .collect())
+    }
+
+    // Implementation of recursive distance matrix computation
+    fn distance_matrix_recursive_impl(
+        &self,
         current: &NodeId,
         prev: Option<&NodeId>,
         lengths: &mut [f64],
@@ -1537,8 +4006,7 @@ impl Tree {
     /// assert_eq!(phylip, matrix.to_phylip(true).unwrap())
     /// ```
     pub fn distance_matrix(&self) -> Result<DistanceMatrix<f64>, TreeError> {
-        let mut leaf_order = self.get_leaves();
-        leaf_order.sort_by(|a, b| self.get(a).unwrap().name.cmp(&self.get(b).unwrap().name));
+        let leaf_order = self.get_leaves_sorted()?;
 
         let n = self.n_leaves();
         let mut pairwise_vec = vec![NaiveSum::zero(); n * (n - 1) / 2];
@@ -1633,6 +4101,195 @@ impl Tree {
 
         Ok(matrix?)
     }
+
+    /// Computes the distance matrix of the tree in parallel, using all available
+    /// threads *(see [`rayon`])*. The result is the same as [`Tree::distance_matrix()`],
+    /// but faster on large trees with multiple cores.
+    /// ```
+    /// use phylotree::tree::Tree;
+    ///
+    /// let tree = Tree::from_newick("((T3:2,T1:2):3,(T2:4,T0:5):6);").unwrap();
+    /// let matrix = tree.distance_matrix_parallel().unwrap();
+    ///
+    /// let phylip="\
+    /// 4
+    /// T0    0  16  9  16
+    /// T1    16  0  15  4
+    /// T2    9  15  0  15
+    /// T3    16  4  15  0
+    /// ";
+    ///
+    /// assert_eq!(phylip, matrix.to_phylip(true).unwrap())
+    /// ```
+    pub fn distance_matrix_parallel(&self) -> Result<DistanceMatrix<f64>, TreeError> {
+        self.check_tip_names()?;
+
+        let leaf_order = self.get_leaves_sorted()?;
+
+        // Copy the tree structure to plain vectors that can be shared between threads
+        let root = self.get_root()?;
+        let mut parents = vec![None; self.nodes.len()];
+        let mut depths = vec![0; self.nodes.len()];
+        let mut edges = vec![0.0; self.nodes.len()];
+        for id in self.preorder(&root)?.into_iter().skip(1) {
+            let node = &self.nodes[id];
+            let parent = node.parent.ok_or(NodeError::HasNoParent(id))?;
+            parents[id] = Some(parent);
+            depths[id] = depths[parent] + 1;
+            // Use topological distance if no edge length
+            edges[id] = node.parent_edge.unwrap_or(1.0);
+        }
+
+        let distance = |mut a: NodeId, mut b: NodeId| -> f64 {
+            let (mut dist_a, mut dist_b) = (0.0, 0.0);
+            while depths[a] > depths[b] {
+                dist_a += edges[a];
+                a = parents[a].unwrap();
+            }
+            while depths[b] > depths[a] {
+                dist_b += edges[b];
+                b = parents[b].unwrap();
+            }
+            while a != b {
+                dist_a += edges[a];
+                dist_b += edges[b];
+                a = parents[a].unwrap();
+                b = parents[b].unwrap();
+            }
+            dist_a + dist_b
+        };
+
+        // Rows of the lower triangular matrix are computed in parallel
+        let pairwise_vec: Vec<f64> = (0..leaf_order.len())
+            .into_par_iter()
+            .flat_map_iter(|i| (0..i).map(move |j| (i, j)))
+            .map(|(i, j)| distance(leaf_order[i], leaf_order[j]))
+            .collect();
+
+        let taxa = leaf_order
+            .iter()
+            .map(|leaf| self.nodes[*leaf].name.clone().unwrap())
+            .collect();
+
+        Ok(DistanceMatrix::from_precomputed(taxa, pairwise_vec)?)
+    }
+
+    /// Computes the cophenetic matrix of the tree, *i.e.* for each pair of leaves
+    /// the distance between the root and their most recent common ancestor.
+    /// If the tree has no branch lengths, the topological depth of the common ancestor
+    /// is used instead. Leaves are sorted by name.
+    ///
+    /// Returns [`TreeError::IsNotRooted`] if the tree is not rooted and
+    /// [`TreeError::MissingBranchLengths`] if only some of the branches have a length.
+    /// ```
+    /// use phylotree::tree::Tree;
+    ///
+    /// let tree = Tree::from_newick("((A:1,B:1):2,(C:1,(D:1,E:1):1):1);").unwrap();
+    /// let matrix = tree.cophenetic_matrix().unwrap();
+    ///
+    /// assert_eq!(matrix.get("A", "B").unwrap(), &2.0);
+    /// assert_eq!(matrix.get("D", "E").unwrap(), &2.0);
+    /// assert_eq!(matrix.get("C", "E").unwrap(), &1.0);
+    /// assert_eq!(matrix.get("A", "E").unwrap(), &0.0);
+    /// ```
+    pub fn cophenetic_matrix(&self) -> Result<DistanceMatrix<EdgeLength>, TreeError> {
+        if !self.is_rooted()? {
+            return Err(TreeError::IsNotRooted);
+        }
+        self.check_tip_names()?;
+
+        let root = self.get_root()?;
+        let has_lengths = self
+            .nodes
+            .iter()
+            .any(|node| !node.deleted && node.parent_edge.is_some());
+        let root_dists = if has_lengths {
+            self.root_distances()?
+        } else {
+            self.nodes
+                .iter()
+                .map(|node| node.get_depth() as f64)
+                .collect()
+        };
+
+        let leaf_order = self.get_leaves_sorted()?;
+        let mut leaf_index = vec![0; self.nodes.len()];
+        for (i, leaf) in leaf_order.iter().enumerate() {
+            leaf_index[*leaf] = i;
+        }
+
+        let n = leaf_order.len();
+        let mut pairwise_vec = vec![0.0; n * n.saturating_sub(1) / 2];
+        let mut subtree_leaves: Vec<Vec<usize>> = vec![vec![]; self.nodes.len()];
+
+        for node_id in self.postorder(&root)? {
+            let node = self.get(&node_id)?;
+            if node.is_tip() {
+                subtree_leaves[node_id].push(leaf_index[node_id]);
+                continue;
+            }
+
+            // Leaves in different child subtrees have this node as common ancestor
+            for pair in node.children.iter().combinations(2) {
+                for i in subtree_leaves[*pair[0]].iter() {
+                    for j in subtree_leaves[*pair[1]].iter() {
+                        pairwise_vec[tril_to_rowvec_index(n, *i, *j)] = root_dists[node_id];
+                    }
+                }
+            }
+
+            let leaves = node
+                .children
+                .iter()
+                .flat_map(|child| std::mem::take(&mut subtree_leaves[*child]))
+                .collect();
+            subtree_leaves[node_id] = leaves;
+        }
+
+        let taxa = leaf_order
+            .iter()
+            .map(|leaf| self.nodes[*leaf].name.clone().unwrap())
+            .collect();
+
+        Ok(DistanceMatrix::from_precomputed(taxa, pairwise_vec)?)
+    }
+
+    /// Builds a rooted ultrametric tree from a distance matrix with the
+    /// [UPGMA](https://en.wikipedia.org/wiki/UPGMA) algorithm
+    /// *(see [`DistanceMatrix::upgma()`])*.
+    /// ```
+    /// use phylotree::tree::Tree;
+    ///
+    /// let tree = Tree::from_newick("((A:1,B:1):2,(C:2,D:2):1);").unwrap();
+    /// let built = Tree::upgma(&tree.distance_matrix().unwrap()).unwrap();
+    ///
+    /// assert!(built.is_rooted().unwrap());
+    /// assert_eq!(built.weighted_robinson_foulds(&tree).unwrap(), 0.0);
+    /// ```
+    pub fn upgma<T: PairwiseDist>(matrix: &DistanceMatrix<T>) -> Result<Self, TreeError> {
+        Ok(matrix.upgma()?)
+    }
+
+    /// Builds an unrooted tree from a distance matrix with the
+    /// [neighbor joining](https://en.wikipedia.org/wiki/Neighbor_joining) algorithm
+    /// *(see [`DistanceMatrix::neighbor_joining()`])*. Returns
+    /// [`TreeError::MissingBranchLengths`] if some distances are missing *(i.e. `NaN`)*.
+    /// ```
+    /// use phylotree::tree::Tree;
+    ///
+    /// let tree = Tree::from_newick("(A:1,B:2,((C:2,D:3):1,E:1):2);").unwrap();
+    /// let built = Tree::nj(&tree.distance_matrix().unwrap()).unwrap();
+    ///
+    /// assert!(!built.is_rooted().unwrap());
+    /// assert_eq!(built.robinson_foulds(&tree).unwrap(), 0);
+    /// ```
+    pub fn nj<T: PairwiseDist>(matrix: &DistanceMatrix<T>) -> Result<Self, TreeError> {
+        if matrix.iter().any(|d| d.is_nan()) {
+            return Err(TreeError::MissingBranchLengths);
+        }
+
+        Ok(matrix.neighbor_joining()?)
+    }
 }
 
 /// Methods to manipulate and alter the [`Tree`] object.
@@ -1670,6 +4327,138 @@ impl Tree {
         Ok(())
     }
 
+    /// Reads branch support values stored in internal node names, as written by
+    /// RAxML or IQ-TREE *(e.g. `((A,B)95,(C,D)88)100;`)*. Names that are numbers
+    /// are moved to the [`Node::support`] field and removed, names formatted as
+    /// `name/support` are split between the two fields. Other internal node names
+    /// are left as is. When an internal node has no name, its support value is
+    /// written in its place by [`Tree::to_newick()`].
+    /// # Example
+    /// ```
+    /// use phylotree::tree::Tree;
+    ///
+    /// let mut tree = Tree::from_newick("((A,B)95,(C,D)CD/88)100;").unwrap();
+    /// tree.annotate_with_bootstrap().unwrap();
+    ///
+    /// let root = tree.get(&tree.get_root().unwrap()).unwrap();
+    /// assert_eq!(root.name, None);
+    /// assert_eq!(root.support, Some(100.0));
+    ///
+    /// let cd = tree.get_by_name("CD").unwrap();
+    /// assert_eq!(cd.support, Some(88.0));
+    ///
+    /// assert_eq!(tree.to_newick().unwrap(), "((A,B)95,(C,D)CD)100;");
+    /// ```
+    pub fn annotate_with_bootstrap(&mut self) -> Result<(), TreeError> {
+        self.get_root()?;
+
+        for node in self.nodes.iter_mut() {
+            if node.deleted || node.is_tip() {
+                continue;
+            }
+            let Some(name) = node.name.as_ref() else {
+                continue;
+            };
+
+            if let Ok(support) = name.parse() {
+                node.support = Some(support);
+                node.name = None;
+            } else if let Some((label, support)) = name.rsplit_once('/') {
+                if let Ok(support) = support.parse() {
+                    node.support = Some(support);
+                    node.name = (!label.is_empty()).then(|| label.to_string());
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Renames a tip of the tree. Returns an error if the node is not a tip.
+    /// # Example
+    /// ```
+    /// use phylotree::tree::Tree;
+    ///
+    /// let mut tree = Tree::from_newick("((A,B)C,D)E;").unwrap();
+    /// let tip = tree.get_by_name("A").unwrap().id;
+    ///
+    /// tree.rename_tip(&tip, "Z").unwrap();
+    ///
+    /// assert_eq!(tree.to_newick().unwrap(), "((Z,B)C,D)E;");
+    /// assert!(tree.rename_tip(&tree.get_root().unwrap(), "Y").is_err());
+    /// ```
+    pub fn rename_tip(&mut self, id: &NodeId, new_name: &str) -> Result<(), TreeError> {
+        let node = self.get_mut(id)?;
+        if !node.is_tip() {
+            return Err(TreeError::GeneralError("Only tips can be renamed"));
+        }
+        node.set_name(new_name.to_string());
+
+        self.reset_bipartition_cache();
+
+        Ok(())
+    }
+
+    /// Renames all the named tips of the tree by applying a function to their
+    /// current names. Unnamed tips are left untouched.
+    /// # Example
+    /// ```
+    /// use phylotree::tree::Tree;
+    ///
+    /// let mut tree = Tree::from_newick("((A,B)C,d)E;").unwrap();
+    ///
+    /// tree.rename_tips_with(|name| name.to_uppercase() + "_1");
+    ///
+    /// assert_eq!(tree.to_newick().unwrap(), "((A_1,B_1)C,D_1)E;");
+    /// ```
+    pub fn rename_tips_with<F: Fn(&str) -> String>(&mut self, f: F) {
+        for node in self.nodes.iter_mut().filter(|n| !n.deleted && n.is_tip()) {
+            if let Some(name) = node.name.as_deref() {
+                node.name = Some(f(name));
+            }
+        }
+
+        self.reset_bipartition_cache();
+    }
+
+    /// Sets an age calibration on a node, with a minimum age and an optional maximum age.
+    /// Returns [`TreeError::InvalidCalibration`] if the minimum age is negative or higher
+    /// than the maximum age. Calibrations of different nodes can be checked against each
+    /// other with [`Tree::validate_calibrations()`].
+    /// ```
+    /// use phylotree::tree::{Calibration, Tree};
+    ///
+    /// let mut tree = Tree::from_newick("((A,B)C,D)E;").unwrap();
+    /// let c = tree.get_by_name("C").unwrap().id;
+    /// tree.set_calibration(&c, 20.0, Some(35.5)).unwrap();
+    ///
+    /// assert_eq!(
+    ///     tree.get(&c).unwrap().calibration,
+    ///     Some(Calibration { min: 20.0, max: Some(35.5) })
+    /// );
+    /// assert!(tree.set_calibration(&c, 20.0, Some(10.0)).is_err());
+    /// ```
+    pub fn set_calibration(
+        &mut self,
+        node_id: &NodeId,
+        min_age: f64,
+        max_age: Option<f64>,
+    ) -> Result<(), TreeError> {
+        // Comparisons with NaN are false so NaN ages are rejected
+        let valid_min = min_age >= 0.0;
+        let valid_max = max_age.into_iter().all(|max| max >= min_age);
+        if !(valid_min && valid_max) {
+            return Err(TreeError::InvalidCalibration(*node_id));
+        }
+
+        self.get_mut(node_id)?.calibration = Some(Calibration {
+            min: min_age,
+            max: max_age,
+        });
+
+        Ok(())
+    }
+
     // Removes a single node
     fn compress_node(&mut self, id: &NodeId) -> Result<(), TreeError> {
         let node = self.get(id)?;
@@ -1692,15 +4481,19 @@ impl Tree {
         };
 
         self.get_mut(&child)?.set_parent(parent, new_edge);
-        self.get_mut(&parent)?.add_child(child, new_edge);
-        self.get_mut(&parent)?.remove_child(&to_remove)?;
+        self.get_mut(&parent)?
+            .replace_child(&to_remove, child, new_edge)?;
 
         self.get_mut(&to_remove)?.delete();
 
+        let depth = self.get(&parent)?.get_depth() + 1;
+        self.reset_depth_impl(&child, depth)?;
+
         Ok(())
     }
 
-    /// Compress the tree (i.e. remove nodes with exactly 1 parent and 1 child and fuse branches together)
+    /// Compress the tree (i.e. remove nodes with exactly 1 parent and 1 child and fuse branches together).
+    /// Returns [`TreeError::MissingBranchLengths`] if only one of the fused branches has a length.
     /// ```
     /// use phylotree::tree::Tree;
     ///
@@ -1715,7 +4508,6 @@ impl Tree {
             .nodes
             .iter()
             .filter(|node| !node.deleted && node.parent.is_some() && node.children.len() == 1)
-            .cloned()
             .map(|node| node.id)
             .collect();
 
@@ -1723,6 +4515,85 @@ impl Tree {
             self.compress_node(&id)?;
         }
 
+        self.reset_partitions();
+
+        Ok(())
+    }
+
+    /// Keeps only the leaves whose names are in `keep`. The other leaves are removed,
+    /// along with internal nodes that no longer have any descendant leaves. The tree is
+    /// then compressed *(see [`Tree::compress()`])* so that the remaining branches
+    /// connect the kept leaves directly. If the root is left with a single child, that
+    /// child becomes the new root.
+    ///
+    /// Returns [`TreeError::UnnamedLeaves`] if any leaf lacks a name, and an error if
+    /// some of the names in `keep` are not leaves of the tree.
+    /// # Example
+    /// ```
+    /// use phylotree::tree::Tree;
+    ///
+    /// let mut tree = Tree::from_newick("((A:1,(B:1,C:2)E:1)F:2,(D:3,G:1)H:1)I;").unwrap();
+    ///
+    /// tree.prune_to_taxa(&["A", "C", "D", "G"]).unwrap();
+    ///
+    /// assert_eq!(tree.n_leaves(), 4);
+    /// assert_eq!(tree.to_newick().unwrap(), "((A:1,C:3)F:2,(D:3,G:1)H:1)I;");
+    /// ```
+    pub fn prune_to_taxa(&mut self, keep: &[&str]) -> Result<(), TreeError> {
+        let mut to_remove = vec![];
+        let mut found = HashSet::new();
+        for leaf in self.get_leaves() {
+            let name = self
+                .get(&leaf)?
+                .name
+                .as_deref()
+                .ok_or(TreeError::UnnamedLeaves)?;
+            if keep.contains(&name) {
+                found.insert(name);
+            } else {
+                to_remove.push(leaf);
+            }
+        }
+
+        if keep.iter().any(|name| !found.contains(name)) {
+            return Err(TreeError::GeneralError(
+                "Some of the taxa to keep are not leaves of the tree",
+            ));
+        }
+        if found.is_empty() {
+            return Err(TreeError::GeneralError("At least one taxon must be kept"));
+        }
+
+        // Remove leaves, and their ancestors that are left without children
+        for leaf in to_remove {
+            let mut current = leaf;
+            loop {
+                let parent = self.get(&current)?.parent;
+                self.prune(&current)?;
+                match parent {
+                    Some(parent) if self.get(&parent)?.children.is_empty() => current = parent,
+                    _ => break,
+                }
+            }
+        }
+
+        loop {
+            let root = self.get_root()?;
+            let children = &self.get(&root)?.children;
+            if children.len() != 1 {
+                break;
+            }
+            let child = children[0];
+            self.get_mut(&root)?.delete();
+            let new_root = self.get_mut(&child)?;
+            new_root.parent = None;
+            new_root.parent_edge = None;
+        }
+
+        self.compress()?;
+        self.reset_depths()?;
+        self.reset_bipartition_cache();
+
         Ok(())
     }
 
@@ -1745,72 +4616,244 @@ impl Tree {
         }
     }
 
-    /// Randomly resolve multifurcations to binarize the tree
-    ///
+    /// Returns a copy of the tree with only its topology and leaf names. Branch lengths,
+    /// internal node names, support values and comments are removed.
     /// ```
     /// use phylotree::tree::Tree;
     ///
-    /// let mut tree = Tree::from_newick("((A:0.1,B:0.2):0.3, (C:0.1,D:0.2,E:0.4)F:0.5)G;").unwrap();
-    /// assert!(!tree.is_binary().unwrap());
+    /// let tree = Tree::from_newick("((A:0.1,B:0.2)90:0.3,(C:0.3[comment],D:0.4)E:0.5)F;").unwrap();
+    /// let topology = tree.clone_topology();
     ///
-    /// tree.resolve();
-    /// assert!(tree.is_binary().unwrap());
+    /// assert_eq!(topology.to_newick().unwrap(), "((A,B),(C,D));");
+    /// assert_eq!(tree.to_newick().unwrap(), "((A:0.1,B:0.2)90:0.3,(C:0.3[comment],D:0.4)E:0.5)F;");
     /// ```
-    pub fn resolve(&mut self) -> Result<(), TreeError> {
-        let rng = &mut rand::thread_rng();
-        let mut to_binarize = vec![];
-        for node in self.nodes.iter() {
-            if node.children.len() > 2 {
-                to_binarize.push(node.id);
+    pub fn clone_topology(&self) -> Tree {
+        let mut tree = self.clone();
+        for node in tree.nodes.iter_mut().filter(|node| !node.deleted) {
+            if !node.is_tip() {
+                node.name = None;
             }
+            node.parent_edge = None;
+            node.child_edges = None;
+            node.support = None;
+            node.comment = None;
+            node.nhx = None;
+            node.subtree_distances = RefCell::new(None);
         }
 
-        for &node_id in to_binarize.iter() {
-            loop {
-                let mut children = self.get(&node_id)?.children.clone();
-                children.shuffle(rng);
-
-                let parent = self.add_child(Node::new(), node_id, Some(0.0))?;
-
-                for _ in 0..2 {
-                    let child = children.pop().unwrap();
-                    let edge = self.get(&child)?.parent_edge;
-                    self.get_mut(&parent)?.add_child(child, edge);
-                    self.get_mut(&child)?.set_parent(parent, edge);
-                    self.get_mut(&node_id)?.remove_child(&child)?;
-                }
-
-                children.push(parent);
-
-                if children.len() <= 2 {
-                    break;
-                }
-            }
-        }
-        Ok(())
+        tree
     }
 
-    /// Sort children of a node by number of descendants
-    ///
+    /// Returns a copy of the tree with all branch lengths multiplied by `factor`,
+    /// leaving the tree unchanged *(see [`Tree::rescale()`])*.
     /// ```
-    ///use phylotree::tree::Tree;
-    ///
-    ///let mut tree = Tree::from_newick("(A,(((D,(E,F)),C),B));").unwrap();
-    ///tree.ladderize();
+    /// use phylotree::tree::Tree;
     ///
-    ///assert_eq!("(A,(B,(C,(D,(E,F)))));", tree.to_newick().unwrap());
+    /// let tree = Tree::from_newick("(A:0.1,B:0.2,(C:0.3,D:0.4)E:0.5)F;").unwrap();
+    /// let scaled = tree.clone_with_lengths_scaled(2.0);
     ///
+    /// assert_eq!(scaled.to_newick().unwrap(), "(A:0.2,B:0.4,(C:0.6,D:0.8)E:1)F;");
+    /// assert_eq!(tree.to_newick().unwrap(), "(A:0.1,B:0.2,(C:0.3,D:0.4)E:0.5)F;");
     /// ```
-    pub fn ladderize(&mut self) -> Result<(), TreeError> {
-        let mut descendant_counter = vec![0; self.nodes.len()];
-        let root = self.get_root()?;
+    pub fn clone_with_lengths_scaled(&self, factor: f64) -> Tree {
+        let mut tree = self.clone();
+        tree.rescale(factor);
+
+        tree
+    }
+
+    /// Adjusts the terminal branch lengths so that the tree is exactly ultrametric.
+    /// Each tip is moved to the mean root to tip distance: the excess or deficit
+    /// of each tip is absorbed by its terminal branch. If this would make a terminal
+    /// branch negative, the target distance is raised to the smallest value that keeps
+    /// all terminal branches non-negative. The tree must be rooted and have all
+    /// branch lengths.
+    /// ```
+    /// use phylotree::tree::Tree;
+    ///
+    /// let mut tree = Tree::from_newick("((A:1,B:2):1,(C:1.5,D:1.5):1);").unwrap();
+    /// tree.make_ultrametric_tips().unwrap();
+    ///
+    /// assert!(tree.is_ultrametric(0.0).unwrap());
+    /// assert_eq!(tree.to_newick().unwrap(), "((A:1.5,B:1.5):1,(C:1.5,D:1.5):1);");
+    /// ```
+    pub fn make_ultrametric_tips(&mut self) -> Result<(), TreeError> {
+        if !self.is_rooted()? {
+            return Err(TreeError::IsNotRooted);
+        }
+
+        let root_dists = self.root_distances()?;
+        let leaves = self.get_leaves();
+
+        let mean = leaves.iter().map(|id| root_dists[*id]).sum::<f64>() / leaves.len() as f64;
+        let min_target = leaves
+            .iter()
+            .map(|id| root_dists[self.get(id).unwrap().parent.unwrap()])
+            .fold(f64::MIN, f64::max);
+        let target = mean.max(min_target);
+
+        for leaf in leaves {
+            let node = self.get(&leaf)?;
+            let parent = node.parent.unwrap();
+            let edge = target - root_dists[parent];
+
+            self.get_mut(&leaf)?.parent_edge = Some(edge);
+            self.get_mut(&parent)?.set_child_edge(&leaf, Some(edge));
+        }
+
+        self.reset_partitions();
+
+        Ok(())
+    }
+
+    /// Randomly resolve multifurcations to binarize the tree
+    ///
+    /// ```
+    /// use phylotree::tree::Tree;
+    ///
+    /// let mut tree = Tree::from_newick("((A:0.1,B:0.2):0.3, (C:0.1,D:0.2,E:0.4)F:0.5)G;").unwrap();
+    /// assert!(!tree.is_binary().unwrap());
+    ///
+    /// tree.resolve();
+    /// assert!(tree.is_binary().unwrap());
+    /// ```
+    pub fn resolve(&mut self) -> Result<(), TreeError> {
+        self.randomly_resolve_polytomies(&mut rand::thread_rng(), true)
+    }
+
+    /// Randomly resolves every node with more than 2 children into a random binary
+    /// arrangement, using the given random number generator. New internal branches
+    /// have a length of `0.0` if `zero_length` is `true` and no length otherwise.
+    /// ```
+    /// use phylotree::tree::Tree;
+    /// use rand::{rngs::StdRng, SeedableRng};
+    ///
+    /// let mut tree = Tree::from_newick("(A:1,B:1,C:1,(D:1,E:1,F:1):1);").unwrap();
+    /// let mut rng = StdRng::seed_from_u64(42);
+    ///
+    /// tree.randomly_resolve_polytomies(&mut rng, true).unwrap();
+    ///
+    /// assert!(tree.is_binary().unwrap());
+    /// assert_eq!(tree.n_leaves(), 6);
+    /// assert_eq!(tree.length().unwrap(), 7.0);
+    /// ```
+    pub fn randomly_resolve_polytomies(
+        &mut self,
+        rng: &mut impl Rng,
+        zero_length: bool,
+    ) -> Result<(), TreeError> {
+        let new_edge = zero_length.then_some(0.0);
+        let to_binarize: Vec<_> = self
+            .nodes
+            .iter()
+            .filter(|node| !node.deleted && node.children.len() > 2)
+            .map(|node| node.id)
+            .collect();
+
+        for &node_id in to_binarize.iter() {
+            loop {
+                let mut children = self.get(&node_id)?.children.clone();
+                children.shuffle(rng);
+
+                let parent = self.add_child(Node::new(), node_id, new_edge)?;
+
+                for _ in 0..2 {
+                    let child = children.pop().unwrap();
+                    let edge = self.get(&child)?.parent_edge;
+                    self.get_mut(&parent)?.add_child(child, edge);
+                    self.get_mut(&child)?.set_parent(parent, edge);
+                    self.get_mut(&node_id)?.remove_child(&child)?;
+                }
+
+                children.push(parent);
+
+                if children.len() <= 2 {
+                    break;
+                }
+            }
+        }
+
+        self.reset_depths()?;
+        self.reset_partitions();
+
+        Ok(())
+    }
+
+    /// Reverses the order of the children of a node. For a binary node this swaps
+    /// its left and right children. The topology of the tree is unchanged.
+    /// ```
+    /// use phylotree::tree::Tree;
+    ///
+    /// let mut tree = Tree::from_newick("((A,B)D,C)E;").unwrap();
+    ///
+    /// let d = tree.get_by_name("D").unwrap().id;
+    /// tree.swap_children(&d).unwrap();
+    /// assert_eq!(tree.to_newick().unwrap(), "((B,A)D,C)E;");
+    ///
+    /// let root = tree.get_root().unwrap();
+    /// tree.swap_children(&root).unwrap();
+    /// assert_eq!(tree.to_newick().unwrap(), "(C,(B,A)D)E;");
+    /// ```
+    pub fn swap_children(&mut self, node_id: &NodeId) -> Result<(), TreeError> {
+        self.get_mut(node_id)?.children.reverse();
+
+        Ok(())
+    }
+
+    /// Randomly permutes the children of every node of the tree. This gives
+    /// a different newick representation of the same topology.
+    /// ```
+    /// use phylotree::tree::Tree;
+    /// use rand::{rngs::StdRng, SeedableRng};
+    ///
+    /// let mut tree = Tree::from_newick("((A,B),(C,(D,E)),F);").unwrap();
+    /// let original = tree.clone();
+    ///
+    /// tree.swap_all_children_randomly(&mut StdRng::seed_from_u64(42));
+    ///
+    /// assert!(tree.topology_equal(&original).unwrap());
+    /// ```
+    pub fn swap_all_children_randomly(&mut self, rng: &mut impl Rng) {
+        for node in self.nodes.iter_mut().filter(|node| !node.deleted) {
+            node.children.shuffle(rng);
+        }
+        self.reset_root_paths();
+    }
+
+    /// Sort the children of each node by the number of leaves in their subtrees.
+    /// If `ascending` is `true` then children with smaller clades come first,
+    /// otherwise they come last. Ties keep their original order.
+    ///
+    /// ```
+    ///use phylotree::tree::Tree;
+    ///
+    ///let mut tree = Tree::from_newick("(A,(((D,(E,F)),C),B));").unwrap();
+    ///
+    ///tree.ladderize(true).unwrap();
+    ///assert_eq!("(A,(B,(C,(D,(E,F)))));", tree.to_newick().unwrap());
+    ///
+    ///tree.ladderize(false).unwrap();
+    ///assert_eq!("(((((E,F),D),C),B),A);", tree.to_newick().unwrap());
+    /// ```
+    pub fn ladderize(&mut self, ascending: bool) -> Result<(), TreeError> {
+        let mut leaf_counter = vec![0; self.nodes.len()];
+        let root = self.get_root()?;
         // Go from tips to root
         for node_id in self.levelorder(&root)?.into_iter().rev() {
             let node = self.get_mut(&node_id)?;
+            if node.is_tip() {
+                leaf_counter[node_id] = 1;
+                continue;
+            }
             for child in node.children.iter() {
-                descendant_counter[node_id] += descendant_counter[*child] + 1;
+                leaf_counter[node_id] += leaf_counter[*child];
+            }
+            if ascending {
+                node.children.sort_by_key(|v| leaf_counter[*v]);
+            } else {
+                node.children
+                    .sort_by_key(|v| std::cmp::Reverse(leaf_counter[*v]));
             }
-            node.children.sort_by_key(|v| descendant_counter[*v]);
         }
 
         Ok(())
@@ -1890,1126 +4933,2565 @@ impl Tree {
 
         Ok(parent)
     }
-}
 
-/// Methods to read and write [`Tree`] objects to and from files or [`String`] objects.
-///   
-/// ----
-/// ----
-impl Tree {
-    // ########################
-    // # READ AND WRITE TREES #
-    // ########################
+    /// Groups two sibling nodes under a new internal node. The new node takes the
+    /// place of `node_a` among the children of their former parent, and `node_a` and
+    /// `node_b` are attached to it with branches of length `edge_a` and `edge_b`.
+    /// Returns the id of the new node, or [`TreeError::MergingNonSiblingNodes`]
+    /// if the nodes do not share a parent.
+    /// ```
+    /// use phylotree::tree::Tree;
+    ///
+    /// let mut tree = Tree::from_newick("(A:1,B:2,C:3,D:4)E;").unwrap();
+    /// let b = tree.get_by_name("B").unwrap().id;
+    /// let d = tree.get_by_name("D").unwrap().id;
+    ///
+    /// let ancestor = tree.add_ancestor(&b, &d, Some(0.5), Some(1.5)).unwrap();
+    ///
+    /// assert_eq!(tree.get(&ancestor).unwrap().get_depth(), 1);
+    /// assert_eq!(tree.to_newick().unwrap(), "(A:1,(B:0.5,D:1.5),C:3)E;");
+    /// ```
+    pub fn add_ancestor(
+        &mut self,
+        node_a: &NodeId,
+        node_b: &NodeId,
+        edge_a: Option<EdgeLength>,
+        edge_b: Option<EdgeLength>,
+    ) -> Result<NodeId, TreeError> {
+        let parent = match (self.get(node_a)?.parent, self.get(node_b)?.parent) {
+            (Some(p1), Some(p2)) if p1 == p2 && node_a != node_b => p1,
+            _ => return Err(TreeError::MergingNonSiblingNodes(*node_a, *node_b)),
+        };
 
-    /// Generate newick representation of tree
-    fn to_newick_impl(&self, root: &NodeId, format: NewickFormat) -> Result<String, TreeError> {
-        let root = self.get(root)?;
-        if root.children.is_empty() {
-            Ok(root.to_newick(format))
+        let ancestor = self.add(Node::new());
+        let parent_node = self.get_mut(&parent)?;
+        parent_node.replace_child(node_a, ancestor, None)?;
+        parent_node.remove_child(node_b)?;
+
+        let depth = self.get(&parent)?.get_depth() + 1;
+        let new = self.get_mut(&ancestor)?;
+        new.set_parent(parent, None);
+        new.add_child(*node_a, edge_a);
+        new.add_child(*node_b, edge_b);
+        self.get_mut(node_a)?.set_parent(ancestor, edge_a);
+        self.get_mut(node_b)?.set_parent(ancestor, edge_b);
+        self.reset_depth_impl(&ancestor, depth)?;
+
+        self.reset_partitions();
+
+        Ok(ancestor)
+    }
+
+    // Returns the (parent, child) ordering of the nodes defining an edge
+    fn orient_edge(&self, edge: (&NodeId, &NodeId)) -> Result<(NodeId, NodeId), TreeError> {
+        let (a, b) = edge;
+        if self.get(b)?.parent == Some(*a) {
+            Ok((*a, *b))
+        } else if self.get(a)?.parent == Some(*b) {
+            Ok((*b, *a))
         } else {
-            Ok("(".to_string()
-                + &(root
-                    .children
-                    .iter()
-                    .map(|child_idx| self.to_newick_impl(child_idx, format).unwrap()))
-                .collect::<Vec<String>>()
-                .join(",")
-                + ")"
-                + &(root.to_newick(format)))
+            Err(NodeError::HasNoChild {
+                parent: *a,
+                child: *b,
+            })?
         }
     }
 
-    /// Writes the tree as a newick formatted string
-    /// # Example
+    /// Grafts a copy of another tree onto a branch of this tree. The branch defined
+    /// by `target_edge` is split in half by a new connector node, and the root of
+    /// `subtree` is attached to this connector with a branch of length `edge_to_subtree`.
+    /// Returns the id of the connector node.
     /// ```
     /// use phylotree::tree::Tree;
     ///
-    /// let newick = "(A:0.1,B:0.2,(C:0.3,D:0.4)E:0.5)F:0.6;";
-    /// let tree = Tree::from_newick(newick).unwrap();
+    /// let mut tree = Tree::from_newick("((A:1,B:1)E:2,C:1)F;").unwrap();
+    /// let subtree = Tree::from_newick("(G:1,H:1)I;").unwrap();
     ///
-    /// assert_eq!(tree.to_newick().unwrap(), newick);
+    /// let e = tree.get_by_name("E").unwrap().id;
+    /// let f = tree.get_by_name("F").unwrap().id;
+    /// tree.graft(&subtree, (&f, &e), Some(0.5)).unwrap();
+    ///
+    /// assert_eq!(
+    ///     tree.to_newick().unwrap(),
+    ///     "(((A:1,B:1)E:1,(G:1,H:1)I:0.5):1,C:1)F;"
+    /// );
     /// ```
-    pub fn to_newick(&self) -> Result<String, TreeError> {
-        let root = self.get_root()?;
-        Ok(self.to_newick_impl(&root, NewickFormat::AllFields)? + ";")
+    pub fn graft(
+        &mut self,
+        subtree: &Tree,
+        target_edge: (&NodeId, &NodeId),
+        edge_to_subtree: Option<EdgeLength>,
+    ) -> Result<NodeId, TreeError> {
+        let (upper, lower) = self.orient_edge(target_edge)?;
+        let subtree_root = subtree.get_root()?;
+
+        let half = self.get(&lower)?.parent_edge.map(|l| l / 2.0);
+        let connector = self.split_edge(&upper, &lower, half)?;
+        self.attach_subtree(subtree, &subtree_root, &connector, edge_to_subtree)?;
+
+        self.reset_bipartition_cache();
+
+        Ok(connector)
     }
 
-    /// Writes the tree as a newick formatted string with a specified
-    /// output format from [`NewickFormat`].
-    /// # Example
+    // Copies the subtree of `other` rooted at `root` as a child of `parent`,
+    // returns the new id of the subtree root.
+    fn attach_subtree(
+        &mut self,
+        other: &Tree,
+        root: &NodeId,
+        parent: &NodeId,
+        edge: Option<EdgeLength>,
+    ) -> Result<NodeId, TreeError> {
+        let mut new_ids = HashMap::new();
+        for old_id in other.preorder(root)? {
+            let old_node = other.get(&old_id)?;
+
+            let mut node = old_node.clone();
+            node.children.clear();
+            node.child_edges = None;
+            node.subtree_distances = RefCell::new(None);
+
+            let (parent, edge) = if old_id == *root {
+                (*parent, edge)
+            } else {
+                (new_ids[&old_node.parent.unwrap()], old_node.parent_edge)
+            };
+            new_ids.insert(old_id, self.add_child(node, parent, edge)?);
+        }
+
+        Ok(new_ids[root])
+    }
+
+    /// Creates a new tree by attaching two trees to a new root node,
+    /// with the given branch lengths between the new root and the roots of the trees.
     /// ```
-    /// use phylotree::tree::{Tree, NewickFormat};
+    /// use phylotree::tree::Tree;
     ///
-    /// let newick = "(A:0.1,B:0.2,(C:0.3,D:0.4)E:0.5)F:0.6;";
-    /// let tree = Tree::from_newick(newick).unwrap();
+    /// let left = Tree::from_newick("(A:1,B:1);").unwrap();
+    /// let right = Tree::from_newick("(C:1,(D:1,E:1):1);").unwrap();
+    /// let merged = Tree::merge(&left, &right, Some(2.0), Some(1.0)).unwrap();
     ///
-    /// assert_eq!(tree.to_formatted_newick(NewickFormat::Topology).unwrap(), "(,,(,));");
-    /// assert_eq!(
-    ///     tree.to_formatted_newick(NewickFormat::OnlyNames).unwrap(),
-    ///     "(A,B,(C,D)E)F;"
-    /// );
+    /// assert!(merged.is_binary().unwrap());
     /// assert_eq!(
-    ///     tree.to_formatted_newick(NewickFormat::InternalLengthsLeafNames).unwrap(),
-    ///     "(A,B,(C,D):0.5):0.6;"
+    ///     merged.to_newick().unwrap(),
+    ///     "((A:1,B:1):2,(C:1,(D:1,E:1):1):1);"
     /// );
     /// ```
-    pub fn to_formatted_newick(&self, format: NewickFormat) -> Result<String, TreeError> {
-        let root = self.get_root()?;
-        Ok(self.to_newick_impl(&root, format)? + ";")
+    pub fn merge(
+        tree1: &Tree,
+        tree2: &Tree,
+        connector_len1: Option<EdgeLength>,
+        connector_len2: Option<EdgeLength>,
+    ) -> Result<Tree, TreeError> {
+        let mut merged = Tree::new();
+        let root = merged.add(Node::new());
+
+        merged.attach_subtree(tree1, &tree1.get_root()?, &root, connector_len1)?;
+        merged.attach_subtree(tree2, &tree2.get_root()?, &root, connector_len2)?;
+
+        Ok(merged)
     }
 
-    /// Read a newick formatted string and build a [`Tree`] struct from it.
-    /// # Example
+    // Inserts a new node on the branch between parent and child, at distance
+    // `upper_length` from the parent. Returns the id of the new node.
+    fn split_edge(
+        &mut self,
+        parent: &NodeId,
+        child: &NodeId,
+        upper_length: Option<EdgeLength>,
+    ) -> Result<NodeId, TreeError> {
+        let lower_length = match (self.get(child)?.parent_edge, upper_length) {
+            (Some(length), Some(upper)) => Some(length - upper),
+            (None, None) => None,
+            _ => return Err(TreeError::MissingBranchLengths),
+        };
+
+        let new_node = self.add(Node::new());
+        self.get_mut(parent)?
+            .replace_child(child, new_node, upper_length)?;
+        self.get_mut(child)?.set_parent(new_node, lower_length);
+
+        let depth = self.get(parent)?.get_depth() + 1;
+        let new = self.get_mut(&new_node)?;
+        new.set_parent(*parent, upper_length);
+        new.add_child(*child, lower_length);
+        self.reset_depth_impl(&new_node, depth)?;
+
+        Ok(new_node)
+    }
+
+    /// Removes the root of a rooted tree. The first internal child of the root
+    /// becomes the root of the unrooted tree, and the other child is attached to it
+    /// with a branch whose length is the sum of the two branches below the former
+    /// root *(or no length if one of them is missing)*.
+    ///
+    /// Returns [`TreeError::IsNotRooted`] if the tree is already unrooted and
+    /// [`TreeError::IsNotBinary`] if the tree has only two leaves.
     /// ```
     /// use phylotree::tree::Tree;
     ///
-    /// let newick = "(A:0.1,B:0.2,(C:0.3,D:0.4)E:0.5)F;";
-    /// let tree = Tree::from_newick(newick).unwrap();
+    /// let mut tree = Tree::from_newick("((A:1,B:1)C:2,(D:1,E:1)F:3)G;").unwrap();
+    /// tree.unroot().unwrap();
     ///
-    /// assert_eq!(tree.size(), 6);
-    /// assert_eq!(tree.n_leaves(), 4);
-    /// assert_eq!(tree.is_rooted().unwrap(), false);
+    /// assert!(!tree.is_rooted().unwrap());
+    /// assert_eq!(tree.to_newick().unwrap(), "(A:1,B:1,(D:1,E:1)F:5)C;");
     /// ```
-    pub fn from_newick(newick: &str) -> Result<Self, NewickParseError> {
-        #[derive(Debug, PartialEq)]
-        enum Field {
-            Name,
-            Length,
-            Comment,
+    pub fn unroot(&mut self) -> Result<(), TreeError> {
+        if !self.is_rooted()? {
+            return Err(TreeError::IsNotRooted);
         }
 
-        let mut tree = Tree::new();
+        let root = self.get_root()?;
+        let children = self.get(&root)?.children.clone();
+        let (new_root, other) = match (
+            self.get(&children[0])?.is_tip(),
+            self.get(&children[1])?.is_tip(),
+        ) {
+            (false, _) => (children[0], children[1]),
+            (true, false) => (children[1], children[0]),
+            (true, true) => return Err(TreeError::IsNotBinary),
+        };
 
-        let mut parsing = Field::Name;
-        let mut current_name: Option<String> = None;
-        let mut current_length: Option<String> = None;
-        let mut current_comment: Option<String> = None;
-        let mut current_index: Option<NodeId> = None;
-        let mut parent_stack: Vec<NodeId> = Vec::new();
+        let edge = match (
+            self.get(&new_root)?.parent_edge,
+            self.get(&other)?.parent_edge,
+        ) {
+            (Some(l1), Some(l2)) => Some(l1 + l2),
+            _ => None,
+        };
 
-        let mut open_delimiters = Vec::new();
-        let mut within_quotes = false;
+        self.get_mut(&root)?.delete();
+        let new = self.get_mut(&new_root)?;
+        new.parent = None;
+        new.parent_edge = None;
+        new.add_child(other, edge);
+        self.get_mut(&other)?.set_parent(new_root, edge);
 
-        for c in newick.chars() {
-            // Add character in quotes to name
-            if within_quotes && parsing == Field::Name && c != '"' {
-                if let Some(name) = current_name.as_mut() {
-                    name.push(c)
-                } else {
-                    current_name = Some(c.into())
-                }
-                continue;
-            }
+        self.reset_depths()?;
+        self.reset_partitions();
 
-            // Add current character to comment
-            if parsing == Field::Comment && c != ']' {
-                if let Some(comment) = current_comment.as_mut() {
-                    comment.push(c)
-                } else {
-                    current_comment = Some(c.into())
-                }
-                continue;
-            }
+        Ok(())
+    }
 
-            // Skip unquoted whitespace
-            if c.is_whitespace() && !within_quotes {
-                continue;
-            }
+    // Reroots the tree at a given node by reversing the branches on the path from
+    // the current root. If the former root is left with a single child it is removed.
+    fn reroot_at(&mut self, new_root: &NodeId) -> Result<(), TreeError> {
+        let path = self.get_path_from_root(new_root)?;
+        let old_root = path[0];
+
+        for pair in path.windows(2) {
+            let (parent, child) = (pair[0], pair[1]);
+            let edge = self.get(&child)?.parent_edge;
+            self.get_mut(&parent)?.remove_child(&child)?;
+            self.get_mut(&parent)?.set_parent(child, edge);
+            self.get_mut(&child)?.add_child(parent, edge);
+        }
 
-            match c {
-                '"' => {
-                    // Enter or close quoted section (name)
-                    // TODO: handle escaped quotes
-                    within_quotes = !within_quotes;
-                    if parsing == Field::Name {
-                        if let Some(name) = current_name.as_mut() {
-                            name.push(c)
-                        } else {
-                            current_name = Some(c.into())
-                        }
-                    }
-                }
-                '[' => {
-                    parsing = Field::Comment;
-                }
-                ']' => {
-                    parsing = Field::Name;
-                }
-                '(' => {
-                    // Start subtree
-                    match parent_stack.last() {
-                        None => parent_stack.push(tree.add(Node::new())),
-                        Some(parent) => {
-                            parent_stack.push(tree.add_child(Node::new(), *parent, None)?)
-                        }
-                    };
-                    open_delimiters.push(0);
-                }
-                ':' => {
-                    // Start parsing length
-                    parsing = Field::Length;
-                }
-                ',' => {
-                    // Add sibling
-                    let node = if let Some(index) = current_index {
-                        tree.get_mut(&index)?
-                    } else {
-                        if let Some(parent) = parent_stack.last() {
-                            current_index = Some(tree.add_child(Node::new(), *parent, None)?);
-                        } else {
-                            unreachable!("Sould not be possible to have named child with no parent")
-                        };
-                        tree.get_mut(current_index.as_ref().unwrap())?
-                    };
+        let root = self.get_mut(new_root)?;
+        root.parent = None;
+        root.parent_edge = None;
 
-                    if let Some(name) = current_name {
-                        node.set_name(name);
-                    }
+        if old_root != *new_root && self.get(&old_root)?.children.len() == 1 {
+            self.compress_node(&old_root)?;
+        }
 
-                    let edge = if let Some(length) = current_length {
-                        Some(length.parse()?)
-                    } else {
-                        None
-                    };
-                    if let Some(parent) = node.parent {
-                        node.set_parent(parent, edge);
-                    }
+        self.reset_depths()?;
+        self.reset_partitions();
 
-                    node.comment = current_comment;
+        Ok(())
+    }
 
-                    current_name = None;
-                    current_comment = None;
-                    current_length = None;
-                    current_index = None;
+    /// Roots the tree at the midpoint of the longest path between two leaves.
+    /// A new root node is inserted on the branch containing the midpoint. All
+    /// the branches of the tree must have lengths.
+    /// ```
+    /// use phylotree::tree::Tree;
+    ///
+    /// let mut tree = Tree::from_newick("(A:1,B:2,(C:3,D:7)E:2)F;").unwrap();
+    /// tree.midpoint_root().unwrap();
+    ///
+    /// assert!(tree.is_rooted().unwrap());
+    /// assert_eq!(tree.to_newick().unwrap(), "(D:5.5,(C:3,(A:1,B:2)F:2)E:1.5);");
+    /// ```
+    pub fn midpoint_root(&mut self) -> Result<(), TreeError> {
+        let missing_lengths = self
+            .nodes
+            .iter()
+            .any(|node| !node.deleted && node.parent.is_some() && node.parent_edge.is_none());
+        if missing_lengths {
+            return Err(TreeError::MissingBranchLengths);
+        }
 
-                    parsing = Field::Name;
-                }
-                ')' => {
-                    // Close subtree
-                    open_delimiters.pop();
-                    let node = if let Some(index) = current_index {
-                        tree.get_mut(&index)?
-                    } else {
-                        if let Some(parent) = parent_stack.last() {
-                            current_index = Some(tree.add_child(Node::new(), *parent, None)?);
-                        } else {
-                            unreachable!("Sould not be possible to have named child with no parent")
-                        };
-                        tree.get_mut(current_index.as_ref().unwrap())?
-                    };
+        let (source, target, diameter) = self
+            .get_leaves()
+            .into_iter()
+            .tuple_combinations()
+            .map(|(a, b)| {
+                let (dist, _) = self.get_distance(&a, &b).unwrap();
+                (a, b, dist.unwrap())
+            })
+            .max_by(|a, b| a.2.partial_cmp(&b.2).unwrap_or(std::cmp::Ordering::Equal))
+            .ok_or(TreeError::IsEmpty)?;
 
-                    if let Some(name) = current_name {
-                        node.set_name(name);
-                    }
+        let half = diameter / 2.0;
+        let ancestor = self.get_common_ancestor(&source, &target)?;
+        let (source_dist, _) = self.get_distance(&source, &ancestor)?;
 
-                    let edge = if let Some(length) = current_length {
-                        Some(length.parse()?)
-                    } else {
-                        None
-                    };
-                    if let Some(parent) = node.parent {
-                        node.set_parent(parent, edge);
-                    }
+        // Walk up from the endpoint that is furthest from the common ancestor
+        let mut current = if source_dist.unwrap() >= half {
+            source
+        } else {
+            target
+        };
+        let mut walked = 0.0;
+        loop {
+            let node = self.get(&current)?;
+            let edge = node.parent_edge.unwrap();
+            let parent = node.parent.ok_or(TreeError::RootNotFound)?;
 
-                    node.comment = current_comment;
+            if walked + edge >= half {
+                let new_root = self.split_edge(&parent, &current, Some(walked + edge - half))?;
+                return self.reroot_at(&new_root);
+            }
 
-                    current_name = None;
-                    current_comment = None;
-                    current_length = None;
+            walked += edge;
+            current = parent;
+        }
+    }
 
-                    parsing = Field::Name;
+    /// Roots an unrooted tree *(i.e. with a virtual root that has 3 children)* at
+    /// the midpoint of the longest path between two leaves. A new bifurcating root
+    /// node is inserted on the branch containing the midpoint, and the former
+    /// virtual root is left with 2 children.
+    ///
+    /// Returns [`TreeError::MissingBranchLengths`] if any branch has no length and
+    /// [`TreeError::IsNotBinary`] if the tree has internal polytomies.
+    /// ```
+    /// use phylotree::tree::Tree;
+    ///
+    /// let mut tree = Tree::from_newick("(A:1,B:2,(C:3,D:7)E:2)F;").unwrap();
+    /// tree.root_at_midpoint_of_longest_path().unwrap();
+    ///
+    /// assert!(tree.is_rooted().unwrap());
+    ///
+    /// let root = tree.get_root().unwrap();
+    /// let d = tree.get_by_name("D").unwrap().id;
+    /// let b = tree.get_by_name("B").unwrap().id;
+    /// assert_eq!(tree.get_distance(&root, &d).unwrap().0, Some(5.5));
+    /// assert_eq!(tree.get_distance(&root, &b).unwrap().0, Some(5.5));
+    /// ```
+    pub fn root_at_midpoint_of_longest_path(&mut self) -> Result<(), TreeError> {
+        if self.is_rooted()? {
+            return Err(TreeError::GeneralError(
+                "The tree is already rooted, use Tree::midpoint_root instead",
+            ));
+        }
+        if !self.is_binary()? {
+            return Err(TreeError::IsNotBinary);
+        }
 
-                    if let Some(parent) = parent_stack.pop() {
-                        current_index = Some(parent)
-                    } else {
-                        return Err(NewickParseError::NoSubtreeParent);
-                    }
-                }
-                ';' => {
-                    // Finish parsing the Tree
-                    if !open_delimiters.is_empty() {
-                        return Err(NewickParseError::UnclosedBracket);
-                    }
-                    let node = tree.get_mut(current_index.as_ref().unwrap())?;
-                    node.name = current_name;
-                    node.comment = current_comment;
-                    if let Some(length) = current_length {
-                        node.parent_edge = Some(length.parse()?);
-                    }
+        self.midpoint_root()
+    }
 
-                    // Finishing pass to make sure that branch lenghts are set in both children and parents
-                    let ids: Vec<_> = tree.nodes.iter().map(|node| node.id).collect();
-                    for node_id in ids {
-                        if let Some(edge) = tree.get(&node_id)?.parent_edge {
-                            if let Some(parent) = tree.get(&node_id)?.parent {
-                                tree.get_mut(&parent)?.set_child_edge(&node_id, Some(edge));
-                            }
-                        }
-                    }
+    /// Roots the tree on the branch leading to an outgroup. A new root node
+    /// is inserted at the middle of the branch above the most recent common
+    /// ancestor of the outgroup tips. The tree must be binary.
+    /// ```
+    /// use phylotree::tree::Tree;
+    ///
+    /// let mut tree = Tree::from_newick("(A:1,B:2,(C:3,D:4)E:2)F;").unwrap();
+    /// let outgroup = vec![
+    ///     tree.get_by_name("C").unwrap().id,
+    ///     tree.get_by_name("D").unwrap().id,
+    /// ];
+    ///
+    /// tree.outgroup_root(&outgroup).unwrap();
+    ///
+    /// assert!(tree.is_rooted().unwrap());
+    /// assert_eq!(tree.to_newick().unwrap(), "((C:3,D:4)E:1,(A:1,B:2)F:1);");
+    /// ```
+    pub fn outgroup_root(&mut self, outgroup_tips: &[NodeId]) -> Result<(), TreeError> {
+        if !self.is_binary()? {
+            return Err(TreeError::IsNotBinary);
+        }
 
-                    return Ok(tree);
-                }
-                _ => {
-                    // Parse characters in fields
-                    match parsing {
-                        Field::Name => {
-                            if let Some(name) = current_name.as_mut() {
-                                name.push(c)
-                            } else {
-                                current_name = Some(c.into())
-                            }
-                        }
-                        Field::Length => {
-                            if c.is_whitespace() {
-                                return Err(NewickParseError::WhiteSpaceInNumber);
-                            }
-                            if let Some(length) = current_length.as_mut() {
-                                length.push(c)
-                            } else {
-                                current_length = Some(c.into())
-                            }
-                        }
-                        Field::Comment => unimplemented!(),
-                    };
-                }
+        if outgroup_tips.is_empty() {
+            return Err(TreeError::GeneralError(
+                "The outgroup must contain at least one tip",
+            ));
+        }
+
+        let mut ancestor = self.get_mrca(outgroup_tips)?;
+
+        // If the outgroup spans the current root, move the root into the ingroup
+        if ancestor == self.get_root()? {
+            let ingroup_tip = self
+                .get_leaves()
+                .into_iter()
+                .find(|tip| !outgroup_tips.contains(tip))
+                .ok_or(TreeError::GeneralError(
+                    "The ingroup must contain at least one tip",
+                ))?;
+            let ingroup_parent = self
+                .get(&ingroup_tip)?
+                .parent
+                .ok_or(NodeError::HasNoParent(ingroup_tip))?;
+            self.reroot_at(&ingroup_parent)?;
+
+            ancestor = self.get_mrca(outgroup_tips)?;
+            if ancestor == self.get_root()? {
+                return Err(TreeError::GeneralError(
+                    "The outgroup does not form a clade in the tree",
+                ));
             }
         }
 
-        Err(NewickParseError::NoClosingSemicolon)
+        let parent = self
+            .get(&ancestor)?
+            .parent
+            .ok_or(NodeError::HasNoParent(ancestor))?;
+        let half = self.get(&ancestor)?.parent_edge.map(|l| l / 2.0);
+
+        let new_root = self.split_edge(&parent, &ancestor, half)?;
+        self.reroot_at(&new_root)
     }
 
-    /// Writes the tree to a newick file
-    pub fn to_file(&self, path: &Path) -> Result<(), TreeError> {
-        match fs::write(path, self.to_newick()?) {
-            Ok(_) => Ok(()),
-            Err(e) => Err(e.into()),
+    /// Performs a nearest neighbour interchange (NNI) across the internal branch
+    /// defined by `edge`. The sibling subtree of the lower node is exchanged with
+    /// one of the children of the lower node, chosen with `swap`. Branch lengths
+    /// are moved along with the subtrees.
+    ///
+    /// Both ends of the branch must be bifurcating internal nodes *(the virtual root
+    /// of an unrooted tree may have 3 children)*, otherwise [`TreeError::IsNotBinary`]
+    /// is returned.
+    /// ```
+    /// use phylotree::tree::{Tree, NniSwap};
+    ///
+    /// let mut tree = Tree::from_newick("((A,B)E,(C,D)F)G;").unwrap();
+    /// let g = tree.get_by_name("G").unwrap().id;
+    /// let e = tree.get_by_name("E").unwrap().id;
+    ///
+    /// // Swap F with A
+    /// tree.nni((&g, &e), NniSwap::Left).unwrap();
+    ///
+    /// assert_eq!(tree.to_newick().unwrap(), "(((C,D)F,B)E,A)G;");
+    /// ```
+    pub fn nni(&mut self, edge: (&NodeId, &NodeId), swap: NniSwap) -> Result<(), TreeError> {
+        let (upper, lower) = self.orient_edge(edge)?;
+
+        let upper_node = self.get(&upper)?;
+        let lower_node = self.get(&lower)?;
+
+        let upper_binary = upper_node.children.len() == 2
+            || (upper_node.is_root() && upper_node.children.len() == 3);
+        if !upper_binary || lower_node.children.len() != 2 {
+            return Err(TreeError::IsNotBinary);
         }
-    }
 
-    /// Creates a tree from a newick file
-    pub fn from_file(path: &Path) -> Result<Self, NewickParseError> {
-        let newick_string = fs::read_to_string(path)?;
-        Self::from_newick(&newick_string)
-    }
+        let sibling = *upper_node.children.iter().find(|&&id| id != lower).unwrap();
+        let nephew = match swap {
+            NniSwap::Left => lower_node.children[0],
+            NniSwap::Right => lower_node.children[1],
+        };
 
-    /// Outputs a Nexus formatted string of the tree
-    pub fn to_nexus(&self) -> Result<String, TreeError> {
-        let nwk = self.to_newick()?;
-        let n = self.n_leaves();
-        let labels = self
-            .nodes
-            .iter()
-            .filter_map(|node| {
-                if node.is_tip() {
-                    node.name.clone()
-                } else {
-                    None
-                }
-            })
-            .join(" ");
+        let sibling_edge = self.get(&sibling)?.parent_edge;
+        let nephew_edge = self.get(&nephew)?.parent_edge;
 
-        Ok(format!(
-            "#NEXUS
-BEGIN TAXA;
-    DIMENSIONS NTAX={n};
-    TAXLABELS {labels};
-END;
-BEGIN TREES;
-    TREE tree1 = {nwk}
-END;
-"
-        ))
+        self.get_mut(&upper)?
+            .replace_child(&sibling, nephew, nephew_edge)?;
+        self.get_mut(&lower)?
+            .replace_child(&nephew, sibling, sibling_edge)?;
+        self.get_mut(&nephew)?.set_parent(upper, nephew_edge);
+        self.get_mut(&sibling)?.set_parent(lower, sibling_edge);
+
+        let upper_depth = self.get(&upper)?.get_depth();
+        self.reset_depth_impl(&nephew, upper_depth + 1)?;
+        self.reset_depth_impl(&sibling, upper_depth + 2)?;
+
+        self.reset_partitions();
+
+        Ok(())
     }
 
-    /// Recursive function that adds node representation to a printable tree builder
-    fn print_nodes(
-        &self,
-        root_idx: &NodeId,
-        output_tree: &mut TreeBuilder,
-        debug: bool,
+    /// Performs a subtree pruning and regrafting (SPR) move. The subtree rooted at
+    /// `prune_node` is detached from the tree and reattached to a new node inserted
+    /// on the branch defined by `regraft_edge`. If present, the length of the regraft
+    /// branch is split equally on either side of the new node, and the branch length
+    /// above `prune_node` is kept.
+    ///
+    /// The former parent of `prune_node` is removed if it is left with a single child
+    /// *(if it was the root, its remaining child becomes the new root)*.
+    /// ```
+    /// use phylotree::tree::Tree;
+    ///
+    /// let mut tree = Tree::from_newick("((A:1,B:1)E:1,(C:1,D:2)F:1)G;").unwrap();
+    /// let a = tree.get_by_name("A").unwrap().id;
+    /// let f = tree.get_by_name("F").unwrap().id;
+    /// let d = tree.get_by_name("D").unwrap().id;
+    ///
+    /// // Move A to the branch between F and D
+    /// tree.spr(&a, (&f, &d)).unwrap();
+    ///
+    /// assert_eq!(tree.to_newick().unwrap(), "(B:2,(C:1,(D:1,A:1):1)F:1)G;");
+    /// ```
+    pub fn spr(
+        &mut self,
+        prune_node: &NodeId,
+        regraft_edge: (&NodeId, &NodeId),
     ) -> Result<(), TreeError> {
-        let root = self.get(root_idx)?;
-        let label = if debug {
-            format!("{root:?}")
-        } else {
-            format!("{root}")
-        };
+        let (upper, lower) = self.orient_edge(regraft_edge)?;
+
+        let old_parent = self
+            .get(prune_node)?
+            .parent
+            .ok_or(NodeError::HasNoParent(*prune_node))?;
+
+        // The regraft branch must not be in the pruned subtree
+        let mut current = Some(lower);
+        while let Some(id) = current {
+            if id == *prune_node {
+                return Err(TreeError::RegraftInsideSubtree(*prune_node));
+            }
+            current = self.get(&id)?.parent;
+        }
 
-        if root.children.is_empty() {
-            output_tree.add_empty_child(label);
-        } else {
-            output_tree.begin_child(label);
-            for child_idx in root.children.iter() {
-                self.print_nodes(child_idx, output_tree, debug)?;
+        // Prune the subtree
+        let prune_edge = self.get(prune_node)?.parent_edge;
+        self.get_mut(&old_parent)?.remove_child(prune_node)?;
+
+        // Split the regraft branch with a new node
+        let half = self.get(&lower)?.parent_edge.map(|l| l / 2.0);
+        let new_node = self.split_edge(&upper, &lower, half)?;
+
+        // Regraft the subtree
+        self.get_mut(&new_node)?.add_child(*prune_node, prune_edge);
+        self.get_mut(prune_node)?.set_parent(new_node, prune_edge);
+
+        // Remove the former parent if it is now a degree 2 node
+        let old = self.get(&old_parent)?;
+        if old.children.len() == 1 {
+            if old.is_root() {
+                let child = old.children[0];
+                let child = self.get_mut(&child)?;
+                child.parent = None;
+                child.parent_edge = None;
+                self.get_mut(&old_parent)?.delete();
+            } else {
+                self.compress_node(&old_parent)?;
             }
-            output_tree.end_child();
         }
 
+        self.reset_depths()?;
+        self.reset_partitions();
+
         Ok(())
     }
 
-    /// Print a debug view of the tree to the console
-    pub fn print_debug(&self) -> Result<(), TreeError> {
-        let root = self.get_root()?;
-        let mut builder = TreeBuilder::new(format!("{:?}", root));
-        for child_idx in self.get(&root)?.children.iter() {
-            self.print_nodes(child_idx, &mut builder, true)?;
-        }
-        let tree = builder.build();
-        print_tree(&tree)?;
-        Ok(())
-    }
-
-    /// Print the tree to the console
-    pub fn print(&self) -> Result<(), TreeError> {
-        let root = self.get_root()?;
-        let mut builder = TreeBuilder::new(format!("{:?}", root));
-        for child_idx in self.get(&root)?.children.iter() {
-            self.print_nodes(child_idx, &mut builder, false)?;
+    /// Moves a leaf to a new node inserted on the branch defined by `target_edge`,
+    /// with a branch of length `edge_len` between the leaf and the new node. This is
+    /// an [SPR move](Tree::spr()) restricted to leaves, used to place pruned taxa back
+    /// into a tree. Returns [`TreeError::RegraftInsideSubtree`] if the target branch
+    /// is the branch above the leaf.
+    /// ```
+    /// use phylotree::tree::Tree;
+    ///
+    /// let mut tree = Tree::from_newick("((A:1,B:1)E:1,(C:1,D:2)F:1)G;").unwrap();
+    /// let a = tree.get_by_name("A").unwrap().id;
+    /// let f = tree.get_by_name("F").unwrap().id;
+    /// let d = tree.get_by_name("D").unwrap().id;
+    ///
+    /// tree.regraft_leaf(&a, (&f, &d), Some(0.5)).unwrap();
+    ///
+    /// assert_eq!(tree.to_newick().unwrap(), "(B:2,(C:1,(D:1,A:0.5):1)F:1)G;");
+    /// ```
+    pub fn regraft_leaf(
+        &mut self,
+        leaf: &NodeId,
+        target_edge: (&NodeId, &NodeId),
+        edge_len: Option<EdgeLength>,
+    ) -> Result<(), TreeError> {
+        if !self.get(leaf)?.is_tip() {
+            return Err(TreeError::GeneralError("Only leaves can be regrafted"));
         }
-        let tree = builder.build();
-        print_tree(&tree)?;
+
+        self.spr(leaf, target_edge)?;
+
+        let parent = self.get(leaf)?.parent.unwrap();
+        self.get_mut(leaf)?.parent_edge = edge_len;
+        self.get_mut(&parent)?
+            .replace_child(leaf, *leaf, edge_len)?;
+
         Ok(())
     }
 }
 
-#[derive(Debug, Clone, Copy, Default)]
-pub(crate) struct IdentityHasher(usize);
+/// Methods to read and write [`Tree`] objects to and from files or [`String`] objects.
+///   
+/// ----
+/// ----
+impl Tree {
+    // ########################
+    // # READ AND WRITE TREES #
+    // ########################
 
-impl core::hash::Hasher for IdentityHasher {
-    fn finish(&self) -> u64 {
-        self.0 as u64
-    }
+    /// Writes the newick representation of the subtree rooted at `root`
+    fn write_newick_impl<W: Write>(
+        &self,
+        writer: &mut W,
+        root: &Node,
+        format: NewickFormat,
+        precision: LengthPrecision,
+    ) -> Result<(), TreeError> {
+        if !root.children.is_empty() {
+            writer.write_all(b"(")?;
+            for (i, child_idx) in root.children.iter().enumerate() {
+                if i > 0 {
+                    writer.write_all(b",")?;
+                }
+                self.write_newick_impl(writer, self.get(child_idx)?, format, precision)?;
+            }
+            writer.write_all(b")")?;
+        }
+        writer.write_all(root.to_newick_with_precision(format, precision).as_bytes())?;
 
-    fn write(&mut self, _bytes: &[u8]) {
-        unimplemented!("IdentityHasher only supports usize keys")
+        Ok(())
     }
 
-    fn write_usize(&mut self, i: usize) {
-        self.0 = i;
+    /// Writes the tree in the newick format to any [`Write`] implementor
+    /// *(file, stdout, ...)* without building the whole newick string in memory.
+    /// # Example
+    /// ```
+    /// use phylotree::tree::Tree;
+    ///
+    /// let newick = "(A:0.1,B:0.2,(C:0.3,D:0.4)E:0.5)F:0.6;";
+    /// let tree = Tree::from_newick(newick).unwrap();
+    ///
+    /// let mut buffer = Vec::new();
+    /// tree.write_newick(&mut buffer).unwrap();
+    ///
+    /// assert_eq!(String::from_utf8(buffer).unwrap(), newick);
+    /// ```
+    pub fn write_newick<W: Write>(&self, writer: &mut W) -> Result<(), TreeError> {
+        self.write_formatted_newick(writer, NewickFormat::AllFields)
     }
-}
 
-type BuildIdentityHasher = core::hash::BuildHasherDefault<IdentityHasher>;
+    /// Writes the tree in the newick format to any [`Write`] implementor, with a
+    /// specified output format from [`NewickFormat`].
+    pub fn write_formatted_newick<W: Write>(
+        &self,
+        writer: &mut W,
+        format: NewickFormat,
+    ) -> Result<(), TreeError> {
+        let root = self.get_root()?;
+        self.write_newick_impl(writer, self.get(&root)?, format, LengthPrecision::Shortest)?;
+        writer.write_all(b";")?;
 
-impl Default for Tree {
-    fn default() -> Self {
-        Self::new()
+        Ok(())
     }
-}
-
-#[cfg(test)]
-// #[allow(clippy::excessive_precision)]
-mod tests {
 
-    use core::f64;
+    /// Writes the tree as a newick formatted string
+    /// # Example
+    /// ```
+    /// use phylotree::tree::Tree;
+    ///
+    /// let newick = "(A:0.1,B:0.2,(C:0.3,D:0.4)E:0.5)F:0.6;";
+    /// let tree = Tree::from_newick(newick).unwrap();
+    ///
+    /// assert_eq!(tree.to_newick().unwrap(), newick);
+    /// ```
+    pub fn to_newick(&self) -> Result<String, TreeError> {
+        self.to_formatted_newick(NewickFormat::AllFields)
+    }
 
-    use super::*;
+    /// Writes the tree as a newick formatted string with a specified
+    /// output format from [`NewickFormat`].
+    /// # Example
+    /// ```
+    /// use phylotree::tree::{Tree, NewickFormat};
+    ///
+    /// let newick = "(A:0.1,B:0.2,(C:0.3,D:0.4)E:0.5)F:0.6;";
+    /// let tree = Tree::from_newick(newick).unwrap();
+    ///
+    /// assert_eq!(tree.to_formatted_newick(NewickFormat::Topology).unwrap(), "(,,(,));");
+    /// assert_eq!(
+    ///     tree.to_formatted_newick(NewickFormat::OnlyNames).unwrap(),
+    ///     "(A,B,(C,D)E)F;"
+    /// );
+    /// assert_eq!(
+    ///     tree.to_formatted_newick(NewickFormat::InternalLengthsLeafNames).unwrap(),
+    ///     "(A,B,(C,D):0.5):0.6;"
+    /// );
+    /// ```
+    pub fn to_formatted_newick(&self, format: NewickFormat) -> Result<String, TreeError> {
+        let mut buffer = Vec::new();
+        self.write_formatted_newick(&mut buffer, format)?;
 
-    /// Generates example tree from the tree traversal wikipedia page
-    /// https://en.wikipedia.org/wiki/Tree_traversal#Depth-first_search
-    /// The difference is that I is the left child of G since this tree structure
-    /// cannot represent a right child only.
-    fn build_simple_tree() -> Result<Tree, TreeError> {
-        let mut tree = Tree::new();
-        tree.add(Node::new_named("F")); // 0
-        tree.add_child(Node::new_named("B"), 0, None)?; // 1
-        tree.add_child(Node::new_named("G"), 0, None)?; // 2
-        tree.add_child(Node::new_named("A"), 1, None)?; // 3
-        tree.add_child(Node::new_named("D"), 1, None)?; // 4
-        tree.add_child(Node::new_named("I"), 2, None)?; // 5
-        tree.add_child(Node::new_named("C"), 4, None)?; // 6
-        tree.add_child(Node::new_named("E"), 4, None)?; // 7
-        tree.add_child(Node::new_named("H"), 5, None)?; // 8
+        Ok(String::from_utf8(buffer).expect("Newick strings are written from valid UTF-8"))
+    }
 
-        Ok(tree)
+    /// Writes the tree as a newick formatted string with branch lengths rounded
+    /// to `precision` digits after the decimal point, avoiding floating-point
+    /// artifacts such as `0.30000000000000004`.
+    /// # Example
+    /// ```
+    /// use phylotree::tree::Tree;
+    ///
+    /// let mut tree = Tree::from_newick("(A:0.1,B:0.2)C;").unwrap();
+    /// tree.rescale(3.0);
+    ///
+    /// assert_eq!(tree.to_newick().unwrap(), "(A:0.30000000000000004,B:0.6000000000000001)C;");
+    /// assert_eq!(tree.to_newick_with_precision(2).unwrap(), "(A:0.30,B:0.60)C;");
+    /// ```
+    pub fn to_newick_with_precision(&self, precision: usize) -> Result<String, TreeError> {
+        self.to_newick_with_length_format(LengthPrecision::Decimals(precision))
     }
 
-    /// Generates example tree from the newick format wikipedia page
-    /// https://en.wikipedia.org/wiki/Newick_format#Examples
-    fn build_tree_with_lengths() -> Result<Tree, TreeError> {
-        let mut tree = Tree::new();
-        tree.add(Node::new_named("F")); // 0
-        tree.add_child(Node::new_named("A"), 0, Some(0.1))?; // 1
-        tree.add_child(Node::new_named("B"), 0, Some(0.2))?; // 2
-        tree.add_child(Node::new_named("E"), 0, Some(0.5))?; // 3
-        tree.add_child(Node::new_named("C"), 3, Some(0.3))?; // 4
-        tree.add_child(Node::new_named("D"), 3, Some(0.4))?; // 5
+    /// Writes the tree as a newick formatted string with branch lengths rounded
+    /// to `digits` significant digits. Digits before the decimal point are never
+    /// dropped, so large branch lengths may be written with more significant digits.
+    /// # Example
+    /// ```
+    /// use phylotree::tree::Tree;
+    ///
+    /// let tree = Tree::from_newick("(A:0.000123456,B:1.23456,C:123.456)D;").unwrap();
+    ///
+    /// assert_eq!(
+    ///     tree.to_newick_significant(3).unwrap(),
+    ///     "(A:0.000123,B:1.23,C:123)D;"
+    /// );
+    /// ```
+    pub fn to_newick_significant(&self, digits: usize) -> Result<String, TreeError> {
+        self.to_newick_with_length_format(LengthPrecision::Significant(digits))
+    }
 
-        Ok(tree)
+    fn to_newick_with_length_format(
+        &self,
+        precision: LengthPrecision,
+    ) -> Result<String, TreeError> {
+        let root = self.get_root()?;
+        let mut buffer = Vec::new();
+        self.write_newick_impl(
+            &mut buffer,
+            self.get(&root)?,
+            NewickFormat::AllFields,
+            precision,
+        )?;
+        buffer.push(b';');
+
+        Ok(String::from_utf8(buffer).expect("Newick strings are written from valid UTF-8"))
     }
 
-    fn build_tree_without_lengths() -> Result<Tree, TreeError> {
-        let mut tree = Tree::new();
-        tree.add(Node::new_named("F")); // 0
-        tree.add_child(Node::new_named("A"), 0, None)?; // 1
-        tree.add_child(Node::new_named("B"), 0, None)?; // 2
-        tree.add_child(Node::new_named("E"), 0, None)?; // 3
-        tree.add_child(Node::new_named("C"), 3, None)?; // 4
-        tree.add_child(Node::new_named("D"), 3, None)?; // 5
+    /// Writes the tree as a newick formatted string with the support values of internal
+    /// nodes as labels, rounded to `precision` decimal places. Internal nodes without
+    /// a support value are written without a label, and internal node names are not written.
+    /// # Example
+    /// ```
+    /// use phylotree::tree::Tree;
+    ///
+    /// let tree = Tree::from_newick_with_support("((A:1,B:1)0.954:1,(C:1,D:1)CD:1);").unwrap();
+    ///
+    /// assert_eq!(
+    ///     tree.to_newick_with_support(2).unwrap(),
+    ///     "((A:1,B:1)0.95:1,(C:1,D:1):1);"
+    /// );
+    /// ```
+    pub fn to_newick_with_support(&self, precision: usize) -> Result<String, TreeError> {
+        let mut tree = self.clone();
+        for node in tree.nodes.iter_mut().filter(|node| !node.is_tip()) {
+            node.name = node
+                .support
+                .take()
+                .map(|support| format!("{support:.precision$}"));
+        }
 
-        Ok(tree)
-    }
-    fn get_values(indices: &[usize], tree: &Tree) -> Vec<Option<String>> {
-        indices
-            .iter()
-            .map(|idx| tree.get(idx).unwrap().name.clone())
-            .collect()
+        tree.to_newick()
     }
 
-    #[test]
-    fn test_tips() {
-        let mut tree = Tree::new();
-        tree.add(Node::new_named("root"));
-        assert_eq!(tree.get_leaves(), vec![0]);
-
-        tree.add_child(Node::new_named("A"), 0, Some(0.1)).unwrap(); // 1
-        tree.add_child(Node::new_named("B"), 0, Some(0.2)).unwrap(); // 2
-        tree.add_child(Node::new_named("E"), 0, Some(0.5)).unwrap(); // 3
+    /// Writes the tree as a newick formatted string with the age calibrations of nodes
+    /// *(see [`Tree::set_calibration()`])* written as `[&calibration={min,max}]` comments,
+    /// or `[&calibration={min}]` if the maximum age is not bounded. Other comments of
+    /// calibrated nodes are not written.
+    /// # Example
+    /// ```
+    /// use phylotree::tree::Tree;
+    ///
+    /// let mut tree = Tree::from_newick("((A:1,B:1)C:2,D:3)E;").unwrap();
+    /// let root = tree.get_root().unwrap();
+    /// let c = tree.get_by_name("C").unwrap().id;
+    /// tree.set_calibration(&root, 50.0, Some(60.0)).unwrap();
+    /// tree.set_calibration(&c, 20.0, None).unwrap();
+    ///
+    /// assert_eq!(
+    ///     tree.to_newick_with_calibrations().unwrap(),
+    ///     "((A:1,B:1)C:2[&calibration={20}],D:3)E[&calibration={50,60}];"
+    /// );
+    /// ```
+    pub fn to_newick_with_calibrations(&self) -> Result<String, TreeError> {
+        let mut tree = self.clone();
+        for node in tree.nodes.iter_mut().filter(|node| !node.deleted) {
+            if let Some(calibration) = node.calibration {
+                node.comment = Some(format!("&calibration={calibration}"));
+                node.nhx = None;
+            }
+        }
 
-        assert_eq!(tree.get_leaves(), vec![1, 2, 3]);
+        tree.to_newick()
+    }
 
-        tree.add_child(Node::new_named("C"), 3, Some(0.3)).unwrap(); // 4
-        tree.add_child(Node::new_named("D"), 3, Some(0.4)).unwrap(); // 5
+    /// Writes the tree as a newick formatted string starting with the `[&R]` tag,
+    /// used by BEAST and FigTree to mark rooted trees. The branch length above the
+    /// root is kept if present.
+    /// # Example
+    /// ```
+    /// use phylotree::tree::Tree;
+    ///
+    /// let tree = Tree::from_newick("((A:1,B:1):1,C:2):0.5;").unwrap();
+    ///
+    /// assert_eq!(
+    ///     tree.to_newick_rooted_format().unwrap(),
+    ///     "[&R] ((A:1,B:1):1,C:2):0.5;"
+    /// );
+    /// ```
+    pub fn to_newick_rooted_format(&self) -> Result<String, TreeError> {
+        Ok(format!("[&R] {}", self.to_newick()?))
+    }
 
-        assert_eq!(tree.get_leaves(), vec![1, 2, 4, 5]);
+    /// Writes the tree as a newick formatted string starting with the `[&U]` tag,
+    /// used by BEAST and FigTree to mark unrooted trees. Since unrooted trees have
+    /// no branch above the root, the root branch length is not written.
+    /// # Example
+    /// ```
+    /// use phylotree::tree::Tree;
+    ///
+    /// let tree = Tree::from_newick("(A:1,B:1,C:2):0.5;").unwrap();
+    ///
+    /// assert_eq!(tree.to_newick_unrooted_format().unwrap(), "[&U] (A:1,B:1,C:2);");
+    /// ```
+    pub fn to_newick_unrooted_format(&self) -> Result<String, TreeError> {
+        let mut root = self.get(&self.get_root()?)?.clone();
+        root.parent_edge = None;
+
+        let mut buffer = b"[&U] ".to_vec();
+        self.write_newick_impl(
+            &mut buffer,
+            &root,
+            NewickFormat::AllFields,
+            LengthPrecision::Shortest,
+        )?;
+        buffer.push(b';');
+
+        Ok(String::from_utf8(buffer).expect("Newick strings are written from valid UTF-8"))
     }
 
-    #[test]
-    fn test_binary() {
-        let mut tree = Tree::new();
-        tree.add(Node::new_named("root"));
+    /// Read a newick formatted string and build a [`Tree`] struct from it.
+    /// A leading `[&R]` or `[&U]` rooting tag is ignored.
+    /// # Example
+    /// ```
+    /// use phylotree::tree::Tree;
+    ///
+    /// let newick = "(A:0.1,B:0.2,(C:0.3,D:0.4)E:0.5)F;";
+    /// let tree = Tree::from_newick(newick).unwrap();
+    ///
+    /// assert_eq!(tree.size(), 6);
+    /// assert_eq!(tree.n_leaves(), 4);
+    /// assert_eq!(tree.is_rooted().unwrap(), false);
+    /// ```
+    pub fn from_newick(newick: &str) -> Result<Self, NewickParseError> {
+        #[derive(Debug, PartialEq)]
+        enum Field {
+            Name,
+            Length,
+            Comment,
+        }
 
-        tree.add_child(Node::new_named("0L"), 0, None).unwrap(); //1
-        tree.add_child(Node::new_named("0R"), 0, None).unwrap(); //2
+        let mut tree = Tree::new();
 
-        assert!(tree.is_binary().unwrap());
+        let mut parsing = Field::Name;
+        let mut current_name: Option<String> = None;
+        let mut current_length: Option<String> = None;
+        let mut current_comment: Option<String> = None;
+        let mut current_index: Option<NodeId> = None;
+        let mut parent_stack: Vec<NodeId> = Vec::new();
 
-        tree.add_child(Node::new_named("1L"), 1, None).unwrap(); //3
-        tree.add_child(Node::new_named("1R"), 1, None).unwrap(); //4
+        // Offsets of opening brackets and of the start of the current branch length
+        let mut open_delimiters = Vec::new();
+        let mut length_pos = 0;
+        let mut within_quotes = false;
 
-        assert!(tree.is_binary().unwrap());
+        // Rooting tags are not attached to any node
+        let trimmed = newick.trim_start();
+        let stripped = ["[&R]", "[&U]", "[&r]", "[&u]"]
+            .iter()
+            .find_map(|tag| trimmed.strip_prefix(tag))
+            .unwrap_or(newick);
+        let input = newick;
+        let newick = stripped;
+        let skipped = input.chars().count() - newick.chars().count();
 
-        tree.add_child(Node::new_named("3L"), 3, None).unwrap(); //5
-        tree.add_child(Node::new_named("3R"), 3, None).unwrap(); //6
-        assert!(tree.is_binary().unwrap());
+        for (offset, c) in newick.chars().enumerate() {
+            let offset = offset + skipped;
 
-        tree.add_child(Node::new_named("3?"), 3, None).unwrap(); //7
-        assert!(!tree.is_binary().unwrap());
-    }
+            // Add character in quotes to name
+            if within_quotes && parsing == Field::Name && c != '"' {
+                if let Some(name) = current_name.as_mut() {
+                    name.push(c)
+                } else {
+                    current_name = Some(c.into())
+                }
+                continue;
+            }
 
-    #[test]
-    fn binary_from_newick() {
-        let test_cases = vec![
-            ("((A,B,C)D,E)F;", false),   // Rooted non binary
-            ("(A,B,(C,D)E)F;", true),    // Unrooted binary
-            ("((D,E)B,(F,G)C)A;", true), // rooted binary
-        ];
+            // Add current character to comment
+            if parsing == Field::Comment && c != ']' {
+                if let Some(comment) = current_comment.as_mut() {
+                    comment.push(c)
+                } else {
+                    current_comment = Some(c.into())
+                }
+                continue;
+            }
 
-        for (newick, is_binary) in test_cases {
-            assert_eq!(
-                Tree::from_newick(newick).unwrap().is_binary().unwrap(),
-                is_binary
-            )
-        }
-    }
+            // Skip unquoted whitespace
+            if c.is_whitespace() && !within_quotes {
+                continue;
+            }
 
-    #[test]
-    fn prune_tree() {
-        let mut tree = build_simple_tree().unwrap();
-        tree.prune(&4).unwrap(); // prune D subtree
+            match c {
+                '"' => {
+                    // Enter or close quoted section (name)
+                    // TODO: handle escaped quotes
+                    within_quotes = !within_quotes;
+                    if parsing == Field::Name {
+                        if let Some(name) = current_name.as_mut() {
+                            name.push(c)
+                        } else {
+                            current_name = Some(c.into())
+                        }
+                    }
+                }
+                '[' => {
+                    parsing = Field::Comment;
+                }
+                ']' => {
+                    parsing = Field::Name;
+                }
+                '(' => {
+                    // Start subtree
+                    match parent_stack.last() {
+                        None => parent_stack.push(tree.add(Node::new())),
+                        Some(parent) => {
+                            parent_stack.push(tree.add_child(Node::new(), *parent, None)?)
+                        }
+                    };
+                    open_delimiters.push(offset);
+                }
+                ':' => {
+                    // Start parsing length
+                    parsing = Field::Length;
+                    length_pos = offset + 1;
+                }
+                ',' => {
+                    // Add sibling
+                    let node = if let Some(index) = current_index {
+                        tree.get_mut(&index)?
+                    } else {
+                        if let Some(parent) = parent_stack.last() {
+                            current_index = Some(tree.add_child(Node::new(), *parent, None)?);
+                        } else {
+                            unreachable!("Sould not be possible to have named child with no parent")
+                        };
+                        tree.get_mut(current_index.as_ref().unwrap())?
+                    };
 
-        assert_eq!(tree.to_newick().unwrap(), "((A)B,((H)I)G)F;");
-    }
+                    if let Some(name) = current_name {
+                        node.set_name(name);
+                    }
 
-    #[test]
-    fn path_from_root() {
-        let tree = build_simple_tree().unwrap();
-        let values: Vec<_> = get_values(&(tree.get_path_from_root(&7).unwrap()), &tree)
-            .into_iter()
-            .flatten()
-            .collect();
-        assert_eq!(values, vec!["F", "B", "D", "E"])
-    }
+                    let edge = if let Some(length) = current_length {
+                        Some(parse_branch_length(&length, length_pos)?)
+                    } else {
+                        None
+                    };
+                    if let Some(parent) = node.parent {
+                        node.set_parent(parent, edge);
+                    }
 
-    #[test]
-    fn last_common_ancestor() {
-        let test_cases = vec![
-            ((3, 7), 1), // (A,E) -> B
-            ((6, 8), 0), // (C,H) -> F
-            ((3, 3), 3), // (A,A) -> A
-            ((8, 5), 5), // (H,I) -> I
-            ((4, 7), 4), // (D,E) -> D
-        ];
-        let tree = build_simple_tree().unwrap();
-        for ((source, target), ancestor) in test_cases {
-            println!(
-                "Testing: ({:?}, {:?}) -> {:?}",
-                tree.get(&source).unwrap().name,
-                tree.get(&target).unwrap().name,
-                tree.get(&ancestor).unwrap().name
-            );
-            assert_eq!(
-                ancestor,
-                tree.get_common_ancestor(&source, &target).unwrap()
-            );
-        }
-    }
+                    node.set_comment(current_comment);
 
-    #[test]
-    fn get_distances_lengths() {
-        let test_cases = vec![
-            ((1, 3), (0.6, 2)), // (A,E)
-            ((1, 4), (0.9, 3)), // (A,C)
-            ((4, 5), (0.7, 2)), // (C,D)
-            ((5, 2), (1.1, 3)), // (D,B)
-            ((2, 5), (1.1, 3)), // (B,D)
-            ((0, 2), (0.2, 1)), // (F,B)
-            ((1, 1), (0.0, 0)), // (A,A)
-        ];
-        let tree = build_tree_with_lengths().unwrap();
-        let tree2 = build_tree_without_lengths().unwrap();
+                    current_name = None;
+                    current_comment = None;
+                    current_length = None;
+                    current_index = None;
 
-        for ((idx_s, idx_t), (dist, branches)) in test_cases {
-            let (d_pred, b_pred) = tree.get_distance(&idx_s, &idx_t).unwrap();
-            let (d_pred2, b_pred2) = tree2.get_distance(&idx_s, &idx_t).unwrap();
-            assert_eq!(branches, b_pred);
-            assert_eq!(branches, b_pred2);
+                    parsing = Field::Name;
+                }
+                ')' => {
+                    // Close subtree
+                    open_delimiters.pop();
+                    let node = if let Some(index) = current_index {
+                        tree.get_mut(&index)?
+                    } else {
+                        if let Some(parent) = parent_stack.last() {
+                            current_index = Some(tree.add_child(Node::new(), *parent, None)?);
+                        } else {
+                            unreachable!("Sould not be possible to have named child with no parent")
+                        };
+                        tree.get_mut(current_index.as_ref().unwrap())?
+                    };
 
-            assert!(d_pred.is_some());
-            assert!((d_pred.unwrap() - dist).abs() < f64::EPSILON);
-            if idx_s != idx_t {
-                assert!(d_pred2.is_none(), "{d_pred2:?}");
-            }
-        }
-    }
+                    if let Some(name) = current_name {
+                        node.set_name(name);
+                    }
 
-    #[test]
-    fn get_correct_leaves() {
-        let tree = build_simple_tree().unwrap();
-        let values: Vec<_> = get_values(&(tree.get_leaves()), &tree)
-            .into_iter()
-            .flatten()
-            .collect();
-        assert_eq!(values, vec!["A", "C", "E", "H"])
-    }
+                    let edge = if let Some(length) = current_length {
+                        Some(parse_branch_length(&length, length_pos)?)
+                    } else {
+                        None
+                    };
+                    if let Some(parent) = node.parent {
+                        node.set_parent(parent, edge);
+                    }
 
-    #[test]
-    fn to_newick() {
-        let tree = build_tree_with_lengths().unwrap();
-        dbg!(&tree);
-        assert_eq!(
-            "(A:0.1,B:0.2,(C:0.3,D:0.4)E:0.5)F;",
-            tree.to_newick().unwrap()
-        );
-    }
+                    node.set_comment(current_comment);
 
-    #[test]
-    fn to_formatted_newick() {
-        let newick = "(A:0.1[Comment_1],B:0.2,(C:0.3,D:0.4)E:0.5[Comment_2])F;";
-        let tree = Tree::from_newick(newick).unwrap();
+                    current_name = None;
+                    current_comment = None;
+                    current_length = None;
 
-        let cases = [
-            (NewickFormat::AllFields, newick),
-            (
-                NewickFormat::NoComments,
-                "(A:0.1,B:0.2,(C:0.3,D:0.4)E:0.5)F;",
-            ),
-            (NewickFormat::Topology, "(,,(,));"),
-            (NewickFormat::OnlyNames, "(A,B,(C,D)E)F;"),
-            (NewickFormat::OnlyLengths, "(:0.1,:0.2,(:0.3,:0.4):0.5);"),
-            (
-                NewickFormat::LeafLengthsAllNames,
-                "(A:0.1,B:0.2,(C:0.3,D:0.4)E)F;",
-            ),
-            (
-                NewickFormat::LeafLengthsLeafNames,
-                "(A:0.1,B:0.2,(C:0.3,D:0.4));",
-            ),
-            (NewickFormat::InternalLengthsLeafNames, "(A,B,(C,D):0.5);"),
-            (
-                NewickFormat::AllLengthsLeafNames,
-                "(A:0.1,B:0.2,(C:0.3,D:0.4):0.5);",
-            ),
-        ];
+                    parsing = Field::Name;
 
-        for (format, expected) in cases {
-            assert_eq!(
-                expected,
-                tree.to_formatted_newick(format).unwrap(),
-                "Failed to write newick for format: {format:?}"
-            )
-        }
-    }
+                    if let Some(parent) = parent_stack.pop() {
+                        current_index = Some(parent)
+                    } else {
+                        return Err(NewickParseError::NoSubtreeParent(offset));
+                    }
+                }
+                ';' => {
+                    // Finish parsing the Tree
+                    if let Some(pos) = open_delimiters.last() {
+                        return Err(NewickParseError::UnclosedBracket(*pos));
+                    }
+                    let node = tree.get_mut(current_index.as_ref().unwrap())?;
+                    node.name = current_name;
+                    node.set_comment(current_comment);
+                    if let Some(length) = current_length {
+                        node.parent_edge = Some(parse_branch_length(&length, length_pos)?);
+                    }
 
-    // test cases from https://github.com/ila/Newick-validator
-    #[test]
-    fn read_newick() {
-        let newick_strings = vec![
-            "((D,E)B,(F,G)C)A;",
-            "(A:0.1,B:0.2,(C:0.3,D:0.4)E:0.5)F;",
-            "(A:0.1,B:0.2,(C:0.3,D:0.4):0.5);",
-            "(dog:20,(elephant:30,horse:60):20):50;",
-            "(A,B,(C,D));",
-            "(A,B,(C,D)E)F;",
-            "(((One:0.2,Two:0.3):0.3,(Three:0.5,Four:0.3):0.2):0.3,Five:0.7):0;",
-            "(:0.1,:0.2,(:0.3,:0.4):0.5);",
-            "(:0.1,:0.2,(:0.3,:0.4):0.5):0;",
-            "(A:0.1,B:0.2,(C:0.3,D:0.4):0.5);",
-            "(A:0.1,B:0.2,(C:0.3,D:0.4)E:0.5)F;",
-            "((B:0.2,(C:0.3,D:0.4)E:0.5)A:0.1)F;",
-            "(,,(,));",
-            "(\"hungarian dog\":20,(\"indian elephant\":30,\"swedish horse\":60):20):50;",
-            "(\"hungarian dog\":20[Comment_1],(\"indian elephant\":30,\"swedish horse\":60[Another interesting comment]):20):50;",
-        ];
-        for newick in newick_strings {
-            let tree = Tree::from_newick(newick).unwrap();
-            assert_eq!(newick, tree.to_newick().unwrap());
+                    // Finishing pass to make sure that branch lenghts are set in both children and parents
+                    let ids: Vec<_> = tree.nodes.iter().map(|node| node.id).collect();
+                    for node_id in ids {
+                        if let Some(edge) = tree.get(&node_id)?.parent_edge {
+                            if let Some(parent) = tree.get(&node_id)?.parent {
+                                tree.get_mut(&parent)?.set_child_edge(&node_id, Some(edge));
+                            }
+                        }
+                    }
+
+                    return Ok(tree);
+                }
+                _ => {
+                    // Parse characters in fields
+                    match parsing {
+                        Field::Name => {
+                            if let Some(name) = current_name.as_mut() {
+                                name.push(c)
+                            } else {
+                                current_name = Some(c.into())
+                            }
+                        }
+                        Field::Length => {
+                            if c.is_whitespace() {
+                                return Err(NewickParseError::WhiteSpaceInNumber {
+                                    pos: offset,
+                                    context: input
+                                        .chars()
+                                        .skip(offset.saturating_sub(10))
+                                        .take(20)
+                                        .collect(),
+                                });
+                            }
+                            if let Some(length) = current_length.as_mut() {
+                                length.push(c)
+                            } else {
+                                current_length = Some(c.into())
+                            }
+                        }
+                        Field::Comment => unimplemented!(),
+                    };
+                }
+            }
         }
-    }
 
-    #[ignore]
-    #[test]
-    fn to_nexus() {
-        let tree = crate::generate_tree(10, true, crate::distr::Distr::Uniform).unwrap();
-        println!("{}", tree.to_nexus().unwrap());
-        panic!()
+        Err(NewickParseError::NoClosingSemicolon(input.chars().count()))
     }
 
-    #[test]
-    fn read_newick_fails() {
-        let newick_strings = vec![
-            ("((D,E)B,(F,G,C)A;", NewickParseError::UnclosedBracket),
-            ("((D,E)B,(F,G)C)A", NewickParseError::NoClosingSemicolon),
-        ];
-        for (newick, _error) in newick_strings {
-            let tree = Tree::from_newick(newick);
-            assert!(tree.is_err());
-        }
+    /// Read a newick formatted string where internal node labels are branch support
+    /// values, and store them in the [`Node::support`] field of internal nodes
+    /// *(see [`Tree::annotate_with_bootstrap()`])*.
+    /// # Example
+    /// ```
+    /// use phylotree::tree::Tree;
+    ///
+    /// let tree = Tree::from_newick_with_support("((A,B)0.95,(C,D)0.8);").unwrap();
+    ///
+    /// let parent = tree.get(&tree.get_by_name("A").unwrap().parent.unwrap()).unwrap();
+    /// assert_eq!(parent.name, None);
+    /// assert_eq!(parent.support, Some(0.95));
+    /// ```
+    pub fn from_newick_with_support(newick: &str) -> Result<Self, NewickParseError> {
+        let mut tree = Self::from_newick(newick)?;
+        tree.annotate_with_bootstrap()?;
+
+        Ok(tree)
     }
 
-    #[test]
-    fn test_subtree_leaves() {
-        let test_cases = vec![
-            (
-                "((T0,T1)I1,(T2,T3)I2,((T4,T5)I4,(T6,T7)I4)I3)I0;",
-                7,
-                vec!["T4", "T5", "T6", "T7"],
-            ),
-            (
-                "((T0,T1)I1,(T2,T3)I2,((T4,T5)I4,(T6,T7)I4)I3)I0;",
-                1,
-                vec!["T0", "T1"],
-            ),
-            (
-                "((((((((T9,T8)I7,T7)I6,T6)I5,T5)I4,T4)I3,T3)I2,T2)I1,T0,T1)I0;",
-                5,
-                vec!["T9", "T8", "T7", "T6"],
-            ),
-        ];
+    /// Writes the tree to a newick file
+    pub fn to_file(&self, path: &Path) -> Result<(), TreeError> {
+        let mut writer = BufWriter::new(fs::File::create(path)?);
+        self.write_newick(&mut writer)?;
+        writer.flush()?;
 
-        for (newick, root, leaves) in test_cases {
-            let tree = Tree::from_newick(newick).unwrap();
-            let sub_leaves: Vec<String> =
-                get_values(&tree.get_subtree_leaves(&root).unwrap(), &tree)
-                    .iter()
-                    .map(|v| v.clone().unwrap())
-                    .collect();
+        Ok(())
+    }
 
-            assert_eq!(sub_leaves, leaves);
-        }
+    /// Creates a tree from a newick file
+    pub fn from_file(path: &Path) -> Result<Self, NewickParseError> {
+        let newick_string = fs::read_to_string(path)?;
+        Self::from_newick(&newick_string)
     }
 
-    #[test]
-    fn test_height() {
-        // heights computed with ete3
-        let test_cases = vec![
-            ("((A:0.1,B:0.2)G:0.1,(C:0.3,D:0.4)E:0.5)F;", 0.9),
-            ("((B:0.2,(C:0.3,D:0.4)E:0.5)A:0.1,G:0.1)F;", 1.0),
-            ("((A,B)G,(C,D)E)F;", 2.0),
-            (
-                "((((((((Tip9,Tip8),Tip7),Tip6),Tip5),Tip4),Tip3),Tip2),Tip1);",
-                8.0,
-            ),
-        ];
+    /// Reads several newick trees from a string. Trees are separated by semicolons,
+    /// blank lines and lines starting with `#` are skipped.
+    /// See [`TreeFileReader`] for more details.
+    /// # Example
+    /// ```
+    /// use phylotree::tree::Tree;
+    ///
+    /// let trees = Tree::many_from_str("# Trees\n((A,B),C);\n((A,C),B);\n").unwrap();
+    ///
+    /// assert_eq!(trees.len(), 2);
+    /// assert_eq!(trees[1].to_newick().unwrap(), "((A,C),B);");
+    /// ```
+    pub fn many_from_str(s: &str) -> Result<Vec<Self>, NewickParseError> {
+        TreeFileReader::new(s.as_bytes()).collect()
+    }
 
-        for (newick, height) in test_cases {
-            assert_eq!(Tree::from_newick(newick).unwrap().height().unwrap(), height)
-        }
+    /// Reads several newick trees from a file, *e.g.* a sample of trees from an MCMC
+    /// posterior. Use [`TreeFileReader`] to iterate over the trees without storing
+    /// all of them in memory.
+    pub fn many_from_file(path: &Path) -> Result<Vec<Self>, NewickParseError> {
+        TreeFileReader::open(path)?.collect()
     }
 
-    #[test]
-    fn test_diam() {
-        let test_cases = vec![
-            ("((D,E)B,(F,G)C)A;", 4.0),
-            ("(A:0.1,B:0.2,(C:0.3,D:0.4)E:0.5)F;", 1.1),
-            ("(A:0.1,B:0.2,(C:0.3,D:0.4):0.5);", 1.1),
-            ("(A,B,(C,D));", 3.0),
-            ("(A,B,(C,D)E)F;", 3.0),
-        ];
+    /// Outputs a Nexus formatted string of the tree
+    pub fn to_nexus(&self) -> Result<String, TreeError> {
+        let nwk = self.to_newick()?;
+        let n = self.n_leaves();
+        let labels = self
+            .nodes
+            .iter()
+            .filter_map(|node| {
+                if node.is_tip() {
+                    node.name.clone()
+                } else {
+                    None
+                }
+            })
+            .join(" ");
 
-        for (newick, diameter) in test_cases {
-            assert_eq!(
-                Tree::from_newick(newick).unwrap().diameter().unwrap(),
-                diameter
-            )
-        }
+        Ok(format!(
+            "#NEXUS
+BEGIN TAXA;
+    DIMENSIONS NTAX={n};
+    TAXLABELS {labels};
+END;
+BEGIN TREES;
+    TREE tree1 = {nwk}
+END;
+"
+        ))
     }
 
-    #[test]
-    fn test_cherries() {
-        // Number of cherries computed with gotree
-        let test_cases = vec![
-            ("(((((((((Tip9,Tip8),Tip7),Tip6),Tip5),Tip4),Tip3),Tip2),Tip1),Tip0);", 1),
-            ("(((i:0.1,j:0.1):0.1,(a:0.1,b:0.1):0.1):0.1,((c:0.1,d:0.1):0.1,((e:0.1,f:0.1):0.1,(g:0.1,h:0.1):0.1):0.1):0.1);", 5),
-            ("((a:0.2,b:0.2):0.2,((c:0.2,d:0.2):0.2,((e:0.2,f:0.2):0.2,((g:0.2,h:0.2):0.2,(i:0.2,j:0.2):0.2):0.2):0.2):0.2);", 5),
-            ("(((d:0.3,e:0.3):0.3,((f:0.3,g:0.3):0.3,(h:0.3,(i:0.3,j:0.3):0.3):0.3):0.3):0.3,(a:0.3,(b:0.3,c:0.3):0.3):0.3);", 4),
-        ];
-
-        for (newick, true_cherries) in test_cases {
-            let tree = Tree::from_newick(newick).unwrap();
-            let cherries = tree.cherries();
-            assert!(cherries.is_ok());
-            assert_eq!(cherries.unwrap(), true_cherries);
-        }
+    /// Outputs the tree as a directed graph in the [Graphviz DOT](https://graphviz.org/doc/info/lang.html)
+    /// language, with edges going from parents to children. Nodes are labelled with
+    /// their name, or their [`NodeId`] if they have none, and edges with their length.
+    /// Returns [`TreeError::IsEmpty`] if the tree has no nodes.
+    /// # Example
+    /// ```
+    /// use phylotree::tree::Tree;
+    ///
+    /// let tree = Tree::from_newick("(A:0.1,B:0.2);").unwrap();
+    /// let expected = [
+    ///     "digraph {",
+    ///     "    0 [label=\"0\"];",
+    ///     "    1 [label=\"A\"];",
+    ///     "    2 [label=\"B\"];",
+    ///     "    0 -> 1 [label=\"0.1\"];",
+    ///     "    0 -> 2 [label=\"0.2\"];",
+    ///     "}",
+    /// ];
+    ///
+    /// assert_eq!(tree.to_dot().unwrap(), expected.join("\n"));
+    /// ```
+    pub fn to_dot(&self) -> Result<String, TreeError> {
+        self.to_dot_impl("digraph", "->")
     }
 
-    #[test]
-    fn manual_colless() {
-        let newick = "(((((((((T8,T9)I8,T7)I7,T6)I6,T5)I5,T4)I4,T3)I3,T2)I2,T1)I1,T0)I0;";
-        let tree = Tree::from_newick(newick).unwrap();
+    /// Outputs the tree as an undirected graph in the Graphviz DOT language
+    /// *(see [`Tree::to_dot()`])*.
+    /// # Example
+    /// ```
+    /// use phylotree::tree::Tree;
+    ///
+    /// let tree = Tree::from_newick("(A,B)C;").unwrap();
+    /// let dot = tree.to_dot_undirected().unwrap();
+    ///
+    /// assert!(dot.starts_with("graph {"));
+    /// assert!(dot.contains("    0 -- 1;"));
+    /// ```
+    pub fn to_dot_undirected(&self) -> Result<String, TreeError> {
+        self.to_dot_impl("graph", "--")
+    }
 
-        let mut colless = 0;
+    // Writes the nodes then the edges of the tree in DOT format
+    fn to_dot_impl(&self, graph: &str, edge_op: &str) -> Result<String, TreeError> {
+        if self.size() == 0 {
+            return Err(TreeError::IsEmpty);
+        }
+        let quote = |label: &str| label.replace('\\', "\\\\").replace('"', "\\\"");
 
-        dbg!(&tree);
+        let preorder = self.preorder(&self.get_root()?)?;
+        let mut lines = vec![format!("{graph} {{")];
 
-        for node in tree.nodes.iter().filter(|node| !node.is_tip()) {
-            let left = tree.get_subtree_leaves(&node.children[0]).unwrap();
-            let right = tree.get_subtree_leaves(&node.children[1]).unwrap();
-            eprintln!("Node {:?}:", node.name.clone().unwrap());
-            eprintln!(
-                "\tLeft: {:?}",
-                get_values(&left, &tree)
-                    .into_iter()
-                    .flatten()
-                    .collect::<Vec<_>>()
-            );
-            eprintln!(
-                "\tRight: {:?}",
-                get_values(&right, &tree)
-                    .into_iter()
-                    .flatten()
-                    .collect::<Vec<_>>()
-            );
-            colless += left.len().abs_diff(right.len());
-            eprintln!("Colless: {colless}\n")
+        for id in preorder.iter() {
+            let label = match &self.get(id)?.name {
+                Some(name) => quote(name),
+                None => id.to_string(),
+            };
+            lines.push(format!("    {id} [label=\"{label}\"];"));
+        }
+        for id in preorder.iter().skip(1) {
+            let node = self.get(id)?;
+            let parent = node.parent.ok_or(NodeError::HasNoParent(*id))?;
+            match node.parent_edge {
+                Some(length) => {
+                    lines.push(format!("    {parent} {edge_op} {id} [label=\"{length}\"];"))
+                }
+                None => lines.push(format!("    {parent} {edge_op} {id};")),
+            }
         }
+        lines.push("}".to_string());
 
-        assert_eq!(colless, 36)
+        Ok(lines.join("\n"))
     }
 
-    #[test]
-    fn test_colless_rooted() {
-        // Colless index computed with gotree
-        let test_cases = vec![
-            ("(((((((((Tip9,Tip8),Tip7),Tip6),Tip5),Tip4),Tip3),Tip2),Tip1),Tip0);", 36),
-            ("(((i:0.1,j:0.1):0.1,(a:0.1,b:0.1):0.1):0.1,((c:0.1,d:0.1):0.1,((e:0.1,f:0.1):0.1,(g:0.1,h:0.1):0.1):0.1):0.1);", 4),
-            ("((a:0.2,b:0.2):0.2,((c:0.2,d:0.2):0.2,((e:0.2,f:0.2):0.2,((g:0.2,h:0.2):0.2,(i:0.2,j:0.2):0.2):0.2):0.2):0.2);", 12),
-            ("(((d:0.3,e:0.3):0.3,((f:0.3,g:0.3):0.3,(h:0.3,(i:0.3,j:0.3):0.3):0.3):0.3):0.3,(a:0.3,(b:0.3,c:0.3):0.3):0.3);", 10),
-        ];
-
-        for (newick, true_colless) in test_cases {
-            let tree = Tree::from_newick(newick).unwrap();
-            let colless = tree.colless();
-            assert!(colless.is_ok());
-            if tree.colless().unwrap() != true_colless {
-                panic!(
-                    "Computed colless {} not equal to true {true_colless}",
-                    tree.colless().unwrap()
-                )
-            };
+    /// Recursive function that adds node representation to a printable tree builder
+    fn print_nodes(
+        &self,
+        root_idx: &NodeId,
+        output_tree: &mut TreeBuilder,
+        debug: bool,
+    ) -> Result<(), TreeError> {
+        let root = self.get(root_idx)?;
+        let label = if debug {
+            format!("{root:?}")
+        } else {
+            format!("{root}")
+        };
+
+        if root.children.is_empty() {
+            output_tree.add_empty_child(label);
+        } else {
+            output_tree.begin_child(label);
+            for child_idx in root.children.iter() {
+                self.print_nodes(child_idx, output_tree, debug)?;
+            }
+            output_tree.end_child();
         }
-    }
 
-    #[test]
-    fn test_sackin_rooted() {
-        // Sackin index computed with gotree
-        let test_cases = vec![
-            ("(((((((((Tip9,Tip8),Tip7),Tip6),Tip5),Tip4),Tip3),Tip2),Tip1),Tip0);", 54),
-            ("(((i:0.1,j:0.1):0.1,(a:0.1,b:0.1):0.1):0.1,((c:0.1,d:0.1):0.1,((e:0.1,f:0.1):0.1,(g:0.1,h:0.1):0.1):0.1):0.1);", 34),
-            ("((a:0.2,b:0.2):0.2,((c:0.2,d:0.2):0.2,((e:0.2,f:0.2):0.2,((g:0.2,h:0.2):0.2,(i:0.2,j:0.2):0.2):0.2):0.2):0.2);", 38),
-            ("(((d:0.3,e:0.3):0.3,((f:0.3,g:0.3):0.3,(h:0.3,(i:0.3,j:0.3):0.3):0.3):0.3):0.3,(a:0.3,(b:0.3,c:0.3):0.3):0.3);", 36),
-        ];
+        Ok(())
+    }
 
-        for (newick, true_sackin) in test_cases {
-            let tree = Tree::from_newick(newick).unwrap();
-            let sackin = tree.sackin();
-            assert!(sackin.is_ok());
-            assert_eq!(tree.sackin().unwrap(), true_sackin);
+    /// Print a debug view of the tree to the console
+    pub fn print_debug(&self) -> Result<(), TreeError> {
+        let root = self.get_root()?;
+        let mut builder = TreeBuilder::new(format!("{:?}", root));
+        for child_idx in self.get(&root)?.children.iter() {
+            self.print_nodes(child_idx, &mut builder, true)?;
         }
+        let tree = builder.build();
+        print_tree(&tree)?;
+        Ok(())
     }
 
-    #[test]
-    fn test_sackin_unrooted() {
-        let test_cases = vec![
-            "(A:0.1,B:0.2,(C:0.3,D:0.4)E:0.5);",
-            "((B:0.2,(C:0.3,D:0.4)E:0.5)A:0.1);",
-            "(A,B,(C,D)E);",
-            "((((((((Tip9,Tip8),Tip7),Tip6),Tip5),Tip4),Tip3),Tip2),Tip0,Tip1);",
-        ];
-
-        for newick in test_cases {
-            let tree = Tree::from_newick(newick).unwrap();
-            assert!(tree.sackin().is_err())
+    /// Print the tree to the console
+    pub fn print(&self) -> Result<(), TreeError> {
+        let root = self.get_root()?;
+        let mut builder = TreeBuilder::new(format!("{:?}", root));
+        for child_idx in self.get(&root)?.children.iter() {
+            self.print_nodes(child_idx, &mut builder, false)?;
         }
+        let tree = builder.build();
+        print_tree(&tree)?;
+        Ok(())
     }
 
-    #[test]
-    fn test_rescale() {
-        let test_cases = [
-            ("((D:0.05307533041908017723553570021977,(C:0.08550401213833067060043902074540,(B:0.27463239708134284944307523801399,A:0.37113575171985613287972682883264)1:0.18134330279626256765546088445262)1:0.08033066840794983454188127325324)1:0.13864016688124142229199264875206,E:0.05060148260657528623829293223935);",
-            "((D:0.04212872094323715649322181775460,(C:0.06786909546224775824363462106703,(B:0.21799038323938338401752901063446,A:0.29459024358034957558061250892933)1:0.14394185279875840177687962295749)1:0.06376273658252405718283029045779)1:0.11004609591585229333432494058798,E:0.04016509597234880352134567260691);",
-            0.6525060248498331),
-            ("(E:0.01699652764738122934229380689430,(D:0.00408169520164380558724381842239,(C:0.19713461567160570075962766622979,(B:0.12068059163592816107613003850929,A:0.45190753170439451613660253315174)1:0.03279750996120785189180679708443)1:0.21625179801434316062547225101298)1:0.03998705111996220251668887613050);",
-            "(E:0.01986870266959113798255209815125,(D:0.00477144449924469995355513773916,(C:0.23044760352958004734347241537762,(B:0.14107392068250154681940955470054,A:0.52827357257097584675165080625447)1:0.03833982959587604877338407050047)1:0.25279532182407132845369801543711)1:0.04674430247278672095889717752470);",
-            0.8860217291333011),
-            ("((C:0.20738366520293352590620372666308,(B:0.19695170474498663315543467433599,A:0.02188551422116874478618342436675)1:0.05940680521299050026451382677806)1:0.13029006694844610936279138968530,(E:0.17189347707484656235799036494427,D:0.05867747522240193691622778260353)1:0.08673941227771603257323818070290);",
-            "((C:0.18371634870356487456710681271943,(B:0.17447491841406459478491797199240,A:0.01938786624432843955223582099734)1:0.05262710219338979922287791168856)1:0.11542092936147484161235610145013,(E:0.15227641937588842768747099398752,D:0.05198100577716616849111019860175)1:0.07684042085359836515845444182560);",
-            0.571639790198416),
-        ];
-
-        for (orig, rescaled, scale) in test_cases {
-            let mut tree = Tree::from_newick(orig).unwrap();
-            let rescaled = Tree::from_newick(rescaled).unwrap();
+    /// Draws the tree as ASCII art, with one leaf per line and leaf names written
+    /// on the right. If all the branches have lengths, the horizontal lines are
+    /// proportional to the branch lengths, otherwise all branches have the same width.
+    /// This is meant for quick visualisation of small trees *(up to ~50 leaves)*.
+    /// Returns [`TreeError::IsEmpty`] if the tree has no nodes.
+    /// # Example
+    /// ```
+    /// use phylotree::tree::Tree;
+    ///
+    /// let tree = Tree::from_newick("((A,B),C);").unwrap();
+    /// let expected = [
+    ///     "      +----- A",
+    ///     "+-----|",
+    ///     "|     +----- B",
+    ///     "|",
+    ///     "+----- C",
+    /// ];
+    ///
+    /// assert_eq!(tree.to_ascii().unwrap(), expected.join("\n"));
+    /// ```
+    pub fn to_ascii(&self) -> Result<String, TreeError> {
+        // Width of branches when the tree has no branch lengths
+        const EDGE_WIDTH: usize = 6;
+        // Width of the longest root to tip path when drawing branch lengths
+        const MAX_WIDTH: f64 = 40.0;
 
-            let diam = tree.diameter().unwrap();
+        if self.nodes.iter().all(|node| node.deleted) {
+            return Err(TreeError::IsEmpty);
+        }
+        let root = self.get_root()?;
+        let preorder = self.preorder(&root)?;
+
+        // Distances from the root are only used if all branches have lengths
+        let mut distances = HashMap::from([(root, 0.0)]);
+        let mut has_lengths = true;
+        for id in preorder.iter().skip(1) {
+            let node = &self.nodes[*id];
+            let Some(length) = node.parent_edge else {
+                has_lengths = false;
+                break;
+            };
+            distances.insert(*id, distances[&node.parent.unwrap()] + length);
+        }
+        let max_distance = distances.values().copied().fold(0.0, f64::max);
+        let scale = (has_lengths && max_distance > 0.0).then(|| MAX_WIDTH / max_distance);
+
+        // Column of each node
+        let mut columns = HashMap::from([(root, 0)]);
+        for id in preorder.iter().skip(1) {
+            let node = &self.nodes[*id];
+            let width = match (scale, node.parent_edge) {
+                (Some(scale), Some(length)) => ((length * scale).round() as usize).max(1) + 1,
+                _ => EDGE_WIDTH,
+            };
+            columns.insert(*id, columns[&node.parent.unwrap()] + width);
+        }
 
-            tree.rescale(scale / diam);
+        // Row of each node, leaves are separated by an empty row
+        let mut rows = HashMap::new();
+        let mut leaf_rows = HashMap::new();
+        let mut n_leaves = 0;
+        for id in self.postorder(&root)? {
+            let node = &self.nodes[id];
+            let row = if node.is_tip() {
+                n_leaves += 1;
+                leaf_rows.insert(2 * (n_leaves - 1), id);
+                2 * (n_leaves - 1)
+            } else {
+                (rows[&node.children[0]] + rows[node.children.last().unwrap()]) / 2
+            };
+            rows.insert(id, row);
+        }
 
-            println!("Dealing with tree: {} and scale {}", orig, scale);
-            for (n1, n2) in zip(tree.nodes, rescaled.nodes) {
-                assert_eq!(n1, n2)
+        let width = columns.values().max().unwrap() + 1;
+        let mut grid = vec![vec![' '; width]; 2 * n_leaves - 1];
+        for id in preorder.iter() {
+            let node = &self.nodes[*id];
+            let Some(last) = node.children.last() else {
+                continue;
+            };
+            let column = columns[id];
+            for line in grid[rows[&node.children[0]]..=rows[last]].iter_mut() {
+                line[column] = '|';
+            }
+            for child in node.children.iter() {
+                let row = rows[child];
+                grid[row][column] = '+';
+                for cell in grid[row][(column + 1)..columns[child]].iter_mut() {
+                    *cell = '-';
+                }
             }
         }
-    }
 
-    #[test]
-    fn test_unique_tip_names() {
-        let test_cases = vec![
-            ("(((((((((Tip9,Tip8),Tip7),Tip6),Tip5),Tip4),Tip3),Tip2),Tip1),Tip0);",true),
-            ("(((i:0.1,j:0.1):0.1,(a:0.1,b:0.1):0.1):0.1,((c:0.1,d:0.1):0.1,((e:0.1,f:0.1):0.1,(g:0.1,h:0.1):0.1):0.1):0.1);", true),
-            ("(((((((((Tip8,Tip8),Tip7),Tip6),Tip5),Tip4),Tip3),Tip2),Tip1),Tip0);",false),
-        ];
+        let lines: Vec<String> = grid
+            .iter()
+            .enumerate()
+            .map(|(row, cells)| {
+                let mut line: String = cells.iter().collect::<String>().trim_end().into();
+                if let Some(leaf) = leaf_rows.get(&row) {
+                    if !line.is_empty() {
+                        line.push(' ');
+                    }
+                    line += self.nodes[*leaf].name.as_deref().unwrap_or_default();
+                }
+                line
+            })
+            .collect();
 
-        for (newick, is_unique) in test_cases {
-            assert_eq!(
-                Tree::from_newick(newick)
-                    .unwrap()
-                    .has_unique_tip_names()
-                    .unwrap(),
-                is_unique,
-                "Failed on: {newick}"
-            )
-        }
+        Ok(lines.join("\n"))
+    }
+}
 
-        assert!(Tree::from_newick("(((((((((,),),),),),),),),);")
-            .unwrap()
-            .has_unique_tip_names()
-            .is_err())
+// Parse a newick branch length starting at offset pos, rejecting negative values
+fn parse_branch_length(raw: &str, pos: usize) -> Result<EdgeLength, NewickParseError> {
+    let length: EdgeLength = raw.parse().map_err(|source| NewickParseError::FloatError {
+        source,
+        pos,
+        raw: raw.to_string(),
+    })?;
+    if length < 0.0 {
+        return Err(NewickParseError::InvalidBranchLength { pos, length });
     }
 
-    #[test]
-    fn test_descendants() {
-        let tree = build_simple_tree().unwrap();
-        eprintln!("{}", tree.to_newick().unwrap());
-        dbg!(&tree);
-        let descendants_b: Vec<_> = get_values(&tree.get_descendants(&1).unwrap(), &tree)
-            .into_iter()
-            .flatten()
-            .sorted()
-            .collect();
-        let descendants_g: Vec<_> = get_values(&tree.get_descendants(&2).unwrap(), &tree)
-            .into_iter()
-            .flatten()
-            .sorted()
-            .collect();
+    Ok(length)
+}
 
-        assert_eq!(descendants_b, vec!["A", "C", "D", "E"]);
-        assert_eq!(descendants_g, vec!["H", "I"]);
-    }
+// Product of all the odd numbers up to n, None on overflow
+fn double_factorial(n: usize) -> Option<u128> {
+    (1..=n as u128)
+        .step_by(2)
+        .try_fold(1u128, |product, k| product.checked_mul(k))
+}
 
-    #[test]
-    fn test_compress() {
-        let mut tree = Tree::new();
-        tree.add(Node::new_named("root"));
-        tree.add_child(Node::new_named("tip_A"), 0, Some(1.0))
-            .unwrap();
-        tree.add_child(Node::new_named("in_B"), 0, Some(1.0))
-            .unwrap();
-        tree.add_child(Node::new_named("in_C"), 2, Some(1.0))
-            .unwrap();
-        tree.add_child(Node::new_named("tip_D"), 3, Some(1.0))
-            .unwrap();
+#[derive(Debug, Clone, Copy, Default)]
+pub(crate) struct IdentityHasher(usize);
 
-        tree.compress().unwrap();
+impl core::hash::Hasher for IdentityHasher {
+    fn finish(&self) -> u64 {
+        self.0 as u64
+    }
 
-        assert_eq!(tree.to_newick().unwrap(), "(tip_A:1,tip_D:3)root;");
+    fn write(&mut self, _bytes: &[u8]) {
+        unimplemented!("IdentityHasher only supports usize keys")
     }
 
-    #[test]
-    fn test_get_partitions() {
-        let test_cases = vec![
-            (
-                "(((((((((Tip9,Tip8),Tip7),Tip6),Tip5),Tip4),Tip3),Tip2),Tip1),Tip0);",
-                "(Tip0,((Tip2,(Tip3,(Tip4,(Tip5,(Tip6,((Tip8,Tip9),Tip7)))))),Tip1));",
-            ),
-            (
-                "(((i:0.1,j:0.1):0.1,(a:0.1,b:0.1):0.1):0.1,((c:0.1,d:0.1):0.1,((e:0.1,f:0.1):0.1,(g:0.1,h:0.1):0.1):0.1):0.1);",
-                "(((c:0.1,d:0.1):0.1,((g:0.1,h:0.1):0.1,(f:0.1,e:0.1):0.1):0.1):0.1,((i:0.1,j:0.1):0.1,(a:0.1,b:0.1):0.1):0.1);",
-            ),
-            (
-                "((a:0.2,b:0.2):0.2,((c:0.2,d:0.2):0.2,((e:0.2,f:0.2):0.2,((g:0.2,h:0.2):0.2,(i:0.2,j:0.2):0.2):0.2):0.2):0.2);",
-                "((((e:0.2,f:0.2):0.2,((i:0.2,j:0.2):0.2,(g:0.2,h:0.2):0.2):0.2):0.2,(d:0.2,c:0.2):0.2):0.2,(b:0.2,a:0.2):0.2);",
-            ),
-            (
-                "(((d:0.3,e:0.3):0.3,((f:0.3,g:0.3):0.3,(h:0.3,(i:0.3,j:0.3):0.3):0.3):0.3):0.3,(a:0.3,(b:0.3,c:0.3):0.3):0.3);",
-                "((((g:0.3,f:0.3):0.3,((i:0.3,j:0.3):0.3,h:0.3):0.3):0.3,(d:0.3,e:0.3):0.3):0.3,((b:0.3,c:0.3):0.3,a:0.3):0.3);",
-            ),
-        ];
+    fn write_usize(&mut self, i: usize) {
+        self.0 = i;
+    }
+}
 
-        for (newick, rot_newick) in test_cases {
-            let tree = Tree::from_newick(newick).unwrap();
-            let rota = Tree::from_newick(rot_newick).unwrap();
+type BuildIdentityHasher = core::hash::BuildHasherDefault<IdentityHasher>;
 
-            let ps_orig: HashSet<_> = HashSet::from_iter(tree.get_partitions().unwrap());
-            let ps_rota: HashSet<_> = HashSet::from_iter(rota.get_partitions().unwrap());
+// Rooted binary forest used to build agreement forests of two trees. The first
+// nodes are labelled leaves, internal nodes are added when joining subtrees.
+struct AgreementForest {
+    parent: Vec<Option<usize>>,
+    children: Vec<Vec<usize>>,
+    removed: Vec<bool>,
+}
 
-            assert_eq!(ps_orig, ps_rota);
+impl AgreementForest {
+    fn with_leaves(n_leaves: usize) -> Self {
+        Self {
+            parent: vec![None; n_leaves],
+            children: vec![vec![]; n_leaves],
+            removed: vec![false; n_leaves],
         }
     }
 
-    #[test]
-    fn self_rf() {
-        let test_cases = vec![
-            (
-                "(((((((((Tip9,Tip8),Tip7),Tip6),Tip5),Tip4),Tip3),Tip2),Tip1),Tip0);",
-                "(Tip0,((Tip2,(Tip3,(Tip4,(Tip5,(Tip6,((Tip8,Tip9),Tip7)))))),Tip1));",
-            ),
-            (
-                "(((i:0.1,j:0.1):0.1,(a:0.1,b:0.1):0.1):0.1,((c:0.1,d:0.1):0.1,((e:0.1,f:0.1):0.1,(g:0.1,h:0.1):0.1):0.1):0.1);",
-                "(((c:0.1,d:0.1):0.1,((g:0.1,h:0.1):0.1,(f:0.1,e:0.1):0.1):0.1):0.1,((i:0.1,j:0.1):0.1,(a:0.1,b:0.1):0.1):0.1);",
-            ),
-            (
-                "((a:0.2,b:0.2):0.2,((c:0.2,d:0.2):0.2,((e:0.2,f:0.2):0.2,((g:0.2,h:0.2):0.2,(i:0.2,j:0.2):0.2):0.2):0.2):0.2);",
-                "((((e:0.2,f:0.2):0.2,((i:0.2,j:0.2):0.2,(g:0.2,h:0.2):0.2):0.2):0.2,(d:0.2,c:0.2):0.2):0.2,(b:0.2,a:0.2):0.2);",
-            ),
-            (
-                "(((d:0.3,e:0.3):0.3,((f:0.3,g:0.3):0.3,(h:0.3,(i:0.3,j:0.3):0.3):0.3):0.3):0.3,(a:0.3,(b:0.3,c:0.3):0.3):0.3);",
-                "((((g:0.3,f:0.3):0.3,((i:0.3,j:0.3):0.3,h:0.3):0.3):0.3,(d:0.3,e:0.3):0.3):0.3,((b:0.3,c:0.3):0.3,a:0.3):0.3);",
-            ),
-        ];
+    // Add a new node with the two given nodes as children
+    fn join(&mut self, left: usize, right: usize) -> usize {
+        let id = self.parent.len();
+        self.parent.push(None);
+        self.children.push(vec![left, right]);
+        self.removed.push(false);
+        self.parent[left] = Some(id);
+        self.parent[right] = Some(id);
 
-        for (newick, rot_newick) in test_cases {
-            let tree = Tree::from_newick(newick).unwrap();
-            let rota = Tree::from_newick(rot_newick).unwrap();
+        id
+    }
 
-            tree.init_leaf_index().unwrap();
-            rota.init_leaf_index().unwrap();
+    fn is_leaf(&self, node: usize) -> bool {
+        self.children[node].is_empty()
+    }
 
-            assert_eq!(
-                tree.robinson_foulds(&rota).unwrap(),
-                0,
-                "Ref{:#?}\nRot:{:#?}",
-                tree.leaf_index,
-                rota.leaf_index
-            );
+    fn sibling(&self, node: usize) -> Option<usize> {
+        let parent = self.parent[node]?;
+        self.children[parent]
+            .iter()
+            .copied()
+            .find(|&child| child != node)
+    }
+
+    fn root_of(&self, mut node: usize) -> usize {
+        while let Some(parent) = self.parent[node] {
+            node = parent;
         }
+        node
     }
 
-    #[test]
-    // Robinson foulds distances according to
-    // https://evolution.genetics.washington.edu/phylip/doc/treedist.html
-    fn robinson_foulds_treedist() {
-        let trees = [
-            "(A:0.1,(B:0.1,(H:0.1,(D:0.1,(J:0.1,(((G:0.1,E:0.1):0.1,(F:0.1,I:0.1):0.1):0.1,C:0.1):0.1):0.1):0.1):0.1):0.1);",
-            "(A:0.1,(B:0.1,(D:0.1,((J:0.1,H:0.1):0.1,(((G:0.1,E:0.1):0.1,(F:0.1,I:0.1):0.1):0.1,C:0.1):0.1):0.1):0.1):0.1);",
-            "(A:0.1,(B:0.1,(D:0.1,(H:0.1,(J:0.1,(((G:0.1,E:0.1):0.1,(F:0.1,I:0.1):0.1):0.1,C:0.1):0.1):0.1):0.1):0.1):0.1);",
-            "(A:0.1,(B:0.1,(E:0.1,(G:0.1,((F:0.1,I:0.1):0.1,((J:0.1,(H:0.1,D:0.1):0.1):0.1,C:0.1):0.1):0.1):0.1):0.1):0.1);",
-            "(A:0.1,(B:0.1,(E:0.1,(G:0.1,((F:0.1,I:0.1):0.1,(((J:0.1,H:0.1):0.1,D:0.1):0.1,C:0.1):0.1):0.1):0.1):0.1):0.1);",
-            "(A:0.1,(B:0.1,(E:0.1,((F:0.1,I:0.1):0.1,(G:0.1,((J:0.1,(H:0.1,D:0.1):0.1):0.1,C:0.1):0.1):0.1):0.1):0.1):0.1);",
-            "(A:0.1,(B:0.1,(E:0.1,((F:0.1,I:0.1):0.1,(G:0.1,(((J:0.1,H:0.1):0.1,D:0.1):0.1,C:0.1):0.1):0.1):0.1):0.1):0.1);",
-            "(A:0.1,(B:0.1,(E:0.1,((G:0.1,(F:0.1,I:0.1):0.1):0.1,((J:0.1,(H:0.1,D:0.1):0.1):0.1,C:0.1):0.1):0.1):0.1):0.1);",
-            "(A:0.1,(B:0.1,(E:0.1,((G:0.1,(F:0.1,I:0.1):0.1):0.1,(((J:0.1,H:0.1):0.1,D:0.1):0.1,C:0.1):0.1):0.1):0.1):0.1);",
-            "(A:0.1,(B:0.1,(E:0.1,(G:0.1,((F:0.1,I:0.1):0.1,((J:0.1,(H:0.1,D:0.1):0.1):0.1,C:0.1):0.1):0.1):0.1):0.1):0.1);",
-            "(A:0.1,(B:0.1,(D:0.1,(H:0.1,(J:0.1,(((G:0.1,E:0.1):0.1,(F:0.1,I:0.1):0.1):0.1,C:0.1):0.1):0.1):0.1):0.1):0.1);",
-            "(A:0.1,(B:0.1,(E:0.1,((G:0.1,(F:0.1,I:0.1):0.1):0.1,((J:0.1,(H:0.1,D:0.1):0.1):0.1,C:0.1):0.1):0.1):0.1):0.1);",
+    fn is_ancestor(&self, ancestor: usize, mut node: usize) -> bool {
+        loop {
+            if node == ancestor {
+                return true;
+            }
+            match self.parent[node] {
+                Some(parent) => node = parent,
+                None => return false,
+            }
+        }
+    }
+
+    // Detach the subtree rooted at the node and suppress its former parent
+    fn cut(&mut self, node: usize) {
+        let Some(parent) = self.parent[node].take() else {
+            return;
+        };
+        self.children[parent].retain(|&child| child != node);
+
+        if let [child] = self.children[parent][..] {
+            let grandparent = self.parent[parent].take();
+            self.parent[child] = grandparent;
+            if let Some(grandparent) = grandparent {
+                for sibling in self.children[grandparent].iter_mut() {
+                    if *sibling == parent {
+                        *sibling = child;
+                    }
+                }
+            }
+            self.children[parent].clear();
+            self.removed[parent] = true;
+        }
+    }
+
+    // Replace two sibling leaves by their parent, which becomes a leaf
+    fn merge(&mut self, left: usize, right: usize) -> usize {
+        let parent = self.parent[left].expect("Merged leaves have a parent");
+        for leaf in [left, right] {
+            self.parent[leaf] = None;
+            self.removed[leaf] = true;
+        }
+        self.children[parent].clear();
+
+        parent
+    }
+
+    fn n_components(&self) -> usize {
+        (0..self.parent.len())
+            .filter(|&node| !self.removed[node] && self.parent[node].is_none())
+            .count()
+    }
+}
+
+// Iterator over mutable references to the nodes of a tree, in a precomputed order
+struct NodeIterMut<'a> {
+    nodes: *mut Node,
+    len: usize,
+    ids: std::vec::IntoIter<NodeId>,
+    _marker: std::marker::PhantomData<&'a mut Node>,
+}
+
+impl<'a> Iterator for NodeIterMut<'a> {
+    type Item = &'a mut Node;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let id = self.ids.next()?;
+        assert!(id < self.len);
+        // SAFETY: the ids come from a traversal of the tree so they are unique and
+        // in bounds, each node is therefore mutably borrowed at most once. The node
+        // vector is exclusively borrowed for 'a so it cannot be reallocated.
+        Some(unsafe { &mut *self.nodes.add(id) })
+    }
+}
+
+impl Default for Tree {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+// #[allow(clippy::excessive_precision)]
+mod tests {
+
+    use core::f64;
+
+    use super::*;
+
+    /// Generates example tree from the tree traversal wikipedia page
+    /// https://en.wikipedia.org/wiki/Tree_traversal#Depth-first_search
+    /// The difference is that I is the left child of G since this tree structure
+    /// cannot represent a right child only.
+    fn build_simple_tree() -> Result<Tree, TreeError> {
+        let mut tree = Tree::new();
+        tree.add(Node::new_named("F")); // 0
+        tree.add_child(Node::new_named("B"), 0, None)?; // 1
+        tree.add_child(Node::new_named("G"), 0, None)?; // 2
+        tree.add_child(Node::new_named("A"), 1, None)?; // 3
+        tree.add_child(Node::new_named("D"), 1, None)?; // 4
+        tree.add_child(Node::new_named("I"), 2, None)?; // 5
+        tree.add_child(Node::new_named("C"), 4, None)?; // 6
+        tree.add_child(Node::new_named("E"), 4, None)?; // 7
+        tree.add_child(Node::new_named("H"), 5, None)?; // 8
+
+        Ok(tree)
+    }
+
+    /// Generates example tree from the newick format wikipedia page
+    /// https://en.wikipedia.org/wiki/Newick_format#Examples
+    fn build_tree_with_lengths() -> Result<Tree, TreeError> {
+        let mut tree = Tree::new();
+        tree.add(Node::new_named("F")); // 0
+        tree.add_child(Node::new_named("A"), 0, Some(0.1))?; // 1
+        tree.add_child(Node::new_named("B"), 0, Some(0.2))?; // 2
+        tree.add_child(Node::new_named("E"), 0, Some(0.5))?; // 3
+        tree.add_child(Node::new_named("C"), 3, Some(0.3))?; // 4
+        tree.add_child(Node::new_named("D"), 3, Some(0.4))?; // 5
+
+        Ok(tree)
+    }
+
+    fn build_tree_without_lengths() -> Result<Tree, TreeError> {
+        let mut tree = Tree::new();
+        tree.add(Node::new_named("F")); // 0
+        tree.add_child(Node::new_named("A"), 0, None)?; // 1
+        tree.add_child(Node::new_named("B"), 0, None)?; // 2
+        tree.add_child(Node::new_named("E"), 0, None)?; // 3
+        tree.add_child(Node::new_named("C"), 3, None)?; // 4
+        tree.add_child(Node::new_named("D"), 3, None)?; // 5
+
+        Ok(tree)
+    }
+    fn get_values(indices: &[usize], tree: &Tree) -> Vec<Option<String>> {
+        indices
+            .iter()
+            .map(|idx| tree.get(idx).unwrap().name.clone())
+            .collect()
+    }
+
+    #[test]
+    fn test_tips() {
+        let mut tree = Tree::new();
+        tree.add(Node::new_named("root"));
+        assert_eq!(tree.get_leaves(), vec![0]);
+
+        tree.add_child(Node::new_named("A"), 0, Some(0.1)).unwrap(); // 1
+        tree.add_child(Node::new_named("B"), 0, Some(0.2)).unwrap(); // 2
+        tree.add_child(Node::new_named("E"), 0, Some(0.5)).unwrap(); // 3
+
+        assert_eq!(tree.get_leaves(), vec![1, 2, 3]);
+
+        tree.add_child(Node::new_named("C"), 3, Some(0.3)).unwrap(); // 4
+        tree.add_child(Node::new_named("D"), 3, Some(0.4)).unwrap(); // 5
+
+        assert_eq!(tree.get_leaves(), vec![1, 2, 4, 5]);
+    }
+
+    #[test]
+    fn test_binary() {
+        let mut tree = Tree::new();
+        tree.add(Node::new_named("root"));
+
+        tree.add_child(Node::new_named("0L"), 0, None).unwrap(); //1
+        tree.add_child(Node::new_named("0R"), 0, None).unwrap(); //2
+
+        assert!(tree.is_binary().unwrap());
+
+        tree.add_child(Node::new_named("1L"), 1, None).unwrap(); //3
+        tree.add_child(Node::new_named("1R"), 1, None).unwrap(); //4
+
+        assert!(tree.is_binary().unwrap());
+
+        tree.add_child(Node::new_named("3L"), 3, None).unwrap(); //5
+        tree.add_child(Node::new_named("3R"), 3, None).unwrap(); //6
+        assert!(tree.is_binary().unwrap());
+
+        tree.add_child(Node::new_named("3?"), 3, None).unwrap(); //7
+        assert!(!tree.is_binary().unwrap());
+    }
+
+    #[test]
+    fn binary_from_newick() {
+        let test_cases = vec![
+            ("((A,B,C)D,E)F;", false),   // Rooted non binary
+            ("(A,B,(C,D)E)F;", true),    // Unrooted binary
+            ("((D,E)B,(F,G)C)A;", true), // rooted binary
         ];
-        let rfs = [
-            vec![0, 4, 2, 10, 10, 10, 10, 10, 10, 10, 2, 10],
-            vec![4, 0, 2, 10, 8, 10, 8, 10, 8, 10, 2, 10],
-            vec![2, 2, 0, 10, 10, 10, 10, 10, 10, 10, 0, 10],
-            vec![10, 10, 10, 0, 2, 2, 4, 2, 4, 0, 10, 2],
-            vec![10, 8, 10, 2, 0, 4, 2, 4, 2, 2, 10, 4],
-            vec![10, 10, 10, 2, 4, 0, 2, 2, 4, 2, 10, 2],
-            vec![10, 8, 10, 4, 2, 2, 0, 4, 2, 4, 10, 4],
-            vec![10, 10, 10, 2, 4, 2, 4, 0, 2, 2, 10, 0],
-            vec![10, 8, 10, 4, 2, 4, 2, 2, 0, 4, 10, 2],
-            vec![10, 10, 10, 0, 2, 2, 4, 2, 4, 0, 10, 2],
-            vec![2, 2, 0, 10, 10, 10, 10, 10, 10, 10, 0, 10],
-            vec![10, 10, 10, 2, 4, 2, 4, 0, 2, 2, 10, 0],
+
+        for (newick, is_binary) in test_cases {
+            assert_eq!(
+                Tree::from_newick(newick).unwrap().is_binary().unwrap(),
+                is_binary
+            )
+        }
+    }
+
+    #[test]
+    fn polytomies() {
+        let test_cases = vec![
+            ("((A,B,C)D,E)F;", vec!["D"]),
+            ("(A,B,(C,D)E)F;", vec![]),
+            ("((D,E)B,(F,G)C)A;", vec![]),
+            ("(A,B,C,(D,E)G)F;", vec!["F"]),
+            ("(A,B,C,(D,E,H)G)F;", vec!["F", "G"]),
+            ("((A,B,C)D,(E,F,G,H)I,J)K;", vec!["D", "I"]),
         ];
 
-        for indices in (0..trees.len()).combinations(2) {
-            let (i0, i1) = (indices[0], indices[1]);
+        for (newick, expected) in test_cases {
+            let tree = Tree::from_newick(newick).unwrap();
+            let mut names: Vec<_> = tree
+                .polytomy_nodes()
+                .iter()
+                .map(|id| tree.get(id).unwrap().name.clone().unwrap())
+                .collect();
+            names.sort();
 
-            let t0 = Tree::from_newick(trees[i0]).unwrap();
-            let t1 = Tree::from_newick(trees[i1]).unwrap();
+            assert_eq!(names, expected, "{newick}");
+            assert_eq!(tree.count_polytomies(), expected.len());
+            assert_eq!(tree.has_polytomy(), !expected.is_empty());
+            assert_eq!(tree.has_polytomy(), !tree.is_binary().unwrap());
+        }
 
-            assert_eq!(t0.robinson_foulds(&t1).unwrap(), rfs[i0][i1])
+        // Removed nodes are not counted
+        let mut tree = Tree::from_newick("((A,B,C)D,(E,F)G)H;").unwrap();
+        let d = tree.get_by_name("D").unwrap().id;
+        tree.prune(&d).unwrap();
+        assert!(!tree.has_polytomy());
+        assert!(tree.polytomy_nodes().is_empty());
+    }
+
+    #[test]
+    fn root_to_tip_regression() {
+        let tree = Tree::from_newick("((A:1,B:2):1,(C:3,D:1):2);").unwrap();
+        let mut tip_dates: HashMap<_, _> =
+            [("A", 2000.0), ("B", 2004.0), ("C", 2010.0), ("D", 2008.0)]
+                .into_iter()
+                .map(|(name, date)| (name.to_string(), date))
+                .collect();
+
+        let rtt = tree.rtt_regression(&tip_dates).unwrap();
+        assert!((rtt.slope - 29.0 / 118.0).abs() < 1e-10);
+        assert!((rtt.intercept - (3.25 - 29.0 / 118.0 * 2005.5)).abs() < 1e-8);
+        assert!((rtt.r_squared - 841.0 / 1121.0).abs() < 1e-10);
+
+        let expected = [
+            ("A", 0.10169491525425656),
+            ("B", 0.11864406779659475),
+            ("C", 0.6440677966101589),
+            ("D", -0.8644067796610102),
+        ];
+        assert_eq!(rtt.residuals.len(), expected.len());
+        for ((name, residual), (expected_name, expected_residual)) in zip(rtt.residuals, expected) {
+            assert_eq!(name, expected_name);
+            assert!((residual - expected_residual).abs() < 1e-8);
         }
+
+        tip_dates.remove("D");
+        assert!(matches!(
+            tree.rtt_regression(&tip_dates),
+            Err(TreeError::UnnamedLeaves)
+        ));
+
+        tip_dates.insert("D".to_string(), 2000.0);
+        let tree = Tree::from_newick("((A:1,B:2):1,(C:3,D):2);").unwrap();
+        assert!(matches!(
+            tree.rtt_regression(&tip_dates),
+            Err(TreeError::MissingBranchLengths)
+        ));
+
+        let tree = Tree::from_newick("((A:1,B:2):1,(C:3,D:1):2);").unwrap();
+        let same_dates = tip_dates
+            .keys()
+            .map(|name| (name.clone(), 2000.0))
+            .collect();
+        assert!(matches!(
+            tree.rtt_regression(&same_dates),
+            Err(TreeError::GeneralError(_))
+        ));
     }
 
     #[test]
-    // Robinson foulds distances according to
-    // https://evolution.genetics.washington.edu/phylip/doc/treedist.html
-    fn weighted_robinson_foulds_treedist() {
-        let trees = [
-            "(A:0.1,(B:0.1,(H:0.1,(D:0.1,(J:0.1,(((G:0.1,E:0.1):0.1,(F:0.1,I:0.1):0.1):0.1,C:0.1):0.1):0.1):0.1):0.1):0.1);",
-            "(A:0.1,(B:0.1,(D:0.1,((J:0.1,H:0.1):0.1,(((G:0.1,E:0.1):0.1,(F:0.1,I:0.1):0.1):0.1,C:0.1):0.1):0.1):0.1):0.1);",
-            "(A:0.1,(B:0.1,(D:0.1,(H:0.1,(J:0.1,(((G:0.1,E:0.1):0.1,(F:0.1,I:0.1):0.1):0.1,C:0.1):0.1):0.1):0.1):0.1):0.1);",
-            "(A:0.1,(B:0.1,(E:0.1,(G:0.1,((F:0.1,I:0.1):0.1,((J:0.1,(H:0.1,D:0.1):0.1):0.1,C:0.1):0.1):0.1):0.1):0.1):0.1);",
-            "(A:0.1,(B:0.1,(E:0.1,(G:0.1,((F:0.1,I:0.1):0.1,(((J:0.1,H:0.1):0.1,D:0.1):0.1,C:0.1):0.1):0.1):0.1):0.1):0.1);",
-            "(A:0.1,(B:0.1,(E:0.1,((F:0.1,I:0.1):0.1,(G:0.1,((J:0.1,(H:0.1,D:0.1):0.1):0.1,C:0.1):0.1):0.1):0.1):0.1):0.1);",
-            "(A:0.1,(B:0.1,(E:0.1,((F:0.1,I:0.1):0.1,(G:0.1,(((J:0.1,H:0.1):0.1,D:0.1):0.1,C:0.1):0.1):0.1):0.1):0.1):0.1);",
-            "(A:0.1,(B:0.1,(E:0.1,((G:0.1,(F:0.1,I:0.1):0.1):0.1,((J:0.1,(H:0.1,D:0.1):0.1):0.1,C:0.1):0.1):0.1):0.1):0.1);",
-            "(A:0.1,(B:0.1,(E:0.1,((G:0.1,(F:0.1,I:0.1):0.1):0.1,(((J:0.1,H:0.1):0.1,D:0.1):0.1,C:0.1):0.1):0.1):0.1):0.1);",
-            "(A:0.1,(B:0.1,(E:0.1,(G:0.1,((F:0.1,I:0.1):0.1,((J:0.1,(H:0.1,D:0.1):0.1):0.1,C:0.1):0.1):0.1):0.1):0.1):0.1);",
-            "(A:0.1,(B:0.1,(D:0.1,(H:0.1,(J:0.1,(((G:0.1,E:0.1):0.1,(F:0.1,I:0.1):0.1):0.1,C:0.1):0.1):0.1):0.1):0.1):0.1);",
-            "(A:0.1,(B:0.1,(E:0.1,((G:0.1,(F:0.1,I:0.1):0.1):0.1,((J:0.1,(H:0.1,D:0.1):0.1):0.1,C:0.1):0.1):0.1):0.1):0.1);",
+    fn prune_tree() {
+        let mut tree = build_simple_tree().unwrap();
+        tree.prune(&4).unwrap(); // prune D subtree
+
+        assert_eq!(tree.to_newick().unwrap(), "((A)B,((H)I)G)F;");
+    }
+
+    #[test]
+    fn path_from_root() {
+        let tree = build_simple_tree().unwrap();
+        let values: Vec<_> = get_values(&(tree.get_path_from_root(&7).unwrap()), &tree)
+            .into_iter()
+            .flatten()
+            .collect();
+        assert_eq!(values, vec!["F", "B", "D", "E"])
+    }
+
+    #[test]
+    fn last_common_ancestor() {
+        let test_cases = vec![
+            ((3, 7), 1), // (A,E) -> B
+            ((6, 8), 0), // (C,H) -> F
+            ((3, 3), 3), // (A,A) -> A
+            ((8, 5), 5), // (H,I) -> I
+            ((4, 7), 4), // (D,E) -> D
         ];
-        let rfs = [
-            [
-                0.,
-                0.4,
-                0.2,
-                0.9999999999999999,
-                0.9999999999999999,
-                0.9999999999999999,
-                0.9999999999999999,
-                0.9999999999999999,
-                0.9999999999999999,
-                0.9999999999999999,
-                0.2,
-                0.9999999999999999,
-            ],
-            [
-                0.4,
-                0.,
-                0.2,
-                0.9999999999999999,
-                0.7999999999999999,
-                0.9999999999999999,
-                0.7999999999999999,
+        let tree = build_simple_tree().unwrap();
+        for ((source, target), ancestor) in test_cases {
+            println!(
+                "Testing: ({:?}, {:?}) -> {:?}",
+                tree.get(&source).unwrap().name,
+                tree.get(&target).unwrap().name,
+                tree.get(&ancestor).unwrap().name
+            );
+            assert_eq!(
+                ancestor,
+                tree.get_common_ancestor(&source, &target).unwrap()
+            );
+        }
+    }
+
+    #[test]
+    fn get_distances_lengths() {
+        let test_cases = vec![
+            ((1, 3), (0.6, 2)), // (A,E)
+            ((1, 4), (0.9, 3)), // (A,C)
+            ((4, 5), (0.7, 2)), // (C,D)
+            ((5, 2), (1.1, 3)), // (D,B)
+            ((2, 5), (1.1, 3)), // (B,D)
+            ((0, 2), (0.2, 1)), // (F,B)
+            ((1, 1), (0.0, 0)), // (A,A)
+        ];
+        let tree = build_tree_with_lengths().unwrap();
+        let tree2 = build_tree_without_lengths().unwrap();
+
+        for ((idx_s, idx_t), (dist, branches)) in test_cases {
+            let (d_pred, b_pred) = tree.get_distance(&idx_s, &idx_t).unwrap();
+            let (d_pred2, b_pred2) = tree2.get_distance(&idx_s, &idx_t).unwrap();
+            assert_eq!(branches, b_pred);
+            assert_eq!(branches, b_pred2);
+
+            assert!(d_pred.is_some());
+            assert!((d_pred.unwrap() - dist).abs() < f64::EPSILON);
+            if idx_s != idx_t {
+                assert!(d_pred2.is_none(), "{d_pred2:?}");
+            }
+        }
+    }
+
+    #[test]
+    fn get_correct_leaves() {
+        let tree = build_simple_tree().unwrap();
+        let values: Vec<_> = get_values(&(tree.get_leaves()), &tree)
+            .into_iter()
+            .flatten()
+            .collect();
+        assert_eq!(values, vec!["A", "C", "E", "H"])
+    }
+
+    #[test]
+    fn to_newick() {
+        let tree = build_tree_with_lengths().unwrap();
+        dbg!(&tree);
+        assert_eq!(
+            "(A:0.1,B:0.2,(C:0.3,D:0.4)E:0.5)F;",
+            tree.to_newick().unwrap()
+        );
+    }
+
+    #[test]
+    fn root_edge() {
+        let mut tree = Tree::from_newick("((A:1,B:2):1,C:3):0.1;").unwrap();
+        assert_eq!(tree.root_edge(), Some(0.1));
+        assert_eq!(tree.to_newick().unwrap(), "((A:1,B:2):1,C:3):0.1;");
+        assert_eq!(
+            tree.to_formatted_newick(NewickFormat::OnlyLengths).unwrap(),
+            "((:1,:2):1,:3):0.1;"
+        );
+        assert_eq!(
+            tree.to_formatted_newick(NewickFormat::OnlyNames).unwrap(),
+            "((A,B),C);"
+        );
+        assert_eq!(
+            tree.to_newick_with_precision(2).unwrap(),
+            "((A:1.00,B:2.00):1.00,C:3.00):0.10;"
+        );
+
+        // The root edge is not a branch of the tree
+        assert_eq!(tree.length().unwrap(), 7.0);
+        assert_eq!(tree.height().unwrap(), 3.0);
+
+        tree.set_root_edge(None).unwrap();
+        assert_eq!(tree.root_edge(), None);
+        assert_eq!(tree.to_newick().unwrap(), "((A:1,B:2):1,C:3);");
+
+        tree.set_root_edge(Some(2.5)).unwrap();
+        assert_eq!(tree.to_newick().unwrap(), "((A:1,B:2):1,C:3):2.5;");
+        let parsed = Tree::from_newick(&tree.to_newick().unwrap()).unwrap();
+        assert_eq!(parsed.root_edge(), Some(2.5));
+
+        let mut tree = Tree::from_newick("(A,B)R;").unwrap();
+        tree.set_root_edge(Some(1.0)).unwrap();
+        assert_eq!(tree.to_newick().unwrap(), "(A,B)R:1;");
+
+        let mut empty = Tree::new();
+        assert_eq!(empty.root_edge(), None);
+        assert!(matches!(
+            empty.set_root_edge(Some(1.0)),
+            Err(TreeError::RootNotFound)
+        ));
+    }
+
+    #[test]
+    fn to_formatted_newick() {
+        let newick = "(A:0.1[Comment_1],B:0.2,(C:0.3,D:0.4)E:0.5[Comment_2])F;";
+        let tree = Tree::from_newick(newick).unwrap();
+
+        let cases = [
+            (NewickFormat::AllFields, newick),
+            (
+                NewickFormat::NoComments,
+                "(A:0.1,B:0.2,(C:0.3,D:0.4)E:0.5)F;",
+            ),
+            (NewickFormat::Topology, "(,,(,));"),
+            (NewickFormat::OnlyNames, "(A,B,(C,D)E)F;"),
+            (NewickFormat::OnlyLengths, "(:0.1,:0.2,(:0.3,:0.4):0.5);"),
+            (
+                NewickFormat::LeafLengthsAllNames,
+                "(A:0.1,B:0.2,(C:0.3,D:0.4)E)F;",
+            ),
+            (
+                NewickFormat::LeafLengthsLeafNames,
+                "(A:0.1,B:0.2,(C:0.3,D:0.4));",
+            ),
+            (NewickFormat::InternalLengthsLeafNames, "(A,B,(C,D):0.5);"),
+            (
+                NewickFormat::AllLengthsLeafNames,
+                "(A:0.1,B:0.2,(C:0.3,D:0.4):0.5);",
+            ),
+        ];
+
+        for (format, expected) in cases {
+            assert_eq!(
+                expected,
+                tree.to_formatted_newick(format).unwrap(),
+                "Failed to write newick for format: {format:?}"
+            )
+        }
+    }
+
+    // test cases from https://github.com/ila/Newick-validator
+    #[test]
+    fn read_newick() {
+        let newick_strings = vec![
+            "((D,E)B,(F,G)C)A;",
+            "(A:0.1,B:0.2,(C:0.3,D:0.4)E:0.5)F;",
+            "(A:0.1,B:0.2,(C:0.3,D:0.4):0.5);",
+            "(dog:20,(elephant:30,horse:60):20):50;",
+            "(A,B,(C,D));",
+            "(A,B,(C,D)E)F;",
+            "(((One:0.2,Two:0.3):0.3,(Three:0.5,Four:0.3):0.2):0.3,Five:0.7):0;",
+            "(:0.1,:0.2,(:0.3,:0.4):0.5);",
+            "(:0.1,:0.2,(:0.3,:0.4):0.5):0;",
+            "(A:0.1,B:0.2,(C:0.3,D:0.4):0.5);",
+            "(A:0.1,B:0.2,(C:0.3,D:0.4)E:0.5)F;",
+            "((B:0.2,(C:0.3,D:0.4)E:0.5)A:0.1)F;",
+            "(,,(,));",
+            "(\"hungarian dog\":20,(\"indian elephant\":30,\"swedish horse\":60):20):50;",
+            "(\"hungarian dog\":20[Comment_1],(\"indian elephant\":30,\"swedish horse\":60[Another interesting comment]):20):50;",
+        ];
+        for newick in newick_strings {
+            let tree = Tree::from_newick(newick).unwrap();
+            assert_eq!(newick, tree.to_newick().unwrap());
+        }
+    }
+
+    #[ignore]
+    #[test]
+    fn to_nexus() {
+        let tree = crate::generate_tree(10, true, crate::distr::Distr::Uniform).unwrap();
+        println!("{}", tree.to_nexus().unwrap());
+        panic!()
+    }
+
+    #[test]
+    fn read_newick_fails() {
+        assert!(matches!(
+            Tree::from_newick("((D,E)B,(F,G,C)A;"),
+            Err(NewickParseError::UnclosedBracket(0))
+        ));
+        assert!(matches!(
+            Tree::from_newick("((D,E)B,(F,G)C)A"),
+            Err(NewickParseError::NoClosingSemicolon(16))
+        ));
+        assert!(matches!(
+            Tree::from_newick("(A,B));"),
+            Err(NewickParseError::NoSubtreeParent(5))
+        ));
+
+        // Branch length offsets point to the start of the number
+        match Tree::from_newick("[&R] (A:0.1,B:0.x2);") {
+            Err(NewickParseError::FloatError { pos, raw, .. }) => {
+                assert_eq!((pos, raw.as_str()), (14, "0.x2"))
+            }
+            other => panic!("Unexpected result: {other:?}"),
+        }
+        match Tree::from_newick("(A:0.1,(B:1,C:-2):1);") {
+            Err(error @ NewickParseError::InvalidBranchLength { .. }) => assert_eq!(
+                error.to_string(),
+                "at offset 14: invalid negative branch length: -2"
+            ),
+            other => panic!("Unexpected result: {other:?}"),
+        }
+    }
+
+    #[test]
+    fn test_subtree_leaves() {
+        let test_cases = vec![
+            (
+                "((T0,T1)I1,(T2,T3)I2,((T4,T5)I4,(T6,T7)I4)I3)I0;",
+                7,
+                vec!["T4", "T5", "T6", "T7"],
+            ),
+            (
+                "((T0,T1)I1,(T2,T3)I2,((T4,T5)I4,(T6,T7)I4)I3)I0;",
+                1,
+                vec!["T0", "T1"],
+            ),
+            (
+                "((((((((T9,T8)I7,T7)I6,T6)I5,T5)I4,T4)I3,T3)I2,T2)I1,T0,T1)I0;",
+                5,
+                vec!["T9", "T8", "T7", "T6"],
+            ),
+        ];
+
+        for (newick, root, leaves) in test_cases {
+            let tree = Tree::from_newick(newick).unwrap();
+            let sub_leaves: Vec<String> =
+                get_values(&tree.get_subtree_leaves(&root).unwrap(), &tree)
+                    .iter()
+                    .map(|v| v.clone().unwrap())
+                    .collect();
+
+            assert_eq!(sub_leaves, leaves);
+        }
+    }
+
+    #[test]
+    fn test_height() {
+        // heights computed with ete3
+        let test_cases = vec![
+            ("((A:0.1,B:0.2)G:0.1,(C:0.3,D:0.4)E:0.5)F;", 0.9),
+            ("((B:0.2,(C:0.3,D:0.4)E:0.5)A:0.1,G:0.1)F;", 1.0),
+            ("((A,B)G,(C,D)E)F;", 2.0),
+            (
+                "((((((((Tip9,Tip8),Tip7),Tip6),Tip5),Tip4),Tip3),Tip2),Tip1);",
+                8.0,
+            ),
+        ];
+
+        for (newick, height) in test_cases {
+            assert_eq!(Tree::from_newick(newick).unwrap().height().unwrap(), height)
+        }
+    }
+
+    #[test]
+    fn test_diam() {
+        let test_cases = vec![
+            ("((D,E)B,(F,G)C)A;", 4.0),
+            ("(A:0.1,B:0.2,(C:0.3,D:0.4)E:0.5)F;", 1.1),
+            ("(A:0.1,B:0.2,(C:0.3,D:0.4):0.5);", 1.1),
+            ("(A,B,(C,D));", 3.0),
+            ("(A,B,(C,D)E)F;", 3.0),
+        ];
+
+        for (newick, diameter) in test_cases {
+            assert_eq!(
+                Tree::from_newick(newick).unwrap().diameter().unwrap(),
+                diameter
+            )
+        }
+    }
+
+    #[test]
+    fn test_cherries() {
+        // Number of cherries computed with gotree
+        let test_cases = vec![
+            ("(((((((((Tip9,Tip8),Tip7),Tip6),Tip5),Tip4),Tip3),Tip2),Tip1),Tip0);", 1),
+            ("(((i:0.1,j:0.1):0.1,(a:0.1,b:0.1):0.1):0.1,((c:0.1,d:0.1):0.1,((e:0.1,f:0.1):0.1,(g:0.1,h:0.1):0.1):0.1):0.1);", 5),
+            ("((a:0.2,b:0.2):0.2,((c:0.2,d:0.2):0.2,((e:0.2,f:0.2):0.2,((g:0.2,h:0.2):0.2,(i:0.2,j:0.2):0.2):0.2):0.2):0.2);", 5),
+            ("(((d:0.3,e:0.3):0.3,((f:0.3,g:0.3):0.3,(h:0.3,(i:0.3,j:0.3):0.3):0.3):0.3):0.3,(a:0.3,(b:0.3,c:0.3):0.3):0.3);", 4),
+        ];
+
+        for (newick, true_cherries) in test_cases {
+            let tree = Tree::from_newick(newick).unwrap();
+            let cherries = tree.cherries();
+            assert!(cherries.is_ok());
+            assert_eq!(cherries.unwrap(), true_cherries);
+        }
+    }
+
+    #[test]
+    fn manual_colless() {
+        let newick = "(((((((((T8,T9)I8,T7)I7,T6)I6,T5)I5,T4)I4,T3)I3,T2)I2,T1)I1,T0)I0;";
+        let tree = Tree::from_newick(newick).unwrap();
+
+        let mut colless = 0;
+
+        dbg!(&tree);
+
+        for node in tree.nodes.iter().filter(|node| !node.is_tip()) {
+            let left = tree.get_subtree_leaves(&node.children[0]).unwrap();
+            let right = tree.get_subtree_leaves(&node.children[1]).unwrap();
+            eprintln!("Node {:?}:", node.name.clone().unwrap());
+            eprintln!(
+                "\tLeft: {:?}",
+                get_values(&left, &tree)
+                    .into_iter()
+                    .flatten()
+                    .collect::<Vec<_>>()
+            );
+            eprintln!(
+                "\tRight: {:?}",
+                get_values(&right, &tree)
+                    .into_iter()
+                    .flatten()
+                    .collect::<Vec<_>>()
+            );
+            colless += left.len().abs_diff(right.len());
+            eprintln!("Colless: {colless}\n")
+        }
+
+        assert_eq!(colless, 36)
+    }
+
+    #[test]
+    fn test_colless_rooted() {
+        // Colless index computed with gotree
+        let test_cases = vec![
+            ("(((((((((Tip9,Tip8),Tip7),Tip6),Tip5),Tip4),Tip3),Tip2),Tip1),Tip0);", 36),
+            ("(((i:0.1,j:0.1):0.1,(a:0.1,b:0.1):0.1):0.1,((c:0.1,d:0.1):0.1,((e:0.1,f:0.1):0.1,(g:0.1,h:0.1):0.1):0.1):0.1);", 4),
+            ("((a:0.2,b:0.2):0.2,((c:0.2,d:0.2):0.2,((e:0.2,f:0.2):0.2,((g:0.2,h:0.2):0.2,(i:0.2,j:0.2):0.2):0.2):0.2):0.2);", 12),
+            ("(((d:0.3,e:0.3):0.3,((f:0.3,g:0.3):0.3,(h:0.3,(i:0.3,j:0.3):0.3):0.3):0.3):0.3,(a:0.3,(b:0.3,c:0.3):0.3):0.3);", 10),
+        ];
+
+        for (newick, true_colless) in test_cases {
+            let tree = Tree::from_newick(newick).unwrap();
+            let colless = tree.colless();
+            assert!(colless.is_ok());
+            if tree.colless().unwrap() != true_colless {
+                panic!(
+                    "Computed colless {} not equal to true {true_colless}",
+                    tree.colless().unwrap()
+                )
+            };
+        }
+    }
+
+    #[test]
+    fn test_sackin_rooted() {
+        // Sackin index computed with gotree
+        let test_cases = vec![
+            ("(((((((((Tip9,Tip8),Tip7),Tip6),Tip5),Tip4),Tip3),Tip2),Tip1),Tip0);", 54),
+            ("(((i:0.1,j:0.1):0.1,(a:0.1,b:0.1):0.1):0.1,((c:0.1,d:0.1):0.1,((e:0.1,f:0.1):0.1,(g:0.1,h:0.1):0.1):0.1):0.1);", 34),
+            ("((a:0.2,b:0.2):0.2,((c:0.2,d:0.2):0.2,((e:0.2,f:0.2):0.2,((g:0.2,h:0.2):0.2,(i:0.2,j:0.2):0.2):0.2):0.2):0.2);", 38),
+            ("(((d:0.3,e:0.3):0.3,((f:0.3,g:0.3):0.3,(h:0.3,(i:0.3,j:0.3):0.3):0.3):0.3):0.3,(a:0.3,(b:0.3,c:0.3):0.3):0.3);", 36),
+        ];
+
+        for (newick, true_sackin) in test_cases {
+            let tree = Tree::from_newick(newick).unwrap();
+            let sackin = tree.sackin();
+            assert!(sackin.is_ok());
+            assert_eq!(tree.sackin().unwrap(), true_sackin);
+        }
+    }
+
+    #[test]
+    fn test_sackin_unrooted() {
+        let test_cases = vec![
+            "(A:0.1,B:0.2,(C:0.3,D:0.4)E:0.5);",
+            "((B:0.2,(C:0.3,D:0.4)E:0.5)A:0.1);",
+            "(A,B,(C,D)E);",
+            "((((((((Tip9,Tip8),Tip7),Tip6),Tip5),Tip4),Tip3),Tip2),Tip0,Tip1);",
+        ];
+
+        for newick in test_cases {
+            let tree = Tree::from_newick(newick).unwrap();
+            assert!(tree.sackin().is_err())
+        }
+    }
+
+    #[test]
+    fn test_rescale() {
+        let test_cases = [
+            ("((D:0.05307533041908017723553570021977,(C:0.08550401213833067060043902074540,(B:0.27463239708134284944307523801399,A:0.37113575171985613287972682883264)1:0.18134330279626256765546088445262)1:0.08033066840794983454188127325324)1:0.13864016688124142229199264875206,E:0.05060148260657528623829293223935);",
+            "((D:0.04212872094323715649322181775460,(C:0.06786909546224775824363462106703,(B:0.21799038323938338401752901063446,A:0.29459024358034957558061250892933)1:0.14394185279875840177687962295749)1:0.06376273658252405718283029045779)1:0.11004609591585229333432494058798,E:0.04016509597234880352134567260691);",
+            0.6525060248498331),
+            ("(E:0.01699652764738122934229380689430,(D:0.00408169520164380558724381842239,(C:0.19713461567160570075962766622979,(B:0.12068059163592816107613003850929,A:0.45190753170439451613660253315174)1:0.03279750996120785189180679708443)1:0.21625179801434316062547225101298)1:0.03998705111996220251668887613050);",
+            "(E:0.01986870266959113798255209815125,(D:0.00477144449924469995355513773916,(C:0.23044760352958004734347241537762,(B:0.14107392068250154681940955470054,A:0.52827357257097584675165080625447)1:0.03833982959587604877338407050047)1:0.25279532182407132845369801543711)1:0.04674430247278672095889717752470);",
+            0.8860217291333011),
+            ("((C:0.20738366520293352590620372666308,(B:0.19695170474498663315543467433599,A:0.02188551422116874478618342436675)1:0.05940680521299050026451382677806)1:0.13029006694844610936279138968530,(E:0.17189347707484656235799036494427,D:0.05867747522240193691622778260353)1:0.08673941227771603257323818070290);",
+            "((C:0.18371634870356487456710681271943,(B:0.17447491841406459478491797199240,A:0.01938786624432843955223582099734)1:0.05262710219338979922287791168856)1:0.11542092936147484161235610145013,(E:0.15227641937588842768747099398752,D:0.05198100577716616849111019860175)1:0.07684042085359836515845444182560);",
+            0.571639790198416),
+        ];
+
+        for (orig, rescaled, scale) in test_cases {
+            let mut tree = Tree::from_newick(orig).unwrap();
+            let rescaled = Tree::from_newick(rescaled).unwrap();
+
+            let diam = tree.diameter().unwrap();
+
+            tree.rescale(scale / diam);
+
+            println!("Dealing with tree: {} and scale {}", orig, scale);
+            assert_eq!(
+                tree.to_newick_with_precision(10).unwrap(),
+                rescaled.to_newick_with_precision(10).unwrap()
+            );
+            for (n1, n2) in zip(tree.nodes, rescaled.nodes) {
+                assert_eq!(n1, n2)
+            }
+        }
+    }
+
+    #[test]
+    fn newick_with_precision() {
+        let mut tree = Tree::from_newick("((A:0.1,B:0.2)E:0.05,(C:1,D:9.996)F:12.5)G;").unwrap();
+        tree.rescale(3.0);
+
+        assert_eq!(
+            tree.to_newick_with_precision(3).unwrap(),
+            "((A:0.300,B:0.600)E:0.150,(C:3.000,D:29.988)F:37.500)G;"
+        );
+        assert_eq!(
+            tree.to_newick_with_precision(0).unwrap(),
+            "((A:0,B:1)E:0,(C:3,D:30)F:38)G;"
+        );
+        assert_eq!(
+            tree.to_newick_significant(2).unwrap(),
+            "((A:0.30,B:0.60)E:0.15,(C:3.0,D:30)F:38)G;"
+        );
+
+        // Rounding up to the next power of 10 keeps the number of significant digits
+        let tree = Tree::from_newick("(A:0.0999,B:9.99,C:0)D;").unwrap();
+        assert_eq!(
+            tree.to_newick_significant(2).unwrap(),
+            "(A:0.10,B:10,C:0.0)D;"
+        );
+
+        // Rounded branch lengths are read back as usual
+        let written = tree.to_newick_with_precision(1).unwrap();
+        assert_eq!(written, "(A:0.1,B:10.0,C:0.0)D;");
+        assert_eq!(
+            Tree::from_newick(&written).unwrap().to_newick().unwrap(),
+            "(A:0.1,B:10,C:0)D;"
+        );
+
+        assert!(matches!(
+            Tree::new().to_newick_with_precision(2),
+            Err(TreeError::RootNotFound)
+        ));
+    }
+
+    #[test]
+    fn test_unique_tip_names() {
+        let test_cases = vec![
+            ("(((((((((Tip9,Tip8),Tip7),Tip6),Tip5),Tip4),Tip3),Tip2),Tip1),Tip0);",true),
+            ("(((i:0.1,j:0.1):0.1,(a:0.1,b:0.1):0.1):0.1,((c:0.1,d:0.1):0.1,((e:0.1,f:0.1):0.1,(g:0.1,h:0.1):0.1):0.1):0.1);", true),
+            ("(((((((((Tip8,Tip8),Tip7),Tip6),Tip5),Tip4),Tip3),Tip2),Tip1),Tip0);",false),
+        ];
+
+        for (newick, is_unique) in test_cases {
+            assert_eq!(
+                Tree::from_newick(newick)
+                    .unwrap()
+                    .has_unique_tip_names()
+                    .unwrap(),
+                is_unique,
+                "Failed on: {newick}"
+            )
+        }
+
+        assert!(Tree::from_newick("(((((((((,),),),),),),),),);")
+            .unwrap()
+            .has_unique_tip_names()
+            .is_err())
+    }
+
+    #[test]
+    fn test_descendants() {
+        let tree = build_simple_tree().unwrap();
+        let descendants_b: Vec<_> = get_values(&tree.get_descendants(&1).unwrap(), &tree)
+            .into_iter()
+            .flatten()
+            .sorted()
+            .collect();
+        let descendants_g: Vec<_> = get_values(&tree.get_descendants(&2).unwrap(), &tree)
+            .into_iter()
+            .flatten()
+            .sorted()
+            .collect();
+
+        assert_eq!(descendants_b, vec!["A", "C", "D", "E"]);
+        assert_eq!(descendants_g, vec!["H", "I"]);
+
+        // Descendants are returned in preorder
+        for id in tree.preorder(&tree.get_root().unwrap()).unwrap() {
+            let preorder = tree.preorder(&id).unwrap();
+            assert_eq!(tree.get_descendants(&id).unwrap(), preorder[1..]);
+        }
+        assert!(tree.get_descendants(&100).is_err());
+    }
+
+    #[test]
+    fn test_compress() {
+        let mut tree = Tree::new();
+        tree.add(Node::new_named("root"));
+        tree.add_child(Node::new_named("tip_A"), 0, Some(1.0))
+            .unwrap();
+        tree.add_child(Node::new_named("in_B"), 0, Some(1.0))
+            .unwrap();
+        tree.add_child(Node::new_named("in_C"), 2, Some(1.0))
+            .unwrap();
+        tree.add_child(Node::new_named("tip_D"), 3, Some(1.0))
+            .unwrap();
+
+        tree.compress().unwrap();
+
+        assert_eq!(tree.to_newick().unwrap(), "(tip_A:1,tip_D:3)root;");
+    }
+
+    #[test]
+    fn prune_and_compress() {
+        let mut tree =
+            Tree::from_newick("((A:1,(B:1,C:2)E:1)F:2,(D:3,(G:1,H:1)I:1)J:1,K:1)L;").unwrap();
+        let expected = Tree::from_newick("((A:1,C:3)F:2,D:4,K:1)L;").unwrap();
+
+        for name in ["B", "I"] {
+            let id = tree.get_by_name(name).unwrap().id;
+            tree.prune(&id).unwrap();
+        }
+        tree.compress().unwrap();
+
+        assert_eq!(tree.to_newick().unwrap(), expected.to_newick().unwrap());
+        assert_eq!(tree.robinson_foulds(&expected).unwrap(), 0);
+        assert_eq!(tree.weighted_robinson_foulds(&expected).unwrap(), 0.0);
+        for node in tree.nodes.iter().filter(|n| !n.deleted) {
+            let n_edges = tree.get_path_from_root(&node.id).unwrap().len() - 1;
+            assert_eq!(node.get_depth(), n_edges);
+        }
+
+        // Mixed branch lengths cannot be fused
+        let mut tree = Tree::from_newick("((A:1)B,C:1)D;").unwrap();
+        assert!(matches!(
+            tree.compress(),
+            Err(TreeError::MissingBranchLengths)
+        ));
+    }
+
+    #[test]
+    fn test_get_partitions() {
+        let test_cases = vec![
+            (
+                "(((((((((Tip9,Tip8),Tip7),Tip6),Tip5),Tip4),Tip3),Tip2),Tip1),Tip0);",
+                "(Tip0,((Tip2,(Tip3,(Tip4,(Tip5,(Tip6,((Tip8,Tip9),Tip7)))))),Tip1));",
+            ),
+            (
+                "(((i:0.1,j:0.1):0.1,(a:0.1,b:0.1):0.1):0.1,((c:0.1,d:0.1):0.1,((e:0.1,f:0.1):0.1,(g:0.1,h:0.1):0.1):0.1):0.1);",
+                "(((c:0.1,d:0.1):0.1,((g:0.1,h:0.1):0.1,(f:0.1,e:0.1):0.1):0.1):0.1,((i:0.1,j:0.1):0.1,(a:0.1,b:0.1):0.1):0.1);",
+            ),
+            (
+                "((a:0.2,b:0.2):0.2,((c:0.2,d:0.2):0.2,((e:0.2,f:0.2):0.2,((g:0.2,h:0.2):0.2,(i:0.2,j:0.2):0.2):0.2):0.2):0.2);",
+                "((((e:0.2,f:0.2):0.2,((i:0.2,j:0.2):0.2,(g:0.2,h:0.2):0.2):0.2):0.2,(d:0.2,c:0.2):0.2):0.2,(b:0.2,a:0.2):0.2);",
+            ),
+            (
+                "(((d:0.3,e:0.3):0.3,((f:0.3,g:0.3):0.3,(h:0.3,(i:0.3,j:0.3):0.3):0.3):0.3):0.3,(a:0.3,(b:0.3,c:0.3):0.3):0.3);",
+                "((((g:0.3,f:0.3):0.3,((i:0.3,j:0.3):0.3,h:0.3):0.3):0.3,(d:0.3,e:0.3):0.3):0.3,((b:0.3,c:0.3):0.3,a:0.3):0.3);",
+            ),
+        ];
+
+        for (newick, rot_newick) in test_cases {
+            let tree = Tree::from_newick(newick).unwrap();
+            let rota = Tree::from_newick(rot_newick).unwrap();
+
+            let ps_orig: HashSet<_> = HashSet::from_iter(tree.get_partitions().unwrap());
+            let ps_rota: HashSet<_> = HashSet::from_iter(rota.get_partitions().unwrap());
+
+            assert_eq!(ps_orig, ps_rota);
+        }
+    }
+
+    #[test]
+    fn self_rf() {
+        let test_cases = vec![
+            (
+                "(((((((((Tip9,Tip8),Tip7),Tip6),Tip5),Tip4),Tip3),Tip2),Tip1),Tip0);",
+                "(Tip0,((Tip2,(Tip3,(Tip4,(Tip5,(Tip6,((Tip8,Tip9),Tip7)))))),Tip1));",
+            ),
+            (
+                "(((i:0.1,j:0.1):0.1,(a:0.1,b:0.1):0.1):0.1,((c:0.1,d:0.1):0.1,((e:0.1,f:0.1):0.1,(g:0.1,h:0.1):0.1):0.1):0.1);",
+                "(((c:0.1,d:0.1):0.1,((g:0.1,h:0.1):0.1,(f:0.1,e:0.1):0.1):0.1):0.1,((i:0.1,j:0.1):0.1,(a:0.1,b:0.1):0.1):0.1);",
+            ),
+            (
+                "((a:0.2,b:0.2):0.2,((c:0.2,d:0.2):0.2,((e:0.2,f:0.2):0.2,((g:0.2,h:0.2):0.2,(i:0.2,j:0.2):0.2):0.2):0.2):0.2);",
+                "((((e:0.2,f:0.2):0.2,((i:0.2,j:0.2):0.2,(g:0.2,h:0.2):0.2):0.2):0.2,(d:0.2,c:0.2):0.2):0.2,(b:0.2,a:0.2):0.2);",
+            ),
+            (
+                "(((d:0.3,e:0.3):0.3,((f:0.3,g:0.3):0.3,(h:0.3,(i:0.3,j:0.3):0.3):0.3):0.3):0.3,(a:0.3,(b:0.3,c:0.3):0.3):0.3);",
+                "((((g:0.3,f:0.3):0.3,((i:0.3,j:0.3):0.3,h:0.3):0.3):0.3,(d:0.3,e:0.3):0.3):0.3,((b:0.3,c:0.3):0.3,a:0.3):0.3);",
+            ),
+        ];
+
+        for (newick, rot_newick) in test_cases {
+            let tree = Tree::from_newick(newick).unwrap();
+            let rota = Tree::from_newick(rot_newick).unwrap();
+
+            tree.init_leaf_index().unwrap();
+            rota.init_leaf_index().unwrap();
+
+            assert_eq!(
+                tree.robinson_foulds(&rota).unwrap(),
+                0,
+                "Ref{:#?}\nRot:{:#?}",
+                tree.leaf_index,
+                rota.leaf_index
+            );
+        }
+    }
+
+    #[test]
+    // Robinson foulds distances according to
+    // https://evolution.genetics.washington.edu/phylip/doc/treedist.html
+    fn robinson_foulds_treedist() {
+        let trees = [
+            "(A:0.1,(B:0.1,(H:0.1,(D:0.1,(J:0.1,(((G:0.1,E:0.1):0.1,(F:0.1,I:0.1):0.1):0.1,C:0.1):0.1):0.1):0.1):0.1):0.1);",
+            "(A:0.1,(B:0.1,(D:0.1,((J:0.1,H:0.1):0.1,(((G:0.1,E:0.1):0.1,(F:0.1,I:0.1):0.1):0.1,C:0.1):0.1):0.1):0.1):0.1);",
+            "(A:0.1,(B:0.1,(D:0.1,(H:0.1,(J:0.1,(((G:0.1,E:0.1):0.1,(F:0.1,I:0.1):0.1):0.1,C:0.1):0.1):0.1):0.1):0.1):0.1);",
+            "(A:0.1,(B:0.1,(E:0.1,(G:0.1,((F:0.1,I:0.1):0.1,((J:0.1,(H:0.1,D:0.1):0.1):0.1,C:0.1):0.1):0.1):0.1):0.1):0.1);",
+            "(A:0.1,(B:0.1,(E:0.1,(G:0.1,((F:0.1,I:0.1):0.1,(((J:0.1,H:0.1):0.1,D:0.1):0.1,C:0.1):0.1):0.1):0.1):0.1):0.1);",
+            "(A:0.1,(B:0.1,(E:0.1,((F:0.1,I:0.1):0.1,(G:0.1,((J:0.1,(H:0.1,D:0.1):0.1):0.1,C:0.1):0.1):0.1):0.1):0.1):0.1);",
+            "(A:0.1,(B:0.1,(E:0.1,((F:0.1,I:0.1):0.1,(G:0.1,(((J:0.1,H:0.1):0.1,D:0.1):0.1,C:0.1):0.1):0.1):0.1):0.1):0.1);",
+            "(A:0.1,(B:0.1,(E:0.1,((G:0.1,(F:0.1,I:0.1):0.1):0.1,((J:0.1,(H:0.1,D:0.1):0.1):0.1,C:0.1):0.1):0.1):0.1):0.1);",
+            "(A:0.1,(B:0.1,(E:0.1,((G:0.1,(F:0.1,I:0.1):0.1):0.1,(((J:0.1,H:0.1):0.1,D:0.1):0.1,C:0.1):0.1):0.1):0.1):0.1);",
+            "(A:0.1,(B:0.1,(E:0.1,(G:0.1,((F:0.1,I:0.1):0.1,((J:0.1,(H:0.1,D:0.1):0.1):0.1,C:0.1):0.1):0.1):0.1):0.1):0.1);",
+            "(A:0.1,(B:0.1,(D:0.1,(H:0.1,(J:0.1,(((G:0.1,E:0.1):0.1,(F:0.1,I:0.1):0.1):0.1,C:0.1):0.1):0.1):0.1):0.1):0.1);",
+            "(A:0.1,(B:0.1,(E:0.1,((G:0.1,(F:0.1,I:0.1):0.1):0.1,((J:0.1,(H:0.1,D:0.1):0.1):0.1,C:0.1):0.1):0.1):0.1):0.1);",
+        ];
+        let rfs = [
+            vec![0, 4, 2, 10, 10, 10, 10, 10, 10, 10, 2, 10],
+            vec![4, 0, 2, 10, 8, 10, 8, 10, 8, 10, 2, 10],
+            vec![2, 2, 0, 10, 10, 10, 10, 10, 10, 10, 0, 10],
+            vec![10, 10, 10, 0, 2, 2, 4, 2, 4, 0, 10, 2],
+            vec![10, 8, 10, 2, 0, 4, 2, 4, 2, 2, 10, 4],
+            vec![10, 10, 10, 2, 4, 0, 2, 2, 4, 2, 10, 2],
+            vec![10, 8, 10, 4, 2, 2, 0, 4, 2, 4, 10, 4],
+            vec![10, 10, 10, 2, 4, 2, 4, 0, 2, 2, 10, 0],
+            vec![10, 8, 10, 4, 2, 4, 2, 2, 0, 4, 10, 2],
+            vec![10, 10, 10, 0, 2, 2, 4, 2, 4, 0, 10, 2],
+            vec![2, 2, 0, 10, 10, 10, 10, 10, 10, 10, 0, 10],
+            vec![10, 10, 10, 2, 4, 2, 4, 0, 2, 2, 10, 0],
+        ];
+
+        for indices in (0..trees.len()).combinations(2) {
+            let (i0, i1) = (indices[0], indices[1]);
+
+            let t0 = Tree::from_newick(trees[i0]).unwrap();
+            let t1 = Tree::from_newick(trees[i1]).unwrap();
+
+            assert_eq!(t0.robinson_foulds(&t1).unwrap(), rfs[i0][i1])
+        }
+    }
+
+    #[test]
+    fn bipartition_supports() {
+        let tree = Tree::from_newick("(((A,B)AB,C)ABC,(D,E)DE,F);").unwrap();
+        let references = vec![
+            Tree::from_newick("(((A,B),C),(D,E),F);").unwrap(),
+            Tree::from_newick("(((A,B),D),(C,E),F);").unwrap(),
+            Tree::from_newick("((A,(B,C)),(D,E),F);").unwrap(),
+            // Same tree with a different root
+            Tree::from_newick("((((D,E),F),C),A,B);").unwrap(),
+        ];
+
+        let supports = tree.bipartition_support(&references).unwrap();
+        let get = |name| supports[&tree.get_by_name(name).unwrap().id];
+
+        assert_eq!(supports.len(), 3);
+        assert_eq!(get("AB"), 0.75);
+        assert_eq!(get("ABC"), 0.75);
+        assert_eq!(get("DE"), 0.75);
+
+        // Identical trees support all branches
+        let supports = tree.bipartition_support(&[tree.clone()]).unwrap();
+        assert!(supports.values().all(|support| *support == 1.0));
+
+        // Branches under the root of a rooted tree define the same bipartition
+        let rooted = Tree::from_newick("((A,B)AB,(C,D)CD);").unwrap();
+        let references = vec![Tree::from_newick("((A,B),C,D);").unwrap()];
+        let supports = rooted.bipartition_support(&references).unwrap();
+        assert_eq!(supports.values().collect::<Vec<_>>(), vec![&1.0, &1.0]);
+
+        assert!(matches!(
+            tree.bipartition_support(&[]),
+            Err(TreeError::IsEmpty)
+        ));
+        let other = Tree::from_newick("(((A,B),C),(D,E),G);").unwrap();
+        assert!(matches!(
+            tree.bipartition_support(&[other]),
+            Err(TreeError::DifferentTipIndices)
+        ));
+    }
+
+    #[test]
+    // Robinson foulds distances according to
+    // https://evolution.genetics.washington.edu/phylip/doc/treedist.html
+    fn weighted_robinson_foulds_treedist() {
+        let trees = [
+            "(A:0.1,(B:0.1,(H:0.1,(D:0.1,(J:0.1,(((G:0.1,E:0.1):0.1,(F:0.1,I:0.1):0.1):0.1,C:0.1):0.1):0.1):0.1):0.1):0.1);",
+            "(A:0.1,(B:0.1,(D:0.1,((J:0.1,H:0.1):0.1,(((G:0.1,E:0.1):0.1,(F:0.1,I:0.1):0.1):0.1,C:0.1):0.1):0.1):0.1):0.1);",
+            "(A:0.1,(B:0.1,(D:0.1,(H:0.1,(J:0.1,(((G:0.1,E:0.1):0.1,(F:0.1,I:0.1):0.1):0.1,C:0.1):0.1):0.1):0.1):0.1):0.1);",
+            "(A:0.1,(B:0.1,(E:0.1,(G:0.1,((F:0.1,I:0.1):0.1,((J:0.1,(H:0.1,D:0.1):0.1):0.1,C:0.1):0.1):0.1):0.1):0.1):0.1);",
+            "(A:0.1,(B:0.1,(E:0.1,(G:0.1,((F:0.1,I:0.1):0.1,(((J:0.1,H:0.1):0.1,D:0.1):0.1,C:0.1):0.1):0.1):0.1):0.1):0.1);",
+            "(A:0.1,(B:0.1,(E:0.1,((F:0.1,I:0.1):0.1,(G:0.1,((J:0.1,(H:0.1,D:0.1):0.1):0.1,C:0.1):0.1):0.1):0.1):0.1):0.1);",
+            "(A:0.1,(B:0.1,(E:0.1,((F:0.1,I:0.1):0.1,(G:0.1,(((J:0.1,H:0.1):0.1,D:0.1):0.1,C:0.1):0.1):0.1):0.1):0.1):0.1);",
+            "(A:0.1,(B:0.1,(E:0.1,((G:0.1,(F:0.1,I:0.1):0.1):0.1,((J:0.1,(H:0.1,D:0.1):0.1):0.1,C:0.1):0.1):0.1):0.1):0.1);",
+            "(A:0.1,(B:0.1,(E:0.1,((G:0.1,(F:0.1,I:0.1):0.1):0.1,(((J:0.1,H:0.1):0.1,D:0.1):0.1,C:0.1):0.1):0.1):0.1):0.1);",
+            "(A:0.1,(B:0.1,(E:0.1,(G:0.1,((F:0.1,I:0.1):0.1,((J:0.1,(H:0.1,D:0.1):0.1):0.1,C:0.1):0.1):0.1):0.1):0.1):0.1);",
+            "(A:0.1,(B:0.1,(D:0.1,(H:0.1,(J:0.1,(((G:0.1,E:0.1):0.1,(F:0.1,I:0.1):0.1):0.1,C:0.1):0.1):0.1):0.1):0.1):0.1);",
+            "(A:0.1,(B:0.1,(E:0.1,((G:0.1,(F:0.1,I:0.1):0.1):0.1,((J:0.1,(H:0.1,D:0.1):0.1):0.1,C:0.1):0.1):0.1):0.1):0.1);",
+        ];
+        let rfs = [
+            [
+                0.,
+                0.4,
+                0.2,
+                0.9999999999999999,
+                0.9999999999999999,
+                0.9999999999999999,
+                0.9999999999999999,
+                0.9999999999999999,
+                0.9999999999999999,
+                0.9999999999999999,
+                0.2,
+                0.9999999999999999,
+            ],
+            [
+                0.4,
+                0.,
+                0.2,
+                0.9999999999999999,
+                0.7999999999999999,
+                0.9999999999999999,
+                0.7999999999999999,
                 0.9999999999999999,
                 0.7999999999999999,
                 0.9999999999999999,
@@ -3080,358 +7562,2774 @@ mod tests {
                 0.2,
                 0.2,
                 0.,
-                0.4,
+                0.4,
+                0.2,
+                0.4,
+                0.9999999999999999,
+                0.4,
+            ],
+            [
+                0.9999999999999999,
+                0.9999999999999999,
+                0.9999999999999999,
+                0.2,
+                0.4,
+                0.2,
+                0.4,
+                0.,
+                0.2,
+                0.2,
+                0.9999999999999999,
+                0.,
+            ],
+            [
+                0.9999999999999999,
+                0.7999999999999999,
+                0.9999999999999999,
+                0.4,
+                0.2,
+                0.4,
+                0.2,
+                0.2,
+                0.,
+                0.4,
+                0.9999999999999999,
+                0.2,
+            ],
+            [
+                0.9999999999999999,
+                0.9999999999999999,
+                0.9999999999999999,
+                0.,
+                0.2,
+                0.2,
+                0.4,
+                0.2,
+                0.4,
+                0.,
+                0.9999999999999999,
+                0.2,
+            ],
+            [
+                0.2,
+                0.2,
+                0.,
+                0.9999999999999999,
+                0.9999999999999999,
+                0.9999999999999999,
+                0.9999999999999999,
+                0.9999999999999999,
+                0.9999999999999999,
+                0.9999999999999999,
+                0.,
+                0.9999999999999999,
+            ],
+            [
+                0.9999999999999999,
+                0.9999999999999999,
+                0.9999999999999999,
+                0.2,
+                0.4,
+                0.2,
+                0.4,
+                0.,
+                0.2,
+                0.2,
+                0.9999999999999999,
+                0.,
+            ],
+        ];
+
+        for indices in (0..trees.len()).combinations(2) {
+            let (i0, i1) = (indices[0], indices[1]);
+            let t0 = Tree::from_newick(trees[i0]).unwrap();
+            let t1 = Tree::from_newick(trees[i1]).unwrap();
+
+            assert!((t0.weighted_robinson_foulds(&t1).unwrap() - rfs[i0][i1]).abs() <= f64::EPSILON)
+        }
+    }
+
+    #[test]
+    fn bipartitions_to_newick() {
+        let tree = Tree::from_newick("(((A,B)E,C)F,(D,G)H)I;").unwrap();
+        assert!(matches!(
+            tree.bipartition_to_newick(&FixedBitSet::with_capacity(5)),
+            Err(TreeError::LeafIndexNotInitialized)
+        ));
+
+        let leaf_index = tree.get_leaf_index().unwrap();
+        let to_partition = |names: &[&str]| {
+            let mut partition = FixedBitSet::with_capacity(leaf_index.len());
+            for name in names {
+                partition.insert(leaf_index.iter().position(|n| n == name).unwrap());
+            }
+            partition
+        };
+
+        let cases = [
+            (vec!["A", "B"], "(A,B)E;"),
+            (vec!["A", "B", "C"], "((A,B)E,C)F;"),
+            // Complement of a clade
+            (vec!["C", "D", "G"], "(A,B)E;"),
+            (vec!["D"], "D;"),
+            (vec!["A", "B", "C", "D", "G"], "(((A,B)E,C)F,(D,G)H)I;"),
+        ];
+        for (names, expected) in cases {
+            assert_eq!(
+                tree.bipartition_to_newick(&to_partition(&names)).unwrap(),
+                expected
+            );
+        }
+
+        assert!(matches!(
+            tree.bipartition_to_newick(&to_partition(&["A", "C"])),
+            Err(TreeError::PartitionNotFound)
+        ));
+    }
+
+    #[test]
+    fn quartet_distances() {
+        let tree = Tree::from_newick("((A,B),(C,D),E);").unwrap();
+        let cases = [
+            ("((A,B),(C,D),E);", 0),
+            ("(((A,B),E),(C,D));", 0),
+            ("((A,B),C,D,E);", 2),
+            ("(A,B,C,D,E);", 5),
+            ("((A,C),(B,D),E);", 5),
+            ("(((A,B),C),D,E);", 2),
+        ];
+        for (newick, expected) in cases {
+            let other = Tree::from_newick(newick).unwrap();
+            assert_eq!(tree.quartet_distance(&other).unwrap(), expected, "{newick}");
+            assert_eq!(other.quartet_distance(&tree).unwrap(), expected, "{newick}");
+        }
+
+        let other = Tree::from_newick("((A,B),(C,D),F);").unwrap();
+        assert!(matches!(
+            tree.quartet_distance(&other),
+            Err(TreeError::DifferentTipIndices)
+        ));
+    }
+
+    #[test]
+    // Branch score distances according to
+    // https://evolution.genetics.washington.edu/phylip/doc/treedist.html
+    fn khuner_felsenstein_treedist() {
+        let trees = [
+            "(A:0.1,(B:0.1,(H:0.1,(D:0.1,(J:0.1,(((G:0.1,E:0.1):0.1,(F:0.1,I:0.1):0.1):0.1,C:0.1):0.1):0.1):0.1):0.1):0.1);",
+            "(A:0.1,(B:0.1,(D:0.1,((J:0.1,H:0.1):0.1,(((G:0.1,E:0.1):0.1,(F:0.1,I:0.1):0.1):0.1,C:0.1):0.1):0.1):0.1):0.1);",
+            "(A:0.1,(B:0.1,(D:0.1,(H:0.1,(J:0.1,(((G:0.1,E:0.1):0.1,(F:0.1,I:0.1):0.1):0.1,C:0.1):0.1):0.1):0.1):0.1):0.1);",
+            "(A:0.1,(B:0.1,(E:0.1,(G:0.1,((F:0.1,I:0.1):0.1,((J:0.1,(H:0.1,D:0.1):0.1):0.1,C:0.1):0.1):0.1):0.1):0.1):0.1);",
+            "(A:0.1,(B:0.1,(E:0.1,(G:0.1,((F:0.1,I:0.1):0.1,(((J:0.1,H:0.1):0.1,D:0.1):0.1,C:0.1):0.1):0.1):0.1):0.1):0.1);",
+            "(A:0.1,(B:0.1,(E:0.1,((F:0.1,I:0.1):0.1,(G:0.1,((J:0.1,(H:0.1,D:0.1):0.1):0.1,C:0.1):0.1):0.1):0.1):0.1):0.1);",
+            "(A:0.1,(B:0.1,(E:0.1,((F:0.1,I:0.1):0.1,(G:0.1,(((J:0.1,H:0.1):0.1,D:0.1):0.1,C:0.1):0.1):0.1):0.1):0.1):0.1);",
+            "(A:0.1,(B:0.1,(E:0.1,((G:0.1,(F:0.1,I:0.1):0.1):0.1,((J:0.1,(H:0.1,D:0.1):0.1):0.1,C:0.1):0.1):0.1):0.1):0.1);",
+            "(A:0.1,(B:0.1,(E:0.1,((G:0.1,(F:0.1,I:0.1):0.1):0.1,(((J:0.1,H:0.1):0.1,D:0.1):0.1,C:0.1):0.1):0.1):0.1):0.1);",
+            "(A:0.1,(B:0.1,(E:0.1,(G:0.1,((F:0.1,I:0.1):0.1,((J:0.1,(H:0.1,D:0.1):0.1):0.1,C:0.1):0.1):0.1):0.1):0.1):0.1);",
+            "(A:0.1,(B:0.1,(D:0.1,(H:0.1,(J:0.1,(((G:0.1,E:0.1):0.1,(F:0.1,I:0.1):0.1):0.1,C:0.1):0.1):0.1):0.1):0.1):0.1);",
+            "(A:0.1,(B:0.1,(E:0.1,((G:0.1,(F:0.1,I:0.1):0.1):0.1,((J:0.1,(H:0.1,D:0.1):0.1):0.1,C:0.1):0.1):0.1):0.1):0.1);",
+        ];
+        let rfs = [
+            [
+                0.,
+                0.2,
+                0.14142135623730953,
+                0.316227766016838,
+                0.316227766016838,
+                0.316227766016838,
+                0.316227766016838,
+                0.316227766016838,
+                0.316227766016838,
+                0.316227766016838,
+                0.14142135623730953,
+                0.316227766016838,
+            ],
+            [
+                0.2,
+                0.,
+                0.14142135623730953,
+                0.316227766016838,
+                0.28284271247461906,
+                0.316227766016838,
+                0.28284271247461906,
+                0.316227766016838,
+                0.28284271247461906,
+                0.316227766016838,
+                0.14142135623730953,
+                0.316227766016838,
+            ],
+            [
+                0.14142135623730953,
+                0.14142135623730953,
+                0.,
+                0.316227766016838,
+                0.316227766016838,
+                0.316227766016838,
+                0.316227766016838,
+                0.316227766016838,
+                0.316227766016838,
+                0.316227766016838,
+                0.,
+                0.316227766016838,
+            ],
+            [
+                0.316227766016838,
+                0.316227766016838,
+                0.316227766016838,
+                0.,
+                0.14142135623730953,
+                0.14142135623730953,
+                0.2,
+                0.14142135623730953,
+                0.2,
+                0.,
+                0.316227766016838,
+                0.14142135623730953,
+            ],
+            [
+                0.316227766016838,
+                0.28284271247461906,
+                0.316227766016838,
+                0.14142135623730953,
+                0.,
+                0.2,
+                0.14142135623730953,
+                0.2,
+                0.14142135623730953,
+                0.14142135623730953,
+                0.316227766016838,
+                0.2,
+            ],
+            [
+                0.316227766016838,
+                0.316227766016838,
+                0.316227766016838,
+                0.14142135623730953,
+                0.2,
+                0.,
+                0.14142135623730953,
+                0.14142135623730953,
+                0.2,
+                0.14142135623730953,
+                0.316227766016838,
+                0.14142135623730953,
+            ],
+            [
+                0.316227766016838,
+                0.28284271247461906,
+                0.316227766016838,
+                0.2,
+                0.14142135623730953,
+                0.14142135623730953,
+                0.,
+                0.2,
+                0.14142135623730953,
+                0.2,
+                0.316227766016838,
                 0.2,
-                0.4,
-                0.9999999999999999,
-                0.4,
             ],
             [
-                0.9999999999999999,
-                0.9999999999999999,
-                0.9999999999999999,
+                0.316227766016838,
+                0.316227766016838,
+                0.316227766016838,
+                0.14142135623730953,
                 0.2,
-                0.4,
+                0.14142135623730953,
                 0.2,
-                0.4,
                 0.,
-                0.2,
-                0.2,
-                0.9999999999999999,
+                0.14142135623730953,
+                0.14142135623730953,
+                0.316227766016838,
                 0.,
             ],
             [
-                0.9999999999999999,
-                0.7999999999999999,
-                0.9999999999999999,
-                0.4,
-                0.2,
-                0.4,
+                0.316227766016838,
+                0.28284271247461906,
+                0.316227766016838,
                 0.2,
+                0.14142135623730953,
                 0.2,
+                0.14142135623730953,
+                0.14142135623730953,
                 0.,
-                0.4,
-                0.9999999999999999,
                 0.2,
+                0.316227766016838,
+                0.14142135623730953,
             ],
             [
-                0.9999999999999999,
-                0.9999999999999999,
-                0.9999999999999999,
+                0.316227766016838,
+                0.316227766016838,
+                0.316227766016838,
                 0.,
+                0.14142135623730953,
+                0.14142135623730953,
                 0.2,
+                0.14142135623730953,
                 0.2,
-                0.4,
-                0.2,
-                0.4,
                 0.,
-                0.9999999999999999,
-                0.2,
+                0.316227766016838,
+                0.14142135623730953,
             ],
             [
-                0.2,
-                0.2,
+                0.14142135623730953,
+                0.14142135623730953,
                 0.,
-                0.9999999999999999,
-                0.9999999999999999,
-                0.9999999999999999,
-                0.9999999999999999,
-                0.9999999999999999,
-                0.9999999999999999,
-                0.9999999999999999,
+                0.316227766016838,
+                0.316227766016838,
+                0.316227766016838,
+                0.316227766016838,
+                0.316227766016838,
+                0.316227766016838,
+                0.316227766016838,
                 0.,
-                0.9999999999999999,
+                0.316227766016838,
             ],
             [
-                0.9999999999999999,
-                0.9999999999999999,
-                0.9999999999999999,
+                0.316227766016838,
+                0.316227766016838,
+                0.316227766016838,
+                0.14142135623730953,
                 0.2,
-                0.4,
+                0.14142135623730953,
                 0.2,
-                0.4,
                 0.,
-                0.2,
-                0.2,
-                0.9999999999999999,
+                0.14142135623730953,
+                0.14142135623730953,
+                0.316227766016838,
                 0.,
             ],
         ];
 
-        for indices in (0..trees.len()).combinations(2) {
-            let (i0, i1) = (indices[0], indices[1]);
-            let t0 = Tree::from_newick(trees[i0]).unwrap();
-            let t1 = Tree::from_newick(trees[i1]).unwrap();
+        for indices in (0..trees.len()).combinations(2) {
+            let (i0, i1) = (indices[0], indices[1]);
+            let t0 = Tree::from_newick(trees[i0]).unwrap();
+            let t1 = Tree::from_newick(trees[i1]).unwrap();
+
+            println!(
+                "[{i0}, {i1}] c:{:?} ==? t:{}",
+                t0.khuner_felsenstein(&t1).unwrap(),
+                rfs[i0][i1]
+            );
+
+            assert_eq!(t0.khuner_felsenstein(&t1).unwrap(), rfs[i0][i1])
+        }
+    }
+
+    #[test]
+    fn rooted_vs_unrooted_partitions() {
+        let rooted = Tree::from_newick("((Tip_3,Tip_4),(Tip_0,(Tip_1,Tip_2)));").unwrap();
+        let unrooted = Tree::from_newick("(Tip_3,Tip_4,(Tip_0,(Tip_1,Tip_2)));").unwrap();
+
+        let parts_rooted = rooted.get_partitions().unwrap();
+        let parts_unrooted = unrooted.get_partitions().unwrap();
+
+        assert_eq!(parts_rooted, parts_unrooted);
+    }
+
+    #[test]
+    fn new_vs_old() {
+        let trees = [
+            "(A:0.1,(B:0.1,(H:0.1,(D:0.1,(J:0.1,(((G:0.1,E:0.1):0.1,(F:0.1,I:0.1):0.1):0.1,C:0.1):0.1):0.1):0.1):0.1):0.1);",
+            "(A:0.1,(B:0.1,(D:0.1,((J:0.1,H:0.1):0.1,(((G:0.1,E:0.1):0.1,(F:0.1,I:0.1):0.1):0.1,C:0.1):0.1):0.1):0.1):0.1);",
+            "(A:0.1,(B:0.1,(D:0.1,(H:0.1,(J:0.1,(((G:0.1,E:0.1):0.1,(F:0.1,I:0.1):0.1):0.1,C:0.1):0.1):0.1):0.1):0.1):0.1);",
+            "(A:0.1,(B:0.1,(E:0.1,(G:0.1,((F:0.1,I:0.1):0.1,((J:0.1,(H:0.1,D:0.1):0.1):0.1,C:0.1):0.1):0.1):0.1):0.1):0.1);",
+            "(A:0.1,(B:0.1,(E:0.1,(G:0.1,((F:0.1,I:0.1):0.1,(((J:0.1,H:0.1):0.1,D:0.1):0.1,C:0.1):0.1):0.1):0.1):0.1):0.1);",
+            "(A:0.1,(B:0.1,(E:0.1,((F:0.1,I:0.1):0.1,(G:0.1,((J:0.1,(H:0.1,D:0.1):0.1):0.1,C:0.1):0.1):0.1):0.1):0.1):0.1);",
+            "(A:0.1,(B:0.1,(E:0.1,((F:0.1,I:0.1):0.1,(G:0.1,(((J:0.1,H:0.1):0.1,D:0.1):0.1,C:0.1):0.1):0.1):0.1):0.1):0.1);",
+            "(A:0.1,(B:0.1,(E:0.1,((G:0.1,(F:0.1,I:0.1):0.1):0.1,((J:0.1,(H:0.1,D:0.1):0.1):0.1,C:0.1):0.1):0.1):0.1):0.1);",
+            "(A:0.1,(B:0.1,(E:0.1,((G:0.1,(F:0.1,I:0.1):0.1):0.1,(((J:0.1,H:0.1):0.1,D:0.1):0.1,C:0.1):0.1):0.1):0.1):0.1);",
+            "(A:0.1,(B:0.1,(E:0.1,(G:0.1,((F:0.1,I:0.1):0.1,((J:0.1,(H:0.1,D:0.1):0.1):0.1,C:0.1):0.1):0.1):0.1):0.1):0.1);",
+            "(A:0.1,(B:0.1,(D:0.1,(H:0.1,(J:0.1,(((G:0.1,E:0.1):0.1,(F:0.1,I:0.1):0.1):0.1,C:0.1):0.1):0.1):0.1):0.1):0.1);",
+            "(A:0.1,(B:0.1,(E:0.1,((G:0.1,(F:0.1,I:0.1):0.1):0.1,((J:0.1,(H:0.1,D:0.1):0.1):0.1,C:0.1):0.1):0.1):0.1):0.1);",
+        ];
+
+        for newicks in trees.iter().combinations(2) {
+            let t1 = Tree::from_newick(newicks[0]).unwrap();
+            let t2 = Tree::from_newick(newicks[1]).unwrap();
+
+            assert_eq!(
+                t1.robinson_foulds(&t2).unwrap(),
+                t1.robinson_foulds(&t2).unwrap()
+            )
+        }
+    }
+
+    // the reference distance matrix was computed with ete3
+    #[test]
+    fn compute_distance_matrix() {
+        let tree = Tree::from_newick("((A:0.1,B:0.2)F:0.6,(C:0.3,D:0.4)E:0.5)G;").unwrap();
+        let true_dists: HashMap<(String, String), f64> = HashMap::from_iter(vec![
+            (("A".into(), "B".into()), 0.30000000000000004),
+            (("A".into(), "C".into()), 1.5),
+            (("A".into(), "D".into()), 1.6),
+            (("B".into(), "C".into()), 1.6),
+            (("B".into(), "D".into()), 1.7000000000000002),
+            (("C".into(), "D".into()), 0.7),
+        ]);
+
+        let matrix = tree.distance_matrix().unwrap();
+
+        for ((n1, n2), dist) in true_dists {
+            assert!(
+                (dist - matrix.get(&n1, &n2).unwrap()) <= f64::EPSILON,
+                "d({n1},{n2}) want:{dist} got:{}",
+                matrix.get(&n1, &n2).unwrap()
+            )
+        }
+    }
+
+    #[test]
+    fn nni_moves() {
+        let newick = "((A:0.1,B:0.2)E:0.5,(C:0.3,D:0.4)F:0.6)G;";
+        let cases = [
+            (NniSwap::Left, "(((C:0.3,D:0.4)F:0.6,B:0.2)E:0.5,A:0.1)G;"),
+            (NniSwap::Right, "((A:0.1,(C:0.3,D:0.4)F:0.6)E:0.5,B:0.2)G;"),
+        ];
+
+        for (swap, expected) in cases {
+            let mut tree = Tree::from_newick(newick).unwrap();
+            let e = tree.get_by_name("E").unwrap().id;
+            let g = tree.get_by_name("G").unwrap().id;
+
+            // Edge order should not matter
+            tree.nni((&e, &g), swap).unwrap();
+            assert_eq!(tree.to_newick().unwrap(), expected);
+
+            for node in tree.nodes.iter() {
+                let n_edges = tree.get_path_from_root(&node.id).unwrap().len() - 1;
+                assert_eq!(node.get_depth(), n_edges);
+                if let Some(parent) = node.parent {
+                    let parent = tree.get(&parent).unwrap();
+                    assert_eq!(parent.get_child_edge(&node.id), node.parent_edge);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn nni_errors() {
+        let mut tree = Tree::from_newick("((A,B,C)E,(D,F)G)H;").unwrap();
+        let e = tree.get_by_name("E").unwrap().id;
+        let g = tree.get_by_name("G").unwrap().id;
+        let h = tree.get_by_name("H").unwrap().id;
+
+        assert!(matches!(
+            tree.nni((&h, &e), NniSwap::Left),
+            Err(TreeError::IsNotBinary)
+        ));
+        assert!(tree.nni((&e, &g), NniSwap::Left).is_err());
+    }
+
+    #[test]
+    fn spr_moves() {
+        let newick = "((A:1,B:1)E:1,(C:1,D:2)F:1)G;";
+
+        // Regrafting next to the current position or on the branch above
+        // the former parent should not change the topology
+        let mut tree = Tree::from_newick(newick).unwrap();
+        let reference = tree.clone();
+        let a = tree.get_by_name("A").unwrap().id;
+        let b = tree.get_by_name("B").unwrap().id;
+        let e = tree.get_by_name("E").unwrap().id;
+        tree.spr(&a, (&b, &e)).unwrap();
+        assert_eq!(tree.robinson_foulds(&reference).unwrap(), 0);
+        assert!((tree.length().unwrap() - reference.length().unwrap()).abs() < f64::EPSILON);
+
+        // Pruning a child of the root makes its sibling the new root
+        let mut tree = Tree::from_newick(newick).unwrap();
+        let e = tree.get_by_name("E").unwrap().id;
+        let c = tree.get_by_name("C").unwrap().id;
+        let f = tree.get_by_name("F").unwrap().id;
+        tree.spr(&e, (&c, &f)).unwrap();
+        assert_eq!(
+            tree.to_newick().unwrap(),
+            "((C:0.5,(A:1,B:1)E:1):0.5,D:2)F;"
+        );
+        assert_eq!(tree.get_root().unwrap(), f);
+
+        for node in tree.nodes.iter().filter(|n| !n.deleted) {
+            let n_edges = tree.get_path_from_root(&node.id).unwrap().len() - 1;
+            assert_eq!(node.get_depth(), n_edges);
+            if let Some(parent) = node.parent {
+                let parent = tree.get(&parent).unwrap();
+                assert_eq!(parent.get_child_edge(&node.id), node.parent_edge);
+            }
+        }
+    }
+
+    #[test]
+    fn spr_errors() {
+        let mut tree = Tree::from_newick("((A,B)E,(C,D)F)G;").unwrap();
+        let a = tree.get_by_name("A").unwrap().id;
+        let b = tree.get_by_name("B").unwrap().id;
+        let c = tree.get_by_name("C").unwrap().id;
+        let e = tree.get_by_name("E").unwrap().id;
+        let g = tree.get_by_name("G").unwrap().id;
+
+        assert!(matches!(
+            tree.spr(&e, (&e, &a)),
+            Err(TreeError::RegraftInsideSubtree(_))
+        ));
+        assert!(tree.spr(&g, (&e, &a)).is_err());
+        assert!(tree.spr(&a, (&b, &c)).is_err());
+    }
+
+    #[test]
+    fn read_nhx() {
+        let newick = "((A:1[&&NHX:S=human:E=1.1.1.1],B:1[&&NHX:S=mouse])C:1[&&NHX:D=Y:B=95:flag=Red],D:2[comment])E;";
+        let tree = Tree::from_newick(newick).unwrap();
+
+        let a = tree.get_by_name("A").unwrap();
+        let a_nhx = a.nhx.as_ref().unwrap();
+        assert_eq!(a_nhx.species(), Some("human"));
+        assert_eq!(a_nhx.ec_number(), Some("1.1.1.1"));
+        assert_eq!(a.comment, None);
+
+        let c_nhx = tree.get_by_name("C").unwrap().nhx.clone().unwrap();
+        assert_eq!(c_nhx.duplication(), Some(true));
+        assert_eq!(c_nhx.bootstrap(), Some(95.0));
+        assert_eq!(c_nhx.get("flag"), Some("Red"));
+
+        let d = tree.get_by_name("D").unwrap();
+        assert!(d.nhx.is_none());
+        assert_eq!(d.comment, Some("comment".into()));
+
+        assert_eq!(tree.to_newick().unwrap(), newick);
+        assert_eq!(
+            tree.to_formatted_newick(NewickFormat::NoComments).unwrap(),
+            "((A:1,B:1)C:1,D:2)E;"
+        );
+    }
+
+    #[test]
+    fn nhx_round_trip() {
+        let newick = "((A[&&NHX:X:S=human],B[&&NHX:flag=Red:D=N:B=80:E=])C[&&NHX:S=],D)E;";
+        let tree = Tree::from_newick(newick).unwrap();
+        assert_eq!(tree.to_newick().unwrap(), newick);
+
+        let a_nhx = tree.get_by_name("A").unwrap().nhx.clone().unwrap();
+        assert_eq!(
+            a_nhx.pairs,
+            vec![("X".into(), None), ("S".into(), Some("human".into()))]
+        );
+        assert_eq!(a_nhx.get("X"), None);
+        assert_eq!(a_nhx.species(), Some("human"));
+
+        // Empty values are kept as such
+        let b_nhx = tree.get_by_name("B").unwrap().nhx.clone().unwrap();
+        assert_eq!(b_nhx.get("E"), Some(""));
+        assert_eq!(b_nhx.duplication(), Some(false));
+
+        // Setting a value keeps the position of the key
+        let mut nhx = a_nhx.clone();
+        nhx.set("X", Some("1".into()));
+        nhx.set("D", Some("Y".into()));
+        assert_eq!(nhx.to_string(), "&&NHX:X=1:S=human:D=Y");
+        assert_eq!(
+            crate::tree::NhxAnnotation::from_comment(&nhx.to_string()),
+            Some(nhx)
+        );
+    }
+
+    #[test]
+    fn midpoint_rooting() {
+        let newicks = [
+            "((A:0.1,B:0.2)G:0.1,(C:0.3,D:0.4)E:0.5)F;",
+            "(A:1,B:2,(C:3,D:7)E:2)F;",
+            "(A:1,B:1,C:1)D;",
+            "((A:1,(B:3,C:6)E:4)F:2,(G:1,H:0.5)I:1.5)J;",
+        ];
+
+        for newick in newicks {
+            let mut tree = Tree::from_newick(newick).unwrap();
+            let diameter = tree.diameter().unwrap();
+            let n_leaves = tree.n_leaves();
+
+            tree.midpoint_root().unwrap();
+            assert!(tree.is_rooted().unwrap());
+            assert_eq!(tree.n_leaves(), n_leaves);
+            assert!((tree.diameter().unwrap() - diameter).abs() < 1e-10);
+            assert!((tree.height().unwrap() - diameter / 2.0).abs() < 1e-10);
+
+            for node in tree.nodes.iter().filter(|n| !n.deleted) {
+                let n_edges = tree.get_path_from_root(&node.id).unwrap().len() - 1;
+                assert_eq!(node.get_depth(), n_edges);
+            }
+        }
+
+        let mut tree = Tree::from_newick("((A,B),C);").unwrap();
+        assert!(matches!(
+            tree.midpoint_root(),
+            Err(TreeError::MissingBranchLengths)
+        ));
+    }
+
+    #[test]
+    fn midpoint_of_longest_path() {
+        let newicks = [
+            "(A:1,B:2,(C:3,D:7)E:2)F;",
+            "(A:1,B:1,C:1)D;",
+            "((A:1,(B:3,C:6)E:4)F:2,(G:1,H:0.5)I:1.5,K:2)J;",
+            // The midpoint is on a branch adjacent to the virtual root
+            "((A:1,B:1)E:4,C:1,D:1)F;",
+        ];
+
+        for newick in newicks {
+            let mut tree = Tree::from_newick(newick).unwrap();
+            let old_root = tree.get_root().unwrap();
+            let diameter = tree.diameter().unwrap();
+            let (source, target) = tree
+                .get_leaves()
+                .into_iter()
+                .tuple_combinations()
+                .find(|(a, b)| {
+                    let dist = tree.get_distance(a, b).unwrap().0.unwrap();
+                    (dist - diameter).abs() < 1e-10
+                })
+                .unwrap();
+
+            tree.root_at_midpoint_of_longest_path().unwrap();
+            assert!(tree.is_rooted().unwrap());
+            assert!(tree.is_binary().unwrap());
+            assert_eq!(tree.get(&old_root).unwrap().children.len(), 2);
+
+            let root = tree.get_root().unwrap();
+            let to_source = tree.get_distance(&root, &source).unwrap().0.unwrap();
+            let to_target = tree.get_distance(&root, &target).unwrap().0.unwrap();
+            assert!((to_source - diameter / 2.0).abs() < 1e-10);
+            assert!((to_target - diameter / 2.0).abs() < 1e-10);
+        }
+
+        let mut tree = Tree::from_newick("(A:1,B:1,(C:1,D:1,E:1)F:1)G;").unwrap();
+        assert!(matches!(
+            tree.root_at_midpoint_of_longest_path(),
+            Err(TreeError::IsNotBinary)
+        ));
+
+        let mut tree = Tree::from_newick("(A:1,B,(C:1,D:1)E:1)F;").unwrap();
+        assert!(matches!(
+            tree.root_at_midpoint_of_longest_path(),
+            Err(TreeError::MissingBranchLengths)
+        ));
+
+        let mut tree = Tree::from_newick("((A:1,B:1)E:1,C:1)F;").unwrap();
+        assert!(tree.root_at_midpoint_of_longest_path().is_err());
+    }
+
+    #[test]
+    fn outgroup_rooting() {
+        let newick = "((A:1,B:2)G:1,(C:3,D:4)E:2,H:1)F;";
+        let cases = [
+            (vec!["A"], "(A:0.5,(B:2,((C:3,D:4)E:2,H:1)F:1)G:0.5);"),
+            (vec!["C", "D"], "((C:3,D:4)E:1,((A:1,B:2)G:1,H:1)F:1);"),
+            // The outgroup spans the root of the tree
+            (vec!["A", "B", "H"], "(((A:1,B:2)G:1,H:1)F:1,(C:3,D:4)E:1);"),
+        ];
+
+        for (outgroup, expected) in cases {
+            let mut tree = Tree::from_newick(newick).unwrap();
+            let length = tree.length().unwrap();
+            let outgroup: Vec<_> = outgroup
+                .into_iter()
+                .map(|name| tree.get_by_name(name).unwrap().id)
+                .collect();
+
+            tree.outgroup_root(&outgroup).unwrap();
+            assert!(tree.is_rooted().unwrap());
+            assert_eq!(tree.to_newick().unwrap(), expected);
+            assert!((tree.length().unwrap() - length).abs() < f64::EPSILON);
+        }
+
+        // Outgroup is not a clade
+        let mut tree = Tree::from_newick(newick).unwrap();
+        let outgroup = [
+            tree.get_by_name("A").unwrap().id,
+            tree.get_by_name("C").unwrap().id,
+        ];
+        assert!(tree.outgroup_root(&outgroup).is_err());
+
+        let mut tree = Tree::from_newick("(A,B,C,D)E;").unwrap();
+        let a = tree.get_by_name("A").unwrap().id;
+        assert!(matches!(
+            tree.outgroup_root(&[a]),
+            Err(TreeError::IsNotBinary)
+        ));
+    }
+
+    #[test]
+    fn ladderize_tree() {
+        let newick = "((C:0.3,(D:0.4,E:0.5)F:0.6)G:0.7,A:0.1,B:0.2)H;";
+
+        let mut tree = Tree::from_newick(newick).unwrap();
+        tree.ladderize(true).unwrap();
+        assert_eq!(
+            tree.to_newick().unwrap(),
+            "(A:0.1,B:0.2,(C:0.3,(D:0.4,E:0.5)F:0.6)G:0.7)H;"
+        );
+
+        let mut tree = Tree::from_newick(newick).unwrap();
+        tree.ladderize(false).unwrap();
+        assert_eq!(
+            tree.to_newick().unwrap(),
+            "(((D:0.4,E:0.5)F:0.6,C:0.3)G:0.7,A:0.1,B:0.2)H;"
+        );
+    }
+
+    #[test]
+    fn extract_subtrees() {
+        let tree = Tree::from_newick(
+            "((A:0.1[a],(C:0.3,E:0.2)D:0.4[&&NHX:S=human])B:0.5,((H:0.1)I:0.2)G:0.3)F;",
+        )
+        .unwrap();
+
+        let b = tree.get_by_name("B").unwrap().id;
+        let subtree = tree.extract_subtree(&b).unwrap();
+        assert_eq!(
+            subtree.to_newick().unwrap(),
+            "(A:0.1[a],(C:0.3,E:0.2)D:0.4[&&NHX:S=human])B;"
+        );
+        assert_eq!(subtree.size(), 5);
+        assert_eq!(subtree.get_root().unwrap(), 0);
+        for node in subtree.nodes.iter() {
+            let n_edges = subtree.get_path_from_root(&node.id).unwrap().len() - 1;
+            assert_eq!(node.get_depth(), n_edges);
+        }
+
+        // Extracting the root copies the whole tree
+        let root = tree.get_root().unwrap();
+        let copy = tree.extract_subtree(&root).unwrap();
+        assert_eq!(copy.to_newick().unwrap(), tree.to_newick().unwrap());
+
+        // Extracting a leaf gives a single node tree
+        let h = tree.get_by_name("H").unwrap().id;
+        assert_eq!(tree.extract_subtree(&h).unwrap().to_newick().unwrap(), "H;");
+    }
+
+    #[test]
+    fn graft_trees() {
+        let mut tree = Tree::from_newick("((A:1,B:1)E:2,(C:1,D:1)G:1)F;").unwrap();
+        let subtree = Tree::from_newick("((H:1,I:2)J:1,K:3)L;").unwrap();
+        let n_leaves = tree.n_leaves();
+
+        // Compute partitions to make sure the cache is reset
+        tree.get_partitions().unwrap();
+
+        let c = tree.get_by_name("C").unwrap().id;
+        let g = tree.get_by_name("G").unwrap().id;
+        let connector = tree.graft(&subtree, (&c, &g), None).unwrap();
+
+        assert_eq!(
+            tree.to_newick().unwrap(),
+            "((A:1,B:1)E:2,((C:0.5,((H:1,I:2)J:1,K:3)L)D:1)G:1)F;".replace("D:1)G", ":0.5,D:1)G")
+        );
+        assert_eq!(tree.get(&connector).unwrap().parent, Some(g));
+        assert_eq!(tree.n_leaves(), n_leaves + subtree.n_leaves());
+        assert_eq!(tree.get_partitions().unwrap().len(), 4);
+
+        for node in tree.nodes.iter() {
+            let n_edges = tree.get_path_from_root(&node.id).unwrap().len() - 1;
+            assert_eq!(node.get_depth(), n_edges);
+            if let Some(parent) = node.parent {
+                let parent = tree.get(&parent).unwrap();
+                assert_eq!(parent.get_child_edge(&node.id), node.parent_edge);
+            }
+        }
+
+        // The target must be a branch of the tree
+        let a = tree.get_by_name("A").unwrap().id;
+        assert!(tree.graft(&subtree, (&a, &c), None).is_err());
+    }
+
+    #[test]
+    fn gamma() {
+        // Balanced tree with equal intervals
+        let tree = Tree::from_newick("((A:1,B:1):2,(C:2,D:2):1);").unwrap();
+        assert!((tree.gamma_statistic().unwrap() - -0.544331).abs() < 1e-6);
+
+        // Nodes close to the tips give a positive statistic
+        let tree = Tree::from_newick("(((A:0.1,B:0.1):0.1,C:0.2):0.8,D:1);").unwrap();
+        assert!(tree.gamma_statistic().unwrap() > 0.0);
+
+        // Nodes close to the root give a negative statistic
+        let tree = Tree::from_newick("(((A:0.8,B:0.8):0.1,C:0.9):0.1,D:1);").unwrap();
+        assert!(tree.gamma_statistic().unwrap() < 0.0);
+
+        let tree = Tree::from_newick("(A:1,B:1,C:1);").unwrap();
+        assert!(matches!(
+            tree.gamma_statistic(),
+            Err(TreeError::IsNotRooted)
+        ));
+
+        let tree = Tree::from_newick("((A,B),(C,D));").unwrap();
+        assert!(matches!(
+            tree.gamma_statistic(),
+            Err(TreeError::MissingBranchLengths)
+        ));
+
+        let tree = Tree::from_newick("((A:1,B:1):2,(C:2,D:2.5):1);").unwrap();
+        assert!(matches!(
+            tree.gamma_statistic(),
+            Err(TreeError::GeneralError(_))
+        ));
+    }
+
+    #[test]
+    fn ultrametric() {
+        let tree = Tree::from_newick("((A:0.1,B:0.1)G:0.4,(C:0.3,D:0.3)E:0.2)F;").unwrap();
+        assert!(tree.is_ultrametric(1e-10).unwrap());
+
+        let tree = Tree::from_newick("((A:0.1,B:0.2)G:0.4,(C:0.3,D:0.3)E:0.2)F;").unwrap();
+        assert!(!tree.is_ultrametric(1e-10).unwrap());
+        assert!(tree.is_ultrametric(0.1).unwrap());
+
+        let tree = Tree::from_newick("(A:0.1,B:0.1,C:0.1)F;").unwrap();
+        assert!(matches!(
+            tree.is_ultrametric(0.1),
+            Err(TreeError::IsNotRooted)
+        ));
+        let tree = Tree::from_newick("((A,B),C);").unwrap();
+        assert!(matches!(
+            tree.is_ultrametric(0.1),
+            Err(TreeError::MissingBranchLengths)
+        ));
+    }
+
+    #[test]
+    fn make_ultrametric() {
+        let mut tree = Tree::from_newick("((A:0.1,B:0.2)G:0.4,(C:0.3,D:0.5)E:0.2)F;").unwrap();
+        tree.make_ultrametric_tips().unwrap();
+        assert!(tree.is_ultrametric(1e-10).unwrap());
+        // Tips are moved to the mean root to tip distance
+        assert!((tree.height().unwrap() - 0.575).abs() < 1e-10);
+
+        // The target distance is raised to avoid negative branch lengths
+        let mut tree = Tree::from_newick("((A:0.1,B:0.1)G:2,(C:0.1,D:0.1)E:0.1)F;").unwrap();
+        tree.make_ultrametric_tips().unwrap();
+        assert!(tree.is_ultrametric(1e-10).unwrap());
+        for leaf in tree.get_leaves() {
+            let node = tree.get(&leaf).unwrap();
+            assert!(node.parent_edge.unwrap() >= 0.0);
+            let parent = tree.get(&node.parent.unwrap()).unwrap();
+            assert_eq!(parent.get_child_edge(&leaf), node.parent_edge);
+        }
+    }
+
+    #[test]
+    fn faith_pd() {
+        let tree = Tree::from_newick("((A:1,B:2)E:3,((C:4,D:5)F:6,H:7)I:8)G;").unwrap();
+        let ids = |names: &[&str]| -> Vec<NodeId> {
+            names
+                .iter()
+                .map(|name| tree.get_by_name(name).unwrap().id)
+                .collect()
+        };
+
+        let cases = [
+            (vec![], 0.0),
+            (vec!["A"], 0.0),
+            (vec!["A", "B"], 3.0),
+            (vec!["C", "D", "H"], 22.0),
+            (vec!["A", "C"], 22.0),
+            (vec!["A", "B", "C", "D", "H"], tree.length().unwrap()),
+        ];
+        for (names, expected) in cases {
+            let pd = tree.phylogenetic_diversity(&ids(&names)).unwrap();
+            assert!((pd - expected).abs() < f64::EPSILON);
+        }
+
+        let tree = Tree::from_newick("((A:1,B)E:3,C:2)G;").unwrap();
+        let tips = [
+            tree.get_by_name("A").unwrap().id,
+            tree.get_by_name("B").unwrap().id,
+        ];
+        assert!(matches!(
+            tree.phylogenetic_diversity(&tips),
+            Err(TreeError::MissingBranchLengths)
+        ));
+    }
+
+    #[test]
+    fn b2_index() {
+        let cases = [
+            ("((A,B),(C,D));", 2.0),
+            ("(((A,B),C),D);", 1.75),
+            ("((((A,B),C),D),E);", 1.875),
+            // Polytomies: 3 leaves with p = 1/6 and 4 leaves with p = 1/8
+            ("((A,B,C),(D,E,F,G));", 0.5 * 6.0f64.log2() + 1.5),
+        ];
+        for (newick, expected) in cases {
+            let tree = Tree::from_newick(newick).unwrap();
+            assert!((tree.b2().unwrap() - expected).abs() < 1e-10);
+        }
+
+        let tree = Tree::from_newick("((A,B),C,D);").unwrap();
+        assert!(matches!(tree.b2(), Err(TreeError::IsNotRooted)));
+        assert!(matches!(Tree::new().b2(), Err(TreeError::IsEmpty)));
+    }
+
+    #[test]
+    fn upgma_reproduces_distances() {
+        let tree = Tree::from_newick("(((A:1,B:1):2,C:3):1,(D:2.5,(E:0.5,F:0.5):2):1.5);").unwrap();
+        let matrix = tree.distance_matrix().unwrap();
+
+        let built = Tree::upgma(&matrix).unwrap();
+        assert!(built.is_rooted().unwrap());
+        assert!(built.is_binary().unwrap());
+        assert_eq!(built.robinson_foulds(&tree).unwrap(), 0);
+
+        let built_matrix = built.distance_matrix().unwrap();
+        for ((i, j), d) in matrix.indexed_iter() {
+            let (a, b) = (&matrix.taxa[i], &matrix.taxa[j]);
+            assert!((built_matrix.get(a, b).unwrap() - d).abs() < 1e-10);
+        }
+    }
+
+    #[test]
+    fn nj_reproduces_distances() {
+        let tree =
+            Tree::from_newick("(A:0.1,(B:0.2,(C:0.3,D:0.4):0.5):0.6,((E:0.7,F:0.8):0.9,G:1):1.1);")
+                .unwrap();
+        let matrix = tree.distance_matrix().unwrap();
+
+        let built = Tree::nj(&matrix).unwrap();
+        assert_eq!(built.robinson_foulds(&tree).unwrap(), 0);
+        assert!((built.length().unwrap() - tree.length().unwrap()).abs() < 1e-10);
+
+        let built_matrix = built.distance_matrix().unwrap();
+        for ((i, j), d) in matrix.indexed_iter() {
+            let (a, b) = (&matrix.taxa[i], &matrix.taxa[j]);
+            assert!((built_matrix.get(a, b).unwrap() - d).abs() < 1e-10);
+        }
+
+        let taxa = matrix.taxa.clone();
+        let mut missing = DistanceMatrix::new_with_size(taxa.len());
+        missing.set_taxa(taxa.clone()).unwrap();
+        for pair in taxa.iter().combinations(2) {
+            let d = if pair[0] == "A" && pair[1] == "B" {
+                f64::NAN
+            } else {
+                1.0
+            };
+            missing.set(pair[0], pair[1], d).unwrap();
+        }
+        assert!(matches!(
+            Tree::nj(&missing),
+            Err(TreeError::MissingBranchLengths)
+        ));
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn serde_roundtrip() {
+        let newick = "((A:0.1[&&NHX:S=human],B:0.2)E:0.3[comment],(C,D:0.4)F)G;";
+        let mut tree = Tree::from_newick(newick).unwrap();
+        let c = tree.get_by_name("C").unwrap().id;
+        tree.prune(&c).unwrap();
+        tree.get_mut(&0).unwrap().support = Some(0.9);
+
+        let json = serde_json::to_string(&tree).unwrap();
+        let from_json: Tree = serde_json::from_str(&json).unwrap();
+
+        let toml = toml::to_string(&tree).unwrap();
+        let from_toml: Tree = toml::from_str(&toml).unwrap();
+
+        for copy in [from_json, from_toml] {
+            assert_eq!(copy.to_newick().unwrap(), tree.to_newick().unwrap());
+            assert_eq!(copy.nodes.len(), tree.nodes.len());
+            for (n1, n2) in copy.nodes.iter().zip(tree.nodes.iter()) {
+                assert_eq!(n1.id, n2.id);
+                assert_eq!(n1.parent, n2.parent);
+                assert_eq!(n1.children, n2.children);
+                assert_eq!(n1.child_edges, n2.child_edges);
+                assert_eq!(n1.comment, n2.comment);
+                assert_eq!(n1.nhx, n2.nhx);
+                assert_eq!(n1.support, n2.support);
+                assert_eq!(n1.get_depth(), n2.get_depth());
+                assert_eq!(n1.deleted, n2.deleted);
+            }
+        }
+    }
+
+    #[test]
+    fn lazy_traversals() {
+        let newicks = [
+            "((A,(C,E)D)B,((H)I)G)F;",
+            "((3,4)2,(6,7)5)1;",
+            "(A,B,(C,D,(E,F)G)H)I;",
+            "((A)B)C;",
+        ];
+
+        for newick in newicks {
+            let tree = Tree::from_newick(newick).unwrap();
+            for root in tree.preorder(&tree.get_root().unwrap()).unwrap() {
+                let ids = |iter: Box<dyn Iterator<Item = &Node> + '_>| {
+                    iter.map(|node| node.id).collect::<Vec<_>>()
+                };
+
+                assert_eq!(
+                    ids(Box::new(tree.iter_preorder(&root).unwrap())),
+                    tree.preorder(&root).unwrap()
+                );
+                assert_eq!(
+                    ids(Box::new(tree.iter_postorder(&root).unwrap())),
+                    tree.postorder(&root).unwrap()
+                );
+                assert_eq!(
+                    ids(Box::new(tree.iter_levelorder(&root).unwrap())),
+                    tree.levelorder(&root).unwrap()
+                );
+                match tree.inorder(&root) {
+                    Ok(inorder) => {
+                        assert_eq!(ids(Box::new(tree.iter_inorder(&root).unwrap())), inorder)
+                    }
+                    Err(_) => assert!(matches!(
+                        tree.iter_inorder(&root),
+                        Err(TreeError::IsNotBinary)
+                    )),
+                }
+            }
+        }
+
+        let tree = Tree::from_newick("(A,B);").unwrap();
+        assert!(tree.iter_preorder(&10).is_err());
+    }
+
+    #[test]
+    fn mutable_traversal() {
+        let mut tree = Tree::from_newick("((A:1,B:2)C:0.5,(D:3,E:1)F:1.5)G;").unwrap();
+        let root = tree.get_root().unwrap();
+        let order = tree.preorder(&root).unwrap();
+
+        let visited: Vec<_> = tree.iter_preorder_mut().map(|node| node.id).collect();
+        assert_eq!(visited, order);
+
+        // Renaming tips after computing bipartitions resets the cached leaf index
+        assert_eq!(tree.get_partitions().unwrap().len(), 1);
+        for node in tree.iter_preorder_mut() {
+            if let Some(name) = node.name.as_mut() {
+                name.insert_str(0, "sp_");
+            }
+        }
+        assert_eq!(
+            tree.to_formatted_newick(NewickFormat::OnlyNames).unwrap(),
+            "((sp_A,sp_B)sp_C,(sp_D,sp_E)sp_F)sp_G;"
+        );
+        let other = Tree::from_newick("((sp_A,sp_D),(sp_B,sp_E));").unwrap();
+        assert_eq!(tree.get_leaf_index().unwrap()[0], "sp_A");
+        assert_eq!(tree.robinson_foulds(&other).unwrap(), 2);
+
+        // Pruned nodes are not visited
+        let f = tree.get_by_name("sp_F").unwrap().id;
+        tree.prune(&f).unwrap();
+        assert_eq!(tree.iter_preorder_mut().count(), 4);
+
+        assert_eq!(Tree::new().iter_preorder_mut().count(), 0);
+    }
+
+    #[test]
+    fn node_iterators() {
+        let mut tree = Tree::from_newick("((A:1,B:2)C:0.5,(D:3,E:1)F:1.5)G;").unwrap();
+
+        let ids: Vec<_> = tree.node_iter().map(|node| node.id).collect();
+        assert_eq!(ids, (0..tree.size()).collect::<Vec<_>>());
+        assert_eq!(
+            tree.node_iter().filter(|node| node.is_tip()).count(),
+            tree.n_leaves()
+        );
+
+        for node in tree.node_iter_mut() {
+            node.comment = node.name.clone();
+        }
+        assert_eq!(
+            tree.to_newick().unwrap(),
+            "((A:1[A],B:2[B])C:0.5[C],(D:3[D],E:1[E])F:1.5[F])G[G];"
+        );
+
+        // Renaming tips after computing bipartitions resets the cached leaf index
+        let other = Tree::from_newick("((a,d),(b,e));").unwrap();
+        assert_eq!(tree.get_partitions().unwrap().len(), 1);
+        for node in tree.node_iter_mut().filter(|node| node.is_tip()) {
+            node.name = node.name.as_ref().map(|name| name.to_lowercase());
+        }
+        assert_eq!(tree.robinson_foulds(&other).unwrap(), 2);
+        assert_eq!(tree.get_leaf_index().unwrap(), vec!["a", "b", "d", "e"]);
+
+        // Deleted nodes are skipped
+        let f = tree.get_by_name("F").unwrap().id;
+        tree.prune(&f).unwrap();
+        assert_eq!(tree.node_iter().count(), 4);
+        assert_eq!(tree.node_iter_mut().count(), 4);
+        assert!(tree.node_iter().all(|node| tree.get(&node.id).is_ok()));
+
+        assert_eq!(Tree::new().node_iter().count(), 0);
+    }
+
+    #[test]
+    fn edge_list() {
+        let mut tree = Tree::from_newick("((A:1,B:2)C:0.5,(D:3,E)F:1.5)G;").unwrap();
+
+        let edges = tree.edge_list();
+        assert_eq!(edges.len(), tree.edge_count());
+        assert_eq!(tree.edge_count(), tree.size() - 1);
+        assert_eq!(tree.edges_with_lengths(), 5);
+
+        // Children are listed in preorder
+        let root = tree.get_root().unwrap();
+        let preorder = tree.preorder(&root).unwrap();
+        let children: Vec<_> = edges.iter().map(|(_, child, _)| *child).collect();
+        assert_eq!(children, preorder[1..]);
+
+        for (parent, child, length) in edges {
+            let node = tree.get(&child).unwrap();
+            assert_eq!(node.parent, Some(parent));
+            assert_eq!(tree.get(&parent).unwrap().get_child_edge(&child), length);
+        }
+
+        let f = tree.get_by_name("F").unwrap().id;
+        tree.prune(&f).unwrap();
+        assert_eq!(tree.edge_list().len(), 3);
+        assert_eq!(tree.edge_count(), 3);
+        assert_eq!(tree.edges_with_lengths(), 3);
+
+        assert!(Tree::new().edge_list().is_empty());
+        assert_eq!(Tree::new().edge_count(), 0);
+    }
+
+    #[test]
+    fn node_identity() {
+        let tree1 = Tree::from_newick("((A:1,B:2)C:1,(D:3[x],E:0)F)G;").unwrap();
+        let tree2 = Tree::from_newick("((E:-0,D:3[x])F,(B:2,A:1)C:1)G;").unwrap();
+
+        let identities: HashMap<_, _> = tree1
+            .node_iter()
+            .map(|node| (node.identity(), node.id))
+            .collect();
+        for node in tree2.node_iter() {
+            let id = identities[&node.identity()];
+            assert_eq!(tree1.get(&id).unwrap().name, node.name);
+        }
+
+        // Different nodes have different identities
+        let c1 = tree1.get_by_name("C").unwrap();
+        let g2 = tree2.get_by_name("G").unwrap();
+        assert_eq!(c1.identity(), tree2.get_by_name("C").unwrap().identity());
+        assert_ne!(c1.identity(), g2.identity());
+
+        let mut other = c1.clone();
+        other.comment = Some("comment".into());
+        assert_ne!(c1.identity(), other.identity());
+        other.comment = None;
+        other.parent_edge = Some(1.5);
+        assert_ne!(c1.identity(), other.identity());
+        other.parent_edge = None;
+        assert_ne!(c1.identity(), other.identity());
+        other.parent_edge = c1.parent_edge;
+        other.children.clear();
+        assert_eq!(c1.identity(), other.identity());
+        assert_ne!(*c1, other);
+    }
+
+    #[test]
+    fn tip_name_checks() {
+        let cases = [
+            ("((A,B),(C,D));", true, None),
+            ("((A,B),(C,A));", false, Some(TreeError::DuplicateLeafNames)),
+            ("((A,B),(C,));", true, Some(TreeError::UnnamedLeaves)),
+            ("((A,),(A,));", false, Some(TreeError::UnnamedLeaves)),
+        ];
+
+        for (newick, unique, error) in cases {
+            let tree = Tree::from_newick(newick).unwrap();
+            assert_eq!(tree.are_tip_names_unique(), unique, "Failed on: {newick}");
+            match error {
+                None => assert!(tree.check_tip_names().is_ok()),
+                Some(TreeError::DuplicateLeafNames) => assert!(matches!(
+                    tree.check_tip_names(),
+                    Err(TreeError::DuplicateLeafNames)
+                )),
+                Some(_) => assert!(matches!(
+                    tree.check_tip_names(),
+                    Err(TreeError::UnnamedLeaves)
+                )),
+            }
+        }
+    }
+
+    #[test]
+    fn rename_tips() {
+        let mut tree = Tree::from_newick("((a_1,b_1)C,(c_2,d_2)D)E;").unwrap();
+        let other = Tree::from_newick("((A,B),(C,D));").unwrap();
+
+        // Partitions are cached with the old names
+        assert!(tree.robinson_foulds(&other).is_err());
+
+        tree.rename_tips_with(|name| name.split('_').next().unwrap().to_uppercase());
+        assert_eq!(tree.to_newick().unwrap(), "((A,B)C,(C,D)D)E;");
+        assert_eq!(tree.robinson_foulds(&other).unwrap(), 0);
+
+        let a = tree.get_by_name("A").unwrap().id;
+        tree.rename_tip(&a, "C").unwrap();
+        assert!(!tree.are_tip_names_unique());
+        assert!(matches!(
+            tree.robinson_foulds(&other),
+            Err(TreeError::DuplicateLeafNames)
+        ));
+
+        let root = tree.get_root().unwrap();
+        assert!(tree.rename_tip(&root, "A").is_err());
+        assert!(tree.rename_tip(&100, "A").is_err());
+    }
+
+    #[test]
+    fn compare_different_tips() {
+        let tree = Tree::from_newick("((A:1,B:1):1,(C:1,D:1):1,(E:1,F:1):1);").unwrap();
+        let others = [
+            // Same number of leaves with a different name
+            "((A:1,B:1):1,(C:1,D:1):1,(E:1,G:1):1);",
+            // Extra leaf
+            "((A:1,B:1):1,(C:1,D:1):1,(E:1,(F:1,G:1):1):1);",
+        ];
+
+        for newick in others {
+            let other = Tree::from_newick(newick).unwrap();
+            assert!(matches!(
+                tree.robinson_foulds(&other),
+                Err(TreeError::DifferentTipIndices)
+            ));
+            assert!(matches!(
+                tree.weighted_robinson_foulds(&other),
+                Err(TreeError::DifferentTipIndices)
+            ));
+            assert!(matches!(
+                tree.khuner_felsenstein(&other),
+                Err(TreeError::DifferentTipIndices)
+            ));
+            assert!(matches!(
+                tree.compare_topologies(&other),
+                Err(TreeError::DifferentTipIndices)
+            ));
+        }
+
+        // Leaf indices are initialized on both trees when needed
+        let other = Tree::from_newick("((A:1,B:1):1,(C:1,E:1):1,(D:1,F:1):1);").unwrap();
+        assert_eq!(other.weighted_robinson_foulds(&tree).unwrap(), 4.0);
+        assert_eq!(other.khuner_felsenstein(&tree).unwrap(), 2.0);
+    }
+
+    #[test]
+    fn prune_taxa() {
+        let newick = "((A:1,(B:1,C:2)E:1)F:2,(D:3,(G:1,H:1)I:1)J:1,K:1)L;";
+        let cases = [
+            (
+                vec!["A", "C", "D", "G", "H", "K"],
+                "((A:1,C:3)F:2,(D:3,(G:1,H:1)I:1)J:1,K:1)L;",
+            ),
+            (vec!["B", "C", "K"], "((B:1,C:2)E:3,K:1)L;"),
+            // The root is left with a single child
+            (vec!["G", "H"], "(G:1,H:1)I;"),
+            (vec!["A", "B", "C"], "(A:1,(B:1,C:2)E:1)F;"),
+            (vec!["A"], "A;"),
+        ];
+
+        for (keep, expected) in cases {
+            let mut tree = Tree::from_newick(newick).unwrap();
+            tree.prune_to_taxa(&keep).unwrap();
+
+            assert_eq!(tree.get_leaves().len(), keep.len());
+            assert_eq!(tree.to_newick().unwrap(), expected);
+            for node in tree.nodes.iter().filter(|n| !n.deleted) {
+                let n_edges = tree.get_path_from_root(&node.id).unwrap().len() - 1;
+                assert_eq!(node.get_depth(), n_edges);
+                assert!(node.is_root() || node.is_tip() || node.children.len() > 1);
+            }
+        }
+
+        let mut tree = Tree::from_newick(newick).unwrap();
+        assert!(tree.prune_to_taxa(&["A", "Z"]).is_err());
+        assert!(tree.prune_to_taxa(&[]).is_err());
+        // Nothing is pruned when an error is returned
+        assert_eq!(tree.to_newick().unwrap(), newick);
+
+        let mut tree = Tree::from_newick("((A,B),(C,));").unwrap();
+        assert!(matches!(
+            tree.prune_to_taxa(&["A", "C"]),
+            Err(TreeError::UnnamedLeaves)
+        ));
+    }
+
+    #[test]
+    fn branch_length_statistics() {
+        let tree = Tree::from_newick("((A:1,B:3)E:0.5,(C:2,D:2)F,G:4)H;").unwrap();
+
+        let stats = tree.branch_length_stats().unwrap();
+        assert_eq!((stats.min, stats.max), (0.5, 4.0));
+        assert_eq!((stats.median, stats.total), (2.0, 12.5));
+        assert!((stats.mean - 12.5 / 6.0).abs() < 1e-10);
+        // Sum of squared deviations from the mean is 8.208333...
+        assert!((stats.variance - 8.208333333333334 / 6.0).abs() < 1e-10);
+
+        let internal = tree.internal_branch_length_stats().unwrap();
+        assert_eq!(
+            (internal.min, internal.max, internal.total),
+            (0.5, 0.5, 0.5)
+        );
+        assert_eq!(internal.variance, 0.0);
+
+        let external = tree.external_branch_length_stats().unwrap();
+        assert_eq!(external.total, 12.0);
+        assert_eq!(external.median, 2.0);
+
+        let tree = Tree::from_newick("((A,B),C)D;").unwrap();
+        for result in [
+            tree.branch_length_stats(),
+            tree.internal_branch_length_stats(),
+            tree.external_branch_length_stats(),
+        ] {
+            assert!(matches!(result, Err(TreeError::MissingBranchLengths)));
+        }
+    }
+
+    #[test]
+    fn generate_with_distributions() {
+        use crate::distr::Distr;
+
+        let distrs = [
+            Distr::Uniform,
+            Distr::Gamma,
+            Distr::Exponential { rate: 2.0 },
+            Distr::LogNormal {
+                mu: -1.0,
+                sigma: 0.5,
+            },
+        ];
+        for distr in distrs {
+            let tree = crate::generate_yule(20, true, distr).unwrap();
+            let root = tree.get_root().unwrap();
+            for id in tree.preorder(&root).unwrap().into_iter().skip(1) {
+                assert!(tree.get(&id).unwrap().parent_edge.unwrap() > 0.0);
+            }
+        }
+
+        let invalid = [
+            Distr::Exponential { rate: 0.0 },
+            Distr::Exponential { rate: -1.0 },
+            Distr::LogNormal {
+                mu: 0.0,
+                sigma: 0.0,
+            },
+        ];
+        for distr in invalid {
+            assert!(matches!(
+                crate::generate_tree(10, true, distr),
+                Err(TreeError::DistrError(_))
+            ));
+            assert!(matches!(
+                crate::generate_caterpillar(10, true, distr),
+                Err(TreeError::DistrError(_))
+            ));
+        }
+    }
+
+    #[test]
+    fn birth_death_simulation() {
+        use rand::{rngs::StdRng, SeedableRng};
+
+        let mut rng = StdRng::seed_from_u64(1234);
+        for _ in 0..20 {
+            let tree = match crate::generate_birth_death_tree(30, 1.0, 0.5, &mut rng) {
+                Ok(tree) => tree,
+                Err(TreeError::GeneralError(_)) => continue,
+                Err(e) => panic!("{e}"),
+            };
+            assert_eq!(tree.n_leaves(), 30);
+            assert!(tree.is_binary().unwrap());
+            assert!(tree.is_rooted().unwrap());
+            assert!(tree.is_ultrametric(1e-9).unwrap());
+            assert!(tree
+                .get_leaf_names()
+                .iter()
+                .all(|name| name.as_ref().unwrap().starts_with("Tip_")));
+        }
+
+        // Without extinction the simulation always succeeds
+        let tree = crate::generate_birth_death_tree(50, 1.0, 0.0, &mut rng).unwrap();
+        assert_eq!(tree.n_leaves(), 50);
+        assert_eq!(tree.size(), 99);
+
+        assert!(matches!(
+            crate::generate_birth_death_tree(50, 0.1, 10.0, &mut rng),
+            Err(TreeError::GeneralError(_))
+        ));
+        for (n, birth, death) in [(1, 1.0, 0.0), (10, 0.0, 0.0), (10, 1.0, -1.0)] {
+            assert!(crate::generate_birth_death_tree(n, birth, death, &mut rng).is_err());
+        }
+    }
+
+    #[test]
+    fn depth_vectors() {
+        let mut tree = Tree::from_newick("((A:1,B:2)E:1,(C:1,D:1)F:2)G;").unwrap();
+        assert_eq!(tree.node_depths_vector(), vec![0, 1, 2, 2, 1, 2, 2]);
+
+        let c = tree.get_by_name("C").unwrap().id;
+        tree.prune(&c).unwrap();
+        tree.compress().unwrap();
+        assert_eq!(
+            tree.tip_depths_vector(),
+            vec![
+                ("A".to_string(), 2),
+                ("B".to_string(), 2),
+                ("D".to_string(), 1)
+            ]
+        );
+        assert_eq!(
+            tree.root_to_tip_lengths().unwrap(),
+            vec![
+                ("A".to_string(), 2.0),
+                ("B".to_string(), 3.0),
+                ("D".to_string(), 3.0)
+            ]
+        );
+
+        let tree = Tree::from_newick("((A:1,B)E:1,C:1)G;").unwrap();
+        assert!(matches!(
+            tree.root_to_tip_lengths(),
+            Err(TreeError::MissingBranchLengths)
+        ));
+    }
+
+    #[test]
+    fn total_and_average_lengths() {
+        let mut tree = Tree::from_newick("((A:1,B:2)E:3,(C:4,D:5)F:6)G;").unwrap();
+        assert_eq!(tree.total_tree_length().unwrap(), 21.0);
+        assert_eq!(tree.average_branch_length().unwrap(), 3.5);
+
+        // Deleted nodes are not counted
+        let d = tree.get_by_name("D").unwrap().id;
+        tree.prune(&d).unwrap();
+        assert_eq!(tree.total_tree_length().unwrap(), 16.0);
+        assert_eq!(tree.average_branch_length().unwrap(), 3.2);
+
+        let tree = Tree::from_newick("((A:1,B)E:3,C:4)G;").unwrap();
+        for result in [tree.total_tree_length(), tree.average_branch_length()] {
+            assert!(matches!(result, Err(TreeError::MissingBranchLengths)));
+        }
+
+        let mut tree = Tree::new();
+        tree.add(Node::new());
+        assert_eq!(tree.total_tree_length().unwrap(), 0.0);
+        assert!(matches!(
+            tree.average_branch_length(),
+            Err(TreeError::MissingBranchLengths)
+        ));
+    }
+
+    #[test]
+    fn cophenetic_distances() {
+        let tree = Tree::from_newick("((A:1,B:2)E:3,(C:4,(D:5,G:6)H:7)F:8,I:9)R;").unwrap();
+        assert!(matches!(
+            tree.cophenetic_matrix(),
+            Err(TreeError::IsNotRooted)
+        ));
+
+        let tree = Tree::from_newick("((A:1,B:2)E:3,(C:4,(D:5,G:6)H:7)F:8)R;").unwrap();
+        let matrix = tree.cophenetic_matrix().unwrap();
+        assert_eq!(matrix.taxa_names(), vec!["A", "B", "C", "D", "G"]);
+        let expected = [
+            ("A", "B", 3.0),
+            ("A", "C", 0.0),
+            ("A", "G", 0.0),
+            ("C", "D", 8.0),
+            ("C", "G", 8.0),
+            ("D", "G", 15.0),
+        ];
+        for (n1, n2, d) in expected {
+            assert_eq!(*matrix.get(n1, n2).unwrap(), d);
+            assert_eq!(*matrix.get(n2, n1).unwrap(), d);
+        }
+
+        // Topological depth of the common ancestors without branch lengths
+        let tree = Tree::from_newick("((A,B)E,(C,(D,G)H)F)R;").unwrap();
+        let matrix = tree.cophenetic_matrix().unwrap();
+        assert_eq!(*matrix.get("A", "B").unwrap(), 1.0);
+        assert_eq!(*matrix.get("D", "G").unwrap(), 2.0);
+        assert_eq!(*matrix.get("B", "D").unwrap(), 0.0);
+
+        let tree = Tree::from_newick("((A:1,B)E:3,(C:4,(D:5,G:6)H:7)F:8)R;").unwrap();
+        assert!(matches!(
+            tree.cophenetic_matrix(),
+            Err(TreeError::MissingBranchLengths)
+        ));
+    }
+
+    #[test]
+    fn mpd_mntd() {
+        let tree = Tree::from_newick("((A:1,B:2)E:3,(C:4,D:5)F:6)G;").unwrap();
+        // A-B: 3, A-C: 14, A-D: 15, B-C: 15, B-D: 16, C-D: 9
+        assert!((tree.mpd().unwrap() - 72.0 / 6.0).abs() < 1e-10);
+        assert!((tree.mntd().unwrap() - (3.0 + 3.0 + 9.0 + 9.0) / 4.0).abs() < 1e-10);
+
+        // Unnamed leaves are ignored
+        let tree = Tree::from_newick("((A:1,B:2)E:3,(C:4,:5)F:6)G;").unwrap();
+        assert!((tree.mpd().unwrap() - 32.0 / 3.0).abs() < 1e-10);
+        assert!((tree.mntd().unwrap() - (3.0 + 3.0 + 14.0) / 3.0).abs() < 1e-10);
+
+        let tree = Tree::from_newick("((A:1,B)E:3,(C:4,D:5)F:6)G;").unwrap();
+        assert!(matches!(tree.mpd(), Err(TreeError::MissingBranchLengths)));
+        assert!(matches!(tree.mntd(), Err(TreeError::MissingBranchLengths)));
+
+        let tree = Tree::from_newick("((A:1,:2)E:3,:1)G;").unwrap();
+        assert!(matches!(tree.mpd(), Err(TreeError::GeneralError(_))));
+    }
+
+    #[test]
+    fn balance_statistics() {
+        let newicks = [
+            "(((A:1,B:1):1,C:2):1,(D:1,E:2):2);",
+            "((((A,B),C),D),E);",
+            "(((A:1,B:1):1,(C:1,D:1):1):1,((E:1,F:1):1,(G:1,H:1):1):1);",
+        ];
+        for newick in newicks {
+            let tree = Tree::from_newick(newick).unwrap();
+            let stats = tree.balance_stats().unwrap();
+
+            assert_eq!(stats.n_leaves, tree.n_leaves());
+            assert_eq!(stats.n_nodes, tree.size());
+            assert_eq!(stats.cherries, tree.cherries().unwrap());
+            assert_eq!(stats.sackin, tree.sackin().unwrap());
+            assert_eq!(stats.colless, tree.colless().unwrap());
+            assert!((stats.sackin_yule - tree.sackin_yule().unwrap()).abs() < 1e-10);
+            assert!((stats.sackin_pda - tree.sackin_pda().unwrap()).abs() < 1e-10);
+            assert!((stats.colless_yule - tree.colless_yule().unwrap()).abs() < 1e-10);
+            assert!((stats.colless_pda - tree.colless_pda().unwrap()).abs() < 1e-10);
+        }
+
+        let tree = Tree::from_newick(newicks[0]).unwrap();
+        let stats = tree.balance_stats().unwrap();
+        assert_eq!(stats.height, Some(tree.height().unwrap()));
+        assert_eq!(stats.diameter, Some(tree.diameter().unwrap()));
+
+        let tree = Tree::from_newick(newicks[1]).unwrap();
+        let stats = tree.balance_stats().unwrap();
+        assert_eq!(stats.height, None);
+        assert_eq!(stats.diameter, None);
+
+        let tree = Tree::from_newick("(A,B,C);").unwrap();
+        assert!(matches!(tree.balance_stats(), Err(TreeError::IsNotRooted)));
+        let tree = Tree::from_newick("((A,B,C),D);").unwrap();
+        assert!(matches!(tree.balance_stats(), Err(TreeError::IsNotBinary)));
+    }
+
+    #[test]
+    fn monophyly() {
+        let tree = Tree::from_newick("((((A,B)AB,C)ABC,D)ABCD,(E,(F,G)FG)EFG)R;").unwrap();
+
+        for group in [
+            vec!["A"],
+            vec!["B", "A"],
+            vec!["A", "B", "C"],
+            vec!["E", "F", "G"],
+            vec!["A", "B", "C", "D", "E", "F", "G"],
+        ] {
+            assert!(tree.is_monophyletic(&group).unwrap(), "{group:?}");
+            assert!(!tree.is_paraphyletic(&group).unwrap(), "{group:?}");
+        }
+
+        for group in [vec!["C", "D"], vec!["A", "C", "D"], vec!["E", "F"]] {
+            assert!(!tree.is_monophyletic(&group).unwrap(), "{group:?}");
+            assert!(tree.is_paraphyletic(&group).unwrap(), "{group:?}");
+        }
+
+        // Polyphyletic groups
+        for group in [vec!["A", "E"], vec!["B", "D", "G"]] {
+            assert!(!tree.is_monophyletic(&group).unwrap(), "{group:?}");
+            assert!(!tree.is_paraphyletic(&group).unwrap(), "{group:?}");
+        }
+
+        // Internal node names are not tips
+        for group in [vec!["AB", "C"], vec![], vec!["A", "Z"]] {
+            assert!(tree.is_monophyletic(&group).is_err());
+            assert!(tree.is_paraphyletic(&group).is_err());
+        }
+    }
+
+    #[test]
+    fn mrca_of_many_nodes() {
+        let tree = Tree::from_newick("((((A,B)AB,C)ABC,D)ABCD,(E,(F,G)FG)EFG)R;").unwrap();
+        let name = |id: NodeId| tree.get(&id).unwrap().name.clone().unwrap();
+
+        let cases = [
+            (vec!["A"], "A"),
+            (vec!["A", "B"], "AB"),
+            (vec!["B", "C", "A"], "ABC"),
+            (vec!["A", "D"], "ABCD"),
+            (vec!["G", "F", "E"], "EFG"),
+            (vec!["C", "G"], "R"),
+        ];
+        for (names, expected) in cases {
+            assert_eq!(name(tree.get_mrca_by_name(&names).unwrap()), expected);
+        }
+
+        let ab = tree.get_by_name("AB").unwrap().id;
+        let c = tree.get_by_name("C").unwrap().id;
+        let fg = tree.get_by_name("FG").unwrap().id;
+        assert_eq!(name(tree.get_mrca(&[ab]).unwrap()), "AB");
+        assert_eq!(name(tree.get_mrca(&[ab, c]).unwrap()), "ABC");
+        assert_eq!(name(tree.get_mrca(&[c, ab, fg]).unwrap()), "R");
+
+        assert!(matches!(tree.get_mrca(&[]), Err(TreeError::IsEmpty)));
+        assert!(matches!(
+            tree.get_mrca_by_name(&[]),
+            Err(TreeError::IsEmpty)
+        ));
+        assert!(matches!(
+            tree.get_mrca(&[100]),
+            Err(TreeError::NodeNotFound(100))
+        ));
+        assert!(tree.get_mrca_by_name(&["A", "Z"]).is_err());
+    }
+
+    #[test]
+    fn ages() {
+        let tree = Tree::from_newick("(((A:1,B:1)AB:0.5,C:1.5)ABC:2.5,(D:3,E:3)DE:1)R;").unwrap();
+        let ages = tree.node_ages().unwrap();
+        assert_eq!(ages.len(), tree.size());
+
+        let expected = [
+            ("R", 4.0),
+            ("ABC", 1.5),
+            ("AB", 1.0),
+            ("DE", 3.0),
+            ("A", 0.0),
+            ("E", 0.0),
+        ];
+        for (name, age) in expected {
+            let id = tree.get_by_name(name).unwrap().id;
+            assert_eq!(ages[&id], age);
+            assert_eq!(tree.age_of(&id).unwrap(), age);
+        }
+        assert!(matches!(
+            tree.age_of(&100),
+            Err(TreeError::NodeNotFound(100))
+        ));
+
+        let tree = Tree::from_newick("((A:1,B:1.1)AB:0.5,C:1.5)R;").unwrap();
+        assert!(matches!(tree.node_ages(), Err(TreeError::GeneralError(_))));
+        let tree = Tree::from_newick("((A:1,B:1)AB,C:1.5)R;").unwrap();
+        assert!(matches!(
+            tree.node_ages(),
+            Err(TreeError::MissingBranchLengths)
+        ));
+        let tree = Tree::from_newick("(A:1,B:1,C:1)R;").unwrap();
+        assert!(matches!(tree.node_ages(), Err(TreeError::IsNotRooted)));
+    }
+
+    #[test]
+    fn paths_between_nodes() {
+        let tree = Tree::from_newick("((A:1,(C:2,E:3)D:4)B:5,((H:6)I:7)G:8)F;").unwrap();
+        let id = |name: &str| tree.get_by_name(name).unwrap().id;
+        let names = |path: Vec<NodeId>| -> Vec<String> {
+            path.iter()
+                .map(|id| tree.get(id).unwrap().name.clone().unwrap())
+                .collect()
+        };
 
-            assert!((t0.weighted_robinson_foulds(&t1).unwrap() - rfs[i0][i1]).abs() <= f64::EPSILON)
+        let cases = [
+            ("A", "A", vec!["A"]),
+            ("A", "B", vec!["A", "B"]),
+            ("B", "E", vec!["B", "D", "E"]),
+            ("E", "B", vec!["E", "D", "B"]),
+            ("C", "E", vec!["C", "D", "E"]),
+            ("A", "H", vec!["A", "B", "F", "G", "I", "H"]),
+        ];
+        for (source, target, expected) in cases {
+            let path = tree.path_between(&id(source), &id(target)).unwrap();
+            assert_eq!(names(path), expected);
         }
+
+        assert_eq!(
+            tree.path_length_between(&id("E"), &id("H")).unwrap(),
+            (Some(3.0 + 4.0 + 5.0 + 8.0 + 7.0 + 6.0), 6)
+        );
+        assert_eq!(
+            tree.path_length_between(&id("D"), &id("D")).unwrap(),
+            (Some(0.0), 0)
+        );
+        assert!(tree.path_between(&id("A"), &100).is_err());
     }
 
     #[test]
-    // Branch score distances according to
-    // https://evolution.genetics.washington.edu/phylip/doc/treedist.html
-    fn khuner_felsenstein_treedist() {
-        let trees = [
-            "(A:0.1,(B:0.1,(H:0.1,(D:0.1,(J:0.1,(((G:0.1,E:0.1):0.1,(F:0.1,I:0.1):0.1):0.1,C:0.1):0.1):0.1):0.1):0.1):0.1);",
-            "(A:0.1,(B:0.1,(D:0.1,((J:0.1,H:0.1):0.1,(((G:0.1,E:0.1):0.1,(F:0.1,I:0.1):0.1):0.1,C:0.1):0.1):0.1):0.1):0.1);",
-            "(A:0.1,(B:0.1,(D:0.1,(H:0.1,(J:0.1,(((G:0.1,E:0.1):0.1,(F:0.1,I:0.1):0.1):0.1,C:0.1):0.1):0.1):0.1):0.1):0.1);",
-            "(A:0.1,(B:0.1,(E:0.1,(G:0.1,((F:0.1,I:0.1):0.1,((J:0.1,(H:0.1,D:0.1):0.1):0.1,C:0.1):0.1):0.1):0.1):0.1):0.1);",
-            "(A:0.1,(B:0.1,(E:0.1,(G:0.1,((F:0.1,I:0.1):0.1,(((J:0.1,H:0.1):0.1,D:0.1):0.1,C:0.1):0.1):0.1):0.1):0.1):0.1);",
-            "(A:0.1,(B:0.1,(E:0.1,((F:0.1,I:0.1):0.1,(G:0.1,((J:0.1,(H:0.1,D:0.1):0.1):0.1,C:0.1):0.1):0.1):0.1):0.1):0.1);",
-            "(A:0.1,(B:0.1,(E:0.1,((F:0.1,I:0.1):0.1,(G:0.1,(((J:0.1,H:0.1):0.1,D:0.1):0.1,C:0.1):0.1):0.1):0.1):0.1):0.1);",
-            "(A:0.1,(B:0.1,(E:0.1,((G:0.1,(F:0.1,I:0.1):0.1):0.1,((J:0.1,(H:0.1,D:0.1):0.1):0.1,C:0.1):0.1):0.1):0.1):0.1);",
-            "(A:0.1,(B:0.1,(E:0.1,((G:0.1,(F:0.1,I:0.1):0.1):0.1,(((J:0.1,H:0.1):0.1,D:0.1):0.1,C:0.1):0.1):0.1):0.1):0.1);",
-            "(A:0.1,(B:0.1,(E:0.1,(G:0.1,((F:0.1,I:0.1):0.1,((J:0.1,(H:0.1,D:0.1):0.1):0.1,C:0.1):0.1):0.1):0.1):0.1):0.1);",
-            "(A:0.1,(B:0.1,(D:0.1,(H:0.1,(J:0.1,(((G:0.1,E:0.1):0.1,(F:0.1,I:0.1):0.1):0.1,C:0.1):0.1):0.1):0.1):0.1):0.1);",
-            "(A:0.1,(B:0.1,(E:0.1,((G:0.1,(F:0.1,I:0.1):0.1):0.1,((J:0.1,(H:0.1,D:0.1):0.1):0.1,C:0.1):0.1):0.1):0.1):0.1);",
-        ];
-        let rfs = [
-            [
-                0.,
-                0.2,
-                0.14142135623730953,
-                0.316227766016838,
-                0.316227766016838,
-                0.316227766016838,
-                0.316227766016838,
-                0.316227766016838,
-                0.316227766016838,
-                0.316227766016838,
-                0.14142135623730953,
-                0.316227766016838,
-            ],
-            [
-                0.2,
-                0.,
-                0.14142135623730953,
-                0.316227766016838,
-                0.28284271247461906,
-                0.316227766016838,
-                0.28284271247461906,
-                0.316227766016838,
-                0.28284271247461906,
-                0.316227766016838,
-                0.14142135623730953,
-                0.316227766016838,
-            ],
-            [
-                0.14142135623730953,
-                0.14142135623730953,
-                0.,
-                0.316227766016838,
-                0.316227766016838,
-                0.316227766016838,
-                0.316227766016838,
-                0.316227766016838,
-                0.316227766016838,
-                0.316227766016838,
-                0.,
-                0.316227766016838,
-            ],
-            [
-                0.316227766016838,
-                0.316227766016838,
-                0.316227766016838,
-                0.,
-                0.14142135623730953,
-                0.14142135623730953,
-                0.2,
-                0.14142135623730953,
-                0.2,
-                0.,
-                0.316227766016838,
-                0.14142135623730953,
-            ],
-            [
-                0.316227766016838,
-                0.28284271247461906,
-                0.316227766016838,
-                0.14142135623730953,
-                0.,
-                0.2,
-                0.14142135623730953,
-                0.2,
-                0.14142135623730953,
-                0.14142135623730953,
-                0.316227766016838,
-                0.2,
-            ],
-            [
-                0.316227766016838,
-                0.316227766016838,
-                0.316227766016838,
-                0.14142135623730953,
-                0.2,
-                0.,
-                0.14142135623730953,
-                0.14142135623730953,
-                0.2,
-                0.14142135623730953,
-                0.316227766016838,
-                0.14142135623730953,
-            ],
-            [
-                0.316227766016838,
-                0.28284271247461906,
-                0.316227766016838,
-                0.2,
-                0.14142135623730953,
-                0.14142135623730953,
-                0.,
-                0.2,
-                0.14142135623730953,
-                0.2,
-                0.316227766016838,
-                0.2,
-            ],
-            [
-                0.316227766016838,
-                0.316227766016838,
-                0.316227766016838,
-                0.14142135623730953,
-                0.2,
-                0.14142135623730953,
-                0.2,
-                0.,
-                0.14142135623730953,
-                0.14142135623730953,
-                0.316227766016838,
-                0.,
-            ],
-            [
-                0.316227766016838,
-                0.28284271247461906,
-                0.316227766016838,
-                0.2,
-                0.14142135623730953,
-                0.2,
-                0.14142135623730953,
-                0.14142135623730953,
-                0.,
-                0.2,
-                0.316227766016838,
-                0.14142135623730953,
-            ],
-            [
-                0.316227766016838,
-                0.316227766016838,
-                0.316227766016838,
-                0.,
-                0.14142135623730953,
-                0.14142135623730953,
-                0.2,
-                0.14142135623730953,
-                0.2,
-                0.,
-                0.316227766016838,
-                0.14142135623730953,
-            ],
-            [
-                0.14142135623730953,
-                0.14142135623730953,
-                0.,
-                0.316227766016838,
-                0.316227766016838,
-                0.316227766016838,
-                0.316227766016838,
-                0.316227766016838,
-                0.316227766016838,
-                0.316227766016838,
-                0.,
-                0.316227766016838,
-            ],
-            [
-                0.316227766016838,
-                0.316227766016838,
-                0.316227766016838,
-                0.14142135623730953,
-                0.2,
-                0.14142135623730953,
-                0.2,
-                0.,
-                0.14142135623730953,
-                0.14142135623730953,
-                0.316227766016838,
-                0.,
-            ],
+    fn merge_trees() {
+        let left = Tree::from_newick("((A:1,B:2)E:3,C:4)F;").unwrap();
+        let mut right = Tree::from_newick("(G:1,(H:1,I:1)J:2)K;").unwrap();
+        // Deleted nodes in the inputs are not copied
+        let h = right.get_by_name("H").unwrap().id;
+        right.prune(&h).unwrap();
+
+        let merged = Tree::merge(&left, &right, Some(0.5), None).unwrap();
+        assert_eq!(
+            merged.to_newick().unwrap(),
+            "(((A:1,B:2)E:3,C:4)F:0.5,(G:1,(I:1)J:2)K);"
+        );
+        assert_eq!(merged.size(), 10);
+        assert_eq!(merged.n_leaves(), 5);
+        assert!(merged.is_rooted().unwrap());
+
+        let root = merged.get_root().unwrap();
+        assert_eq!(merged.get(&root).unwrap().get_depth(), 0);
+        let a = merged.get_by_name("A").unwrap();
+        assert_eq!(a.get_depth(), 3);
+        assert_eq!(merged.get_mrca_by_name(&["A", "G"]).unwrap(), root);
+
+        let binary = Tree::merge(&left, &left, None, None).unwrap();
+        assert!(binary.is_binary().unwrap());
+        assert!(Tree::merge(&left, &Tree::new(), None, None).is_err());
+    }
+
+    #[test]
+    fn cached_root_paths() {
+        let mut tree = Tree::from_newick("((A,(C,E)D)B,((H)I)G)F;").unwrap();
+        let e = tree.get_by_name("E").unwrap().id;
+        let d = tree.get_by_name("D").unwrap().id;
+        let path = tree.get_path_from_root(&e).unwrap();
+        assert_eq!(tree.get_path_from_root(&e).unwrap(), path);
+        assert_eq!(tree.get_path_from_root(&d).unwrap(), path[..3]);
+
+        // The cache is invalidated when the tree is modified
+        let child = tree.add_child(Node::new_named("X"), e, None).unwrap();
+        let mut expected = path.clone();
+        expected.push(child);
+        assert_eq!(tree.get_path_from_root(&child).unwrap(), expected);
+
+        let b = tree.get_by_name("B").unwrap().id;
+        tree.get_mut(&d).unwrap().parent = None;
+        tree.get_mut(&b).unwrap().remove_child(&d).unwrap();
+        assert_eq!(tree.get_path_from_root(&e).unwrap(), vec![d, e]);
+
+        tree.prune(&d).unwrap();
+        assert!(tree.get_path_from_root(&e).is_err());
+    }
+
+    #[test]
+    fn parallel_distance_matrix() {
+        for (size, brlens) in [(2, true), (10, true), (200, true), (50, false)] {
+            let tree = crate::generate_tree(size, brlens, crate::distr::Distr::Uniform).unwrap();
+            let sequential = tree.distance_matrix().unwrap();
+            let parallel = tree.distance_matrix_parallel().unwrap();
+
+            assert_eq!(sequential.taxa, parallel.taxa);
+            for (d1, d2) in sequential.iter().zip(parallel.iter()) {
+                assert!((d1 - d2).abs() < 1e-10);
+            }
+        }
+
+        let tree = Tree::from_newick("((A:1,:2)E:3,(C:4,D:5)F:6)G;").unwrap();
+        assert!(matches!(
+            tree.distance_matrix_parallel(),
+            Err(TreeError::UnnamedLeaves)
+        ));
+    }
+
+    #[test]
+    fn sorted_leaves() {
+        let tree1 = Tree::from_newick("((E,(C,A)),(D,B));").unwrap();
+        let tree2 = Tree::from_newick("((B,D),(A,(E,C)));").unwrap();
+        let names1 = tree1.get_leaf_names_sorted().unwrap();
+        assert_eq!(names1, vec!["A", "B", "C", "D", "E"]);
+        assert_eq!(names1, tree2.get_leaf_names_sorted().unwrap());
+
+        let leaves = tree1.get_leaves_sorted().unwrap();
+        assert_eq!(leaves.len(), tree1.n_leaves());
+        for (id, name) in leaves.iter().zip(names1.iter()) {
+            assert_eq!(tree1.get(id).unwrap().name.as_ref(), Some(name));
+        }
+
+        let tree = Tree::from_newick("((A,),(D,B));").unwrap();
+        assert!(matches!(
+            tree.get_leaves_sorted(),
+            Err(TreeError::UnnamedLeaves)
+        ));
+        assert!(matches!(
+            tree.get_leaf_names_sorted(),
+            Err(TreeError::UnnamedLeaves)
+        ));
+        assert!(matches!(
+            tree.distance_matrix(),
+            Err(TreeError::UnnamedLeaves)
+        ));
+    }
+
+    #[test]
+    fn bootstrap_annotation() {
+        let mut tree = Tree::from_newick("((A:1,B:1)95:0.5,((C,D)0.75,E)CDE/88)100;").unwrap();
+        tree.annotate_with_bootstrap().unwrap();
+
+        let leaves: Vec<_> = tree
+            .get_leaves()
+            .iter()
+            .map(|id| tree.get(id).unwrap().clone())
+            .collect();
+        assert!(leaves.iter().all(|leaf| leaf.support.is_none()));
+        assert!(leaves.iter().all(|leaf| leaf.name.is_some()));
+
+        let ab = tree.get_by_name("A").unwrap().parent.unwrap();
+        let ab = tree.get(&ab).unwrap();
+        assert_eq!((ab.name.as_deref(), ab.support), (None, Some(95.0)));
+
+        let cd = tree.get_by_name("C").unwrap().parent.unwrap();
+        let cd = tree.get(&cd).unwrap();
+        assert_eq!((cd.name.as_deref(), cd.support), (None, Some(0.75)));
+
+        let cde = tree.get_by_name("CDE").unwrap();
+        assert_eq!(cde.support, Some(88.0));
+
+        assert_eq!(
+            tree.to_newick().unwrap(),
+            "((A:1,B:1)95:0.5,((C,D)0.75,E)CDE)100;"
+        );
+        assert_eq!(
+            tree.to_formatted_newick(NewickFormat::Topology).unwrap(),
+            "((,),((,),));"
+        );
+
+        // Non numeric names are kept
+        let mut tree = Tree::from_newick("((A,B)AB,(C,D)C/D)root;").unwrap();
+        tree.annotate_with_bootstrap().unwrap();
+        assert_eq!(tree.to_newick().unwrap(), "((A,B)AB,(C,D)C/D)root;");
+        assert!(tree.get_by_name("AB").unwrap().support.is_none());
+
+        // Supports without names are read back as names
+        let mut tree = Tree::from_newick("((A,B)/95,C);").unwrap();
+        tree.annotate_with_bootstrap().unwrap();
+        let reread = Tree::from_newick(&tree.to_newick().unwrap()).unwrap();
+        assert_eq!(reread.to_newick().unwrap(), "((A,B)95,C);");
+
+        assert!(matches!(
+            Tree::new().annotate_with_bootstrap(),
+            Err(TreeError::RootNotFound)
+        ));
+    }
+
+    #[test]
+    fn add_ancestor() {
+        let mut tree = Tree::from_newick("((A:1,B:1,C:1)D:1,E:2)F;").unwrap();
+        let a = tree.get_by_name("A").unwrap().id;
+        let c = tree.get_by_name("C").unwrap().id;
+        let e = tree.get_by_name("E").unwrap().id;
+
+        let n_leaves = tree.n_leaves();
+        let ancestor = tree.add_ancestor(&c, &a, Some(0.5), None).unwrap();
+        assert_eq!(tree.to_newick().unwrap(), "((B:1,(C:0.5,A))D:1,E:2)F;");
+        assert_eq!(tree.n_leaves(), n_leaves);
+        assert_eq!(tree.get(&ancestor).unwrap().get_depth(), 2);
+        assert_eq!(tree.get(&a).unwrap().get_depth(), 3);
+        assert_eq!(
+            tree.get_path_from_root(&a).unwrap(),
+            vec![
+                tree.get_root().unwrap(),
+                tree.get_by_name("D").unwrap().id,
+                ancestor,
+                a
+            ]
+        );
+
+        let expected = Tree::from_newick("(((A,C),B),E);").unwrap();
+        assert_eq!(tree.robinson_foulds(&expected).unwrap(), 0);
+
+        // Nodes must be distinct siblings
+        for (n1, n2) in [(a, e), (a, a), (tree.get_root().unwrap(), e)] {
+            assert!(matches!(
+                tree.add_ancestor(&n1, &n2, None, None),
+                Err(TreeError::MergingNonSiblingNodes(_, _))
+            ));
+        }
+        assert!(matches!(
+            tree.add_ancestor(&a, &100, None, None),
+            Err(TreeError::NodeNotFound(100))
+        ));
+    }
+
+    #[test]
+    fn regraft_leaves() {
+        let newick = "((A:1,B:1)E:1,(C:1,D:2)F:1)G;";
+        let mut tree = Tree::from_newick(newick).unwrap();
+        let a = tree.get_by_name("A").unwrap().id;
+        let c = tree.get_by_name("C").unwrap().id;
+        let f = tree.get_by_name("F").unwrap().id;
+
+        tree.regraft_leaf(&a, (&c, &f), None).unwrap();
+        assert_eq!(tree.to_newick().unwrap(), "(B:2,((C:0.5,A):0.5,D:2)F:1)G;");
+        let parent = tree.get(&a).unwrap().parent.unwrap();
+        assert_eq!(tree.get(&parent).unwrap().get_child_edge(&a), None);
+        assert_eq!(tree.n_leaves(), 4);
+
+        for node in tree.nodes.iter().filter(|n| !n.deleted) {
+            let n_edges = tree.get_path_from_root(&node.id).unwrap().len() - 1;
+            assert_eq!(node.get_depth(), n_edges);
+        }
+
+        // Only leaves can be regrafted, not on their own branch
+        let mut tree = Tree::from_newick(newick).unwrap();
+        let a = tree.get_by_name("A").unwrap().id;
+        let e = tree.get_by_name("E").unwrap().id;
+        let f = tree.get_by_name("F").unwrap().id;
+        let g = tree.get_by_name("G").unwrap().id;
+        assert!(matches!(
+            tree.regraft_leaf(&a, (&e, &a), Some(1.0)),
+            Err(TreeError::RegraftInsideSubtree(_))
+        ));
+        assert!(matches!(
+            tree.regraft_leaf(&e, (&g, &f), Some(1.0)),
+            Err(TreeError::GeneralError(_))
+        ));
+        assert_eq!(tree.to_newick().unwrap(), newick);
+    }
+
+    #[test]
+    fn ascii_drawing() {
+        let tree = Tree::from_newick("((A:1,B:3):2,C:1);").unwrap();
+        let expected = [
+            "                 +-------- A",
+            "+----------------|",
+            "|                +------------------------ B",
+            "|",
+            "+-------- C",
         ];
+        assert_eq!(tree.to_ascii().unwrap(), expected.join("\n"));
+
+        // Missing lengths and multifurcations
+        let tree = Tree::from_newick("(A:1,(B,C,D)E,F);").unwrap();
+        let ascii = tree.to_ascii().unwrap();
+        let lines: Vec<_> = ascii.lines().collect();
+        assert_eq!(lines.len(), 2 * tree.n_leaves() - 1);
+        for (line, name) in lines.iter().step_by(2).zip(["A", "B", "C", "D", "F"]) {
+            assert!(line.ends_with(&format!("- {name}")));
+        }
+        assert_eq!(lines[4], "+-----+----- C");
+
+        let mut tree = Tree::new();
+        tree.add(Node::new_named("A"));
+        assert_eq!(tree.to_ascii().unwrap(), "A");
 
-        for indices in (0..trees.len()).combinations(2) {
-            let (i0, i1) = (indices[0], indices[1]);
-            let t0 = Tree::from_newick(trees[i0]).unwrap();
-            let t1 = Tree::from_newick(trees[i1]).unwrap();
+        assert!(matches!(Tree::new().to_ascii(), Err(TreeError::IsEmpty)));
+    }
 
-            println!(
-                "[{i0}, {i1}] c:{:?} ==? t:{}",
-                t0.khuner_felsenstein(&t1).unwrap(),
-                rfs[i0][i1]
+    #[test]
+    fn write_newick_to_writer() {
+        let newick = "((A:1[&&NHX:S=human],B:1)95:0.5,(C,D)E:2)F;";
+        let tree = Tree::from_newick(newick).unwrap();
+
+        let mut buffer = Vec::new();
+        tree.write_newick(&mut buffer).unwrap();
+        assert_eq!(
+            String::from_utf8(buffer).unwrap(),
+            tree.to_newick().unwrap()
+        );
+
+        let mut buffer = Vec::new();
+        tree.write_formatted_newick(&mut buffer, NewickFormat::OnlyNames)
+            .unwrap();
+        assert_eq!(String::from_utf8(buffer).unwrap(), "((A,B)95,(C,D)E)F;");
+
+        let path = std::env::temp_dir().join("phylotree_write_newick_test.nwk");
+        tree.to_file(&path).unwrap();
+        let reread = Tree::from_file(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(reread.to_newick().unwrap(), tree.to_newick().unwrap());
+
+        let mut buffer = Vec::new();
+        assert!(Tree::new().write_newick(&mut buffer).is_err());
+        assert!(buffer.is_empty());
+    }
+
+    #[test]
+    fn b1_index() {
+        // Caterpillar: internal nodes below the root have heights 1..n-2
+        let tree = Tree::from_newick("(((((A,B),C),D),E),F);").unwrap();
+        let expected: f64 = (1..=4).map(|h| 1.0 / h as f64).sum();
+        assert!((tree.b1().unwrap() - expected).abs() < f64::EPSILON);
+
+        // Multifurcations
+        let tree = Tree::from_newick("((A,B,C),((D,E),F,G));").unwrap();
+        assert_eq!(tree.b1().unwrap(), 1.0 + 0.5 + 1.0);
+
+        // Balanced trees are more balanced than caterpillars
+        let balanced = Tree::from_newick("(((A,B),(C,D)),((E,F),(G,H)));").unwrap();
+        let caterpillar = Tree::from_newick("(((((((A,B),C),D),E),F),G),H);").unwrap();
+        assert!(balanced.b1().unwrap() > caterpillar.b1().unwrap());
+
+        let tree = Tree::from_newick("(A,B);").unwrap();
+        assert_eq!(tree.b1().unwrap(), 0.0);
+
+        let unrooted = Tree::from_newick("(A,B,(C,D));").unwrap();
+        assert!(matches!(unrooted.b1(), Err(TreeError::IsNotRooted)));
+        assert!(matches!(Tree::new().b1(), Err(TreeError::IsEmpty)));
+    }
+
+    #[test]
+    fn sister_clades() {
+        let tree = Tree::from_newick("((A,B)E,(C,(D,H)I)F,G)R;").unwrap();
+        let id = |name| tree.get_by_name(name).unwrap().id;
+        let names = |ids: Vec<NodeId>| -> Vec<String> {
+            ids.iter()
+                .map(|id| tree.get(id).unwrap().name.clone().unwrap())
+                .collect()
+        };
+
+        assert_eq!(names(tree.get_sister_clades(&id("A")).unwrap()), vec!["B"]);
+        assert_eq!(
+            names(tree.get_sister_clades(&id("F")).unwrap()),
+            vec!["E", "G"]
+        );
+        assert_eq!(names(tree.get_sister_clades(&id("C")).unwrap()), vec!["I"]);
+        assert_eq!(
+            names(tree.get_sister_leaves(&id("C")).unwrap()),
+            vec!["D", "H"]
+        );
+        assert_eq!(
+            names(tree.get_sister_leaves(&id("E")).unwrap()),
+            vec!["C", "D", "H", "G"]
+        );
+
+        assert!(tree.get_sister_clades(&id("R")).unwrap().is_empty());
+        assert!(tree.get_sister_leaves(&id("R")).unwrap().is_empty());
+        assert!(matches!(
+            tree.get_sister_clades(&100),
+            Err(TreeError::NodeNotFound(100))
+        ));
+    }
+
+    #[test]
+    fn resolve_polytomies() {
+        use rand::{rngs::StdRng, SeedableRng};
+
+        let newick = "((A:1,B:1,C:1,D:1)G:1,(E:1,F:1,H:1,I:1,J:1)K:1,L:1)R;";
+        let reference = Tree::from_newick(newick).unwrap();
+        let mut rng = StdRng::seed_from_u64(1234);
+
+        for zero_length in [true, false] {
+            let mut tree = reference.clone();
+            tree.randomly_resolve_polytomies(&mut rng, zero_length)
+                .unwrap();
+
+            assert!(tree.is_binary().unwrap());
+            assert_eq!(tree.n_leaves(), reference.n_leaves());
+            // 2n - 2 branches in a binary tree with a bifurcating root
+            let n_edges = tree.nodes.iter().filter(|n| !n.deleted).count() - 1;
+            assert_eq!(n_edges, 2 * tree.n_leaves() - 2);
+
+            // Resolved trees refine the original tree
+            let (tree_parts, ref_parts) = (
+                tree.get_partitions().unwrap(),
+                reference.get_partitions().unwrap(),
             );
+            assert!(ref_parts.is_subset(&tree_parts));
 
-            assert_eq!(t0.khuner_felsenstein(&t1).unwrap(), rfs[i0][i1])
+            for node in tree.nodes.iter().filter(|n| !n.deleted) {
+                let n_edges = tree.get_path_from_root(&node.id).unwrap().len() - 1;
+                assert_eq!(node.get_depth(), n_edges);
+            }
+
+            if zero_length {
+                assert_eq!(tree.length().unwrap(), reference.length().unwrap());
+            } else {
+                assert!(matches!(
+                    tree.length(),
+                    Err(TreeError::MissingBranchLengths)
+                ));
+            }
         }
+
+        // Binary trees are left untouched
+        let mut tree = Tree::from_newick("((A,B),(C,D));").unwrap();
+        tree.randomly_resolve_polytomies(&mut rng, true).unwrap();
+        assert_eq!(tree.to_newick().unwrap(), "((A,B),(C,D));");
     }
 
     #[test]
-    fn rooted_vs_unrooted_partitions() {
-        let rooted = Tree::from_newick("((Tip_3,Tip_4),(Tip_0,(Tip_1,Tip_2)));").unwrap();
-        let unrooted = Tree::from_newick("(Tip_3,Tip_4,(Tip_0,(Tip_1,Tip_2)));").unwrap();
+    fn equal_topologies() {
+        let tree1 = Tree::from_newick("(((A:1,B:1)AB:1,C:2):0.5,(D:1,(E:1,F:2)EF:3):1);").unwrap();
+        let tree2 = Tree::from_newick("(((F,E),D)x,(C,(B,A)y));").unwrap();
+        let tree3 = Tree::from_newick("(((A,C),B),(D,(E,F)));").unwrap();
+
+        assert!(tree1.topology_equal(&tree2).unwrap());
+        assert!(tree2.topology_equal(&tree1).unwrap());
+        assert!(!tree1.topology_equal(&tree3).unwrap());
+        for other in [&tree2, &tree3] {
+            assert_eq!(
+                tree1.topology_equal(other).unwrap(),
+                tree1.robinson_foulds(other).unwrap() == 0
+            );
+        }
 
-        let parts_rooted = rooted.get_partitions().unwrap();
-        let parts_unrooted = unrooted.get_partitions().unwrap();
+        // Multifurcations are not resolved
+        let tree4 = Tree::from_newick("((A,B,C),(D,(E,F)));").unwrap();
+        assert!(!tree1.topology_equal(&tree4).unwrap());
 
-        assert_eq!(parts_rooted, parts_unrooted);
+        let other = Tree::from_newick("(((A,B),C),(D,(E,G)));").unwrap();
+        assert!(matches!(
+            tree1.topology_equal(&other),
+            Err(TreeError::DifferentTipIndices)
+        ));
     }
 
     #[test]
-    fn new_vs_old() {
-        let trees = [
-            "(A:0.1,(B:0.1,(H:0.1,(D:0.1,(J:0.1,(((G:0.1,E:0.1):0.1,(F:0.1,I:0.1):0.1):0.1,C:0.1):0.1):0.1):0.1):0.1):0.1);",
-            "(A:0.1,(B:0.1,(D:0.1,((J:0.1,H:0.1):0.1,(((G:0.1,E:0.1):0.1,(F:0.1,I:0.1):0.1):0.1,C:0.1):0.1):0.1):0.1):0.1);",
-            "(A:0.1,(B:0.1,(D:0.1,(H:0.1,(J:0.1,(((G:0.1,E:0.1):0.1,(F:0.1,I:0.1):0.1):0.1,C:0.1):0.1):0.1):0.1):0.1):0.1);",
-            "(A:0.1,(B:0.1,(E:0.1,(G:0.1,((F:0.1,I:0.1):0.1,((J:0.1,(H:0.1,D:0.1):0.1):0.1,C:0.1):0.1):0.1):0.1):0.1):0.1);",
-            "(A:0.1,(B:0.1,(E:0.1,(G:0.1,((F:0.1,I:0.1):0.1,(((J:0.1,H:0.1):0.1,D:0.1):0.1,C:0.1):0.1):0.1):0.1):0.1):0.1);",
-            "(A:0.1,(B:0.1,(E:0.1,((F:0.1,I:0.1):0.1,(G:0.1,((J:0.1,(H:0.1,D:0.1):0.1):0.1,C:0.1):0.1):0.1):0.1):0.1):0.1);",
-            "(A:0.1,(B:0.1,(E:0.1,((F:0.1,I:0.1):0.1,(G:0.1,(((J:0.1,H:0.1):0.1,D:0.1):0.1,C:0.1):0.1):0.1):0.1):0.1):0.1);",
-            "(A:0.1,(B:0.1,(E:0.1,((G:0.1,(F:0.1,I:0.1):0.1):0.1,((J:0.1,(H:0.1,D:0.1):0.1):0.1,C:0.1):0.1):0.1):0.1):0.1);",
-            "(A:0.1,(B:0.1,(E:0.1,((G:0.1,(F:0.1,I:0.1):0.1):0.1,(((J:0.1,H:0.1):0.1,D:0.1):0.1,C:0.1):0.1):0.1):0.1):0.1);",
-            "(A:0.1,(B:0.1,(E:0.1,(G:0.1,((F:0.1,I:0.1):0.1,((J:0.1,(H:0.1,D:0.1):0.1):0.1,C:0.1):0.1):0.1):0.1):0.1):0.1);",
-            "(A:0.1,(B:0.1,(D:0.1,(H:0.1,(J:0.1,(((G:0.1,E:0.1):0.1,(F:0.1,I:0.1):0.1):0.1,C:0.1):0.1):0.1):0.1):0.1):0.1);",
-            "(A:0.1,(B:0.1,(E:0.1,((G:0.1,(F:0.1,I:0.1):0.1):0.1,((J:0.1,(H:0.1,D:0.1):0.1):0.1,C:0.1):0.1):0.1):0.1):0.1);",
-        ];
+    fn clone_topology_and_scaled() {
+        let tree = Tree::from_newick("((A:1,B:2)AB/95:1[&&NHX:S=x],(C:1,D:1)CD:2)R;").unwrap();
+
+        let topology = tree.clone_topology();
+        assert!(topology.topology_equal(&tree).unwrap());
+        assert_eq!(topology.to_newick().unwrap(), "((A,B),(C,D));");
+        assert!(matches!(
+            topology.length(),
+            Err(TreeError::MissingBranchLengths)
+        ));
+        for node in topology.nodes.iter().filter(|n| !n.deleted) {
+            if let Some(parent) = node.parent {
+                assert_eq!(
+                    topology.get(&parent).unwrap().get_child_edge(&node.id),
+                    None
+                );
+            }
+        }
 
-        for newicks in trees.iter().combinations(2) {
-            let t1 = Tree::from_newick(newicks[0]).unwrap();
-            let t2 = Tree::from_newick(newicks[1]).unwrap();
+        // Cached distances are not copied over
+        tree.distance_matrix().unwrap();
+        let scaled = tree.clone_with_lengths_scaled(0.5);
+        let (a, d) = (
+            scaled.get_by_name("A").unwrap().id,
+            scaled.get_by_name("D").unwrap().id,
+        );
+        assert_eq!(scaled.get_distance(&a, &d).unwrap(), (Some(2.5), 4));
+        assert_eq!(scaled.length().unwrap(), tree.length().unwrap() / 2.0);
+        let matrix = scaled.distance_matrix().unwrap();
+        assert_eq!(matrix.get("A", "D").unwrap(), &2.5);
+        assert_eq!(tree.distance_matrix().unwrap().get("A", "D").unwrap(), &5.0);
+    }
+
+    #[test]
+    fn fair_proportion_distinctiveness() {
+        let tree = Tree::from_newick("(((A:1,B:1):2,C:3):1,(D:2,E:4,F:2):3);").unwrap();
+        let ed = tree.evolutionary_distinctiveness().unwrap();
+        let get = |name| ed[&tree.get_by_name(name).unwrap().id];
+
+        assert_eq!(ed.len(), tree.n_leaves());
+        assert!((get("A") - (1.0 + 2.0 / 2.0 + 1.0 / 3.0)).abs() < 1e-10);
+        assert_eq!(get("B"), get("A"));
+        assert!((get("C") - (3.0 + 1.0 / 3.0)).abs() < 1e-10);
+        assert_eq!(get("D"), 2.0 + 1.0);
+        assert_eq!(get("E"), 4.0 + 1.0);
+
+        // Distinctiveness values sum to the total tree length
+        let total: f64 = ed.values().sum();
+        assert!((total - tree.length().unwrap()).abs() < 1e-10);
+
+        let tree = Tree::from_newick("((A:1,B),C:1);").unwrap();
+        assert!(matches!(
+            tree.evolutionary_distinctiveness(),
+            Err(TreeError::MissingBranchLengths)
+        ));
+    }
+
+    #[test]
+    fn unroot_trees() {
+        // The first child is a leaf, the other child becomes the root
+        let mut tree = Tree::from_newick("(A:1,((B:1,C:1):1,D:2):3);").unwrap();
+        let rooted = tree.clone();
+        tree.unroot().unwrap();
+        assert!(!tree.is_rooted().unwrap());
+        assert_eq!(tree.to_newick().unwrap(), "((B:1,C:1):1,D:2,A:4);");
+        assert_eq!(tree.n_leaves(), 4);
+        assert_eq!(tree.length().unwrap(), rooted.length().unwrap());
+
+        let (a, d) = (
+            tree.get_by_name("A").unwrap().id,
+            tree.get_by_name("D").unwrap().id,
+        );
+        assert_eq!(tree.get_distance(&a, &d).unwrap(), (Some(6.0), 2));
+        for node in tree.nodes.iter().filter(|n| !n.deleted) {
+            let n_edges = tree.get_path_from_root(&node.id).unwrap().len() - 1;
+            assert_eq!(node.get_depth(), n_edges);
+        }
+
+        // Missing lengths
+        let mut tree = Tree::from_newick("((A,B):1,(C,D));").unwrap();
+        tree.unroot().unwrap();
+        assert_eq!(tree.to_newick().unwrap(), "(A,B,(C,D));");
+
+        // Trees that cannot be unrooted
+        assert!(matches!(tree.unroot(), Err(TreeError::IsNotRooted)));
+        let mut tree = Tree::from_newick("(A:1,B:2);").unwrap();
+        assert!(matches!(tree.unroot(), Err(TreeError::IsNotBinary)));
+        assert_eq!(tree.to_newick().unwrap(), "(A:1,B:2);");
+    }
+
+    #[test]
+    fn topology_counts() {
+        let unrooted = [1, 1, 1, 1, 3, 15, 105, 945, 10395];
+        for (n, count) in unrooted.iter().enumerate() {
+            assert_eq!(Tree::number_of_topologies(n), Some(*count));
+        }
+        let rooted = [1, 1, 1, 3, 15, 105, 945, 10395];
+        for (n, count) in rooted.iter().enumerate() {
+            assert_eq!(Tree::number_of_rooted_topologies(n), Some(*count));
+        }
 
+        // Rooted trees with n leaves are unrooted trees with n + 1 leaves
+        for n in 2..40 {
             assert_eq!(
-                t1.robinson_foulds(&t2).unwrap(),
-                t1.robinson_foulds(&t2).unwrap()
-            )
+                Tree::number_of_rooted_topologies(n),
+                Tree::number_of_topologies(n + 1)
+            );
         }
+
+        assert!(Tree::number_of_topologies(30).is_some());
+        assert!(Tree::number_of_topologies(31).is_none());
+        assert!(Tree::number_of_rooted_topologies(29).is_some());
+        assert!(Tree::number_of_rooted_topologies(30).is_none());
     }
 
-    // the reference distance matrix was computed with ete3
     #[test]
-    fn compute_distance_matrix() {
-        let tree = Tree::from_newick("((A:0.1,B:0.2)F:0.6,(C:0.3,D:0.4)E:0.5)G;").unwrap();
-        let true_dists: HashMap<(String, String), f64> = HashMap::from_iter(vec![
-            (("A".into(), "B".into()), 0.30000000000000004),
-            (("A".into(), "C".into()), 1.5),
-            (("A".into(), "D".into()), 1.6),
-            (("B".into(), "C".into()), 1.6),
-            (("B".into(), "D".into()), 1.7000000000000002),
-            (("C".into(), "D".into()), 0.7),
-        ]);
+    fn rooting_tags() {
+        let tree = Tree::from_newick("((A:1,B:1)90:1,C:2)R:0.5;").unwrap();
+        let rooted = tree.to_newick_rooted_format().unwrap();
+        let unrooted = tree.to_newick_unrooted_format().unwrap();
+        assert_eq!(rooted, "[&R] ((A:1,B:1)90:1,C:2)R:0.5;");
+        assert_eq!(unrooted, "[&U] ((A:1,B:1)90:1,C:2)R;");
+
+        // Tags are ignored when reading trees
+        let reread = Tree::from_newick(&rooted).unwrap();
+        assert_eq!(reread.to_newick().unwrap(), tree.to_newick().unwrap());
+        let reread = Tree::from_newick(&unrooted).unwrap();
+        assert_eq!(reread.to_newick().unwrap(), "((A:1,B:1)90:1,C:2)R;");
+        let reread = Tree::from_newick("  [&u](A,B,C);").unwrap();
+        assert_eq!(reread.to_newick().unwrap(), "(A,B,C);");
+        assert!(reread.get_by_name("A").unwrap().comment.is_none());
+
+        assert!(Tree::new().to_newick_unrooted_format().is_err());
+    }
 
-        let matrix = tree.distance_matrix().unwrap();
+    #[test]
+    fn swap_node_children() {
+        use rand::{rngs::StdRng, SeedableRng};
 
-        for ((n1, n2), dist) in true_dists {
+        let mut tree = Tree::from_newick("((A:1,B:2)E:1,C:3,D:4)F;").unwrap();
+        let root = tree.get_root().unwrap();
+        tree.swap_children(&root).unwrap();
+        assert_eq!(tree.to_newick().unwrap(), "(D:4,C:3,(A:1,B:2)E:1)F;");
+
+        // Leaves have no children to swap
+        let a = tree.get_by_name("A").unwrap().id;
+        tree.swap_children(&a).unwrap();
+        assert_eq!(tree.to_newick().unwrap(), "(D:4,C:3,(A:1,B:2)E:1)F;");
+        assert!(matches!(
+            tree.swap_children(&100),
+            Err(TreeError::NodeNotFound(100))
+        ));
+
+        let original = Tree::from_newick("((A,B),((C,D),(E,(F,G))),(H,I));").unwrap();
+        let mut rng = StdRng::seed_from_u64(1);
+        let mut newicks = HashSet::new();
+        for _ in 0..20 {
+            let mut tree = original.clone();
+            tree.swap_all_children_randomly(&mut rng);
+            assert!(tree.topology_equal(&original).unwrap());
+            newicks.insert(tree.to_newick().unwrap());
+        }
+        assert!(newicks.len() > 1);
+    }
+
+    #[test]
+    fn internal_nodes() {
+        let tree = Tree::new();
+        assert!(tree.get_internal_nodes().is_empty());
+        assert_eq!(tree.n_internal_nodes(), 0);
+
+        // Rooted binary trees have n-1 non-leaf nodes, including the root
+        for n in 2..20 {
+            let tree = crate::generate_tree(n, false, crate::distr::Distr::Uniform).unwrap();
+            assert_eq!(tree.n_internal_nodes() + 1, n - 1);
+            assert_eq!(tree.get_internal_nodes().len(), tree.n_internal_nodes());
+        }
+
+        let mut tree = Tree::from_newick("((A,B)E,(C,D)G)F;").unwrap();
+        let e = tree.get_by_name("E").unwrap().id;
+        let g = tree.get_by_name("G").unwrap().id;
+        assert_eq!(tree.get_internal_nodes(), vec![e, g]);
+
+        // Deleted nodes are not counted
+        tree.prune(&e).unwrap();
+        tree.prune(&g).unwrap();
+        assert!(tree.get_internal_nodes().is_empty());
+        assert_eq!(tree.n_internal_nodes(), 0);
+    }
+
+    #[test]
+    fn negative_branch_lengths() {
+        for newick in [
+            "((A:-0.1,B:0.2)C:0.3,D:0.4)E;",
+            "((A:0.1,B:0.2)C:-0.3,D:0.4)E;",
+            "((A:0.1,B:0.2)C:0.3,D:0.4)E:-1;",
+        ] {
             assert!(
-                (dist - matrix.get(&n1, &n2).unwrap()) <= f64::EPSILON,
-                "d({n1},{n2}) want:{dist} got:{}",
-                matrix.get(&n1, &n2).unwrap()
-            )
+                matches!(
+                    Tree::from_newick(newick),
+                    Err(NewickParseError::InvalidBranchLength { .. })
+                ),
+                "{newick}"
+            );
+        }
+
+        let tree = Tree::from_newick("((A:0,B:0.2)C:0.3,D)E;").unwrap();
+        assert!(tree.validate_branch_lengths().is_ok());
+
+        let mut tree = Tree::from_newick("((A:0.1,B:0.2)C:0.3,D:0.4)E;").unwrap();
+        let a = tree.get_by_name("A").unwrap().id;
+        let c = tree.get_by_name("C").unwrap().id;
+        tree.get_mut(&a).unwrap().set_parent(c, Some(-0.5));
+        assert!(matches!(
+            tree.validate_branch_lengths(),
+            Err(TreeError::InvalidBranchLength(l)) if l == -0.5
+        ));
+    }
+
+    #[test]
+    fn get_invalid_nodes() {
+        let mut tree = Tree::from_newick("((A,B)C,D)E;").unwrap();
+        let c = tree.get_by_name("C").unwrap().id;
+        let size = tree.size();
+
+        assert!(matches!(tree.get(&size), Err(TreeError::NodeNotFound(id)) if id == size));
+        assert!(matches!(tree.get_mut(&size), Err(TreeError::NodeNotFound(id)) if id == size));
+
+        // Ids of deleted nodes are not valid anymore
+        tree.prune(&c).unwrap();
+        assert!(matches!(tree.get(&c), Err(TreeError::NodeNotFound(id)) if id == c));
+        assert!(matches!(tree.get_mut(&c), Err(TreeError::NodeNotFound(id)) if id == c));
+
+        // Ids from a larger tree are out of bounds in a smaller one
+        let small = Tree::from_newick("(A,B)C;").unwrap();
+        let big = Tree::from_newick("((A,B)C,(D,E)F)G;").unwrap();
+        let f = big.get_by_name("F").unwrap().id;
+        assert!(matches!(small.get(&f), Err(TreeError::NodeNotFound(_))));
+    }
+
+    #[test]
+    fn tree_edit_distances() {
+        let cases = [
+            ("(A,B)C;", "(A,B)C;", 0),
+            ("(A,B)C;", "(A,D)C;", 1),
+            // Children order matters
+            ("(A,B)C;", "(B,A)C;", 2),
+            // Insert or delete an internal node
+            ("(A,B)C;", "((A,B)D)C;", 1),
+            ("((A,B)D,E)C;", "(A,B,E)C;", 1),
+            // Unnamed nodes have the same label
+            ("((A,B),(C,D));", "((A,B),(C,D));", 0),
+            ("((A,B),(C,D));", "((A,C),(B,D));", 2),
+            ("((A,(B)C)D,E)F;", "(((A,B)D)C,E)F;", 2),
+            ("(A,B)C;", "(((A,B)C,D)E,F)G;", 4),
+        ];
+
+        for (newick1, newick2, expected) in cases {
+            let tree1 = Tree::from_newick(newick1).unwrap();
+            let tree2 = Tree::from_newick(newick2).unwrap();
+            assert_eq!(
+                tree1.tree_edit_distance(&tree2).unwrap(),
+                expected,
+                "{newick1} {newick2}"
+            );
+            assert_eq!(
+                tree2.tree_edit_distance(&tree1).unwrap(),
+                expected,
+                "{newick2} {newick1}"
+            );
+        }
+
+        let tree = Tree::from_newick("(A,B)C;").unwrap();
+        assert!(matches!(
+            tree.tree_edit_distance(&Tree::new()),
+            Err(TreeError::RootNotFound)
+        ));
+    }
+
+    #[test]
+    fn spr_distances() {
+        // Pairs of trees with their exact SPR distance
+        let cases = [
+            ("((A,B),C);", "((B,A),C);", 0),
+            ("(((A,B),C),(D,E));", "((E,D),(C,(B,A)));", 0),
+            ("(((A,B),C),(D,E));", "(((A,C),B),(D,E));", 1),
+            ("((A,B),(C,D));", "((A,C),(B,D));", 2),
+            ("(((A,B),C),D);", "(A,(B,(C,D)));", 2),
+            ("((((A,B),C),D),E);", "((((E,D),C),B),A);", 3),
+            (
+                "(((A,B),(C,D)),((E,F),G));",
+                "(((A,G),(C,F)),((E,B),D));",
+                3,
+            ),
+        ];
+
+        for (newick1, newick2, exact) in cases {
+            let tree1 = Tree::from_newick(newick1).unwrap();
+            let tree2 = Tree::from_newick(newick2).unwrap();
+            for distance in [
+                tree1.spr_distance(&tree2).unwrap(),
+                tree2.spr_distance(&tree1).unwrap(),
+            ] {
+                assert!(
+                    exact <= distance && distance <= 3 * exact,
+                    "{newick1} {newick2}: {distance}"
+                );
+            }
+        }
+
+        // Unifurcations are ignored
+        let tree1 = Tree::from_newick("(((A,B)),C);").unwrap();
+        let tree2 = Tree::from_newick("((A,B),C);").unwrap();
+        assert_eq!(tree1.spr_distance(&tree2).unwrap(), 0);
+
+        let tree = Tree::from_newick("((A,B),C);").unwrap();
+        assert!(matches!(
+            tree.spr_distance(&Tree::from_newick("((A,B),D);").unwrap()),
+            Err(TreeError::DifferentTipIndices)
+        ));
+        assert!(matches!(
+            tree.spr_distance(&Tree::from_newick("(A,B,C);").unwrap()),
+            Err(TreeError::IsNotRooted)
+        ));
+        assert!(matches!(
+            Tree::from_newick("((A,B,C),D);")
+                .unwrap()
+                .spr_distance(&Tree::from_newick("((A,B,D),C);").unwrap()),
+            Err(TreeError::IsNotBinary)
+        ));
+    }
+
+    #[test]
+    fn clades_by_name() {
+        let mut tree =
+            Tree::from_newick("(((A,B)Clade,C)Clade,(D,E)Other,(F,G),Clade)Root;").unwrap();
+
+        let first = tree.get_clade_by_name("Clade").unwrap();
+        assert_eq!(tree.get_subtree_leaves(&first).unwrap().len(), 3);
+        assert_eq!(
+            tree.get_clade_by_name("C"),
+            tree.get_by_name("C").map(|n| n.id)
+        );
+        assert_eq!(tree.get_clade_by_name("Missing"), None);
+
+        let clades = tree.named_clades();
+        let mut names: Vec<_> = clades.keys().cloned().collect();
+        names.sort();
+        assert_eq!(names, vec!["Clade", "Other", "Root"]);
+        assert_eq!(clades["Clade"], first);
+        assert_eq!(clades["Root"], tree.get_root().unwrap());
+
+        // Deleted nodes are not returned
+        let other = clades["Other"];
+        tree.prune(&other).unwrap();
+        assert_eq!(tree.get_clade_by_name("Other"), None);
+        assert!(!tree.named_clades().contains_key("Other"));
+
+        assert!(Tree::new().named_clades().is_empty());
+    }
+
+    #[test]
+    fn calibrations() {
+        let mut tree = Tree::from_newick("(((A,B)C,D)E[note],F)G;").unwrap();
+        let ids: HashMap<_, _> = ["C", "E", "G"]
+            .iter()
+            .map(|name| (*name, tree.get_by_name(name).unwrap().id))
+            .collect();
+
+        assert!(tree.validate_calibrations().is_ok());
+
+        tree.set_calibration(&ids["G"], 100.0, Some(120.0)).unwrap();
+        tree.set_calibration(&ids["E"], 60.0, Some(110.0)).unwrap();
+        tree.set_calibration(&ids["C"], 0.0, None).unwrap();
+        assert!(tree.validate_calibrations().is_ok());
+        assert_eq!(
+            tree.to_newick_with_calibrations().unwrap(),
+            "(((A,B)C[&calibration={0}],D)E[&calibration={60,110}],F)G[&calibration={100,120}];"
+        );
+        // Calibrations are not written by default
+        assert_eq!(tree.to_newick().unwrap(), "(((A,B)C,D)E[note],F)G;");
+
+        // Constraints are checked against all ancestors
+        tree.set_calibration(&ids["C"], 115.0, None).unwrap();
+        assert!(matches!(
+            tree.validate_calibrations(),
+            Err(TreeError::InconsistentCalibrations { node, ancestor })
+                if node == ids["C"] && ancestor == ids["E"]
+        ));
+        tree.set_calibration(&ids["E"], 60.0, None).unwrap();
+        tree.set_calibration(&ids["C"], 125.0, None).unwrap();
+        assert!(matches!(
+            tree.validate_calibrations(),
+            Err(TreeError::InconsistentCalibrations { node, ancestor })
+                if node == ids["C"] && ancestor == ids["G"]
+        ));
+        // Equal ages are consistent
+        tree.set_calibration(&ids["C"], 120.0, None).unwrap();
+        assert!(tree.validate_calibrations().is_ok());
+
+        for (min, max) in [(-1.0, None), (10.0, Some(5.0)), (f64::NAN, None)] {
+            assert!(matches!(
+                tree.set_calibration(&ids["C"], min, max),
+                Err(TreeError::InvalidCalibration(id)) if id == ids["C"]
+            ));
+        }
+        assert_eq!(
+            tree.get(&ids["C"]).unwrap().calibration,
+            Some(Calibration {
+                min: 120.0,
+                max: None
+            })
+        );
+        assert!(matches!(
+            tree.set_calibration(&100, 1.0, None),
+            Err(TreeError::NodeNotFound(100))
+        ));
+    }
+
+    #[test]
+    fn newick_with_support() {
+        let newick = "((A:0.1,B:0.2)95:0.3,(C:0.3,D:0.4)CD/70:0.5,(E,F)EF)100;";
+        let tree = Tree::from_newick_with_support(newick).unwrap();
+
+        let supports: Vec<_> = tree
+            .get_leaf_names()
+            .iter()
+            .step_by(2)
+            .map(|name| {
+                let leaf = tree.get_by_name(name.as_ref().unwrap()).unwrap();
+                tree.get(&leaf.parent.unwrap()).unwrap().support
+            })
+            .collect();
+        assert_eq!(supports, vec![Some(95.), Some(70.), None]);
+        assert_eq!(
+            tree.get(&tree.get_root().unwrap()).unwrap().support,
+            Some(100.)
+        );
+
+        assert_eq!(
+            tree.to_newick_with_support(0).unwrap(),
+            "((A:0.1,B:0.2)95:0.3,(C:0.3,D:0.4)70:0.5,(E,F))100;"
+        );
+        assert_eq!(
+            tree.to_newick_with_support(1).unwrap(),
+            "((A:0.1,B:0.2)95.0:0.3,(C:0.3,D:0.4)70.0:0.5,(E,F))100.0;"
+        );
+
+        // Written supports are read back
+        let written = tree.to_newick_with_support(2).unwrap();
+        let reread = Tree::from_newick_with_support(&written).unwrap();
+        assert_eq!(reread.to_newick_with_support(2).unwrap(), written);
+
+        assert!(Tree::from_newick_with_support("((A,B)95,C").is_err());
+    }
+
+    #[test]
+    fn consistent_trees() {
+        let cases = [
+            // Same leaves
+            ("((A,B),(C,D));", "((B,A),(D,C));", true),
+            ("((A,B),(C,D));", "((A,C),(B,D));", false),
+            // Polytomies are compatible with their resolutions
+            ("(A,B,C,D,E);", "((A,B),(C,(D,E)));", true),
+            ("((A,B),C,D,E);", "((A,C),B,D,E);", false),
+            ("((A,B),C,(D,E));", "((A,B,C),D,E);", true),
+            // Different leaf sets
+            ("((A,B),(C,D));", "((A,(B,X)),(Y,(C,D)));", true),
+            ("((A,B),(C,D),E);", "((A,(C,X)),(B,(D,E)));", false),
+            ("((A,B),(C,D));", "((A,B),(X,Y));", true),
+            ("((A,B),(C,D));", "((A,B),(C,X));", true),
+        ];
+
+        for (newick1, newick2, expected) in cases {
+            let tree1 = Tree::from_newick(newick1).unwrap();
+            let tree2 = Tree::from_newick(newick2).unwrap();
+            assert_eq!(
+                tree1.is_consistent_with(&tree2).unwrap(),
+                expected,
+                "{newick1} {newick2}"
+            );
+            assert_eq!(
+                tree2.is_consistent_with(&tree1).unwrap(),
+                expected,
+                "{newick2} {newick1}"
+            );
+        }
+
+        // The original trees are left untouched
+        let tree1 = Tree::from_newick("((A,B),(C,D),E);").unwrap();
+        let tree2 = Tree::from_newick("((A,C,X),(B,Y),D);").unwrap();
+        assert!(!tree1.is_consistent_with(&tree2).unwrap());
+        assert_eq!(tree1.n_leaves(), 5);
+        assert_eq!(tree2.n_leaves(), 6);
+
+        let unnamed = Tree::from_newick("((A,B),(C,));").unwrap();
+        assert!(matches!(
+            tree1.is_consistent_with(&unnamed),
+            Err(TreeError::UnnamedLeaves)
+        ));
+    }
+
+    #[test]
+    fn siblings() {
+        let tree = Tree::from_newick("((A,B,C)E,(D)G)F;").unwrap();
+        let ids: HashMap<_, _> = ["A", "B", "C", "D", "E", "G"]
+            .iter()
+            .map(|name| (*name, tree.get_by_name(name).unwrap().id))
+            .collect();
+
+        assert_eq!(
+            tree.get_siblings(&ids["B"]).unwrap(),
+            vec![ids["A"], ids["C"]]
+        );
+        assert_eq!(tree.get_siblings(&ids["E"]).unwrap(), vec![ids["G"]]);
+        assert!(tree.get_siblings(&ids["D"]).unwrap().is_empty());
+        assert!(tree
+            .get_siblings(&tree.get_root().unwrap())
+            .unwrap()
+            .is_empty());
+        assert!(matches!(
+            tree.get_siblings(&100),
+            Err(TreeError::NodeNotFound(100))
+        ));
+
+        assert!(tree.has_sibling_named(&ids["A"], "C"));
+        assert!(tree.has_sibling_named(&ids["G"], "E"));
+        assert!(!tree.has_sibling_named(&ids["A"], "A"));
+        assert!(!tree.has_sibling_named(&ids["D"], "G"));
+        assert!(!tree.has_sibling_named(&tree.get_root().unwrap(), "F"));
+        assert!(!tree.has_sibling_named(&100, "A"));
+    }
+
+    #[test]
+    fn counts_by_depth() {
+        assert!(Tree::new().node_count_by_depth().is_empty());
+        assert!(Tree::new().leaf_count_by_depth().is_empty());
+
+        let mut tree = Tree::from_newick("(((A,B)E,C)F,(D,(G,H)I)J)K;").unwrap();
+        assert_eq!(
+            tree.node_count_by_depth(),
+            vec![(0, 1), (1, 2), (2, 4), (3, 4)]
+        );
+        assert_eq!(tree.leaf_count_by_depth(), vec![(2, 2), (3, 4)]);
+
+        // Counts follow changes in the tree
+        let e = tree.get_by_name("E").unwrap().id;
+        tree.prune(&e).unwrap();
+        tree.compress().unwrap();
+        assert_eq!(
+            tree.node_count_by_depth(),
+            vec![(0, 1), (1, 2), (2, 2), (3, 2)]
+        );
+        assert_eq!(tree.leaf_count_by_depth(), vec![(1, 1), (2, 1), (3, 2)]);
+
+        // Counts add up to the size of the tree
+        let tree = crate::generate_tree(50, false, crate::distr::Distr::Uniform).unwrap();
+        let n_nodes: usize = tree.node_count_by_depth().iter().map(|(_, n)| n).sum();
+        let n_leaves: usize = tree.leaf_count_by_depth().iter().map(|(_, n)| n).sum();
+        assert_eq!(n_nodes, tree.size());
+        assert_eq!(n_leaves, 50);
+    }
+
+    #[test]
+    fn dot_format() {
+        assert!(matches!(Tree::new().to_dot(), Err(TreeError::IsEmpty)));
+
+        // Special characters in names are escaped
+        let mut quoted = Tree::from_newick("((A:1,B:2):0.5,D)F;").unwrap();
+        let a = quoted.get_by_name("A").unwrap().id;
+        quoted
+            .get_mut(&a)
+            .unwrap()
+            .set_name("A \"x\"\\y".to_string());
+
+        let expected = [
+            "digraph {",
+            "    0 [label=\"F\"];",
+            "    1 [label=\"1\"];",
+            "    2 [label=\"A \\\"x\\\"\\\\y\"];",
+            "    3 [label=\"B\"];",
+            "    4 [label=\"D\"];",
+            "    0 -> 1 [label=\"0.5\"];",
+            "    1 -> 2 [label=\"1\"];",
+            "    1 -> 3 [label=\"2\"];",
+            "    0 -> 4;",
+            "}",
+        ];
+        assert_eq!(quoted.to_dot().unwrap(), expected.join("\n"));
+
+        let undirected = quoted.to_dot_undirected().unwrap();
+        assert_eq!(
+            undirected,
+            expected
+                .join("\n")
+                .replace("digraph", "graph")
+                .replace("->", "--")
+        );
+    }
+
+    #[test]
+    fn homologous_branches() {
+        let tree1 = Tree::from_newick("(((A:1,B:2)E:3,C:4)F:5,(D:6,G:7)H:8,I:9)J;").unwrap();
+        let tree2 = Tree::from_newick("((G:70,D:60):80,(C:40,(B:20,A:10):30):50,I:90);").unwrap();
+
+        let pairs = tree1.homologous_branch_lengths(&tree2).unwrap();
+        assert_eq!(pairs.len(), 3 + 6);
+        for (len_1, len_2) in pairs.iter() {
+            assert_eq!(len_1 * 10., *len_2);
         }
+        // Terminal branches are sorted by leaf name
+        assert_eq!(
+            pairs[3..].iter().map(|(l, _)| *l).collect::<Vec<_>>(),
+            vec![1., 2., 4., 6., 7., 9.]
+        );
+
+        let different_leaves = Tree::from_newick("(((A,B),C),(D,G),X);").unwrap();
+        assert!(matches!(
+            tree1.homologous_branch_lengths(&different_leaves),
+            Err(TreeError::DifferentTipIndices)
+        ));
+        let different_topology = Tree::from_newick("(((A,C),B),(D,G),I);").unwrap();
+        assert!(matches!(
+            tree1.homologous_branch_lengths(&different_topology),
+            Err(TreeError::DifferentTopologies)
+        ));
+        let missing_lengths = Tree::from_newick("(((A,B),C),(D,G),I);").unwrap();
+        assert!(matches!(
+            tree1.homologous_branch_lengths(&missing_lengths),
+            Err(TreeError::MissingBranchLengths)
+        ));
+    }
+
+    #[test]
+    fn scientific_notation_lengths() {
+        let tree = Tree::from_newick("((A:1.5e-4,B:2.3E-10)C:1e6,D:1E+2)E:4.2e0;").unwrap();
+        let lengths: Vec<_> = ["A", "B", "C", "D", "E"]
+            .iter()
+            .map(|name| tree.get_by_name(name).unwrap().parent_edge.unwrap())
+            .collect();
+        assert_eq!(lengths, vec![1.5e-4, 2.3e-10, 1e6, 1e2, 4.2]);
+
+        // Very small and very large lengths are kept when writing the tree back
+        let tree = Tree::from_newick("(A:1e-10,B:1e6);").unwrap();
+        let written = tree.to_newick().unwrap();
+        let reread = Tree::from_newick(&written).unwrap();
+        assert_eq!(reread.get_by_name("A").unwrap().parent_edge, Some(1e-10));
+        assert_eq!(reread.get_by_name("B").unwrap().parent_edge, Some(1e6));
+
+        assert!(matches!(
+            Tree::from_newick("(A:1e-,B:1);"),
+            Err(NewickParseError::FloatError { .. })
+        ));
+        assert!(matches!(
+            Tree::from_newick("(A:-1e-3,B:1);"),
+            Err(NewickParseError::InvalidBranchLength { .. })
+        ));
     }
 }
 
@@ -0,0 +1,200 @@
+//! A container for collections of trees sharing the same set of leaves
+use itertools::Itertools;
+
+use super::consensus::majority_rule_consensus;
+use super::{Tree, TreeError};
+
+/// A collection of trees with the same set of uniquely named leaves,
+/// *e.g.* trees sampled from a posterior distribution or bootstrap replicates.
+///
+/// The leaf index of each tree is initialized when the tree is added to the set,
+/// so bipartitions can be compared efficiently between the trees.
+/// ```
+/// use phylotree::tree::{Tree, TreeSet};
+///
+/// let trees = vec![
+///     Tree::from_newick("((A,B),(C,D),E);").unwrap(),
+///     Tree::from_newick("((A,B),(C,E),D);").unwrap(),
+/// ];
+/// let mut set = TreeSet::from_trees(trees).unwrap();
+/// set.push(Tree::from_newick("((A,C),(B,D),E);").unwrap()).unwrap();
+///
+/// assert_eq!(set.len(), 3);
+/// assert_eq!(set.leaf_names(), &["A", "B", "C", "D", "E"]);
+/// assert!(set.push(Tree::from_newick("((A,B),(C,D),F);").unwrap()).is_err());
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct TreeSet {
+    trees: Vec<Tree>,
+    leaf_names: Vec<String>,
+}
+
+impl TreeSet {
+    /// Creates an empty tree set
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Creates a tree set from a collection of trees. Returns an error if
+    /// the trees do not all have the same set of uniquely named leaves.
+    pub fn from_trees(trees: Vec<Tree>) -> Result<Self, TreeError> {
+        let mut set = Self::new();
+        for tree in trees {
+            set.push(tree)?;
+        }
+
+        Ok(set)
+    }
+
+    /// Adds a tree to the set. Returns an error if its leaves are not
+    /// uniquely named or if they differ from the leaves of the trees in the set.
+    pub fn push(&mut self, tree: Tree) -> Result<(), TreeError> {
+        let leaf_names = tree.get_leaf_index()?;
+        if self.trees.is_empty() {
+            self.leaf_names = leaf_names;
+        } else if leaf_names != self.leaf_names {
+            return Err(TreeError::DifferentTipIndices);
+        }
+        self.trees.push(tree);
+
+        Ok(())
+    }
+
+    /// Returns the trees of the set
+    pub fn trees(&self) -> &[Tree] {
+        &self.trees
+    }
+
+    /// Returns the sorted leaf names shared by all the trees of the set
+    pub fn leaf_names(&self) -> &[String] {
+        &self.leaf_names
+    }
+
+    /// Returns the number of trees in the set
+    pub fn len(&self) -> usize {
+        self.trees.len()
+    }
+
+    /// Checks if the set contains no trees
+    pub fn is_empty(&self) -> bool {
+        self.trees.is_empty()
+    }
+
+    /// Iterates over the trees of the set
+    pub fn iter(&self) -> impl Iterator<Item = &Tree> {
+        self.trees.iter()
+    }
+
+    /// Computes the Robinson-Foulds distance *(see [`Tree::robinson_foulds()`])*
+    /// between all pairs of trees in the set.
+    /// ```
+    /// use phylotree::tree::{Tree, TreeSet};
+    ///
+    /// let set = TreeSet::from_trees(vec![
+    ///     Tree::from_newick("((A,B),(C,D),E);").unwrap(),
+    ///     Tree::from_newick("((A,B),(C,E),D);").unwrap(),
+    ///     Tree::from_newick("((A,B),(C,D),E);").unwrap(),
+    /// ])
+    /// .unwrap();
+    ///
+    /// assert_eq!(
+    ///     set.rf_distance_matrix(),
+    ///     vec![vec![0, 2, 0], vec![2, 0, 2], vec![0, 2, 0]]
+    /// );
+    /// ```
+    pub fn rf_distance_matrix(&self) -> Vec<Vec<usize>> {
+        let n = self.trees.len();
+        let mut matrix = vec![vec![0; n]; n];
+        for ((i, tree1), (j, tree2)) in self.trees.iter().enumerate().tuple_combinations() {
+            let rf = tree1
+                .robinson_foulds(tree2)
+                .expect("Trees of a TreeSet have the same leaves");
+            matrix[i][j] = rf;
+            matrix[j][i] = rf;
+        }
+
+        matrix
+    }
+
+    /// Builds the majority-rule consensus of the trees in the set
+    /// *(see [`majority_rule_consensus()`](super::consensus::majority_rule_consensus))*.
+    pub fn consensus(&self, threshold: f64) -> Result<Tree, TreeError> {
+        majority_rule_consensus(&self.trees, threshold)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn trees() -> Vec<Tree> {
+        [
+            "((A,B),(C,D),(E,F));",
+            "((A,B),(C,E),(D,F));",
+            "((A,C),(B,D),(E,F));",
+            "((B,A),(F,E),(D,C));",
+        ]
+        .iter()
+        .map(|newick| Tree::from_newick(newick).unwrap())
+        .collect()
+    }
+
+    #[test]
+    fn build_set() {
+        let mut set = TreeSet::new();
+        assert!(set.is_empty());
+        for tree in trees() {
+            set.push(tree).unwrap();
+        }
+        assert_eq!(set.len(), 4);
+        assert_eq!(set.leaf_names(), &["A", "B", "C", "D", "E", "F"]);
+
+        let set = TreeSet::from_trees(trees()).unwrap();
+        assert_eq!(set.iter().count(), 4);
+        assert_eq!(set.trees()[3].n_leaves(), 6);
+    }
+
+    #[test]
+    fn invalid_trees() {
+        let mut set = TreeSet::from_trees(trees()).unwrap();
+        let invalid = [
+            "((A,B),(C,D),(E,G));",
+            "((A,B),(C,D),E);",
+            "((A,B),(C,D),(E,F),F);",
+            "((A,B),(C,D),(E,));",
+        ];
+        for newick in invalid {
+            assert!(set.push(Tree::from_newick(newick).unwrap()).is_err());
+        }
+        assert_eq!(set.len(), 4);
+
+        let mut trees = trees();
+        trees.push(Tree::from_newick("((A,B),(C,D),(E,G));").unwrap());
+        assert!(matches!(
+            TreeSet::from_trees(trees),
+            Err(TreeError::DifferentTipIndices)
+        ));
+    }
+
+    #[test]
+    fn distances_and_consensus() {
+        let set = TreeSet::from_trees(trees()).unwrap();
+        let matrix = set.rf_distance_matrix();
+
+        for (i, row) in matrix.iter().enumerate() {
+            for (j, rf) in row.iter().enumerate() {
+                let expected = set.trees()[i].robinson_foulds(&set.trees()[j]).unwrap();
+                assert_eq!(*rf, expected);
+                assert_eq!(*rf, matrix[j][i]);
+            }
+        }
+        assert_eq!(matrix[0][3], 0);
+
+        let consensus = set.consensus(0.5).unwrap();
+        let expected = Tree::from_newick("((A,B),(C,D),(E,F));").unwrap();
+        assert_eq!(consensus.robinson_foulds(&expected).unwrap(), 0);
+
+        assert!(TreeSet::new().consensus(0.5).is_err());
+        assert!(TreeSet::new().rf_distance_matrix().is_empty());
+    }
+}